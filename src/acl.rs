@@ -0,0 +1,129 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{collections::BTreeMap, fmt};
+
+use zenoh::{
+    internal::bail,
+    key_expr::{keyexpr, OwnedKeyExpr},
+    Result as ZResult,
+};
+
+// The operations controlled by the ACL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AclOp {
+    Read,
+    Write,
+    Delete,
+}
+
+impl AclOp {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "read" => Some(AclOp::Read),
+            "write" => Some(AclOp::Write),
+            "delete" => Some(AclOp::Delete),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AclOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AclOp::Read => "read",
+            AclOp::Write => "write",
+            AclOp::Delete => "delete",
+        })
+    }
+}
+
+// An access control list: the operations allowed on the keys matching each key expression.
+// The key expressions are full keys (i.e. including the storage's strip_prefix).
+// An operation is allowed if any rule matching the key allows it.
+pub(crate) struct Acl {
+    strip_prefix: Option<OwnedKeyExpr>,
+    rules: Vec<(OwnedKeyExpr, Vec<AclOp>)>,
+}
+
+impl Acl {
+    pub(crate) fn new(
+        property: &str,
+        value: &BTreeMap<String, Vec<String>>,
+        strip_prefix: Option<OwnedKeyExpr>,
+    ) -> ZResult<Self> {
+        let mut rules = Vec::with_capacity(value.len());
+        for (ke, ops) in value {
+            let ke = match OwnedKeyExpr::autocanonize(ke.clone()) {
+                Ok(ke) => ke,
+                Err(e) => bail!(
+                    r#"Invalid key expression "{}" in `{}`: {}"#,
+                    ke,
+                    property,
+                    e
+                ),
+            };
+            let ops = ops
+                .iter()
+                .map(|op| match AclOp::from_name(op) {
+                    Some(op) => Ok(op),
+                    None => bail!(
+                        r#"Invalid operation "{}" for "{}" in `{}`: must be either "read", "write" or "delete""#,
+                        op,
+                        ke,
+                        property
+                    ),
+                })
+                .collect::<ZResult<Vec<AclOp>>>()?;
+            rules.push((ke, ops));
+        }
+        Ok(Acl {
+            strip_prefix,
+            rules,
+        })
+    }
+
+    // Check that the operation is allowed on the key (as received by the storage, i.e. stripped from strip_prefix)
+    pub(crate) fn check(&self, op: AclOp, key: Option<&keyexpr>) -> ZResult<()> {
+        if self.allows(op, key) {
+            Ok(())
+        } else {
+            bail!(
+                "Access denied: {} on {} is not allowed by the storage's ACL",
+                op,
+                self.full_key(key)
+                    .map(|k| k.to_string())
+                    .unwrap_or_default()
+            )
+        }
+    }
+
+    pub(crate) fn allows(&self, op: AclOp, key: Option<&keyexpr>) -> bool {
+        match self.full_key(key) {
+            Some(full_key) => self
+                .rules
+                .iter()
+                .any(|(ke, ops)| ops.contains(&op) && ke.includes(&full_key)),
+            None => false,
+        }
+    }
+
+    fn full_key(&self, key: Option<&keyexpr>) -> Option<OwnedKeyExpr> {
+        match (&self.strip_prefix, key) {
+            (Some(prefix), Some(key)) => prefix.join(key).ok(),
+            (Some(prefix), None) => Some(prefix.clone()),
+            (None, Some(key)) => Some(key.to_owned()),
+            (None, None) => None,
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fs::{File, OpenOptions},
+    io::prelude::*,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::MultiGzDecoder, write::GzEncoder, Compression};
+use tracing::{trace, warn};
+use zenoh::{internal::zerror, time::Timestamp, Result as ZResult};
+
+// An entry to be archived: its name in the bundle, the file storing its value and its timestamp
+pub(crate) struct ArchiveEntry {
+    pub(crate) name: String,
+    pub(crate) file: PathBuf,
+    pub(crate) timestamp: Timestamp,
+}
+
+// Manages the compressed per-day bundles (gzipped tarballs) where the aged entries are moved.
+// Each archival run appends a new gzip member to the bundle of the day, so a bundle is
+// a concatenation of tar archives. The last occurrence of an entry in a bundle is the valid one.
+pub(crate) struct ArchiveMgr {
+    dir: PathBuf,
+    after: Duration,
+}
+
+impl ArchiveMgr {
+    // Name of the directory within the storage's directory where the bundles are stored
+    pub(crate) const DIR_NAME: &'static str = ".zenoh_archive";
+
+    pub(crate) fn new(base_dir: &Path, after_days: u64) -> Self {
+        let mut dir = PathBuf::from(base_dir);
+        dir.push(ArchiveMgr::DIR_NAME);
+        ArchiveMgr {
+            dir,
+            after: Duration::from_secs(after_days * 24 * 3600),
+        }
+    }
+
+    // Returns true if an entry with such a timestamp must be archived
+    pub(crate) fn is_aged(&self, timestamp: &Timestamp) -> bool {
+        match SystemTime::now().duration_since(timestamp.get_time().to_system_time()) {
+            Ok(age) => age > self.after,
+            Err(_) => false,
+        }
+    }
+
+    // The day (UTC) of a timestamp, formatted as "YYYY-MM-DD"
+    pub(crate) fn day_of(timestamp: &Timestamp) -> String {
+        let time = timestamp.get_time().to_system_time().max(UNIX_EPOCH);
+        humantime::format_rfc3339_seconds(time).to_string()[..10].to_string()
+    }
+
+    fn bundle_path(&self, day: &str) -> PathBuf {
+        let mut path = self.dir.clone();
+        path.push(format!("{day}.tar.gz"));
+        path
+    }
+
+    // Append the entries to the bundle of the day
+    pub(crate) fn append(&self, day: &str, entries: &[ArchiveEntry]) -> ZResult<()> {
+        std::fs::create_dir_all(&self.dir)
+            .map_err(|e| zerror!("Failed to create archive directory {:?}: {}", self.dir, e))?;
+        let path = self.bundle_path(day);
+        trace!("Archive {} entries in {:?}", entries.len(), path);
+        let bundle = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| zerror!("Failed to open archive {:?}: {}", path, e))?;
+        let mut builder = tar::Builder::new(GzEncoder::new(bundle, Compression::default()));
+        for entry in entries {
+            let mut f = File::open(&entry.file)
+                .map_err(|e| zerror!("Failed to archive file {:?}: {}", entry.file, e))?;
+            let size = f
+                .metadata()
+                .map_err(|e| zerror!("Failed to archive file {:?}: {}", entry.file, e))?
+                .len();
+            let mut header = tar::Header::new_gnu();
+            header.set_size(size);
+            header.set_mode(0o644);
+            header.set_mtime(entry.timestamp.get_time().as_secs().into());
+            header.set_cksum();
+            builder
+                .append_data(&mut header, &entry.name, &mut f)
+                .map_err(|e| zerror!("Failed to archive file {:?}: {}", entry.file, e))?;
+        }
+        builder
+            .into_inner()
+            .and_then(|gz| gz.finish())
+            .and_then(|mut f| f.flush())
+            .map_err(|e| zerror!("Failed to write archive {:?}: {}", path, e).into())
+    }
+
+    // Read the content of an entry from the bundle of the day
+    pub(crate) fn read(&self, day: &str, name: &str) -> ZResult<Option<Vec<u8>>> {
+        let path = self.bundle_path(day);
+        let bundle = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Failed to open archive {:?}: {}", path, e);
+                return Ok(None);
+            }
+        };
+        trace!("Read {} from archive {:?}", name, path);
+        let mut archive = tar::Archive::new(MultiGzDecoder::new(bundle));
+        // the bundle is a concatenation of tar archives
+        archive.set_ignore_zeros(true);
+        let mut content = None;
+        for entry in archive
+            .entries()
+            .map_err(|e| zerror!("Failed to read archive {:?}: {}", path, e))?
+        {
+            let mut entry =
+                entry.map_err(|e| zerror!("Failed to read archive {:?}: {}", path, e))?;
+            let is_searched = entry
+                .path()
+                .map(|p| p.to_string_lossy() == name)
+                .unwrap_or(false);
+            if is_searched {
+                let mut buf = Vec::with_capacity(entry.size() as usize);
+                entry
+                    .read_to_end(&mut buf)
+                    .map_err(|e| zerror!("Failed to read archive {:?}: {}", path, e))?;
+                content = Some(buf);
+            }
+        }
+        Ok(content)
+    }
+}
@@ -0,0 +1,110 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::HashMap,
+    fs::metadata,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{info, warn};
+use zenoh::{internal::bail, Result as ZResult};
+
+// The availability of the directories of a storage, which may disappear (e.g. an unmounted network file-system, or
+// an ejected disk). The storage is degraded while one is missing, and recovers as soon as it's back.
+pub(crate) struct Availability {
+    // the device of each directory when first checked, to detect it was unmounted (leaving its mount point)
+    devices: Mutex<HashMap<PathBuf, u64>>,
+    // since when the storage is degraded, with the reason
+    degraded: Mutex<Option<(SystemTime, String)>>,
+    // the number of times the storage got degraded
+    losses: AtomicU64,
+}
+
+impl Availability {
+    pub(crate) fn new() -> Self {
+        Availability {
+            devices: Mutex::new(HashMap::new()),
+            degraded: Mutex::new(None),
+            losses: AtomicU64::new(0),
+        }
+    }
+
+    // Check that all the directories are available, updating the degraded state (and logging its changes)
+    pub(crate) fn check<'a>(&self, dirs: impl Iterator<Item = &'a Path>) -> ZResult<()> {
+        let missing = dirs.into_iter().find_map(|dir| self.check_dir(dir).err());
+        let mut degraded = self.degraded.lock().unwrap();
+        match (missing, degraded.as_ref()) {
+            (None, None) => Ok(()),
+            (None, Some((since, _))) => {
+                info!(
+                    "Storage directories available again, after {}s",
+                    since.elapsed().unwrap_or_default().as_secs()
+                );
+                *degraded = None;
+                Ok(())
+            }
+            (Some(reason), None) => {
+                warn!("Storage degraded: {}", reason);
+                self.losses.fetch_add(1, Ordering::Relaxed);
+                *degraded = Some((SystemTime::now(), reason.clone()));
+                bail!("{}", reason)
+            }
+            (Some(reason), Some(_)) => bail!("{}", reason),
+        }
+    }
+
+    fn check_dir(&self, dir: &Path) -> Result<(), String> {
+        let m = match metadata(dir) {
+            Ok(m) if m.is_dir() => m,
+            Ok(_) => return Err(format!("{dir:?} is no longer a directory")),
+            Err(e) => return Err(format!("{dir:?} is unavailable: {e}")),
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let mut devices = self.devices.lock().unwrap();
+            let device = *devices.entry(dir.to_path_buf()).or_insert(m.dev());
+            if device != m.dev() {
+                return Err(format!(
+                    "{dir:?} is unavailable: its file-system is no longer mounted"
+                ));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = (m, &self.devices);
+        Ok(())
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let degraded = self.degraded.lock().unwrap();
+        let mut status = serde_json::json!({
+            "degraded": degraded.is_some(),
+            "losses": self.losses.load(Ordering::Relaxed),
+        });
+        if let Some((since, reason)) = degraded.as_ref() {
+            status["since"] = since
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .into();
+            status["reason"] = reason.as_str().into();
+        }
+        status
+    }
+}
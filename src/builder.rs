@@ -0,0 +1,212 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::sync::Arc;
+
+use zenoh::{internal::bail, key_expr::OwnedKeyExpr, Result as ZResult};
+use zenoh_backend_traits::{config::StorageConfig, Storage};
+
+use crate::*;
+
+/// A builder of File System Storages, for the applications embedding the backend (and the tests) not to write
+/// the storages' JSON configuration by hand.
+///
+/// For instance:
+/// ```ignore
+/// let volume = FileSystemVolume::new(Path::new("/var/zenoh"), None)?;
+/// let storage = FileSystemStorageBuilder::new("demo", "demo/example/**".try_into()?, "example")
+///     .strip_prefix("demo/example".try_into()?)
+///     .follow_links(true)
+///     .open_files_cache_size(64)
+///     .build(&volume)
+///     .await?;
+/// ```
+///
+/// The properties without a dedicated method can be set with [`property`](Self::property), or all at once with
+/// [`config`](Self::config).
+/// See the README for the description of each property.
+pub struct FileSystemStorageBuilder {
+    name: String,
+    key_expr: OwnedKeyExpr,
+    strip_prefix: Option<OwnedKeyExpr>,
+    volume_cfg: serde_json::Map<String, serde_json::Value>,
+    custom: CustomExtensions,
+}
+
+// The extensions of a storage which can't be configured in JSON
+#[derive(Default)]
+pub(crate) struct CustomExtensions {
+    pub(crate) transforms: Vec<Arc<dyn PayloadTransform>>,
+    pub(crate) interceptors: Vec<Arc<dyn StorageInterceptor>>,
+}
+
+impl FileSystemStorageBuilder {
+    /// A builder of a storage named `name`, storing the keys matching `key_expr` in the `dir` directory
+    /// (relative to the volume's root).
+    pub fn new(name: impl Into<String>, key_expr: OwnedKeyExpr, dir: impl Into<String>) -> Self {
+        let mut volume_cfg = serde_json::Map::new();
+        volume_cfg.insert(PROP_STORAGE_DIR.into(), dir.into().into());
+        FileSystemStorageBuilder {
+            name: name.into(),
+            key_expr,
+            strip_prefix: None,
+            volume_cfg,
+            custom: CustomExtensions::default(),
+        }
+    }
+
+    /// The prefix stripped from the keys before mapping them to files (must be a prefix of the key expression).
+    pub fn strip_prefix(mut self, prefix: OwnedKeyExpr) -> Self {
+        self.strip_prefix = Some(prefix);
+        self
+    }
+
+    /// Reject (or ignore, depending on `read_only_behavior`) the PUTs and DELETEs.
+    pub fn read_only(self, read_only: bool) -> Self {
+        self.property(PROP_STORAGE_READ_ONLY, read_only)
+    }
+
+    /// Write-once mode: the existing entries can't be overwritten nor deleted.
+    pub fn worm(self, worm: bool) -> Self {
+        self.property(PROP_STORAGE_WORM, worm)
+    }
+
+    /// Follow the symbolic links of the storage's directory.
+    pub fn follow_links(self, follow_links: bool) -> Self {
+        self.property(PROP_STORAGE_FOLLOW_LINK, follow_links)
+    }
+
+    /// Guess the encoding of the files created without zenoh from their extension.
+    pub fn keep_mime_types(self, keep_mime_types: bool) -> Self {
+        self.property(PROP_STORAGE_KEEP_MIME, keep_mime_types)
+    }
+
+    /// Delete all the files of the storage when it's closed.
+    pub fn delete_all_on_closure(self, delete_all: bool) -> Self {
+        let on_closure = if delete_all {
+            "delete_all"
+        } else {
+            "do_nothing"
+        };
+        self.property(PROP_STORAGE_ON_CLOSURE, on_closure)
+    }
+
+    /// The max number of file operations run concurrently.
+    pub fn max_concurrent_ops(self, max: usize) -> Self {
+        self.property(PROP_STORAGE_MAX_CONCURRENT_OPS, max)
+    }
+
+    /// The number of open file handles kept to serve the files read frequently.
+    pub fn open_files_cache_size(self, size: usize) -> Self {
+        self.property(PROP_STORAGE_OPEN_FILES_CACHE_SIZE, size)
+    }
+
+    /// The number of files read in advance when the keys of a numbered series are read sequentially.
+    pub fn read_ahead(self, depth: usize) -> Self {
+        self.property(PROP_STORAGE_READ_AHEAD, depth)
+    }
+
+    /// Keep a Bloom filter of the keys having data-info in memory.
+    pub fn bloom_filter(self, bloom_filter: bool) -> Self {
+        self.property(PROP_STORAGE_BLOOM_FILTER, bloom_filter)
+    }
+
+    /// Track the most recent timestamp of the values stored under each top-level prefix.
+    pub fn watermark_by_prefix(self, watermark_by_prefix: bool) -> Self {
+        self.property(PROP_STORAGE_WATERMARK_BY_PREFIX, watermark_by_prefix)
+    }
+
+    /// Keep the keys and timestamps of the entries in memory.
+    pub fn entries_index(self, entries_index: bool) -> Self {
+        self.property(PROP_STORAGE_ENTRIES_INDEX, entries_index)
+    }
+
+    /// Add a transform of the payloads, applied after the ones configured with the `payload_transforms` property
+    /// (and after the previously added ones) on write, and before them on read.
+    pub fn payload_transform(mut self, transform: Arc<dyn PayloadTransform>) -> Self {
+        self.custom.transforms.push(transform);
+        self
+    }
+
+    /// Add an interceptor of the operations, after the ones configured with the `interceptors` property (and after
+    /// the previously added ones).
+    pub fn interceptor(mut self, interceptor: Arc<dyn StorageInterceptor>) -> Self {
+        self.custom.interceptors.push(interceptor);
+        self
+    }
+
+    /// Set any property of the storage's volume-specific configuration.
+    pub fn property(mut self, name: &str, value: impl Into<serde_json::Value>) -> Self {
+        self.volume_cfg.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the properties which are set in `config` (replacing the ones already set).
+    pub fn config(mut self, config: &FsStorageConfig) -> Self {
+        self.volume_cfg.extend(config.to_json());
+        self
+    }
+
+    /// Validate the configuration and return it, as given to a volume by the storage manager.
+    /// The types of the properties are checked, but their values are validated on the storage's creation.
+    /// The transforms and interceptors added with [`payload_transform`](Self::payload_transform) and
+    /// [`interceptor`](Self::interceptor) are not part of it.
+    pub fn build_config(self) -> ZResult<StorageConfig> {
+        if self.name.is_empty() {
+            bail!("Invalid File System Storage configuration: the name must not be empty")
+        }
+        match self.volume_cfg.get(PROP_STORAGE_DIR) {
+            Some(serde_json::Value::String(dir)) if !dir.is_empty() => (),
+            _ => bail!(
+                r#"Invalid File System Storage configuration: `{}` must be a non-empty string"#,
+                PROP_STORAGE_DIR
+            ),
+        }
+        if let Some(prefix) = &self.strip_prefix {
+            let key_expr = self.key_expr.as_str();
+            if !key_expr
+                .strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            {
+                bail!(
+                    "Invalid File System Storage configuration: the prefix to strip {} is not a prefix of {}",
+                    prefix,
+                    key_expr
+                )
+            }
+        }
+        let strict = FsStorageConfig::from_json(&self.volume_cfg)?
+            .strict
+            .unwrap_or(true);
+        config::check_properties(&self.name, &self.volume_cfg, strict)?;
+        Ok(StorageConfig {
+            name: self.name,
+            key_expr: self.key_expr,
+            complete: false,
+            strip_prefix: self.strip_prefix,
+            // the id of the volume, as usually declared in the storage manager's configuration
+            volume_id: "fs".into(),
+            volume_cfg: self.volume_cfg.into(),
+            garbage_collection_config: Default::default(),
+            replication: None,
+        })
+    }
+
+    /// Validate the configuration and create the storage in a volume.
+    pub async fn build(mut self, volume: &FileSystemVolume) -> ZResult<Box<dyn Storage>> {
+        let custom = std::mem::take(&mut self.custom);
+        volume
+            .create_custom_storage(self.build_config()?, custom)
+            .await
+    }
+}
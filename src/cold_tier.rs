@@ -0,0 +1,124 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fs::{copy, metadata, remove_dir, remove_file, DirBuilder, Metadata},
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime},
+};
+
+use tempfile::NamedTempFile;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{debug, warn};
+
+// The cold tier of a storage: a directory (e.g. an object storage bucket mounted with rclone or s3fs) the values not
+// accessed for a while are offloaded to, an empty stub file being left in place of each one. A stub is rehydrated
+// (its value copied back) on its first read.
+pub(crate) struct ColdTier {
+    dir: PathBuf,
+    // the duration without access after which a value is offloaded
+    after: Duration,
+    // for a stub not to be rehydrated twice at once
+    rehydration: Mutex<()>,
+    offloaded: AtomicU64,
+    rehydrated: AtomicU64,
+}
+
+impl ColdTier {
+    pub(crate) fn new(dir: PathBuf, after_days: u64) -> Self {
+        ColdTier {
+            dir,
+            after: Duration::from_secs(after_days * 24 * 3600),
+            rehydration: Mutex::new(()),
+            offloaded: AtomicU64::new(0),
+            rehydrated: AtomicU64::new(0),
+        }
+    }
+
+    // Return true if a file wasn't read nor written for `after` (its access time being only updated once a day on
+    // most file-systems, or never with `noatime`)
+    pub(crate) fn is_cold(&self, m: &Metadata) -> bool {
+        let accessed = m.accessed().ok().max(m.modified().ok());
+        accessed
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .is_some_and(|elapsed| elapsed > self.after)
+    }
+
+    // Copy a file to the cold tier at `relpath`, synced, and renamed once complete
+    pub(crate) fn store(&self, file: &Path, relpath: &str) -> io::Result<()> {
+        let target = self.dir.join(relpath);
+        copy_complete(file, &target)
+    }
+
+    // Copy a value back from the cold tier in place of its stub, returning its size
+    pub(crate) fn load(&self, relpath: &str, stub: &Path) -> io::Result<u64> {
+        copy_complete(&self.dir.join(relpath), stub)?;
+        Ok(metadata(stub)?.len())
+    }
+
+    // Remove a value from the cold tier (once rehydrated, or overwritten), and its directories left empty
+    pub(crate) fn remove(&self, relpath: &str) {
+        let target = self.dir.join(relpath);
+        if let Err(e) = remove_file(&target) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!("Failed to remove {:?} from the cold tier: {}", target, e);
+            }
+            return;
+        }
+        let mut parent = target.parent();
+        while let Some(p) = parent.filter(|p| *p != self.dir && p.starts_with(&self.dir)) {
+            if remove_dir(p).is_err() {
+                break;
+            }
+            parent = p.parent();
+        }
+    }
+
+    pub(crate) async fn lock_rehydration(&self) -> MutexGuard<'_, ()> {
+        self.rehydration.lock().await
+    }
+
+    pub(crate) fn count_offloaded(&self) {
+        self.offloaded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn count_rehydrated(&self) {
+        self.rehydrated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn status_json(&self, stubs: Option<usize>) -> serde_json::Value {
+        serde_json::json!({
+            "dir": self.dir,
+            "stubs": stubs,
+            "offloaded": self.offloaded.load(Ordering::Relaxed),
+            "rehydrated": self.rehydrated.load(Ordering::Relaxed),
+        })
+    }
+}
+
+// Copy a file into a temporary file next to the target, synced, then renamed as the target: the target is never
+// partial, even if the copy is interrupted (e.g. the mount of the cold tier being lost)
+fn copy_complete(from: &Path, to: &Path) -> io::Result<()> {
+    let parent = to
+        .parent()
+        .ok_or_else(|| io::Error::other(format!("{to:?} has no parent directory")))?;
+    DirBuilder::new().recursive(true).create(parent)?;
+    let tmp = NamedTempFile::new_in(parent)?;
+    copy(from, tmp.path())?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(to).map_err(|e| e.error)?;
+    debug!("Copied {:?} to {:?}", from, to);
+    Ok(())
+}
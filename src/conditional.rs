@@ -0,0 +1,83 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use zenoh::{
+    bytes::{Encoding, ZBytes},
+    query::Parameters,
+    time::{Timestamp, NTP64},
+    Result as ZResult,
+};
+use zenoh_backend_traits::StoredData;
+
+use crate::{parse_time, NOT_MODIFIED_SCHEMA, PARAM_IF_MODIFIED_SINCE, PARAM_IF_NONE_MATCH};
+
+// The conditions of a conditional GET, as in HTTP: the value is only replied if it differs from the one the querier
+// already has (e.g. in a browser's cache). The validator (ETag) of a value is its timestamp.
+pub(crate) struct Conditions {
+    // the validators of the values the querier has ("*" for any)
+    none_match: Vec<String>,
+    modified_since: Option<NTP64>,
+}
+
+impl Conditions {
+    // The conditions of a GET, if any
+    pub(crate) fn from_parameters(parameters: &Parameters) -> ZResult<Option<Self>> {
+        let none_match: Vec<String> = parameters
+            .get(PARAM_IF_NONE_MATCH)
+            .map(|etags| etags.split(',').filter_map(unquote).collect())
+            .unwrap_or_default();
+        let modified_since = match parameters.get(PARAM_IF_MODIFIED_SINCE) {
+            Some(since) => Some(parse_time(PARAM_IF_MODIFIED_SINCE, since)?),
+            None => None,
+        };
+        if none_match.is_empty() && modified_since.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Conditions {
+            none_match,
+            modified_since,
+        }))
+    }
+
+    // Return true if the querier already has the value with this timestamp
+    pub(crate) fn not_modified(&self, timestamp: &Timestamp) -> bool {
+        // (as in HTTP, the modification time is ignored if validators are given)
+        if !self.none_match.is_empty() {
+            let etag = timestamp.to_string();
+            return self.none_match.iter().any(|e| e == "*" || *e == etag);
+        }
+        self.modified_since
+            .is_some_and(|since| *timestamp.get_time() <= since)
+    }
+
+    // The reply to a GET whose querier already has the value: empty, with the value's timestamp (distinguishing it
+    // from a missing value)
+    pub(crate) fn not_modified_reply(timestamp: Timestamp) -> StoredData {
+        StoredData {
+            payload: ZBytes::default(),
+            encoding: Encoding::ZENOH_BYTES.with_schema(NOT_MODIFIED_SCHEMA),
+            timestamp,
+        }
+    }
+}
+
+// An entity tag as given in an HTTP If-None-Match header (`"<etag>"` or `W/"<etag>"`), or bare
+fn unquote(etag: &str) -> Option<String> {
+    let etag = etag.trim();
+    let etag = etag.strip_prefix("W/").unwrap_or(etag);
+    let etag = etag
+        .strip_prefix('"')
+        .and_then(|e| e.strip_suffix('"'))
+        .unwrap_or(etag);
+    (!etag.is_empty()).then(|| etag.to_string())
+}
@@ -0,0 +1,828 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use tracing::{info, warn};
+use zenoh::{internal::bail, Result as ZResult};
+
+use crate::{
+    files_mgt::OnConflict, maintenance::Job, timestamp_skew::SkewPolicy,
+    write_queue::OverloadPolicy, *,
+};
+
+// The type of a property's value
+pub(crate) enum PropertyKind {
+    Bool,
+    String,
+    PositiveInt,
+    // a string among the listed ones
+    Enum(&'static [&'static str]),
+    // an object with the cron-like schedule of each maintenance job
+    Schedules,
+    // an object with string values
+    StringMap,
+    // an array of strings
+    StringArray,
+    // an object with the rates and latency of the injected faults
+    FaultInjection,
+    // an array of payload transforms: their type, or an object with their type and options
+    Transforms,
+    // an object with the configuration of each tenant
+    Tenants,
+    // an object with the policy of each key prefix
+    Policies,
+    // an object with the allowed operations per key expression
+    Acl,
+    // a host command: a string (the program) or an array of strings (the program and its first arguments)
+    Command,
+    // an array of interceptors: objects with their type and options
+    Interceptors,
+}
+
+// A property accepted in the volume-specific configuration of a storage
+pub(crate) struct StorageProperty {
+    pub(crate) name: &'static str,
+    pub(crate) kind: PropertyKind,
+    // the value used if the property is not set, if any (in JSON)
+    pub(crate) default: Option<&'static str>,
+    pub(crate) description: &'static str,
+}
+
+// All the properties accepted in the volume-specific configuration of a storage
+pub(crate) const STORAGE_PROPERTIES: &[StorageProperty] = &[
+    StorageProperty {
+        name: PROP_STORAGE_DIR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The directory (relative to ${ZENOH_BACKEND_FS_ROOT}) used to store the data",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_READ_ONLY,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the storage only answers to GET queries and never writes any file",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_READ_ONLY_BEHAVIOR,
+        kind: PropertyKind::Enum(&["error", "silently_ignore", "log_once"]),
+        default: Some(r#""error""#),
+        description: "What to do on a PUT or DELETE received by a read-only storage",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_WORM,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the existing entries can never be overwritten nor deleted (write-once mode)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_SET_IMMUTABLE,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the immutable attribute is set on the written files (Linux only, requires the CAP_LINUX_IMMUTABLE capability)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ON_CLOSURE,
+        kind: PropertyKind::Enum(&["do_nothing", "delete_all"]),
+        default: Some(r#""do_nothing""#),
+        description: "The strategy applied to the stored files when the storage is removed",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ON_CONFLICT,
+        kind: PropertyKind::Enum(OnConflict::ALL),
+        default: Some(r#""rename""#),
+        description: "What to do on a PUT conflicting with an existing file or directory (e.g. on `a/b` while `a` is a file): rename the file, reject the PUT, or keep the most recent and delete the other",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_EMPTY_PAYLOAD,
+        kind: PropertyKind::Enum(&["store", "reject", "delete"]),
+        default: Some(r#""store""#),
+        description: "What to do on a PUT with an empty payload: store an empty file, reject it, or process it as a DELETE",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_SKEWED_TIMESTAMPS,
+        kind: PropertyKind::Enum(SkewPolicy::ALL),
+        default: Some(r#""accept""#),
+        description: "What to do on a PUT or DELETE with a timestamp too far in the future or in the past: accept it, set it to the current time, or reject it",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAX_FUTURE_SKEW_SECS,
+        kind: PropertyKind::PositiveInt,
+        default: Some("60"),
+        description: "The maximum number of seconds a timestamp can be ahead of the current time, before being skewed",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAX_PAST_SKEW_SECS,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The maximum number of seconds a timestamp can be behind the current time, before being skewed (no limit if not set)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_OBFUSCATE_NAMES,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the files are stored under salted hashes of the keys, not revealing the keys in the storage's directory",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_OBFUSCATION_SALT,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The salt of the hashed file names, if `obfuscate_names` is true (a random one is generated by default)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_CANONICALIZE_KEYS,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the keys are canonicalized before being mapped to files, for equivalent keys to map to the same file",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ZENOH_WRITE_ONLY,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the directory is only written by the storage: the GETs of unknown keys are answered from the data-info (or the entries index), without accessing the files",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ENTRIES_INDEX,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the keys and timestamps of the entries are indexed in memory, for their listing not to walk the directory",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_DIGEST_INTERVAL_SECS,
+        kind: PropertyKind::PositiveInt,
+        default: Some("10"),
+        description: "The duration in seconds of the time intervals of the replication digest",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_CONTROL_PREFIX,
+        kind: PropertyKind::String,
+        default: None,
+        description: "If set, a PUT on `<control_prefix>/<op>` runs a control operation instead of being stored: a maintenance job (compaction, gc, retention, archival, normalize or offload), migrate, move, copy, delete, import, manifest, sync, patch, log or scrub",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_EXPORT_DIR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The absolute path of the directory of the host the manifest control operation writes to, the operation being refused if not set",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_IMPORT_ROOTS,
+        kind: PropertyKind::StringArray,
+        default: None,
+        description: "The absolute paths of the directories of the host the directories and tarballs imported by the import control operation must be under, the operation being refused if not set",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAX_UNPACK_SIZE,
+        kind: PropertyKind::PositiveInt,
+        default: Some("268435456"),
+        description: "The maximum size in bytes of the files unpacked from the archive of a PUT with `_unpack=true` (and of a tarball once decompressed)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAX_PATCHED_SIZE,
+        kind: PropertyKind::PositiveInt,
+        default: Some("67108864"),
+        description: "The maximum size in bytes of a value built by the patch control operation, a larger one being rejected",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_STRIPES,
+        kind: PropertyKind::StringArray,
+        default: None,
+        description: "The absolute paths of other directories (e.g. on other disks) the files are distributed across, with the storage's directory, by a hash of their path. Can't be changed once files are stored",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MIRROR_DIR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The absolute path of a directory (local or mounted) each written, renamed or deleted file is replicated to in background",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_FAILOVER_DIR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The absolute path of a replica directory (e.g. the mirror_dir of the storage) the GETs are answered from while the storage's directories are unavailable",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_LOWER_DIR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The absolute path of a read-only lower layer (e.g. a shipped dataset) the entries missing in the storage's directory are read from. The deletions are recorded as whiteouts hiding its entries",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_FOLLOW_LINK,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the storage follows the symbolic links",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAX_LINK_DEPTH,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "With follow_links, the maximum number of symbolic links followed in a file's path (the deeper entries being skipped). Unbounded by default",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_KEEP_MIME,
+        kind: PropertyKind::Bool,
+        default: Some("true"),
+        description: "If true, the mime-type guessed from a file's extension is kept as encoding schema",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_DEFAULT_ENCODING,
+        kind: PropertyKind::String,
+        default: Some(r#""application/octet-stream""#),
+        description: "The encoding (with an optional schema, e.g. \"application/cdr;MyType\") of the files created without zenoh, if not deduced from their extension",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ENCODING_SCHEMAS,
+        kind: PropertyKind::StringMap,
+        default: None,
+        description: "The encoding schema of the files created without zenoh, per directory (relative to the storage's directory)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ENCODING_OVERRIDES,
+        kind: PropertyKind::StringMap,
+        default: None,
+        description: "The encoding of the files created without zenoh, per key prefix (relative to strip_prefix), whatever their extension",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_TIMESTAMP_ID,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The id (hexadecimal) of the timestamps of the files created without zenoh, if not the storage's random one",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_PAYLOAD_TRANSFORMS,
+        kind: PropertyKind::Transforms,
+        default: None,
+        description: "The transforms (gzip compression, aes-256-gcm encryption) applied in order to the payloads before they are written, and in the reverse order when they are read",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ENVELOPE,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the values are written in an envelope with their encoding, timestamp and checksum, for the files to be recoverable without the data-info database",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_PASSTHROUGH,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the storage never renames nor adds files in its directory: the conflicting PUTs are rejected, and the data-info database is kept out of the directory",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_VERIFY_ON_START,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the data-info database is checked against the files at storage creation",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_LAZY_INIT,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the scans of the files at storage creation (quotas accounting, verify_on_start, entries index) run in background",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAX_CONCURRENT_OPS,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The maximum number of file operations performed in parallel (unbounded if not set)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_WRITE_QUEUE_DEPTH,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The maximum number of writes waiting or in progress (unbounded if not set)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_WRITE_QUEUE_OVERLOAD,
+        kind: PropertyKind::Enum(OverloadPolicy::ALL),
+        default: Some(r#""block""#),
+        description: "What to do with a write when the write queue is full: wait for room, drop the oldest write not started yet, or reject it",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_DEDICATED_RUNTIME_THREADS,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "If set, the operations and background tasks of the storage run on a runtime of its own, with this number of threads",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_OPEN_FILES_CACHE_SIZE,
+        kind: PropertyKind::PositiveInt,
+        default: Some("0"),
+        description: "The number of recently read files for which the handle is kept open",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_READ_AHEAD,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "If set, the number of files read in advance when the keys of a numbered series are read sequentially",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_NFS_SAFE,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the storage operates safely on a network file-system (NFS, SMB): no file kept open, synced writes, retries on transient errors",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_RETRY_ATTEMPTS,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The number of retries of the file reads and writes and of the data-info operations failing with a transient error (3 with nfs_safe, else none)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_RETRY_BACKOFF_MS,
+        kind: PropertyKind::PositiveInt,
+        default: Some("50"),
+        description: "The delay in milliseconds before the first retry, doubled before each next one",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_RETRY_ERRORS,
+        kind: PropertyKind::StringArray,
+        default: None,
+        description: "The classes of the errors which are retried (interrupted, timed_out, stale, busy). All by default",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_VERIFY_WRITES,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, each written file is synced and read back, the write failing if its checksum differs",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_DRY_RUN,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the PUTs and DELETEs are checked (validation, conflicts, quotas) and logged, but the disk isn't modified",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_LOG_LEVEL,
+        kind: PropertyKind::Enum(storage_log::LEVELS),
+        default: None,
+        description: "The maximum level of the storage's logs, lower or higher than zenohd's one (which applies if not set)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_TRACE_OPS,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, each PUT, DELETE and GET is logged with its duration and result, at the info level",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_DATA_INFO_CACHE_SIZE,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The number of data-info records cached in memory in front of the database (no cache if not set)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_DIR_LISTINGS_CACHE_SIZE,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The number of directory listings cached in memory for the walks of the storage's directory (no cache if not set)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_BLOOM_FILTER,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, a Bloom filter of the keys with data-info is kept in memory, the lookups of the others not querying the database",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_WATERMARK_BY_PREFIX,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, the most recent timestamp of the values stored under each top-level prefix is tracked, besides the storage's one",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_SHM_POOL_SIZE,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The size in bytes of the shared-memory pool used to serve the large files (requires the shared-memory feature)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_SHM_THRESHOLD,
+        kind: PropertyKind::PositiveInt,
+        default: Some("65536"),
+        description: "The minimal size in bytes of a file to be served through shared-memory",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_FAULT_INJECTION,
+        kind: PropertyKind::FaultInjection,
+        default: None,
+        description: "The IO errors, latency and partial writes injected into the file operations, for tests (requires the fault-injection feature)",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_EXPOSE_FS_PATH,
+        kind: PropertyKind::Bool,
+        default: Some("false"),
+        description: "If true, a GET with the _fs_path parameter is replied with the path of the file",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ARCHIVE_AFTER_DAYS,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The age in days after which the entries are moved into compressed archive bundles",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_RETENTION_DAYS,
+        kind: PropertyKind::PositiveInt,
+        default: None,
+        description: "The age in days after which the entries are deleted",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_COLD_TIER_DIR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "The absolute path of a cold tier directory (e.g. a mounted object storage bucket) the entries not accessed for a while are offloaded to by the offload maintenance job, leaving empty stubs rehydrated on read",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_COLD_AFTER_DAYS,
+        kind: PropertyKind::PositiveInt,
+        default: Some("30"),
+        description: "With cold_tier_dir, the number of days without access after which an entry is offloaded",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_TENANTS,
+        kind: PropertyKind::Tenants,
+        default: None,
+        description: "The tenants of the storage: the first chunk of a key is its tenant's name and sub-directory",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_POLICIES,
+        kind: PropertyKind::Policies,
+        default: None,
+        description: "The policies (payload transforms, retention, size cap) overriding the storage's settings for the keys under each prefix",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ACL,
+        kind: PropertyKind::Acl,
+        default: None,
+        description: "The operations (read, write, delete) allowed on the keys matching each key expression",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ON_PUT,
+        kind: PropertyKind::Command,
+        default: None,
+        description: "A host command run after each PUT, with the key and the file path as last arguments",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_ON_DELETE,
+        kind: PropertyKind::Command,
+        default: None,
+        description: "A host command run after each DELETE, with the key and the file path as last arguments",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_INTERCEPTORS,
+        kind: PropertyKind::Interceptors,
+        default: None,
+        description: "The interceptors of the PUTs, DELETEs and GETs (audit, validate, rewrite), called in order before each operation and in the reverse order after it",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_NOTIFICATION_KEY_EXPR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "A key expression (without wildcard) on which a JSON notification is published for each PUT and DELETE",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_NOTIFICATION_CONNECT,
+        kind: PropertyKind::StringArray,
+        default: None,
+        description: "The endpoints the session publishing the notifications and statistics connects to, required if `notification_key_expr` or `stats_key_expr` is set",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_STATS_KEY_EXPR,
+        kind: PropertyKind::String,
+        default: None,
+        description: "A key expression (without wildcard) on which the storage's statistics are published periodically, in JSON",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_STATS_INTERVAL_SECS,
+        kind: PropertyKind::PositiveInt,
+        default: Some("60"),
+        description: "The interval in seconds between the publications of the statistics",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_MAINTENANCE,
+        kind: PropertyKind::Schedules,
+        default: None,
+        description: "The cron-like schedules (in UTC) of the maintenance jobs",
+    },
+    StorageProperty {
+        name: PROP_STORAGE_STRICT,
+        kind: PropertyKind::Bool,
+        default: Some("true"),
+        description: "If true, an unknown property in the configuration is an error",
+    },
+];
+
+// The deprecated names of properties, with the current name they are mapped to.
+// An entry is to be added here whenever a property is renamed, so the existing configurations keep working.
+pub(crate) const DEPRECATED_PROPERTIES: &[(&str, &str)] = &[
+    ("follow_link", PROP_STORAGE_FOLLOW_LINK),
+    ("keep_mime", PROP_STORAGE_KEEP_MIME),
+    ("readonly", PROP_STORAGE_READ_ONLY),
+];
+
+// Rename the deprecated properties of a storage's configuration to their current names,
+// warning about each of them. Setting both a deprecated name and its current name is an error.
+pub(crate) fn resolve_deprecated_properties(
+    storage: &str,
+    volume_cfg: &mut serde_json::Map<String, serde_json::Value>,
+) -> ZResult<()> {
+    for (old, new) in DEPRECATED_PROPERTIES {
+        if let Some(value) = volume_cfg.remove(*old) {
+            if volume_cfg.contains_key(*new) {
+                bail!(
+                    r#"File System Storage {}: both "{}" and its deprecated alias "{}" are set"#,
+                    storage,
+                    new,
+                    old
+                )
+            }
+            warn!(
+                r#"File System Storage {}: property "{}" is deprecated, use "{}" instead"#,
+                storage, old, new
+            );
+            volume_cfg.insert((*new).into(), value);
+        }
+    }
+    Ok(())
+}
+
+// The properties which can't be set in the default storage properties of a volume, each storage needing its own
+// directories
+const PER_STORAGE_PROPERTIES: &[&str] = &[
+    PROP_STORAGE_DIR,
+    PROP_STORAGE_STRIPES,
+    PROP_STORAGE_MIRROR_DIR,
+    PROP_STORAGE_FAILOVER_DIR,
+    PROP_STORAGE_COLD_TIER_DIR,
+];
+
+// Check the default storage properties of a volume (with their deprecated aliases resolved): all of them must be
+// known, whatever the `strict` property of the storages
+pub(crate) fn check_storage_defaults(
+    defaults: &mut serde_json::Map<String, serde_json::Value>,
+) -> ZResult<()> {
+    resolve_deprecated_properties(PROP_BACKEND_STORAGE_DEFAULTS, defaults)?;
+    for key in defaults.keys() {
+        if PER_STORAGE_PROPERTIES.contains(&key.as_str()) {
+            bail!(
+                r#"Property "{}" can't be set in "{}": it must be specific to each storage"#,
+                key,
+                PROP_BACKEND_STORAGE_DEFAULTS
+            )
+        }
+        if !STORAGE_PROPERTIES.iter().any(|p| p.name == key) {
+            let hint = match closest_property(key) {
+                Some(name) => format!(r#" (did you mean "{name}"?)"#),
+                None => String::new(),
+            };
+            bail!(
+                r#"Unknown property "{}" in "{}"{}"#,
+                key,
+                PROP_BACKEND_STORAGE_DEFAULTS,
+                hint
+            )
+        }
+    }
+    Ok(())
+}
+
+// Keys which may be left by zenoh in the volume-specific configuration
+const IGNORED_KEYS: &[&str] = &["id"];
+
+// The JSON Schema of an array of payload transforms
+fn transforms_schema() -> serde_json::Value {
+    serde_json::json!({
+        "type": "array",
+        "items": {
+            "oneOf": [
+                { "type": "string", "enum": [GzipTransform::NAME, ZstdSeekableTransform::NAME, AesGcmTransform::NAME] },
+                {
+                    "type": "object",
+                    "properties": {
+                        "type": { "type": "string", "enum": [GzipTransform::NAME, ZstdSeekableTransform::NAME, AesGcmTransform::NAME] },
+                        "level": { "type": "integer", "minimum": 0, "maximum": 22 },
+                        "frame_size": { "type": "integer", "minimum": 1 },
+                        "key_file": { "type": "string" }
+                    },
+                    "required": ["type"],
+                    "additionalProperties": false
+                }
+            ]
+        }
+    })
+}
+
+// The JSON Schema (draft 2020-12) of the volume-specific configuration of a storage
+pub(crate) fn storage_config_schema() -> serde_json::Value {
+    let mut properties = serde_json::Map::new();
+    properties.insert(
+        "id".into(),
+        serde_json::json!({
+            "type": "string",
+            "description": "The identifier of the volume (i.e. of the backend)"
+        }),
+    );
+    for p in STORAGE_PROPERTIES {
+        let mut schema = match p.kind {
+            PropertyKind::Bool => serde_json::json!({ "type": "boolean" }),
+            PropertyKind::String => serde_json::json!({ "type": "string" }),
+            PropertyKind::PositiveInt => serde_json::json!({ "type": "integer", "minimum": 1 }),
+            PropertyKind::Enum(values) => serde_json::json!({ "type": "string", "enum": values }),
+            PropertyKind::StringMap => serde_json::json!({
+                "type": "object",
+                "additionalProperties": { "type": "string" }
+            }),
+            PropertyKind::Tenants => serde_json::json!({
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        PROP_TENANT_MAX_TOTAL_SIZE: { "type": "integer", "minimum": 1 },
+                        PROP_TENANT_RETENTION_DAYS: { "type": "integer", "minimum": 1 }
+                    },
+                    "additionalProperties": false
+                }
+            }),
+            PropertyKind::Policies => serde_json::json!({
+                "type": "object",
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": {
+                        PROP_STORAGE_PAYLOAD_TRANSFORMS: transforms_schema(),
+                        PROP_POLICY_MAX_TOTAL_SIZE: { "type": "integer", "minimum": 1 },
+                        PROP_POLICY_RETENTION_DAYS: { "type": "integer", "minimum": 1 }
+                    },
+                    "additionalProperties": false
+                }
+            }),
+            PropertyKind::Acl => serde_json::json!({
+                "type": "object",
+                "additionalProperties": {
+                    "type": "array",
+                    "items": { "type": "string", "enum": ["read", "write", "delete"] }
+                }
+            }),
+            PropertyKind::FaultInjection => serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "io_error_rate": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "partial_write_rate": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "latency_ms": { "type": "integer", "minimum": 0 },
+                    "seed": { "type": "integer", "minimum": 0 }
+                },
+                "additionalProperties": false
+            }),
+            PropertyKind::Transforms => transforms_schema(),
+            PropertyKind::StringArray => serde_json::json!({
+                "type": "array",
+                "items": { "type": "string" },
+                "minItems": 1
+            }),
+            PropertyKind::Interceptors => serde_json::json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "type": {
+                            "type": "string",
+                            "enum": [AuditInterceptor::TYPE, ValidateInterceptor::TYPE, RewriteInterceptor::TYPE]
+                        },
+                        "file": { "type": "string" },
+                        "key_regex": { "type": "string" },
+                        "max_payload_size": { "type": "integer", "minimum": 0 },
+                        "encodings": { "type": "array", "items": { "type": "string" } },
+                        "from": { "type": "string" },
+                        "to": { "type": "string" }
+                    },
+                    "required": ["type"],
+                    "additionalProperties": false
+                }
+            }),
+            PropertyKind::Command => serde_json::json!({
+                "oneOf": [
+                    { "type": "string", "minLength": 1 },
+                    { "type": "array", "items": { "type": "string", "minLength": 1 }, "minItems": 1 }
+                ]
+            }),
+            PropertyKind::Schedules => {
+                let jobs: serde_json::Map<String, serde_json::Value> = Job::ALL
+                    .iter()
+                    .map(|job| (job.name().into(), serde_json::json!({ "type": "string" })))
+                    .collect();
+                serde_json::json!({
+                    "type": "object",
+                    "properties": jobs,
+                    "additionalProperties": false
+                })
+            }
+        };
+        schema["description"] = p.description.into();
+        if let Some(default) = p.default {
+            schema["default"] = serde_json::from_str(default).unwrap_or(default.into());
+        }
+        properties.insert(p.name.into(), schema);
+    }
+    for (old, new) in DEPRECATED_PROPERTIES {
+        properties.insert(
+            (*old).into(),
+            serde_json::json!({
+                "deprecated": true,
+                "description": format!("Deprecated alias of `{new}`")
+            }),
+        );
+    }
+    serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "File System Storage configuration",
+        "type": "object",
+        "properties": properties,
+        "required": [PROP_STORAGE_DIR],
+        "additionalProperties": false
+    })
+}
+
+// Check that all the properties of a storage's configuration are known.
+// An unknown property is an error in strict mode, and only a warning otherwise.
+// The properties which are not set and have a default value are reported.
+pub(crate) fn check_properties(
+    storage: &str,
+    volume_cfg: &serde_json::Map<String, serde_json::Value>,
+    strict: bool,
+) -> ZResult<()> {
+    for key in volume_cfg.keys() {
+        if IGNORED_KEYS.contains(&key.as_str()) || STORAGE_PROPERTIES.iter().any(|p| p.name == key)
+        {
+            continue;
+        }
+        let hint = match closest_property(key) {
+            Some(name) => format!(r#" (did you mean "{name}"?)"#),
+            None => String::new(),
+        };
+        if strict {
+            bail!(
+                r#"Unknown property "{}" for File System Storage {}{}. Set "{}": false to ignore unknown properties"#,
+                key,
+                storage,
+                hint,
+                PROP_STORAGE_STRICT
+            )
+        }
+        warn!(
+            r#"Unknown property "{}" for File System Storage {}{} - ignored"#,
+            key, storage, hint
+        );
+    }
+    let defaults: Vec<String> = STORAGE_PROPERTIES
+        .iter()
+        .filter(|p| !volume_cfg.contains_key(p.name))
+        .filter_map(|p| p.default.map(|d| format!("{}={}", p.name, d)))
+        .collect();
+    if !defaults.is_empty() {
+        info!(
+            "File System Storage {} uses default values: {}",
+            storage,
+            defaults.join(", ")
+        );
+    }
+    Ok(())
+}
+
+// The known property the closest to an unknown one, if close enough to be a typo
+fn closest_property(key: &str) -> Option<&'static str> {
+    STORAGE_PROPERTIES
+        .iter()
+        .map(|p| (p.name, edit_distance(key, p.name)))
+        .filter(|(_, d)| *d <= 2)
+        .min_by_key(|(_, d)| *d)
+        .map(|(name, _)| name)
+}
+
+// Levenshtein distance, counting a transposition of 2 adjacent characters as 1 edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
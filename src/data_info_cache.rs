@@ -0,0 +1,89 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use zenoh::{bytes::Encoding, time::Timestamp};
+
+// A bounded in-memory cache of the data-info records, in front of the database, for the reads not to take the
+// database's lock. It's written through by the DataInfoMgr on each change of a record, so it also caches the files
+// without data-info (as None).
+pub(crate) struct DataInfoCache {
+    capacity: usize,
+    records: Mutex<HashMap<String, Option<(Encoding, Timestamp)>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl DataInfoCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        DataInfoCache {
+            capacity,
+            records: Mutex::new(HashMap::with_capacity(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // The cached record of a file: None if not cached, Some(None) if the file is known to have no data-info
+    pub(crate) fn get(&self, key: &str) -> Option<Option<(Encoding, Timestamp)>> {
+        let record = self.records.lock().unwrap().get(key).cloned();
+        match record {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        record
+    }
+
+    // Cache the record of a file, evicting another one if the cache is full
+    pub(crate) fn put(&self, key: &str, record: Option<(Encoding, Timestamp)>) {
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= self.capacity && !records.contains_key(key) {
+            // evict an arbitrary record (the iteration order of a HashMap being random, it's a random eviction)
+            if let Some(evicted) = records.keys().next().cloned() {
+                records.remove(&evicted);
+            }
+        }
+        records.insert(key.to_string(), record);
+    }
+
+    pub(crate) fn remove(&self, key: &str) {
+        self.records.lock().unwrap().remove(key);
+    }
+
+    pub(crate) fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    pub(crate) fn stats(&self) -> serde_json::Value {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let hit_rate = match hits + misses {
+            0 => 0.0,
+            total => hits as f64 / total as f64,
+        };
+        serde_json::json!({
+            "capacity": self.capacity,
+            "size": self.records.lock().unwrap().len(),
+            "hits": hits,
+            "misses": misses,
+            "hit_rate": hit_rate,
+        })
+    }
+}
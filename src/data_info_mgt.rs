@@ -12,14 +12,14 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 use std::{
+    collections::HashMap,
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
-use rocksdb::DB;
 use tokio::sync::Mutex;
-use tracing::trace;
+use tracing::{trace, warn};
 use zenoh::{
     bytes::{Encoding, ZBytes},
     internal::{bail, buffers::ZSlice, zerror},
@@ -28,6 +28,15 @@ use zenoh::{
 };
 use zenoh_ext::{z_deserialize, z_serialize};
 
+use crate::{
+    data_info_cache::DataInfoCache,
+    files_mgt::CONFLICT_SUFFIX,
+    keys_filter::KeysFilter,
+    kv_store::{KvBatch, KvStore},
+    progress::InitProgress,
+    retry::RetryPolicy,
+};
+
 lazy_static::lazy_static! {
     static ref GC_PERIOD: Duration = Duration::new(30, 0);
     static ref MIN_DELAY_BEFORE_REMOVAL: NTP64 = NTP64::from(Duration::new(5, 0));
@@ -72,37 +81,140 @@ impl DataInfo {
 
 pub(crate) struct DataInfoMgr {
     // Note: rocksdb isn't thread-safe. See https://github.com/rust-rocksdb/rust-rocksdb/issues/404
-    db: Arc<Mutex<DB>>,
+    db: Arc<Mutex<KvStore>>,
+    // the cache of the data-info records, if enabled (updated while holding the database's lock)
+    cache: Option<DataInfoCache>,
+    // the Bloom filter of the keys of the data-info records, if enabled (updated while holding the database's lock)
+    keys_filter: Option<KeysFilter>,
 }
 
 impl DataInfoMgr {
-    // Name of the RocksDB directory for the data-info database
+    // Name of the directory of the data-info database (RocksDB, or sidecar log)
     pub(crate) const DB_FILENAME: &'static str = ".zenoh_datainfo";
 
-    pub(crate) async fn new(base_dir: &Path) -> ZResult<Self> {
-        let mut backup_file = PathBuf::from(base_dir);
-        backup_file.push(DataInfoMgr::DB_FILENAME);
+    // Prefix of the records marking the files moved into an archive bundle (the value is the bundle's day).
+    // Those records can't be confused with data-info as their keys are absolute paths.
+    const ARCHIVED_PREFIX: &'static str = "@archived/";
+
+    // Prefix of the records giving the key chunk of each hashed file name (when names are obfuscated)
+    const NAME_PREFIX: &'static str = "@name/";
+
+    // Key of the record with the salt of the hashed file names
+    const SALT_KEY: &'static str = "@salt";
+
+    // Key of the record with the id of the timestamps of the files without data-info
+    const TIMESTAMP_ID_KEY: &'static str = "@timestamp_id";
+
+    // Prefix of the records registering the conflict files (renamed with CONFLICT_SUFFIX) which have data-info.
+    // They're updated with the data-info of those files.
+    const CONFLICT_PREFIX: &'static str = "@conflict/";
+
+    // Key of the record with the directories of the stripes of the storage, if striped
+    const STRIPES_KEY: &'static str = "@stripes";
+
+    // Key of the record marking that the conflict files were registered (for the databases created before the registry)
+    const CONFLICTS_REGISTERED_KEY: &'static str = "@conflicts_registered";
 
-        let db = DB::open_default(&backup_file).map_err(|e| {
+    // Prefix of the records of the whiteouts: the entries deleted from a storage with a lower layer, for the lower
+    // layer's values to be hidden (the value is empty)
+    const WHITEOUT_PREFIX: &'static str = "@whiteout/";
+
+    // Prefix of the records marking the files offloaded to the cold tier, left as empty stubs (the value is the path
+    // of the offloaded value in the cold tier). The data-info of those files are kept.
+    const COLD_PREFIX: &'static str = "@cold/";
+
+    // Open the database in `backup_file` (usually `DB_FILENAME` in the storage's directory)
+    pub(crate) async fn new(backup_file: &Path) -> ZResult<Self> {
+        let mut db = KvStore::open(backup_file, merge_timestamp).map_err(|e| {
             zerror!(
                 "Failed to open data-info database from {:?}: {}",
                 backup_file,
                 e
             )
         })?;
+        register_conflicts(&mut db)
+            .map_err(|e| zerror!("Failed to register the conflict files: {}", e))?;
         let db = Arc::new(Mutex::new(db));
 
-        Ok(DataInfoMgr { db })
+        Ok(DataInfoMgr {
+            db,
+            cache: None,
+            keys_filter: None,
+        })
+    }
+
+    // Cache up to `capacity` data-info records in memory
+    pub(crate) fn enable_cache(&mut self, capacity: usize) {
+        self.cache = Some(DataInfoCache::new(capacity));
+    }
+
+    // Keep a Bloom filter of the keys of the data-info records in memory, built from the database
+    pub(crate) async fn enable_keys_filter(&mut self) -> ZResult<()> {
+        let keys = data_info_keys(&self.db.lock().await)?;
+        self.keys_filter = Some(KeysFilter::new(&keys));
+        Ok(())
+    }
+
+    fn filter_insert(&self, key: &str) {
+        if let Some(keys_filter) = &self.keys_filter {
+            keys_filter.insert(key);
+        }
+    }
+
+    fn cache_put(&self, key: &str, record: Option<(Encoding, Timestamp)>) {
+        if let Some(cache) = &self.cache {
+            cache.put(key, record);
+        }
+    }
+
+    fn cache_remove(&self, key: &str) {
+        if let Some(cache) = &self.cache {
+            cache.remove(key);
+        }
+    }
+
+    // Persist the data-info written so far (e.g. before the storage is dropped)
+    pub(crate) async fn flush(&self) -> ZResult<()> {
+        self.db
+            .lock()
+            .await
+            .flush()
+            .map_err(|err| zerror!("Failed to flush data-info database: {}", err).into())
     }
 
     pub(crate) async fn close(&self) -> ZResult<()> {
-        let db = self.db.lock().await;
-        // Flush before to close
-        db.flush()
-            .and_then(|()| DB::destroy(&rocksdb::Options::default(), db.path()))
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        self.db
+            .lock()
+            .await
+            .destroy()
             .map_err(|err| zerror!("Failed to close data-info database: {}", err).into())
     }
 
+    // The same as flush() and close(), without waiting for the database's lock (e.g. in a destructor)
+    pub(crate) fn flush_now(&self) -> ZResult<()> {
+        match self.db.try_lock() {
+            Ok(mut db) => db
+                .flush()
+                .map_err(|err| zerror!("Failed to flush data-info database: {}", err).into()),
+            Err(_) => bail!("Failed to flush data-info database: it's in use"),
+        }
+    }
+
+    pub(crate) fn close_now(&self) -> ZResult<()> {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+        match self.db.try_lock() {
+            Ok(mut db) => db
+                .destroy()
+                .map_err(|err| zerror!("Failed to close data-info database: {}", err).into()),
+            Err(_) => bail!("Failed to close data-info database: it's in use"),
+        }
+    }
+
     pub(crate) async fn put_data_info<P: AsRef<Path>>(
         &self,
         file: P,
@@ -112,24 +224,38 @@ impl DataInfoMgr {
         let key = file.as_ref().to_string_lossy();
         trace!("Put data-info for {}", key);
 
-        let data_info = DataInfo {
-            timestamp: *timestamp,
-            encoding,
+        let mut db = self.db.lock().await;
+        self.filter_insert(&key);
+        // if the record is cached with the same encoding, only its timestamp is updated (a merge not rewriting it)
+        let result = match self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            Some(Some((cached, _))) if cached == encoding => {
+                db.merge(key.as_bytes(), &timestamp_operand(timestamp))
+            }
+            _ => {
+                let value = encode_encoding_timestamp_to_value(encoding.clone(), timestamp);
+                let mut batch = KvBatch::default();
+                batch.put(key.as_bytes(), &value);
+                update_conflict(&mut batch, &key, true);
+                db.write(&batch)
+            }
         };
-        let z_bytes = z_serialize(&data_info.as_tuple());
-
-        self.db
-            .lock()
-            .await
-            .put(key.as_bytes(), z_bytes.to_bytes())
+        match result {
+            Ok(()) => self.cache_put(&key, Some((encoding, *timestamp))),
+            Err(_) => self.cache_remove(&key),
+        }
+        result
             .map_err(|e| zerror!("Failed to save data-info for {:?}: {}", file.as_ref(), e).into())
     }
 
     pub(crate) async fn del_data_info<P: AsRef<Path>>(&self, file: P) -> ZResult<()> {
         let key = file.as_ref().to_string_lossy();
         trace!("Delete data-info for {}", key);
-        let db = self.db.lock().await;
-        match db.delete(key.as_bytes()) {
+        let mut batch = KvBatch::default();
+        batch.delete(key.as_bytes());
+        update_conflict(&mut batch, &key, false);
+        let mut db = self.db.lock().await;
+        self.cache_remove(&key);
+        match db.write(&batch) {
             Ok(()) => Ok(()),
             Err(e) => Err(format!(
                 "Failed to delete data-info for file {:?}: {}",
@@ -140,20 +266,48 @@ impl DataInfoMgr {
         }
     }
 
+    // Delete the data-info of several files at once
+    pub(crate) async fn del_data_info_batch(&self, files: &[PathBuf]) -> ZResult<()> {
+        let keys: Vec<String> = files
+            .iter()
+            .map(|file| file.to_string_lossy().into_owned())
+            .collect();
+        trace!("Delete data-info for {} files", keys.len());
+        let mut batch = KvBatch::default();
+        for key in &keys {
+            batch.delete(key.as_bytes());
+            update_conflict(&mut batch, key, false);
+        }
+        let mut db = self.db.lock().await;
+        for key in &keys {
+            self.cache_remove(key);
+        }
+        db.write(&batch).map_err(|e| {
+            zerror!(
+                "Failed to delete data-info for {} files: {}",
+                files.len(),
+                e
+            )
+            .into()
+        })
+    }
+
     pub(crate) async fn rename_key<P: AsRef<Path>>(&self, from: P, to: P) -> ZResult<()> {
         let from_key = from.as_ref().to_string_lossy();
         let to_key = to.as_ref().to_string_lossy();
         trace!("Changing data-info from {} to {}", from_key, to_key);
-        let db_instance = self.db.lock().await;
-        let val = db_instance.get_pinned(from_key.as_bytes());
+        let mut db_instance = self.db.lock().await;
+        self.cache_remove(&from_key);
+        self.cache_remove(&to_key);
+        let val = db_instance.get(from_key.as_bytes());
         match val {
-            Ok(Some(pin_val)) => {
-                db_instance.put(to_key.as_bytes(), pin_val).map_err(|e| {
-                    zerror!("Failed to save data-info for {:?}: {}", to.as_ref(), e)
-                })?;
-                db_instance.delete(from_key.as_bytes()).map_err(|e| {
-                    zerror!("Failed to save data-info for {:?}: {}", to.as_ref(), e).into()
-                })
+            Ok(Some(val)) => {
+                self.filter_insert(&to_key);
+                move_batch(&db_instance, &from_key, &to_key, &val)
+                    .and_then(|batch| db_instance.write(&batch))
+                    .map_err(|e| {
+                        zerror!("Failed to save data-info for {:?}: {}", to.as_ref(), e).into()
+                    })
             }
             Ok(None) => {
                 trace!("data-info for {:?} not found", from.as_ref());
@@ -166,16 +320,464 @@ impl DataInfoMgr {
         }
     }
 
+    // Move the data-info of a file to another one (in a single lock of the database), with a new timestamp
+    pub(crate) async fn move_data_info<P: AsRef<Path>>(
+        &self,
+        from: P,
+        to: P,
+        encoding: Encoding,
+        timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        let from_key = from.as_ref().to_string_lossy();
+        let to_key = to.as_ref().to_string_lossy();
+        trace!("Move data-info from {} to {}", from_key, to_key);
+        let data_info = DataInfo {
+            timestamp: *timestamp,
+            encoding,
+        };
+        let z_bytes = z_serialize(&data_info.as_tuple());
+        let mut db = self.db.lock().await;
+        self.cache_remove(&from_key);
+        self.cache_remove(&to_key);
+        self.filter_insert(&to_key);
+        move_batch(&db, &from_key, &to_key, &z_bytes.to_bytes())
+            .and_then(|batch| db.write(&batch))
+            .map_err(|e| {
+                zerror!(
+                    "Failed to move data-info from {:?} to {:?}: {}",
+                    from.as_ref(),
+                    to.as_ref(),
+                    e
+                )
+                .into()
+            })
+    }
+
+    // Check all the data-info entries, removing the ones which can't be decoded or
+    // which don't have a corresponding file anymore (unless archived). Returns the number of removed entries.
+    // The database is locked for each entry, not for the whole check, for the operations not to wait for it.
+    pub(crate) async fn verify(&self, progress: Option<&InitProgress>) -> ZResult<usize> {
+        let mut keys = Vec::new();
+        self.db
+            .lock()
+            .await
+            .scan(b"", |key, _| {
+                // skip the records which are not data-info
+                if !key.starts_with(b"@") {
+                    keys.push(String::from_utf8_lossy(key).into_owned());
+                }
+                Ok(())
+            })
+            .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        let mut removed = 0;
+        for key in keys {
+            if let Some(progress) = progress {
+                progress.file_seen();
+            }
+            let mut db = self.db.lock().await;
+            let corrupted = match db.get(key.as_bytes()) {
+                Ok(Some(val)) => {
+                    let file = Path::new(&key);
+                    let is_archived = matches!(db.get(archived_key(&key).as_bytes()), Ok(Some(_)));
+                    if !file.is_file() && !is_archived {
+                        warn!("data-info found for {:?} but the file is missing", file);
+                        true
+                    } else if decode_encoding_timestamp_from_value(&val).is_err() {
+                        warn!("data-info for {:?} is corrupted", file);
+                        true
+                    } else {
+                        false
+                    }
+                }
+                // deleted meanwhile
+                Ok(None) => false,
+                Err(e) => bail!("Failed to get data-info for {}: {}", key, e),
+            };
+            if corrupted {
+                self.cache_remove(&key);
+                let mut batch = KvBatch::default();
+                batch.delete(key.as_bytes());
+                update_conflict(&mut batch, &key, false);
+                db.write(&batch)
+                    .map_err(|e| zerror!("Failed to remove data-info for {}: {}", key, e))?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    // Return the files which have data-info but are missing (and aren't archived), without removing their data-info
+    // (as verify() does)
+    pub(crate) async fn get_missing_files(&self) -> ZResult<Vec<PathBuf>> {
+        let mut keys = Vec::new();
+        self.db
+            .lock()
+            .await
+            .scan(b"", |key, _| {
+                // skip the records which are not data-info
+                if !key.starts_with(b"@") {
+                    keys.push(String::from_utf8_lossy(key).into_owned());
+                }
+                Ok(())
+            })
+            .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        let mut missing = Vec::new();
+        for key in keys {
+            let file = PathBuf::from(&key);
+            if file.is_file() {
+                continue;
+            }
+            // (the lock being taken for each file, not to block the storage's operations)
+            let db = self.db.lock().await;
+            let is_archived = matches!(db.get(archived_key(&key).as_bytes()), Ok(Some(_)));
+            // (unless deleted meanwhile)
+            if !is_archived && matches!(db.get(key.as_bytes()), Ok(Some(_))) {
+                missing.push(file);
+            }
+        }
+        Ok(missing)
+    }
+
+    // Metrics of the data-info database, for the admin status
+    pub(crate) async fn stats(&self) -> serde_json::Value {
+        let mut stats = self.db.lock().await.stats();
+        if let (Some(cache), Some(obj)) = (&self.cache, stats.as_object_mut()) {
+            obj.insert("cache".into(), cache.stats());
+        }
+        if let (Some(keys_filter), Some(obj)) = (&self.keys_filter, stats.as_object_mut()) {
+            obj.insert("keys_filter".into(), keys_filter.stats());
+        }
+        stats
+    }
+
+    // Compact the database, and rebuild the keys filter if it holds more keys than it was sized for
+    pub(crate) async fn compact(&self) {
+        let mut db = self.db.lock().await;
+        db.compact();
+        if let Some(keys_filter) = self.keys_filter.as_ref().filter(|f| f.is_outgrown()) {
+            match data_info_keys(&db) {
+                Ok(keys) => keys_filter.rebuild(&keys),
+                Err(e) => warn!("Failed to rebuild the keys filter of the data-info: {}", e),
+            }
+        }
+    }
+
+    pub(crate) async fn mark_archived<P: AsRef<Path>>(&self, file: P, day: &str) -> ZResult<()> {
+        let key = archived_key(&file.as_ref().to_string_lossy());
+        trace!("Mark {:?} as archived in bundle of {}", file.as_ref(), day);
+        self.db
+            .lock()
+            .await
+            .put(key.as_bytes(), day.as_bytes())
+            .map_err(|e| zerror!("Failed to mark {:?} as archived: {}", file.as_ref(), e).into())
+    }
+
+    // Delete the data-info of an archived file, and its archive mark
+    pub(crate) async fn del_archived_data_info<P: AsRef<Path>>(&self, file: P) -> ZResult<()> {
+        let key = file.as_ref().to_string_lossy();
+        trace!("Delete data-info and archive mark for {}", key);
+        let mut batch = KvBatch::default();
+        batch.delete(archived_key(&key).as_bytes());
+        batch.delete(key.as_bytes());
+        update_conflict(&mut batch, &key, false);
+        let mut db = self.db.lock().await;
+        self.cache_remove(&key);
+        db.write(&batch).map_err(|e| {
+            zerror!(
+                "Failed to delete data-info of archived {:?}: {}",
+                file.as_ref(),
+                e
+            )
+            .into()
+        })
+    }
+
+    // Return the day of the bundle where the file was archived, if it was
+    pub(crate) async fn get_archive_day<P: AsRef<Path>>(&self, file: P) -> ZResult<Option<String>> {
+        let key = archived_key(&file.as_ref().to_string_lossy());
+        match self.db.lock().await.get(key.as_bytes()) {
+            Ok(Some(val)) => Ok(Some(String::from_utf8_lossy(&val).into_owned())),
+            Ok(None) => Ok(None),
+            Err(e) => bail!("Failed to get archive for {:?}: {}", file.as_ref(), e),
+        }
+    }
+
+    // Retry the operations of the database failing with a transient error
+    pub(crate) async fn set_retry(&self, retry: RetryPolicy) {
+        self.db.lock().await.set_retry(retry);
+    }
+
+    // Return the registered conflict files (i.e. with data-info), in the order of their paths
+    pub(crate) async fn get_conflict_files(&self) -> ZResult<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let prefix = DataInfoMgr::CONFLICT_PREFIX.as_bytes();
+        self.db
+            .lock()
+            .await
+            .scan(prefix, |key, _| {
+                files.push(PathBuf::from(
+                    String::from_utf8_lossy(&key[prefix.len()..]).into_owned(),
+                ));
+                Ok(())
+            })
+            .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        Ok(files)
+    }
+
+    // Return all the archived files with their timestamp
+    // Call `f` with the file and timestamp of each data-info record (the undecodable ones being skipped)
+    pub(crate) async fn for_each_timestamp(
+        &self,
+        mut f: impl FnMut(&Path, &Timestamp),
+    ) -> ZResult<()> {
+        self.db
+            .lock()
+            .await
+            .scan(b"", |key, val| {
+                if !key.starts_with(b"@") {
+                    if let Ok((_, timestamp)) = decode_encoding_timestamp_from_value(val) {
+                        f(Path::new(&*String::from_utf8_lossy(key)), &timestamp);
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e| zerror!("Failed to iterate over data-info: {}", e).into())
+    }
+
+    pub(crate) async fn get_archived_files(&self) -> ZResult<Vec<(PathBuf, Timestamp)>> {
+        let db = self.db.lock().await;
+        let mut files = Vec::new();
+        let prefix = DataInfoMgr::ARCHIVED_PREFIX.as_bytes();
+        db.scan(prefix, |key, _| {
+            files.push(String::from_utf8_lossy(&key[prefix.len()..]).into_owned());
+            Ok(())
+        })
+        .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        let mut result = Vec::new();
+        for file in files {
+            match db.get(file.as_bytes()) {
+                Ok(Some(val)) => {
+                    let (_, timestamp) = decode_encoding_timestamp_from_value(&val)?;
+                    result.push((PathBuf::from(file), timestamp));
+                }
+                Ok(None) => trace!("data-info for archived {:?} not found", file),
+                Err(e) => bail!("Failed to get data-info for {:?}: {}", file, e),
+            }
+        }
+        Ok(result)
+    }
+
+    // Return the salt of the hashed file names, if the storage was created with obfuscated names
+    pub(crate) async fn get_salt(&self) -> ZResult<Option<String>> {
+        match self.db.lock().await.get(DataInfoMgr::SALT_KEY.as_bytes()) {
+            Ok(Some(val)) => Ok(Some(String::from_utf8_lossy(&val).into_owned())),
+            Ok(None) => Ok(None),
+            Err(e) => bail!("Failed to get the salt of the file names: {}", e),
+        }
+    }
+
+    pub(crate) async fn put_salt(&self, salt: &str) -> ZResult<()> {
+        self.db
+            .lock()
+            .await
+            .put(DataInfoMgr::SALT_KEY.as_bytes(), salt.as_bytes())
+            .map_err(|e| zerror!("Failed to save the salt of the file names: {}", e).into())
+    }
+
+    // Return the id of the timestamps of the files without data-info, if already generated
+    pub(crate) async fn get_timestamp_id(&self) -> ZResult<Option<TimestampId>> {
+        match self
+            .db
+            .lock()
+            .await
+            .get(DataInfoMgr::TIMESTAMP_ID_KEY.as_bytes())
+        {
+            Ok(Some(val)) => Ok(Some(TimestampId::try_from(val.as_slice())?)),
+            Ok(None) => Ok(None),
+            Err(e) => bail!("Failed to get the id of the fallback timestamps: {}", e),
+        }
+    }
+
+    pub(crate) async fn put_timestamp_id(&self, id: &TimestampId) -> ZResult<()> {
+        self.db
+            .lock()
+            .await
+            .put(
+                DataInfoMgr::TIMESTAMP_ID_KEY.as_bytes(),
+                &id.to_le_bytes()[..id.size()],
+            )
+            .map_err(|e| zerror!("Failed to save the id of the fallback timestamps: {}", e).into())
+    }
+
+    // Return the directories of the stripes of the storage, if recorded
+    pub(crate) async fn get_stripes(&self) -> ZResult<Option<Vec<PathBuf>>> {
+        match self
+            .db
+            .lock()
+            .await
+            .get(DataInfoMgr::STRIPES_KEY.as_bytes())
+        {
+            Ok(Some(val)) => match serde_json::from_slice::<Vec<PathBuf>>(&val) {
+                Ok(stripes) => Ok(Some(stripes)),
+                Err(e) => bail!("Failed to decode the stripes of the storage: {}", e),
+            },
+            Ok(None) => Ok(None),
+            Err(e) => bail!("Failed to get the stripes of the storage: {}", e),
+        }
+    }
+
+    pub(crate) async fn put_stripes(&self, stripes: &[PathBuf]) -> ZResult<()> {
+        let val = serde_json::to_vec(stripes)
+            .map_err(|e| zerror!("Failed to encode the stripes of the storage: {}", e))?;
+        self.db
+            .lock()
+            .await
+            .put(DataInfoMgr::STRIPES_KEY.as_bytes(), &val)
+            .map_err(|e| zerror!("Failed to save the stripes of the storage: {}", e).into())
+    }
+
+    // Return the key chunk of each hashed file name
+    pub(crate) async fn get_names(&self) -> ZResult<HashMap<String, String>> {
+        let db = self.db.lock().await;
+        let mut result = HashMap::new();
+        let prefix = DataInfoMgr::NAME_PREFIX.as_bytes();
+        db.scan(prefix, |key, val| {
+            let name = String::from_utf8_lossy(&key[prefix.len()..]).into_owned();
+            result.insert(name, String::from_utf8_lossy(val).into_owned());
+            Ok(())
+        })
+        .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        Ok(result)
+    }
+
+    pub(crate) async fn put_names(&self, names: &[(String, String)]) -> ZResult<()> {
+        let mut batch = KvBatch::default();
+        for (name, chunk) in names {
+            trace!("Save key chunk of file name {}", name);
+            batch.put(
+                format!("{}{}", DataInfoMgr::NAME_PREFIX, name).as_bytes(),
+                chunk.as_bytes(),
+            );
+        }
+        self.db.lock().await.write(&batch).map_err(|e| {
+            zerror!(
+                "Failed to save the key chunks of {} file names: {}",
+                names.len(),
+                e
+            )
+            .into()
+        })
+    }
+
+    // Hide (or unhide) the value of an entry in the lower layer of the storage
+    pub(crate) async fn set_whiteout(&self, zpath: &str, hidden: bool) -> ZResult<()> {
+        let key = whiteout_key(zpath);
+        let mut db = self.db.lock().await;
+        let result = match hidden {
+            true => db.put(key.as_bytes(), b""),
+            false => db.delete(key.as_bytes()),
+        };
+        result.map_err(|e| zerror!("Failed to update the whiteout of {}: {}", zpath, e).into())
+    }
+
+    pub(crate) async fn is_whiteout(&self, zpath: &str) -> ZResult<bool> {
+        match self.db.lock().await.get(whiteout_key(zpath).as_bytes()) {
+            Ok(val) => Ok(val.is_some()),
+            Err(e) => bail!("Failed to get the whiteout of {}: {}", zpath, e),
+        }
+    }
+
+    // Return the number of whiteouts
+    pub(crate) async fn count_whiteouts(&self) -> ZResult<usize> {
+        let mut count = 0;
+        self.db
+            .lock()
+            .await
+            .scan(DataInfoMgr::WHITEOUT_PREFIX.as_bytes(), |_, _| {
+                count += 1;
+                Ok(())
+            })
+            .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        Ok(count)
+    }
+
+    // Mark (or unmark) a file as offloaded to the cold tier, at `relpath`
+    pub(crate) async fn set_cold<P: AsRef<Path>>(
+        &self,
+        file: P,
+        relpath: Option<&str>,
+    ) -> ZResult<()> {
+        let key = cold_key(&file.as_ref().to_string_lossy());
+        let mut db = self.db.lock().await;
+        let result = match relpath {
+            Some(relpath) => db.put(key.as_bytes(), relpath.as_bytes()),
+            None => db.delete(key.as_bytes()),
+        };
+        result.map_err(|e| {
+            zerror!(
+                "Failed to update the cold tier mark of {:?}: {}",
+                file.as_ref(),
+                e
+            )
+            .into()
+        })
+    }
+
+    // Return the path in the cold tier of the value of a file, if it was offloaded
+    pub(crate) async fn get_cold<P: AsRef<Path>>(&self, file: P) -> ZResult<Option<String>> {
+        let key = cold_key(&file.as_ref().to_string_lossy());
+        match self.db.lock().await.get(key.as_bytes()) {
+            Ok(Some(val)) => Ok(Some(String::from_utf8_lossy(&val).into_owned())),
+            Ok(None) => Ok(None),
+            Err(e) => bail!(
+                "Failed to get the cold tier mark of {:?}: {}",
+                file.as_ref(),
+                e
+            ),
+        }
+    }
+
+    // Return the number of files offloaded to the cold tier
+    pub(crate) async fn count_cold(&self) -> ZResult<usize> {
+        let mut count = 0;
+        self.db
+            .lock()
+            .await
+            .scan(DataInfoMgr::COLD_PREFIX.as_bytes(), |_, _| {
+                count += 1;
+                Ok(())
+            })
+            .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+        Ok(count)
+    }
+
     pub(crate) async fn get_encoding_and_timestamp<P: AsRef<Path>>(
         &self,
         file: P,
     ) -> ZResult<Option<(Encoding, Timestamp)>> {
         let key = file.as_ref().to_string_lossy();
         trace!("Get data-info for {}", key);
-        match self.db.lock().await.get_pinned(key.as_bytes()) {
-            Ok(Some(pin_val)) => decode_encoding_timestamp_from_value(pin_val.as_ref()).map(Some),
+        if self
+            .keys_filter
+            .as_ref()
+            .is_some_and(|keys_filter| !keys_filter.may_contain(&key))
+        {
+            trace!("data-info for {:?} not found (filtered)", file.as_ref());
+            return Ok(None);
+        }
+        if let Some(record) = self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            return Ok(record);
+        }
+        let db = self.db.lock().await;
+        match db.get(key.as_bytes()) {
+            Ok(Some(val)) => {
+                let record = decode_encoding_timestamp_from_value(&val)?;
+                self.cache_put(&key, Some(record.clone()));
+                Ok(Some(record))
+            }
             Ok(None) => {
                 trace!("data-info for {:?} not found", file.as_ref());
+                self.cache_put(&key, None);
                 Ok(None)
             }
             Err(e) => bail!("Failed to get data-info for {:?}: {}", file.as_ref(), e),
@@ -183,7 +785,135 @@ impl DataInfoMgr {
     }
 }
 
-fn decode_encoding_timestamp_from_value(val: &[u8]) -> ZResult<(Encoding, Timestamp)> {
+// The keys of the data-info records of the database (the other records' keys starting with '@')
+fn data_info_keys(db: &KvStore) -> ZResult<Vec<String>> {
+    let mut keys = Vec::new();
+    db.scan(b"", |key, _| {
+        if !key.starts_with(b"@") {
+            keys.push(String::from_utf8_lossy(key).into_owned());
+        }
+        Ok(())
+    })
+    .map_err(|e| zerror!("Failed to iterate over data-info: {}", e))?;
+    Ok(keys)
+}
+
+fn archived_key(file: &str) -> String {
+    format!("{}{}", DataInfoMgr::ARCHIVED_PREFIX, file)
+}
+
+fn conflict_key(file: &str) -> String {
+    format!("{}{}", DataInfoMgr::CONFLICT_PREFIX, file)
+}
+
+fn whiteout_key(zpath: &str) -> String {
+    format!("{}{}", DataInfoMgr::WHITEOUT_PREFIX, zpath)
+}
+
+fn cold_key(file: &str) -> String {
+    format!("{}{}", DataInfoMgr::COLD_PREFIX, file)
+}
+
+// Register (or unregister) a conflict file in the registry, in the batch storing (or deleting) its data-info
+fn update_conflict(batch: &mut KvBatch, file: &str, stored: bool) {
+    if !file.ends_with(CONFLICT_SUFFIX) {
+        return;
+    }
+    match stored {
+        true => batch.put(conflict_key(file).as_bytes(), b""),
+        false => batch.delete(conflict_key(file).as_bytes()),
+    }
+}
+
+// The batch moving the data-info of a renamed file (with the given value), and its cold tier mark if offloaded (its
+// stub being renamed)
+fn move_batch(db: &KvStore, from: &str, to: &str, value: &[u8]) -> ZResult<KvBatch> {
+    let mut batch = KvBatch::default();
+    batch.put(to.as_bytes(), value);
+    update_conflict(&mut batch, to, true);
+    batch.delete(from.as_bytes());
+    update_conflict(&mut batch, from, false);
+    let from_cold = cold_key(from);
+    if let Some(relpath) = db.get(from_cold.as_bytes())? {
+        batch.put(cold_key(to).as_bytes(), &relpath);
+        batch.delete(from_cold.as_bytes());
+    }
+    Ok(batch)
+}
+
+// Register the conflict files with data-info, if not done yet (for a database created before the registry)
+fn register_conflicts(db: &mut KvStore) -> ZResult<()> {
+    if db
+        .get(DataInfoMgr::CONFLICTS_REGISTERED_KEY.as_bytes())?
+        .is_some()
+    {
+        return Ok(());
+    }
+    let mut files = Vec::new();
+    db.scan(b"", |key, _| {
+        let key = String::from_utf8_lossy(key);
+        if !key.starts_with('@') && key.ends_with(CONFLICT_SUFFIX) {
+            files.push(key.into_owned());
+        }
+        Ok(())
+    })?;
+    let mut batch = KvBatch::default();
+    for file in files {
+        update_conflict(&mut batch, &file, true);
+    }
+    batch.put(DataInfoMgr::CONFLICTS_REGISTERED_KEY.as_bytes(), b"");
+    db.write(&batch)
+}
+
+// The merge operand updating the timestamp of a data-info record: the timestamp's time and id
+fn timestamp_operand(timestamp: &Timestamp) -> Vec<u8> {
+    let mut operand = timestamp.get_time().as_u64().to_le_bytes().to_vec();
+    operand.extend_from_slice(&timestamp.get_id().to_le_bytes());
+    operand
+}
+
+// Replace the timestamp of a data-info record by the one of a merge operand (the record of a missing or corrupted
+// one getting the default encoding, an invalid operand being ignored)
+fn merge_timestamp(existing: Option<&[u8]>, operand: &[u8]) -> Vec<u8> {
+    let timestamp = match operand.len() {
+        24 => {
+            let time = u64::from_le_bytes(operand[..8].try_into().unwrap());
+            let id = <[u8; 16]>::try_from(&operand[8..]).unwrap();
+            TimestampId::try_from(id)
+                .ok()
+                .map(|id| Timestamp::new(NTP64(time), id))
+        }
+        _ => None,
+    };
+    let encoding = existing.and_then(|v| decode_encoding_timestamp_from_value(v).ok());
+    match (timestamp, encoding) {
+        (Some(timestamp), Some((encoding, _))) => {
+            encode_encoding_timestamp_to_value(encoding, &timestamp)
+        }
+        (Some(timestamp), None) => {
+            warn!("Merged a timestamp into a missing or corrupted data-info record");
+            encode_encoding_timestamp_to_value(Encoding::default(), &timestamp)
+        }
+        (None, _) => {
+            warn!("Ignored an invalid timestamp merged into a data-info record");
+            existing.map(|v| v.to_vec()).unwrap_or_default()
+        }
+    }
+}
+
+// The data-info of a value, serialized (as stored in the database, and in the files' envelopes)
+pub(crate) fn encode_encoding_timestamp_to_value(
+    encoding: Encoding,
+    timestamp: &Timestamp,
+) -> Vec<u8> {
+    let data_info = DataInfo {
+        timestamp: *timestamp,
+        encoding,
+    };
+    z_serialize(&data_info.as_tuple()).to_bytes().into_owned()
+}
+
+pub(crate) fn decode_encoding_timestamp_from_value(val: &[u8]) -> ZResult<(Encoding, Timestamp)> {
     let bytes = ZBytes::from(val);
     let tuple: DataInfoTuple =
         z_deserialize(&bytes).map_err(|_| zerror!("Failed to decode data-info"))?;
@@ -0,0 +1,205 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use zenoh::{
+    internal::{bail, zerror},
+    time::Timestamp,
+    Result as ZResult,
+};
+
+// The instructions of a delta
+const OP_COPY: u8 = 1;
+const OP_INSERT: u8 = 2;
+
+// A delta against a base value (identified by its timestamp), as sent by the clients:
+// the base timestamp followed by a '\n', then a sequence of instructions building the new value:
+//  - COPY (0x01) followed by an offset and a length (as u64 big-endian): copies a range of the base value
+//  - INSERT (0x02) followed by a length (as u64 big-endian) and the bytes to insert
+pub(crate) struct Delta<'a> {
+    base: Timestamp,
+    instructions: &'a [u8],
+}
+
+impl<'a> Delta<'a> {
+    pub(crate) fn parse(bytes: &'a [u8]) -> ZResult<Self> {
+        let Some(eol) = bytes.iter().position(|b| *b == b'\n') else {
+            bail!("Invalid delta: missing base timestamp")
+        };
+        let base = std::str::from_utf8(&bytes[..eol])
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+            .ok_or_else(|| zerror!("Invalid delta: invalid base timestamp"))?;
+        Ok(Delta {
+            base,
+            instructions: &bytes[eol + 1..],
+        })
+    }
+
+    pub(crate) fn base(&self) -> &Timestamp {
+        &self.base
+    }
+
+    // Build the new value from the base one, failing if it would exceed `max_size` bytes
+    pub(crate) fn apply(&self, base: &[u8], max_size: usize) -> ZResult<Vec<u8>> {
+        let mut result = Vec::with_capacity(base.len().min(max_size));
+        let mut rest = self.instructions;
+        while let Some((op, tail)) = rest.split_first() {
+            rest = tail;
+            match *op {
+                OP_COPY => {
+                    let offset = read_u64(&mut rest)?;
+                    let len = read_u64(&mut rest)?;
+                    let range = usize::try_from(offset)
+                        .ok()
+                        .zip(usize::try_from(len).ok())
+                        .and_then(|(offset, len)| base.get(offset..offset.checked_add(len)?))
+                        .ok_or_else(|| {
+                            zerror!(
+                                "Invalid delta: copy of {} bytes at {} out of the base value",
+                                len,
+                                offset
+                            )
+                        })?;
+                    check_size(&result, range.len(), max_size)?;
+                    result.extend_from_slice(range);
+                }
+                OP_INSERT => {
+                    let len = read_u64(&mut rest)?;
+                    let Some(bytes) = usize::try_from(len).ok().and_then(|len| rest.get(..len))
+                    else {
+                        bail!("Invalid delta: truncated insertion of {} bytes", len)
+                    };
+                    check_size(&result, bytes.len(), max_size)?;
+                    result.extend_from_slice(bytes);
+                    rest = &rest[bytes.len()..];
+                }
+                op => bail!("Invalid delta: unknown instruction {:#04x}", op),
+            }
+        }
+        Ok(result)
+    }
+}
+
+// Check the new value doesn't exceed the maximum size before growing it
+fn check_size(result: &[u8], len: usize, max_size: usize) -> ZResult<()> {
+    if result.len().saturating_add(len) > max_size {
+        bail!(
+            "Invalid delta: the new value exceeds the maximum size of {} bytes",
+            max_size
+        )
+    }
+    Ok(())
+}
+
+fn read_u64(bytes: &mut &[u8]) -> ZResult<u64> {
+    if bytes.len() < 8 {
+        bail!("Invalid delta: truncated instruction")
+    }
+    let (int, rest) = bytes.split_at(8);
+    *bytes = rest;
+    Ok(u64::from_be_bytes(int.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use zenoh::time::{TimestampId, NTP64};
+
+    use super::*;
+
+    fn delta(base: &Timestamp, instructions: &[u8]) -> Vec<u8> {
+        let mut bytes = format!("{base}\n").into_bytes();
+        bytes.extend_from_slice(instructions);
+        bytes
+    }
+
+    fn copy(offset: u64, len: u64) -> Vec<u8> {
+        let mut bytes = vec![OP_COPY];
+        bytes.extend_from_slice(&offset.to_be_bytes());
+        bytes.extend_from_slice(&len.to_be_bytes());
+        bytes
+    }
+
+    fn insert(inserted: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![OP_INSERT];
+        bytes.extend_from_slice(&(inserted.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(inserted);
+        bytes
+    }
+
+    #[test]
+    fn parse_and_apply() {
+        let base = Timestamp::new(NTP64(42), TimestampId::rand());
+        let bytes = delta(
+            &base,
+            &[copy(0, 5), insert(b", brave new"), copy(5, 7)].concat(),
+        );
+        let parsed = Delta::parse(&bytes).unwrap();
+        assert_eq!(parsed.base(), &base);
+        assert_eq!(
+            parsed.apply(b"Hello world!", 1024).unwrap(),
+            b"Hello, brave new world!"
+        );
+        // no instruction: an empty value
+        let bytes = delta(&base, &[]);
+        assert!(Delta::parse(&bytes)
+            .unwrap()
+            .apply(b"Hello world!", 1024)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_malformed() {
+        assert!(Delta::parse(b"").is_err());
+        assert!(Delta::parse(b"no base timestamp").is_err());
+        assert!(Delta::parse(b"not a timestamp\n").is_err());
+    }
+
+    #[test]
+    fn apply_malformed() {
+        let base = Timestamp::new(NTP64(42), TimestampId::rand());
+        let apply =
+            |instructions: &[u8]| Delta::parse(&delta(&base, instructions))?.apply(b"Hello", 1024);
+        // a copy out of the base value
+        assert!(apply(&copy(3, 3)).is_err());
+        assert!(apply(&copy(10, 1)).is_err());
+        assert!(apply(&copy(u64::MAX, 2)).is_err());
+        // truncated instructions
+        assert!(apply(&[OP_COPY, 0, 0, 0]).is_err());
+        assert!(apply(&copy(0, 1)[..12]).is_err());
+        assert!(apply(&insert(b"abc")[..10]).is_err());
+        // an unknown instruction
+        assert!(apply(&[3]).is_err());
+    }
+
+    #[test]
+    fn apply_max_size() {
+        let base = Timestamp::new(NTP64(42), TimestampId::rand());
+        let instructions = [copy(0, 5), insert(b" "), copy(0, 5)].concat();
+        let bytes = delta(&base, &instructions);
+        let parsed = Delta::parse(&bytes).unwrap();
+        assert_eq!(parsed.apply(b"Hello", 11).unwrap(), b"Hello Hello");
+        assert!(parsed.apply(b"Hello", 10).is_err());
+        // copies of the whole base value repeated beyond the maximum size
+        let instructions = copy(0, 5).repeat(1000);
+        let bytes = delta(&base, &instructions);
+        let e = Delta::parse(&bytes)
+            .unwrap()
+            .apply(b"Hello", 1024)
+            .unwrap_err();
+        assert!(
+            e.to_string().contains("maximum size"),
+            "unexpected error: {e}"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::BTreeMap,
+    hash::{Hash, Hasher},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use siphasher::sip::SipHasher13;
+use zenoh::time::Timestamp;
+
+// The number of most recent intervals in the "hot" era, and in the "warm" one (the older ones being "cold")
+const HOT_INTERVALS: u64 = 6;
+const WARM_INTERVALS: u64 = 30;
+
+// The replication digest of a set of entries: the entries are grouped into time intervals (by their timestamp),
+// each one with a checksum of its keys and timestamps. The intervals are summarized in eras, as in the storage
+// manager's replication: the checksums of each hot and warm interval are listed, and the cold era only has a global
+// one. Two replicas compare the checksums of the eras, then of their intervals, to find the entries to align.
+pub(crate) fn digest(
+    entries: &[(String, Timestamp)],
+    interval: Duration,
+    now: SystemTime,
+) -> serde_json::Value {
+    let interval_secs = interval.as_secs().max(1);
+    let mut intervals: BTreeMap<u64, u64> = BTreeMap::new();
+    for (key, timestamp) in entries {
+        let id = timestamp.get_time().as_secs() as u64 / interval_secs;
+        *intervals.entry(id).or_default() ^= entry_checksum(key, timestamp);
+    }
+    let current = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() / interval_secs;
+    let mut eras = [Era::default(), Era::default(), Era::default()];
+    for (id, checksum) in intervals {
+        let era = match current.saturating_sub(id) {
+            age if age < HOT_INTERVALS => &mut eras[0],
+            age if age < HOT_INTERVALS + WARM_INTERVALS => &mut eras[1],
+            _ => &mut eras[2],
+        };
+        era.checksum ^= checksum;
+        era.intervals.insert(id, checksum);
+    }
+    let [hot, warm, cold] = eras;
+    serde_json::json!({
+        "interval_secs": interval_secs,
+        "checksum": format!("{:016x}", hot.checksum ^ warm.checksum ^ cold.checksum),
+        "eras": {
+            "hot": hot.to_json(true),
+            "warm": warm.to_json(true),
+            "cold": cold.to_json(false),
+        }
+    })
+}
+
+#[derive(Default)]
+struct Era {
+    checksum: u64,
+    intervals: BTreeMap<u64, u64>,
+}
+
+impl Era {
+    fn to_json(&self, with_intervals: bool) -> serde_json::Value {
+        let mut era = serde_json::Map::new();
+        era.insert("checksum".into(), format!("{:016x}", self.checksum).into());
+        if with_intervals {
+            let intervals: serde_json::Map<String, serde_json::Value> = self
+                .intervals
+                .iter()
+                .map(|(id, checksum)| (id.to_string(), format!("{:016x}", checksum).into()))
+                .collect();
+            era.insert("intervals".into(), intervals.into());
+        }
+        era.into()
+    }
+}
+
+// The checksum of an entry, combined with the others' by XOR (not to depend on their order)
+fn entry_checksum(key: &str, timestamp: &Timestamp) -> u64 {
+    let mut hasher = SipHasher13::new();
+    key.hash(&mut hasher);
+    timestamp.to_string().hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,115 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs::read_dir,
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, SystemTime},
+};
+
+use crate::links::is_unreported_link;
+
+// The minimum age of the modification time of a directory for its listing to be cached: the modification times of
+// some file-systems are too coarse (e.g. 1s) to tell apart the changes made in the same instant as the listing.
+const MIN_LISTING_AGE: Duration = Duration::from_secs(2);
+
+// A bounded in-memory cache of the listings of the directories (the names and types of their children, sorted by
+// name), for the wildcard queries not to read again the directories which didn't change. A listing is used as long
+// as the modification time of its directory is unchanged (a child being created, removed or renamed in it).
+pub(crate) struct DirListings {
+    capacity: usize,
+    listings: Mutex<HashMap<PathBuf, (SystemTime, Arc<Vec<DirChild>>)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+pub(crate) struct DirChild {
+    pub(crate) name: OsString,
+    // the type of the child itself (not of the target of a symbolic link, a link not being a directory)
+    pub(crate) is_dir: bool,
+    pub(crate) is_symlink: bool,
+}
+
+impl DirListings {
+    pub(crate) fn new(capacity: usize) -> Self {
+        DirListings {
+            capacity,
+            listings: Mutex::new(HashMap::with_capacity(capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    // The children of a directory, sorted by name: cached if the directory didn't change since it was listed
+    pub(crate) fn list(&self, dir: &Path) -> io::Result<Arc<Vec<DirChild>>> {
+        let modified = dir.metadata()?.modified()?;
+        if let Some((listed, children)) = self.listings.lock().unwrap().get(dir) {
+            if *listed == modified {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(children.clone());
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let mut children = read_dir(dir)?
+            .filter_map(|e| {
+                let e = e.ok()?;
+                let file_type = e.file_type().ok()?;
+                let is_symlink =
+                    file_type.is_symlink() || (file_type.is_dir() && is_unreported_link(&e.path()));
+                Some(DirChild {
+                    name: e.file_name(),
+                    is_dir: file_type.is_dir() && !is_symlink,
+                    is_symlink,
+                })
+            })
+            .collect::<Vec<_>>();
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        let children = Arc::new(children);
+        let mut listings = self.listings.lock().unwrap();
+        if modified.elapsed().is_ok_and(|age| age >= MIN_LISTING_AGE) {
+            if listings.len() >= self.capacity && !listings.contains_key(dir) {
+                // evict an arbitrary listing (the iteration order of a HashMap being random, it's a random eviction)
+                if let Some(evicted) = listings.keys().next().cloned() {
+                    listings.remove(&evicted);
+                }
+            }
+            listings.insert(dir.to_path_buf(), (modified, children.clone()));
+        } else {
+            listings.remove(dir);
+        }
+        Ok(children)
+    }
+
+    pub(crate) fn stats(&self) -> serde_json::Value {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let hit_rate = match hits + misses {
+            0 => 0.0,
+            total => hits as f64 / total as f64,
+        };
+        serde_json::json!({
+            "capacity": self.capacity,
+            "size": self.listings.lock().unwrap().len(),
+            "hits": hits,
+            "misses": misses,
+            "hit_rate": hit_rate,
+        })
+    }
+}
@@ -0,0 +1,80 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::info;
+
+// The counts of the updates processed by a storage in dry run, none being applied on the disk
+pub(crate) struct DryRun {
+    storage: String,
+    puts: AtomicU64,
+    deletes: AtomicU64,
+    conflicts: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl DryRun {
+    pub(crate) fn new(storage: &str) -> Self {
+        DryRun {
+            storage: storage.to_string(),
+            puts: AtomicU64::new(0),
+            deletes: AtomicU64::new(0),
+            conflicts: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    // A PUT which would be stored, possibly resolving a conflict
+    pub(crate) fn put(&self, key: &str, size: usize, conflict: Option<&str>) {
+        self.puts.fetch_add(1, Ordering::Relaxed);
+        match conflict {
+            Some(conflict) => {
+                self.conflicts.fetch_add(1, Ordering::Relaxed);
+                info!(
+                    "Dry run on storage {}: PUT on {} would be stored ({} bytes), but {}",
+                    self.storage, key, size, conflict
+                );
+            }
+            None => info!(
+                "Dry run on storage {}: PUT on {} would be stored ({} bytes)",
+                self.storage, key, size
+            ),
+        }
+    }
+
+    // A DELETE which would be applied (`exists` being false if there's no value to delete)
+    pub(crate) fn delete(&self, key: &str, exists: bool) {
+        self.deletes.fetch_add(1, Ordering::Relaxed);
+        info!(
+            "Dry run on storage {}: DELETE on {} would be applied{}",
+            self.storage,
+            key,
+            if exists { "" } else { " (no value stored)" }
+        );
+    }
+
+    // A PUT or DELETE which would be rejected (its error being returned)
+    pub(crate) fn reject(&self) {
+        self.rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "puts": self.puts.load(Ordering::Relaxed),
+            "deletes": self.deletes.load(Ordering::Relaxed),
+            "conflicts": self.conflicts.load(Ordering::Relaxed),
+            "rejected": self.rejected.load(Ordering::Relaxed),
+        })
+    }
+}
@@ -0,0 +1,110 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{collections::HashMap, mem, sync::Mutex};
+
+use zenoh::time::Timestamp;
+
+// An in-memory index of the zpaths and timestamps of the entries of a FilesMgr, for the listing of all the entries
+// (e.g. by the storage manager's replication) not to walk the whole directory each time.
+// It's loaded by a first walk, then kept up to date by the FilesMgr's writes and deletions.
+// The files added to the directory by other means are only indexed after a restart.
+pub(crate) struct EntriesIndex {
+    state: Mutex<IndexState>,
+}
+
+enum IndexState {
+    Unloaded,
+    // a walk is in progress: the updates meanwhile are replayed once it's complete (None being a deletion)
+    Loading(Vec<(String, Option<Timestamp>)>),
+    Loaded(HashMap<String, Timestamp>),
+}
+
+impl EntriesIndex {
+    pub(crate) fn new() -> Self {
+        EntriesIndex {
+            state: Mutex::new(IndexState::Unloaded),
+        }
+    }
+
+    // The indexed entries, if loaded
+    pub(crate) fn entries(&self) -> Option<Vec<(String, Timestamp)>> {
+        match &*self.state.lock().unwrap() {
+            IndexState::Loaded(entries) => Some(
+                entries
+                    .iter()
+                    .map(|(zpath, timestamp)| (zpath.clone(), *timestamp))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    // Whether an entry is indexed, if the index is loaded
+    pub(crate) fn contains(&self, zpath: &str) -> Option<bool> {
+        match &*self.state.lock().unwrap() {
+            IndexState::Loaded(entries) => Some(entries.contains_key(zpath)),
+            _ => None,
+        }
+    }
+
+    // Start the loading of the index, returning false if it's already loading (or loaded)
+    pub(crate) fn begin_load(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match *state {
+            IndexState::Unloaded => {
+                *state = IndexState::Loading(Vec::new());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Complete the loading with the entries found by the walk (or cancel it if the walk failed)
+    pub(crate) fn end_load(&self, walked: Option<&[(String, Timestamp)]>) {
+        let mut state = self.state.lock().unwrap();
+        let IndexState::Loading(updates) = mem::replace(&mut *state, IndexState::Unloaded) else {
+            return;
+        };
+        if let Some(walked) = walked {
+            let mut entries: HashMap<String, Timestamp> = walked.iter().cloned().collect();
+            for (zpath, timestamp) in updates {
+                match timestamp {
+                    Some(timestamp) => entries.insert(zpath, timestamp),
+                    None => entries.remove(&zpath),
+                };
+            }
+            *state = IndexState::Loaded(entries);
+        }
+    }
+
+    pub(crate) fn put(&self, zpath: &str, timestamp: &Timestamp) {
+        match &mut *self.state.lock().unwrap() {
+            IndexState::Unloaded => (),
+            IndexState::Loading(updates) => updates.push((zpath.to_string(), Some(*timestamp))),
+            IndexState::Loaded(entries) => {
+                entries.insert(zpath.to_string(), *timestamp);
+            }
+        }
+    }
+
+    pub(crate) fn delete(&self, zpath: &str) {
+        match &mut *self.state.lock().unwrap() {
+            IndexState::Unloaded => (),
+            IndexState::Loading(updates) => updates.push((zpath.to_string(), None)),
+            IndexState::Loaded(entries) => {
+                entries.remove(zpath);
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fs::File,
+    hash::Hasher,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use siphasher::sip::SipHasher13;
+use zenoh::{
+    bytes::Encoding,
+    internal::{bail, zerror},
+    time::Timestamp,
+    Result as ZResult,
+};
+
+use crate::data_info_mgt::{
+    decode_encoding_timestamp_from_value, encode_encoding_timestamp_to_value,
+};
+
+// The envelope of the values written in the files (if enabled), for a storage directory to be self-describing:
+// this magic number, the format's version, the length (u16 little-endian) of the value's data-info and the data-info
+// (serialized as in the data-info database), then a checksum (u64 little-endian) of the payload and the payload.
+// The files are recognized by their magic number, whether the envelopes are enabled or not.
+const MAGIC: &[u8] = b"\x89ZFE";
+const VERSION: u8 = 1;
+
+// Wrap a payload in an envelope
+pub(crate) fn wrap(payload: &[u8], encoding: Encoding, timestamp: &Timestamp) -> ZResult<Vec<u8>> {
+    let info = encode_encoding_timestamp_to_value(encoding, timestamp);
+    if info.len() > u16::MAX as usize {
+        bail!("Failed to wrap payload in envelope: encoding schema too long")
+    }
+    let mut content = Vec::with_capacity(MAGIC.len() + 3 + info.len() + 8 + payload.len());
+    content.extend_from_slice(MAGIC);
+    content.push(VERSION);
+    content.extend_from_slice(&(info.len() as u16).to_le_bytes());
+    content.extend_from_slice(&info);
+    content.extend_from_slice(&checksum(payload).to_le_bytes());
+    content.extend_from_slice(payload);
+    Ok(content)
+}
+
+// Return the payload of a file's content, after checking its checksum (the content as is if it has no envelope)
+pub(crate) fn unwrap(content: Vec<u8>) -> ZResult<Vec<u8>> {
+    let mut reader = content.as_slice();
+    let Some((_, expected)) = read_header(&mut reader)? else {
+        return Ok(content);
+    };
+    if checksum(reader) != expected {
+        bail!("Invalid envelope: the checksum of the payload doesn't match")
+    }
+    Ok(reader.to_vec())
+}
+
+// The encoding and timestamp in the envelope of a file, if any
+pub(crate) fn read_info(file: &Path) -> ZResult<Option<(Encoding, Timestamp)>> {
+    let mut f = File::open(file).map_err(|e| zerror!("Failed to open file {:?}: {}", file, e))?;
+    Ok(read_header(&mut f)?.map(|(info, _)| info))
+}
+
+// Skip the envelope's header of a file (if it has one), leaving the reader on the payload, without checking its
+// checksum (e.g. to read a part of the payload only)
+pub(crate) fn skip_header<R: Read + Seek>(reader: &mut R) -> ZResult<()> {
+    let start = reader
+        .stream_position()
+        .map_err(|e| zerror!("Failed to read envelope: {}", e))?;
+    if read_header(reader)?.is_none() {
+        reader
+            .seek(SeekFrom::Start(start))
+            .map_err(|e| zerror!("Failed to read envelope: {}", e))?;
+    }
+    Ok(())
+}
+
+// Read a file's content to check the checksum of its envelope (if it has one)
+pub(crate) fn check<R: Read>(mut reader: R) -> ZResult<()> {
+    let header = read_header(&mut reader)?;
+    let mut hasher = SipHasher13::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .map_err(|e| zerror!("Failed to read: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    match header {
+        Some((_, expected)) if hasher.finish() != expected => {
+            bail!("Invalid envelope: the checksum of the payload doesn't match")
+        }
+        _ => Ok(()),
+    }
+}
+
+// Read the envelope's header (the data-info and the checksum), leaving the reader on the payload.
+// Returns None if the content has no envelope.
+fn read_header<R: Read>(reader: &mut R) -> ZResult<Option<((Encoding, Timestamp), u64)>> {
+    let mut magic = [0u8; 4];
+    match reader.read_exact(&mut magic) {
+        Ok(()) if magic == MAGIC => (),
+        Ok(()) => return Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => bail!("Failed to read envelope: {}", e),
+    }
+    let invalid = |e: io::Error| zerror!("Invalid envelope: {}", e);
+    let mut version_and_len = [0u8; 3];
+    reader.read_exact(&mut version_and_len).map_err(invalid)?;
+    if version_and_len[0] != VERSION {
+        bail!("Unsupported envelope version {}", version_and_len[0])
+    }
+    let mut info = vec![0u8; u16::from_le_bytes([version_and_len[1], version_and_len[2]]) as usize];
+    reader.read_exact(&mut info).map_err(invalid)?;
+    let info = decode_encoding_timestamp_from_value(&info)?;
+    let mut checksum = [0u8; 8];
+    reader.read_exact(&mut checksum).map_err(invalid)?;
+    Ok(Some((info, u64::from_le_bytes(checksum))))
+}
+
+fn checksum(payload: &[u8]) -> u64 {
+    let mut hasher = SipHasher13::new();
+    hasher.write(payload);
+    hasher.finish()
+}
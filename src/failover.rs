@@ -0,0 +1,120 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    path::PathBuf,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{info, warn};
+use zenoh::{internal::zerror, Result as ZResult};
+
+use crate::files_mgt::{FilesMgr, FilesMgrSettings};
+
+// The failover of the reads of a storage to a replica directory (e.g. its mirror), while its directories are
+// unavailable. The reads fail back to the storage's directories as soon as they're available again.
+pub(crate) struct Failover {
+    dir: PathBuf,
+    // the directory of the data-info database of the replica, out of the replica's directory
+    data_info_dir: PathBuf,
+    // the FilesMgr reading the replica, built on the first failover
+    replica: Option<Arc<FilesMgr>>,
+    // since when the reads are failed over, with the reason
+    active: Option<(SystemTime, String)>,
+    failovers: u64,
+    reads: u64,
+}
+
+impl Failover {
+    pub(crate) fn new(dir: PathBuf, data_info_dir: PathBuf) -> Self {
+        Failover {
+            dir,
+            data_info_dir,
+            replica: None,
+            active: None,
+            failovers: 0,
+            reads: 0,
+        }
+    }
+
+    // Fail the reads over to the replica, returning its FilesMgr (built with the storage's settings if not yet).
+    // The replica is only read: its files are never modified.
+    pub(crate) async fn fail_over(
+        &mut self,
+        storage: &str,
+        reason: &str,
+        settings: &FilesMgrSettings,
+    ) -> ZResult<Arc<FilesMgr>> {
+        let replica = match &self.replica {
+            Some(replica) => {
+                replica.check_available()?;
+                replica.clone()
+            }
+            None => {
+                std::fs::create_dir_all(&self.data_info_dir).map_err(|e| {
+                    zerror!(
+                        "Failed to create the data-info directory of the replica {:?}: {}",
+                        self.dir,
+                        e
+                    )
+                })?;
+                // (the stripes being merged in the replica)
+                let settings = settings.read_only(self.data_info_dir.clone());
+                let replica = Arc::new(settings.build(self.dir.clone()).await?);
+                self.replica = Some(replica.clone());
+                replica
+            }
+        };
+        if self.active.is_none() {
+            warn!(
+                "Storage {}: reads failed over to {:?}: {}",
+                storage, self.dir, reason
+            );
+            self.failovers += 1;
+            self.active = Some((SystemTime::now(), reason.to_string()));
+        }
+        self.reads += 1;
+        Ok(replica)
+    }
+
+    // Fail the reads back to the storage's directories, if failed over
+    pub(crate) fn fail_back(&mut self, storage: &str) {
+        if let Some((since, _)) = self.active.take() {
+            info!(
+                "Storage {}: reads failed back from {:?}, after {}s",
+                storage,
+                self.dir,
+                since.elapsed().unwrap_or_default().as_secs()
+            );
+        }
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let mut status = serde_json::json!({
+            "dir": self.dir,
+            "active": self.active.is_some(),
+            "failovers": self.failovers,
+            "reads": self.reads,
+        });
+        if let Some((since, reason)) = &self.active {
+            status["since"] = since
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+                .into();
+            status["reason"] = reason.as_str().into();
+        }
+        status
+    }
+}
@@ -0,0 +1,97 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use siphasher::sip::SipHasher13;
+use tracing::warn;
+use zenoh::{internal::bail, Result as ZResult};
+
+use crate::{FsFaultInjectionConfig, PROP_STORAGE_FAULT_INJECTION};
+
+// The faults injected into the file operations of a storage, for tests to exercise the error paths.
+// Each operation is delayed by `latency`, then fails with a probability of `io_error_rate`.
+// A write which doesn't fail is interrupted after half of the content with a probability of `partial_write_rate`,
+// leaving a truncated file as on a crash.
+// The draws are pseudo-random, and reproducible if a `seed` is configured.
+pub(crate) struct Faults {
+    io_error_rate: f64,
+    partial_write_rate: f64,
+    latency: Duration,
+    hasher: SipHasher13,
+    draws: AtomicU64,
+}
+
+impl Faults {
+    pub(crate) fn from_config(config: &FsFaultInjectionConfig) -> ZResult<Self> {
+        let rate = |name: &str, rate: Option<f64>| -> ZResult<f64> {
+            match rate {
+                Some(r) if !(0.0..=1.0).contains(&r) => bail!(
+                    r#"Invalid value for File System Storage configuration: `{}.{}` must be a number between 0 and 1"#,
+                    PROP_STORAGE_FAULT_INJECTION,
+                    name
+                ),
+                Some(r) => Ok(r),
+                None => Ok(0.0),
+            }
+        };
+        let hasher = match config.seed {
+            Some(seed) => SipHasher13::new_with_keys(seed, 0),
+            None => SipHasher13::new_with_keys(RandomState::new().hash_one(0u8), 0),
+        };
+        let faults = Faults {
+            io_error_rate: rate("io_error_rate", config.io_error_rate)?,
+            partial_write_rate: rate("partial_write_rate", config.partial_write_rate)?,
+            latency: Duration::from_millis(config.latency_ms.unwrap_or(0)),
+            hasher,
+            draws: AtomicU64::new(0),
+        };
+        warn!(
+            "Fault injection enabled: IO error rate {}, partial write rate {}, latency {:?}",
+            faults.io_error_rate, faults.partial_write_rate, faults.latency
+        );
+        Ok(faults)
+    }
+
+    // Delay the operation on a file, then fail it according to the configured rate
+    pub(crate) async fn inject(&self, op: &str, file: &Path) -> ZResult<()> {
+        if !self.latency.is_zero() {
+            tokio::time::sleep(self.latency).await;
+        }
+        if self.draw(self.io_error_rate) {
+            bail!("Injected IO error on {} of file {:?}", op, file)
+        }
+        Ok(())
+    }
+
+    // The length to which a write of `len` bytes is truncated, if it's interrupted
+    pub(crate) fn partial_write(&self, len: usize) -> Option<usize> {
+        self.draw(self.partial_write_rate).then_some(len / 2)
+    }
+
+    // Returns true with the probability `rate`
+    fn draw(&self, rate: f64) -> bool {
+        if rate <= 0.0 {
+            return false;
+        }
+        let mut hasher = self.hasher;
+        hasher.write_u64(self.draws.fetch_add(1, Ordering::Relaxed));
+        (hasher.finish() as f64 / u64::MAX as f64) < rate
+    }
+}
@@ -13,38 +13,325 @@
 //
 use std::{
     borrow::Cow,
-    convert::TryFrom,
+    collections::{BTreeSet, HashMap},
     fmt,
-    fs::{metadata, remove_dir, remove_dir_all, remove_file, rename, DirBuilder, File},
+    fs::{
+        copy, metadata, remove_dir, remove_dir_all, remove_file, rename, DirBuilder, File,
+        OpenOptions,
+    },
+    future::Future,
+    hash::Hasher,
     io::prelude::*,
     iter::Iterator,
+    ops::Range,
     path::{Path, PathBuf},
-    time::{SystemTime, UNIX_EPOCH},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-use tracing::{debug, trace, warn};
+use siphasher::sip::SipHasher13;
+use tokio::{
+    runtime::Handle,
+    sync::{Semaphore, SemaphorePermit},
+};
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+use tracing::{debug, error, trace, warn};
 use walkdir::{IntoIter, WalkDir};
 use zenoh::{
     bytes::{Encoding, ZBytes},
     internal::{
         bail,
-        buffers::{SplitBuffer, ZBuf},
+        buffers::{Buffer, SplitBuffer, ZBuf},
         zerror,
     },
-    key_expr::keyexpr,
-    time::{Timestamp, TimestampId},
+    key_expr::{keyexpr, OwnedKeyExpr},
+    time::{Timestamp, TimestampId, NTP64},
     Result as ZResult,
 };
 
-use crate::{blockon_runtime, data_info_mgt::*};
+#[cfg(feature = "fault-injection")]
+use crate::faults::Faults;
+#[cfg(feature = "shared-memory")]
+use crate::shm::ShmPayloads;
+use crate::{
+    archive_mgt::{ArchiveEntry, ArchiveMgr},
+    availability::Availability,
+    cold_tier::ColdTier,
+    data_info_mgt::*,
+    dir_listings::{DirChild, DirListings},
+    entries_index::EntriesIndex,
+    envelope,
+    fs_info::sync_fs,
+    handles_pool::HandlesPool,
+    immutable::set_immutable,
+    key_locks::KeyLocks,
+    links::{is_loop, is_symlink, is_unreported_link},
+    mirror::Mirror,
+    names::{random_salt, NamesMapper},
+    policies::{Policy, PolicyConfig},
+    prefetch::{Prefetcher, MAX_PREFETCHED_FILE_SIZE},
+    progress::InitProgress,
+    quota::{DiskQuota, QuotaShare},
+    retry::RetryPolicy,
+    storage_log::StorageLog,
+    tenants::{Tenant, TenantConfig},
+    transforms::Transforms,
+    watermark::Watermark,
+    write_queue::{OverloadPolicy, QueuedWrite, WriteQueue},
+    PayloadTransform, PROP_STORAGE_POLICIES, PROP_STORAGE_READ_AHEAD, PROP_STORAGE_TENANTS,
+    ROOT_KEY,
+};
 
 pub const CONFLICT_SUFFIX: &str = ".##z";
 
+// The prefix of the files renamed by an NFS client when removed while open ("silly renames")
+const NFS_SILLY_RENAME_PREFIX: &str = ".nfs";
+
+// The directory (in each stripe's root) where the files failing to be decoded are moved
+pub(crate) const QUARANTINE_DIR: &str = ".zenoh_quarantine";
+
+#[derive(Clone, Copy)]
 pub(crate) enum OnClosure {
     DeleteAll,
     DoNothing,
 }
 
+// What to do on a write conflicting with an existing file or directory (e.g. on `a/b` while `a` is a file, or on `a`
+// while `a` is a directory)
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnConflict {
+    // rename the conflicting file with CONFLICT_SUFFIX (or write the value in a conflict file, if a directory)
+    Rename,
+    // reject the write
+    Reject,
+    // the most recent wins: the conflicting entries are deleted if older than the write, or else the write is outdated
+    Overwrite,
+}
+
+impl OnConflict {
+    pub(crate) const ALL: &'static [&'static str] = &["rename", "reject", "overwrite"];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rename" => Some(OnConflict::Rename),
+            "reject" => Some(OnConflict::Reject),
+            "overwrite" => Some(OnConflict::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+// The settings of the FilesMgr of a storage, to build it in the storage's directory (or in a new one, on migration)
+#[derive(Clone)]
+pub(crate) struct FilesMgrSettings {
+    pub(crate) follow_links: bool,
+    // the maximum number of symbolic links followed in a file's path, if bounded
+    pub(crate) max_link_depth: Option<usize>,
+    // the directories of the stripes other than the storage's directory
+    pub(crate) stripes: Vec<PathBuf>,
+    pub(crate) keep_mime: bool,
+    pub(crate) on_closure: OnClosure,
+    pub(crate) on_conflict: OnConflict,
+    pub(crate) max_concurrent_ops: Option<usize>,
+    pub(crate) open_files_cache_size: Option<usize>,
+    // the number of files read ahead of the sequentially read keys, if enabled
+    pub(crate) read_ahead: Option<usize>,
+    pub(crate) data_info_cache_size: Option<usize>,
+    pub(crate) dir_listings_cache_size: Option<usize>,
+    pub(crate) bloom_filter: bool,
+    pub(crate) watermark_by_prefix: bool,
+    pub(crate) obfuscate_names: bool,
+    pub(crate) obfuscation_salt: Option<String>,
+    // the shared-memory pool size and threshold, if enabled
+    #[cfg(feature = "shared-memory")]
+    pub(crate) shm: Option<(usize, usize)>,
+    pub(crate) archive_after_days: Option<usize>,
+    pub(crate) retention_days: Option<usize>,
+    pub(crate) quota: Option<Arc<DiskQuota>>,
+    pub(crate) tenants: Option<Vec<TenantConfig>>,
+    pub(crate) policies: Vec<PolicyConfig>,
+    pub(crate) canonicalize_keys: bool,
+    pub(crate) zenoh_write_only: bool,
+    pub(crate) default_encoding: Option<Encoding>,
+    pub(crate) timestamp_id: Option<TimestampId>,
+    pub(crate) immutable: bool,
+    pub(crate) encoding_schemas: Vec<(PathBuf, String)>,
+    pub(crate) encoding_overrides: Vec<(String, Encoding)>,
+    pub(crate) entries_index: bool,
+    pub(crate) payload_transforms: Vec<Arc<dyn PayloadTransform>>,
+    pub(crate) envelope: bool,
+    // in passthrough mode, the directory of the data-info database (outside of the storage's directory)
+    pub(crate) passthrough: Option<PathBuf>,
+    #[cfg(feature = "fault-injection")]
+    pub(crate) faults: Option<Arc<Faults>>,
+    // the storage's dedicated runtime, if any
+    pub(crate) runtime: Option<Handle>,
+    // the depth and overload policy of the write queue, if bounded
+    pub(crate) write_queue: Option<(usize, OverloadPolicy)>,
+    pub(crate) nfs_safe: bool,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) verify_writes: bool,
+    pub(crate) dry_run: bool,
+    // the storage is in "read_only" mode
+    pub(crate) read_only: bool,
+    // the replication of the files to a mirror directory, shared by the storage's FilesMgrs
+    pub(crate) mirror: Option<Arc<Mirror>>,
+    // with a lower layer, the deletions are recorded as whiteouts
+    pub(crate) whiteouts: bool,
+    // the cold tier the values not accessed for a while are offloaded to, shared by the storage's FilesMgrs
+    pub(crate) cold_tier: Option<Arc<ColdTier>>,
+    // the logging of the storage, shared by its FilesMgrs
+    pub(crate) log: Arc<StorageLog>,
+}
+
+impl FilesMgrSettings {
+    // The settings of a FilesMgr only reading a replica or a layer of the storage (never modifying its files), with
+    // its data-info database in data_info_dir (out of the read directory)
+    pub(crate) fn read_only(&self, data_info_dir: PathBuf) -> Self {
+        FilesMgrSettings {
+            stripes: vec![],
+            passthrough: Some(data_info_dir),
+            archive_after_days: None,
+            retention_days: None,
+            quota: None,
+            immutable: false,
+            write_queue: None,
+            mirror: None,
+            whiteouts: false,
+            cold_tier: None,
+            dry_run: true,
+            ..self.clone()
+        }
+    }
+
+    // Build the FilesMgr. The existing files are then to be accounted in its quotas, with account_existing_files().
+    pub(crate) async fn build(&self, base_dir: PathBuf) -> ZResult<FilesMgr> {
+        let mut files_mgr = FilesMgr::new(
+            base_dir,
+            self.follow_links,
+            self.keep_mime,
+            self.on_closure,
+            self.max_concurrent_ops,
+            self.open_files_cache_size,
+            self.passthrough.as_deref(),
+            !self.read_only && !self.dry_run,
+        )
+        .await?
+        .with_log(self.log.clone())
+        .with_on_conflict(self.on_conflict);
+        if let Some(capacity) = self.data_info_cache_size {
+            files_mgr = files_mgr.with_data_info_cache(capacity);
+        }
+        if let Some(capacity) = self.dir_listings_cache_size {
+            files_mgr = files_mgr.with_dir_listings_cache(capacity);
+        }
+        if self.bloom_filter {
+            files_mgr = files_mgr.with_keys_filter().await?;
+        }
+        if self.watermark_by_prefix {
+            files_mgr = files_mgr.with_watermark_by_prefix();
+        }
+        if let Some(depth) = self.read_ahead {
+            files_mgr = files_mgr.with_read_ahead(depth);
+        }
+        if self.obfuscate_names {
+            // first, as the other settings may depend on the files' names
+            files_mgr = files_mgr
+                .with_obfuscated_names(self.obfuscation_salt.clone())
+                .await?;
+        }
+        #[cfg(feature = "shared-memory")]
+        if let Some((pool_size, threshold)) = self.shm {
+            files_mgr = files_mgr.with_shm(ShmPayloads::new(pool_size, threshold)?);
+        }
+        // before the settings depending on the files' paths
+        files_mgr = files_mgr.with_stripes(self.stripes.clone()).await?;
+        if let Some(depth) = self.max_link_depth {
+            files_mgr = files_mgr.with_max_link_depth(depth);
+        }
+        if let Some(days) = self.archive_after_days {
+            files_mgr = files_mgr.with_archive(days as u64);
+        }
+        if let Some(days) = self.retention_days {
+            files_mgr = files_mgr.with_retention(days as u64);
+        }
+        if let Some(quota) = &self.quota {
+            files_mgr = files_mgr.with_quota(quota.clone());
+        }
+        if let Some(tenants) = &self.tenants {
+            files_mgr = files_mgr.with_tenants(tenants.clone());
+        }
+        if self.canonicalize_keys {
+            files_mgr = files_mgr.with_canonical_keys();
+        }
+        if self.zenoh_write_only {
+            files_mgr = files_mgr.with_zenoh_write_only();
+        }
+        if let Some(encoding) = &self.default_encoding {
+            files_mgr = files_mgr.with_default_encoding(encoding.clone());
+        }
+        if let Some(id) = self.timestamp_id {
+            files_mgr = files_mgr.with_timestamp_id(id);
+        }
+        if self.immutable {
+            files_mgr = files_mgr.with_immutable()?;
+        }
+        if !self.encoding_schemas.is_empty() {
+            files_mgr = files_mgr.with_encoding_schemas(self.encoding_schemas.clone());
+        }
+        if !self.encoding_overrides.is_empty() {
+            files_mgr = files_mgr.with_encoding_overrides(self.encoding_overrides.clone());
+        }
+        if self.entries_index {
+            files_mgr = files_mgr.with_entries_index();
+        }
+        if !self.payload_transforms.is_empty() {
+            files_mgr = files_mgr.with_transforms(self.payload_transforms.clone())?;
+        }
+        if !self.policies.is_empty() {
+            files_mgr = files_mgr.with_policies(self.policies.clone())?;
+        }
+        if let Some(runtime) = &self.runtime {
+            files_mgr = files_mgr.with_runtime(runtime.clone());
+        }
+        if let Some((depth, policy)) = self.write_queue {
+            files_mgr = files_mgr.with_write_queue(depth, policy);
+        }
+        if self.envelope {
+            files_mgr = files_mgr.with_envelope();
+        }
+        if self.nfs_safe {
+            files_mgr = files_mgr.with_nfs_safe();
+        }
+        files_mgr = files_mgr.with_retry(self.retry.clone()).await;
+        if self.verify_writes {
+            files_mgr = files_mgr.with_verified_writes();
+        }
+        if self.dry_run {
+            files_mgr = files_mgr.with_dry_run();
+        }
+        if let Some(mirror) = &self.mirror {
+            files_mgr = files_mgr.with_mirror(mirror.clone());
+        }
+        if self.whiteouts {
+            files_mgr = files_mgr.with_whiteouts();
+        }
+        if let Some(cold_tier) = &self.cold_tier {
+            files_mgr = files_mgr.with_cold_tier(cold_tier.clone());
+        }
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = &self.faults {
+            files_mgr = files_mgr.with_faults(faults.clone());
+        }
+        // the directories being there, for their unmounting to be detected
+        files_mgr.check_available()?;
+        Ok(files_mgr)
+    }
+}
+
 // a structure holding a zenoh path (absolute) and the corresponding file-system path (including the base_dir)
 #[derive(Debug)]
 pub(crate) struct ZFile<'a> {
@@ -52,6 +339,24 @@ pub(crate) struct ZFile<'a> {
     fspath: PathBuf,
 }
 
+impl ZFile<'_> {
+    // The path of the file storing the value (unless renamed because of a conflict)
+    pub(crate) fn fspath(&self) -> &Path {
+        &self.fspath
+    }
+}
+
+// A value stored in a conflict file (renamed with CONFLICT_SUFFIX), a directory having taken the path of its file
+pub(crate) struct Conflict {
+    pub(crate) zpath: String,
+    pub(crate) timestamp: Timestamp,
+    // the path of the conflict file, relative to its stripe's root
+    pub(crate) file: PathBuf,
+    // the path of the conflicting directory, relative to its stripe's root (None if removed since, the conflict file
+    // being then renamed back by the `normalize` maintenance job)
+    pub(crate) dir: Option<PathBuf>,
+}
+
 impl fmt::Display for ZFile<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self.fspath)
@@ -60,11 +365,113 @@ impl fmt::Display for ZFile<'_> {
 
 pub(crate) struct FilesMgr {
     base_dir: PathBuf,
+    // the directories of the other stripes, the files being distributed across them and base_dir (if set)
+    stripes: Vec<PathBuf>,
+    // whether base_dir and the stripes are available (e.g. not unmounted)
+    availability: Availability,
     data_info_mgr: DataInfoMgr,
     follow_links: bool,
+    // the maximum number of symbolic links followed in a file's path (if following them), if bounded
+    max_link_depth: Option<usize>,
     keep_mime: bool,
+    // the encoding of the files without data-info, if not deduced from their extension
+    default_encoding: Encoding,
+    // the id of the timestamps of the files without data-info (derived from their modification time)
+    timestamp_id: TimestampId,
+    // the encoding schema of the files without data-info, per directory (the deepest directories first)
+    encoding_schemas: Vec<(PathBuf, String)>,
+    // the encoding of the files without data-info, per key prefix (the longest prefixes first)
+    encoding_overrides: Vec<(String, Encoding)>,
     dir_builder: DirBuilder,
     on_closure: OnClosure,
+    // what to do on a write conflicting with an existing file or directory
+    on_conflict: OnConflict,
+    // the number of writes which conflicted with an existing file or directory
+    conflicts: AtomicU64,
+    // bounds the number of file operations in progress (excess operations are queued)
+    ops_permits: Semaphore,
+    // bounds the number of writes waiting or in progress, if set
+    write_queue: Option<WriteQueue>,
+    handles_pool: HandlesPool,
+    // the cache of the directories' listings for the wildcard queries, if enabled
+    dir_listings: Option<Arc<DirListings>>,
+    // the read-ahead of the files of the sequentially read keys, if enabled
+    prefetcher: Option<Prefetcher>,
+    // the most recent timestamp of the stored values
+    watermark: Watermark,
+    #[cfg(feature = "shared-memory")]
+    shm: Option<ShmPayloads>,
+    // the faults injected into the file operations, for tests
+    #[cfg(feature = "fault-injection")]
+    faults: Option<Arc<Faults>>,
+    archive_mgr: Option<ArchiveMgr>,
+    retention: Option<Duration>,
+    // the part of the volume's total size cap used by this storage, if a cap is set
+    quota: Option<QuotaShare>,
+    // set the immutable attribute on the written files
+    immutable: bool,
+    // if set, only the keys starting with a tenant's name are accepted
+    tenants: Option<HashMap<String, Tenant>>,
+    names: Arc<NamesMapper>,
+    // canonicalize the keys before mapping them to files
+    canonicalize_keys: bool,
+    // the directory is only written by the storage: all the files have data-info
+    zenoh_write_only: bool,
+    // the in-memory index of the entries, if enabled
+    index: Option<EntriesIndex>,
+    // the transforms of the payloads written in the files (unless overridden by their policy)
+    transforms: Transforms,
+    // the policies of the key prefixes (the longest prefixes first)
+    policies: Vec<Policy>,
+    // wrap the values written in the files in an envelope with their data-info
+    envelope: bool,
+    // never rename the existing files, nor remove the empty directories
+    passthrough: bool,
+    // the storage's dedicated runtime, running its background tasks (if set)
+    runtime: Option<Handle>,
+    // the directory is on a network file-system (e.g. NFS or SMB)
+    nfs_safe: bool,
+    // how the file operations failing with a transient error are retried
+    retry: RetryPolicy,
+    // the number of files moved into quarantine
+    quarantined: AtomicU64,
+    // the files are never modified: the writes are only checked
+    dry_run: bool,
+    // the mirror the changed files are replicated to, if any
+    mirror: Option<Arc<Mirror>>,
+    // the deletions hide the entries of the storage's lower layer
+    whiteouts: bool,
+    // the cold tier the values not accessed for a while are offloaded to, if any
+    cold_tier: Option<Arc<ColdTier>>,
+    log: Arc<StorageLog>,
+    // read back each written file, checking its checksum
+    verify_writes: bool,
+    // set once the storage is being closed, for its background tasks to stop
+    closing: AtomicBool,
+    // cancelled once the storage is being closed, for its idle background tasks to stop at once
+    closing_token: CancellationToken,
+    // the background tasks of the storage, awaited on its closure
+    tasks: TaskTracker,
+    // set once shutdown() completed, the destructor having nothing left to do
+    closed: AtomicBool,
+    // cancelled once shutdown() completed, for the other closures to wait for it
+    closed_token: CancellationToken,
+    // the last snapshot of the statistics read from the data-info database
+    db_stats: Mutex<DbStats>,
+    // the locks of the keys being updated
+    key_locks: KeyLocks,
+}
+
+// The statistics read from the data-info database (locked asynchronously), refreshed in background for the admin
+// status to read them without blocking
+#[derive(Default)]
+struct DbStats {
+    data_info: serde_json::Value,
+    conflict_files: serde_json::Value,
+    // the number of stubs of the values offloaded to the cold tier, if any
+    cold_stubs: Option<usize>,
+    // the number of whiteouts hiding entries of the lower layer, if any
+    whiteouts: Option<usize>,
 }
 
 impl FilesMgr {
@@ -73,158 +480,2219 @@ impl FilesMgr {
         follow_links: bool,
         keep_mime: bool,
         on_closure: OnClosure,
+        max_concurrent_ops: Option<usize>,
+        open_files_cache_size: Option<usize>,
+        passthrough_data_info: Option<&Path>,
+        writable: bool,
     ) -> ZResult<Self> {
-        let data_info_mgr = DataInfoMgr::new(base_dir.as_path()).await?;
+        // in passthrough mode, nothing is added to base_dir
+        let data_info_mgr = match passthrough_data_info {
+            Some(dir) => DataInfoMgr::new(dir).await?,
+            None => DataInfoMgr::new(&base_dir.join(DataInfoMgr::DB_FILENAME)).await?,
+        };
+        let names = NamesMapper::new(None, data_info_mgr.get_names().await?);
+        // a random id, distinct for each storage and kept across restarts (only kept in memory if nothing must be
+        // written, e.g. for a replica or a dry run)
+        let timestamp_id = match data_info_mgr.get_timestamp_id().await? {
+            Some(id) => id,
+            None => {
+                let id = TimestampId::rand();
+                if writable {
+                    data_info_mgr.put_timestamp_id(&id).await?;
+                }
+                id
+            }
+        };
 
         let mut dir_builder = DirBuilder::new();
         dir_builder.recursive(true);
+        let log = Arc::new(StorageLog::new(&base_dir.to_string_lossy(), None, false));
 
         Ok(FilesMgr {
             base_dir,
+            stripes: Vec::new(),
+            availability: Availability::new(),
             data_info_mgr,
             follow_links,
+            max_link_depth: None,
             keep_mime,
+            default_encoding: Encoding::APPLICATION_OCTET_STREAM,
+            timestamp_id,
+            encoding_schemas: Vec::new(),
+            encoding_overrides: Vec::new(),
             dir_builder,
             on_closure,
+            // (in passthrough mode, the existing files are never renamed)
+            on_conflict: match passthrough_data_info {
+                Some(_) => OnConflict::Reject,
+                None => OnConflict::Rename,
+            },
+            conflicts: AtomicU64::new(0),
+            ops_permits: Semaphore::new(max_concurrent_ops.unwrap_or(Semaphore::MAX_PERMITS)),
+            write_queue: None,
+            handles_pool: HandlesPool::new(open_files_cache_size.unwrap_or(0)),
+            dir_listings: None,
+            prefetcher: None,
+            watermark: Watermark::new(false),
+            #[cfg(feature = "shared-memory")]
+            shm: None,
+            #[cfg(feature = "fault-injection")]
+            faults: None,
+            archive_mgr: None,
+            retention: None,
+            quota: None,
+            immutable: false,
+            tenants: None,
+            names: Arc::new(names),
+            canonicalize_keys: false,
+            zenoh_write_only: false,
+            index: None,
+            transforms: Transforms::default(),
+            policies: Vec::new(),
+            envelope: false,
+            passthrough: passthrough_data_info.is_some(),
+            runtime: None,
+            nfs_safe: false,
+            retry: RetryPolicy::none(),
+            quarantined: AtomicU64::new(0),
+            dry_run: false,
+            mirror: None,
+            whiteouts: false,
+            cold_tier: None,
+            log,
+            verify_writes: false,
+            closing: AtomicBool::new(false),
+            closing_token: CancellationToken::new(),
+            tasks: TaskTracker::new(),
+            closed: AtomicBool::new(false),
+            closed_token: CancellationToken::new(),
+            db_stats: Mutex::new(DbStats::default()),
+            key_locks: KeyLocks::default(),
         })
     }
 
-    pub(crate) fn base_dir(&self) -> &Path {
-        self.base_dir.as_path()
+    // Store the files under salted hashes of the key chunks, for the key hierarchy not to be visible in base_dir.
+    // The salt must remain the same for the storage's lifetime: if not configured, a random one is saved in data-info.
+    pub(crate) async fn with_obfuscated_names(mut self, salt: Option<String>) -> ZResult<Self> {
+        let salt = match (salt, self.data_info_mgr.get_salt().await?) {
+            (Some(salt), Some(saved)) if salt != saved => bail!(
+                "The configured salt for file names differs from the one used so far in {:?}: the stored files couldn't be found",
+                self.base_dir
+            ),
+            (_, Some(saved)) => saved,
+            (salt, None) => {
+                let salt = salt.unwrap_or_else(random_salt);
+                self.data_info_mgr.put_salt(&salt).await?;
+                salt
+            }
+        };
+        let names = self.data_info_mgr.get_names().await?;
+        self.names = Arc::new(NamesMapper::new(Some(&salt), names));
+        Ok(self)
     }
 
-    pub(crate) fn to_zfile<'a>(&self, zpath: &'a str) -> ZFile<'a> {
-        ZFile {
-            zpath: Cow::from(zpath),
-            fspath: self.to_fspath(zpath),
+    // Cache up to `capacity` data-info records in memory, for the reads not to query the database
+    pub(crate) fn with_data_info_cache(mut self, capacity: usize) -> Self {
+        self.data_info_mgr.enable_cache(capacity);
+        self
+    }
+
+    // Keep a Bloom filter of the keys of the data-info in memory, for the lookups of the files without data-info not to
+    // query the database
+    pub(crate) async fn with_keys_filter(mut self) -> ZResult<Self> {
+        self.data_info_mgr.enable_keys_filter().await?;
+        Ok(self)
+    }
+
+    // Cache up to `capacity` listings of directories in memory, for the wildcard queries not to read again the
+    // directories which didn't change
+    pub(crate) fn with_dir_listings_cache(mut self, capacity: usize) -> Self {
+        self.dir_listings = Some(Arc::new(DirListings::new(capacity)));
+        self
+    }
+
+    // Check that base_dir and the stripes' directories are available, failing otherwise (e.g. if unmounted). The
+    // storage is then degraded, until they're available again.
+    pub(crate) fn check_available(&self) -> ZResult<()> {
+        self.availability.check(self.roots())
+    }
+
+    pub(crate) fn availability_status(&self) -> serde_json::Value {
+        self.availability.status_json()
+    }
+
+    // Distribute the files across base_dir and the stripes' directories. The stripes are recorded in the data-info, as
+    // they can't be changed once files are stored (their paths deciding their stripe).
+    pub(crate) async fn with_stripes(mut self, stripes: Vec<PathBuf>) -> ZResult<Self> {
+        match self.data_info_mgr.get_stripes().await? {
+            Some(recorded) if recorded != stripes => bail!(
+                "Cannot change the stripes of the storage on {:?} from {:?} to {:?}: the files are stored across them",
+                self.base_dir,
+                recorded,
+                stripes
+            ),
+            Some(_) => (),
+            None if stripes.is_empty() => (),
+            None => {
+                if self
+                    .matching_files(unsafe { keyexpr::from_str_unchecked("**") })
+                    .next()
+                    .is_some()
+                {
+                    bail!(
+                        "Cannot stripe the storage on {:?}: it already stores files",
+                        self.base_dir
+                    )
+                }
+                self.data_info_mgr.put_stripes(&stripes).await?;
+            }
         }
+        self.stripes = stripes;
+        Ok(self)
     }
 
-    fn to_fspath(&self, zpath: &str) -> PathBuf {
-        let mut fspath = self.base_dir.clone();
-        fspath.push(zpath_to_fspath(zpath).as_ref());
-        fspath
+    pub(crate) fn with_max_link_depth(mut self, depth: usize) -> Self {
+        self.max_link_depth = Some(depth);
+        self
     }
 
-    // ### Behaviour in case of conflict
-    // A possible case of conflict occurs when a PUT operation operates on a prefix of another PUT.
-    // This leads to having a directory and file with the same name which is forbidden on file systems.
-    // We fix this by appending a suffix `.##z` to the conflicting file. This is dealt with internally and not exposed to the user.
+    // Move the entries older than `after_days` into compressed per-day bundles
+    pub(crate) fn with_archive(mut self, after_days: u64) -> Self {
+        self.archive_mgr = Some(ArchiveMgr::new(&self.base_dir, after_days));
+        self
+    }
 
-    pub(crate) async fn write_file(
-        &self,
-        zfile: &ZFile<'_>,
-        content: ZBuf,
-        encoding: Encoding,
-        timestamp: &Timestamp,
-    ) -> ZResult<()> {
-        let file = &zfile.fspath;
+    // Delete the entries older than `days` on retention job
+    pub(crate) fn with_retention(mut self, days: u64) -> Self {
+        self.retention = Some(Duration::from_secs(days * 24 * 3600));
+        self
+    }
 
-        // Create parent directories if needed
-        let parent = file.parent().unwrap();
-        let ancestor = parent.ancestors().collect::<Vec<_>>();
-        for a in ancestor {
-            // if the ancestor is a directory, break, no need to check anything else
-            if a.exists() && a.is_dir() {
-                break;
+    // Canonicalize the keys (e.g. "a/**/**" as "a/**") before mapping them to files,
+    // for the equivalent keys to always map to the same file
+    pub(crate) fn with_canonical_keys(mut self) -> Self {
+        self.canonicalize_keys = true;
+        self
+    }
+
+    // The directory being only written by the storage, look up the data-info (or the entries index) before the
+    // file-system on a GET, for the unknown keys not to cost any file-system access
+    pub(crate) fn with_zenoh_write_only(mut self) -> Self {
+        self.zenoh_write_only = true;
+        self
+    }
+
+    pub(crate) fn with_default_encoding(mut self, encoding: Encoding) -> Self {
+        self.default_encoding = encoding;
+        self
+    }
+
+    // Use the configured id for the timestamps of the files without data-info, instead of the storage's random one
+    pub(crate) fn with_timestamp_id(mut self, id: TimestampId) -> Self {
+        self.timestamp_id = id;
+        self
+    }
+
+    // Set the encoding schema of the files without data-info, per directory (relative to base_dir).
+    // A file gets the schema of its deepest configured ancestor directory.
+    pub(crate) fn with_encoding_schemas(mut self, mut schemas: Vec<(PathBuf, String)>) -> Self {
+        schemas.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+        self.encoding_schemas = schemas;
+        self
+    }
+
+    // Set the encoding of the files without data-info under some key prefixes, whatever their extension
+    pub(crate) fn with_encoding_overrides(
+        mut self,
+        mut overrides: Vec<(String, Encoding)>,
+    ) -> Self {
+        overrides.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.split('/').count()));
+        self.encoding_overrides = overrides;
+        self
+    }
+
+    // Set the immutable attribute on the written files (cleared by the storage itself before a modification).
+    // Fails if the attribute can't be set in base_dir, or in a stripe (e.g. lacking the CAP_LINUX_IMMUTABLE capability).
+    pub(crate) fn with_immutable(mut self) -> ZResult<Self> {
+        for root in self.roots() {
+            let test_file = tempfile::NamedTempFile::new_in(root)
+                .map_err(|e| zerror!("Failed to create a test file in {:?}: {}", root, e))?;
+            set_immutable(test_file.path(), true)
+                .and_then(|()| set_immutable(test_file.path(), false))
+                .map_err(|e| {
+                    zerror!(
+                        "Cannot set the immutable attribute on files in {:?} (is zenohd lacking the CAP_LINUX_IMMUTABLE capability?): {}",
+                        root,
+                        e
+                    )
+                })?;
+        }
+        self.immutable = true;
+        Ok(self)
+    }
+
+    // Set or clear the immutable attribute of a file, if configured
+    fn set_file_immutable(&self, file: &Path, immutable: bool) {
+        if self.immutable {
+            if let Err(e) = set_immutable(file, immutable) {
+                warn!(
+                    "Failed to {} the immutable attribute of {:?}: {}",
+                    if immutable { "set" } else { "clear" },
+                    file,
+                    e
+                );
             }
-            // if the ancestor is a file, rename the file with a conflict suffix and update the info on rocksdb
-            if a.exists() && a.is_file() {
-                let conflict_file = self.get_conflict_file(a.to_path_buf());
-                trace!(
-                    "Conflict detected for {:?}. Writing to conflict free file {:?}",
-                    a,
-                    conflict_file
+        }
+    }
+
+    // Account the stored files in the volume's total size cap, rejecting the writes exceeding it
+    pub(crate) fn with_quota(mut self, quota: Arc<DiskQuota>) -> Self {
+        self.quota = Some(QuotaShare::new(quota, 0));
+        self
+    }
+
+    // Account the files already stored in the quotas (of the volume, tenants and policies), in a single walk
+    pub(crate) fn account_existing_files(&self, progress: &InitProgress) {
+        let has_quotas = self.quota.is_some()
+            || self
+                .tenants
+                .iter()
+                .flatten()
+                .any(|(_, t)| t.quota.is_some())
+            || self.policies.iter().any(|p| p.quota.is_some());
+        if !has_quotas {
+            return;
+        }
+        let mut total = 0;
+        for zfile in self.matching_files(unsafe { keyexpr::from_str_unchecked("**") }) {
+            progress.file_seen();
+            let size = metadata(&zfile.fspath).map(|m| m.len()).unwrap_or(0);
+            for quota in self.quotas_of(&zfile.fspath) {
+                quota.account(size);
+            }
+            total += size;
+        }
+        debug!(
+            "Storage on {:?} already stores {} bytes",
+            self.base_dir, total
+        );
+    }
+
+    // Partition the storage per tenant: the first chunk of a key is the tenant's name (and sub-directory)
+    pub(crate) fn with_tenants(mut self, configs: Vec<TenantConfig>) -> Self {
+        self.tenants = Some(
+            configs
+                .iter()
+                .map(|config| (config.name.clone(), Tenant::new(config)))
+                .collect(),
+        );
+        self
+    }
+
+    // Return true if the key belongs to a tenant (or if the storage isn't partitioned per tenant)
+    pub(crate) fn is_tenant_key(&self, zpath: &str) -> bool {
+        match &self.tenants {
+            Some(tenants) => zpath
+                .split('/')
+                .next()
+                .is_some_and(|chunk| tenants.contains_key(chunk)),
+            None => true,
+        }
+    }
+
+    // The tenant owning a file (from the first component of its path within base_dir)
+    fn tenant_of(&self, file: &Path) -> Option<&Tenant> {
+        let tenants = self.tenants.as_ref()?;
+        let first = self.relpath_of(file)?.components().next()?;
+        let name = self.names.to_zpath(first.as_os_str().to_str()?);
+        tenants.get(get_trimmed_keyexpr(&name))
+    }
+
+    // The size caps applying to a file: the volume's one, its tenant's one and its policy's one
+    fn quotas_of(&self, file: &Path) -> Vec<&QuotaShare> {
+        self.quota
+            .iter()
+            .chain(self.tenant_of(file).and_then(|t| t.quota.as_ref()))
+            .chain(self.policy_of(file).and_then(|p| p.quota.as_ref()))
+            .collect()
+    }
+
+    pub(crate) fn tenants_status(&self) -> Option<serde_json::Value> {
+        self.tenants.as_ref().map(|tenants| {
+            tenants
+                .iter()
+                .map(|(name, tenant)| (name.clone(), tenant.status_json()))
+                .collect::<serde_json::Map<_, _>>()
+                .into()
+        })
+    }
+
+    // Serve the large files contents through shared-memory buffers
+    #[cfg(feature = "shared-memory")]
+    pub(crate) fn with_shm(mut self, shm: ShmPayloads) -> Self {
+        self.shm = Some(shm);
+        self
+    }
+
+    // Bound the number of writes waiting or in progress, applying `policy` to the writes in excess
+    pub(crate) fn with_write_queue(mut self, depth: usize, policy: OverloadPolicy) -> Self {
+        self.write_queue = Some(WriteQueue::new(depth, policy));
+        self
+    }
+
+    pub(crate) fn write_queue_status(&self) -> Option<serde_json::Value> {
+        self.write_queue.as_ref().map(|queue| queue.status_json())
+    }
+
+    // Run the background tasks of the storage on its dedicated runtime
+    pub(crate) fn with_runtime(mut self, runtime: Handle) -> Self {
+        self.runtime = Some(runtime);
+        self
+    }
+
+    pub(crate) fn runtime(&self) -> Option<&Handle> {
+        self.runtime.as_ref()
+    }
+
+    pub(crate) fn with_on_conflict(mut self, on_conflict: OnConflict) -> Self {
+        self.on_conflict = on_conflict;
+        self
+    }
+
+    // Filter the logs of the storage's operations and background tasks with its own level, and log its operations
+    pub(crate) fn with_log(mut self, log: Arc<StorageLog>) -> Self {
+        self.log = log;
+        self
+    }
+
+    pub(crate) fn log(&self) -> &Arc<StorageLog> {
+        &self.log
+    }
+
+    // The FilesMgr of a background task, unless the storage was dropped or is being closed
+    pub(crate) fn upgrade(files_mgr: &Weak<FilesMgr>) -> Option<Arc<FilesMgr>> {
+        files_mgr
+            .upgrade()
+            .filter(|mgr| !mgr.closing.load(Ordering::Relaxed))
+    }
+
+    // Stop the background tasks (at their next step, or at once if idle), returning false if the storage was already
+    // being closed
+    pub(crate) fn begin_close(&self) -> bool {
+        let first = !self.closing.swap(true, Ordering::Relaxed);
+        self.closing_token.cancel();
+        self.tasks.close();
+        first
+    }
+
+    // Wait for `f` (e.g. the pause of a background task between its steps), unless the storage is dropped or being
+    // closed meanwhile
+    pub(crate) async fn idle<F: Future>(files_mgr: &Weak<FilesMgr>, f: F) -> Option<F::Output> {
+        let closing_token = FilesMgr::upgrade(files_mgr)?.closing_token.clone();
+        tokio::select! {
+            output = f => Some(output),
+            () = closing_token.cancelled() => None,
+        }
+    }
+
+    // Run a background task of the storage on a runtime, for the closure to wait for it
+    pub(crate) fn spawn_task<F>(&self, task: F, runtime: &Handle)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.tasks.spawn_on(task, runtime);
+    }
+
+    // Wait for the background tasks to stop, once the storage is being closed
+    pub(crate) async fn stopped_tasks(&self) {
+        self.tasks.wait().await
+    }
+
+    // Wait for the storage to be closed (flushed, or deleted)
+    pub(crate) async fn closed(&self) {
+        self.closed_token.cancelled().await
+    }
+
+    // The number of changes not replicated to the mirror yet
+    pub(crate) fn mirror_pending(&self) -> u64 {
+        self.mirror.as_ref().map_or(0, |mirror| mirror.pending())
+    }
+
+    // Wait for the mirror (if any) to replicate all the changes
+    pub(crate) async fn mirror_caught_up(&self) {
+        if let Some(mirror) = &self.mirror {
+            mirror.caught_up().await;
+        }
+    }
+
+    // Close the storage (once its background tasks are stopped): flush everything to the disks, or delete everything
+    // if `on_closure` is "delete_all"
+    pub(crate) async fn shutdown(&self) {
+        match self.on_closure {
+            OnClosure::DeleteAll => {
+                self.handles_pool.clear();
+                // close the data-info database at first
+                self.data_info_mgr
+                    .close()
+                    .await
+                    .unwrap_or_else(|e| warn!("{}", e));
+                self.remove_roots();
+            }
+            OnClosure::DoNothing => {
+                self.flush().await;
+                debug!(
+                    "Close File System Storage, keeping directory {:?} as it is",
+                    self.base_dir
                 );
-                rename(a, &conflict_file)
-                    .map_err(|e| zerror!("Failed to write in file {:?}: {}", conflict_file, e))?;
-                match self.data_info_mgr.rename_key(a, &conflict_file).await {
-                    Ok(_) => None,
-                    Err(_) => {
-                        // fallback: get encoding and timestamp from file's metadata
-                        let (a_encoding, a_timestamp) = self.generate_metadata(a, timestamp);
-                        self.data_info_mgr
-                            .put_data_info(file, a_encoding, &a_timestamp)
-                            .await
-                            .ok()
-                    }
-                };
             }
         }
+        self.closed.store(true, Ordering::Relaxed);
+        self.closed_token.cancel();
+    }
+
+    // Delete the storage's directories with all their content
+    fn remove_roots(&self) {
+        if self.immutable {
+            for zfile in self.matching_files(unsafe { keyexpr::from_str_unchecked("**") }) {
+                self.set_file_immutable(&zfile.fspath, false);
+            }
+        }
+        for root in self.roots() {
+            remove_dir_all(root)
+                .unwrap_or_else(|err| warn!("Failed to cleanup directory {:?}; {}", root, err));
+        }
+    }
 
-        self.dir_builder
-            .create(parent)
-            .map_err(|e| zerror!("Failed to create directories for file {:?}: {}", file, e))?;
+    // Flush everything to the disks on closure: close the cached file handles, sync the data-info database, then the
+    // file-systems of the storage's directories (the files being written without sync, unless `nfs_safe` or
+    // `verify_writes`)
+    async fn flush(&self) {
+        self.handles_pool.clear();
+        if let Err(e) = self.data_info_mgr.flush().await {
+            warn!(
+                "Failed to flush the data-info of {:?}: {}",
+                self.base_dir, e
+            );
+        }
+        if self.dry_run {
+            return;
+        }
+        for root in self.roots() {
+            if let Err(e) = sync_fs(root) {
+                warn!("Failed to sync the file-system of {:?}: {}", root, e);
+            }
+        }
+    }
+
+    // Keep an in-memory index of the entries, loaded on the first listing
+    pub(crate) fn with_entries_index(mut self) -> Self {
+        self.index = Some(EntriesIndex::new());
+        self
+    }
+
+    // The zpaths and timestamps of all the entries, if indexed and the index is loaded
+    pub(crate) fn indexed_entries(&self) -> Option<Vec<(String, Timestamp)>> {
+        self.index.as_ref().and_then(|index| index.entries())
+    }
+
+    // Start the loading of the index (if enabled and not loaded yet) by a walk of all the entries:
+    // returns false if there is no index to load
+    pub(crate) fn begin_index_load(&self) -> bool {
+        self.index.as_ref().is_some_and(|index| index.begin_load())
+    }
+
+    // Complete the loading of the index with the entries found by the walk (None if it failed)
+    pub(crate) fn end_index_load(&self, entries: Option<&[(String, Timestamp)]>) {
+        if let Some(index) = &self.index {
+            index.end_load(entries);
+        }
+    }
+
+    fn index_put(&self, zpath: &str, timestamp: &Timestamp) {
+        if let Some(index) = &self.index {
+            index.put(zpath, timestamp);
+        }
+    }
+
+    fn index_delete(&self, zpath: &str) {
+        if let Some(index) = &self.index {
+            index.delete(zpath);
+        }
+    }
+
+    // Encode the payloads before writing them, and decode them when read
+    pub(crate) fn with_transforms(
+        mut self,
+        transforms: Vec<Arc<dyn PayloadTransform>>,
+    ) -> ZResult<Self> {
+        self.transforms = Transforms::new(transforms)?;
+        Ok(self)
+    }
+
+    // Override the transforms, retention and quota of the storage for the keys under some prefixes
+    pub(crate) fn with_policies(mut self, mut configs: Vec<PolicyConfig>) -> ZResult<Self> {
+        // the longest prefixes first, for a key to get the policy of its longest matching prefix
+        configs.sort_by_key(|c| std::cmp::Reverse(c.prefix.split('/').count()));
+        self.policies = configs.iter().map(Policy::new).collect::<ZResult<_>>()?;
+        Ok(self)
+    }
+
+    // The policy applying to a file: the one of the longest prefix of its key, if any
+    fn policy_of(&self, file: &Path) -> Option<&Policy> {
+        if self.policies.is_empty() {
+            return None;
+        }
+        let relpath = fspath_to_zpath(self.relpath_of(file)?.to_str()?);
+        let zpath = self.names.to_zpath(&relpath);
+        let zpath = get_trimmed_keyexpr(&zpath);
+        self.policies.iter().find(|p| p.applies_to(zpath))
+    }
+
+    // The transforms of the payload of a file: its policy's ones, or the storage's ones
+    fn transforms_of(&self, file: &Path) -> &Transforms {
+        self.policy_of(file)
+            .and_then(|p| p.transforms.as_ref())
+            .unwrap_or(&self.transforms)
+    }
+
+    // Return true if the payloads of 2 files are encoded with the same transforms
+    fn same_transforms(&self, file1: &Path, file2: &Path) -> bool {
+        std::ptr::eq(self.transforms_of(file1), self.transforms_of(file2))
+    }
+
+    pub(crate) fn policies_status(&self) -> Option<serde_json::Value> {
+        if self.policies.is_empty() {
+            return None;
+        }
+        Some(
+            self.policies
+                .iter()
+                .map(|policy| (policy.prefix.clone(), policy.status_json()))
+                .collect::<serde_json::Map<_, _>>()
+                .into(),
+        )
+    }
+
+    // Write the values in an envelope with their data-info, for them to be recoverable without the data-info database
+    pub(crate) fn with_envelope(mut self) -> Self {
+        self.envelope = true;
+        self
+    }
+
+    // Operate safely on a network file-system: the files aren't kept open (their handles becoming stale when replaced
+    // by another client, and their removal leaving "silly renamed" files), and the writes are synced (for the errors
+    // deferred by the client to be reported).
+    pub(crate) fn with_nfs_safe(mut self) -> Self {
+        self.nfs_safe = true;
+        self.handles_pool = HandlesPool::new(0);
+        self
+    }
+
+    // Read in advance the files of the next `depth` keys of the sequentially read series of keys
+    pub(crate) fn with_read_ahead(mut self, depth: usize) -> Self {
+        self.prefetcher = Some(Prefetcher::new(depth));
+        self
+    }
+
+    // Track the watermark of each top-level prefix, besides the storage's one
+    pub(crate) fn with_watermark_by_prefix(mut self) -> Self {
+        self.watermark = Watermark::new(true);
+        self
+    }
+
+    pub(crate) fn watermark(&self) -> &Watermark {
+        &self.watermark
+    }
+
+    // Initialize the watermark with the timestamps of the data-info records (the files created without zenoh being
+    // ignored)
+    pub(crate) async fn init_watermark(&self) -> ZResult<()> {
+        let base_dir = self.base_dir.clone();
+        self.data_info_mgr
+            .for_each_timestamp(|file, timestamp| {
+                let zpath = match self.watermark.by_prefix() {
+                    true if file == base_dir.join(ROOT_KEY) => None,
+                    true => {
+                        let file = file.to_string_lossy();
+                        let file = file.strip_suffix(CONFLICT_SUFFIX).unwrap_or(&file);
+                        self.zpath_of(Path::new(file))
+                    }
+                    false => None,
+                };
+                self.watermark
+                    .advance(zpath.as_deref().and_then(top_level_prefix), timestamp);
+            })
+            .await
+    }
+
+    // Advance the watermark with the timestamp of a stored value
+    fn advance_watermark(&self, zpath: &str, timestamp: &Timestamp) {
+        let prefix = match zpath {
+            ROOT_KEY => None,
+            zpath => top_level_prefix(zpath),
+        };
+        self.watermark.advance(prefix, timestamp);
+    }
+
+    // Read back each file once written (and synced), failing the write if its content's checksum differs, for the
+    // media which may silently corrupt the data
+    pub(crate) fn with_verified_writes(mut self) -> Self {
+        self.verify_writes = true;
+        self
+    }
+
+    // Retry the reads and writes of the files, and the operations of the data-info database, failing with a
+    // transient error
+    pub(crate) async fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.data_info_mgr.set_retry(retry.clone()).await;
+        self.retry = retry;
+        self
+    }
+
+    // Decode the content of a file, if written in an envelope or with transforms
+    fn decode(&self, content: Vec<u8>, file: &Path) -> ZResult<Vec<u8>> {
+        let content = envelope::unwrap(content)
+            .map_err(|e| zerror!("Error reading file {:?}: {}", file, e))?;
+        self.transforms_of(file)
+            .decode(content)
+            .map_err(|e| zerror!("Error reading file {:?}: {}", file, e).into())
+    }
+
+    // Never modify the files (in dry run, the writes being only checked): e.g. the corrupted files aren't quarantined
+    pub(crate) fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    // Replicate the written, renamed and deleted files to a mirror directory
+    pub(crate) fn with_mirror(mut self, mirror: Arc<Mirror>) -> Self {
+        self.mirror = Some(mirror);
+        self
+    }
+
+    // Schedule the replication of a changed file (written, renamed or deleted), if mirrored
+    fn mirror_changed(&self, file: &Path) {
+        if let (Some(mirror), Some(relpath)) = (&self.mirror, self.relpath_of(file)) {
+            mirror.changed(file, relpath);
+        }
+    }
+
+    // Record the deletions as whiteouts, hiding the entries of the storage's lower layer (the writes unhiding them)
+    pub(crate) fn with_whiteouts(mut self) -> Self {
+        self.whiteouts = true;
+        self
+    }
+
+    async fn set_whiteout(&self, zpath: &str, hidden: bool) -> ZResult<()> {
+        if !self.whiteouts {
+            return Ok(());
+        }
+        self.data_info_mgr.set_whiteout(zpath, hidden).await
+    }
+
+    // Return true if the entry of the lower layer is hidden, having been deleted
+    pub(crate) async fn is_whiteout(&self, zpath: &str) -> ZResult<bool> {
+        self.data_info_mgr.is_whiteout(zpath).await
+    }
+
+    // The number of whiteouts, as of the last refresh of the statistics
+    pub(crate) fn whiteouts_count(&self) -> Option<usize> {
+        self.db_stats.lock().unwrap().whiteouts
+    }
+
+    // Offload the values not accessed for a while to a cold tier (on the offload maintenance job), leaving empty
+    // stubs rehydrated on their first read
+    pub(crate) fn with_cold_tier(mut self, cold_tier: Arc<ColdTier>) -> Self {
+        self.cold_tier = Some(cold_tier);
+        self
+    }
+
+    // Inject faults into the file operations (for tests only)
+    #[cfg(feature = "fault-injection")]
+    pub(crate) fn with_faults(mut self, faults: Arc<Faults>) -> Self {
+        self.faults = Some(faults);
+        self
+    }
+
+    // Delay or fail an operation on a file, if faults are injected
+    async fn inject_faults(&self, _op: &str, _file: &Path) -> ZResult<()> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = &self.faults {
+            return faults.inject(_op, _file).await;
+        }
+        Ok(())
+    }
+
+    // The length to which a write of `_len` bytes is truncated, if faults are injected and it's interrupted
+    fn partial_write(&self, _len: usize) -> Option<usize> {
+        #[cfg(feature = "fault-injection")]
+        if let Some(faults) = &self.faults {
+            return faults.partial_write(_len);
+        }
+        None
+    }
+
+    fn to_payload(&self, content: Vec<u8>) -> ZBytes {
+        #[cfg(feature = "shared-memory")]
+        if let Some(shm) = &self.shm {
+            return shm.to_payload(content);
+        }
+        content.into()
+    }
+
+    pub(crate) fn base_dir(&self) -> &Path {
+        self.base_dir.as_path()
+    }
+
+    // Remove the data-info entries which are corrupted or refer to a missing file.
+    pub(crate) async fn verify_data_info(&self, progress: Option<&InitProgress>) -> ZResult<usize> {
+        self.data_info_mgr.verify(progress).await
+    }
+
+    // The statistics of the storage's files and data-info, reported in its admin status (and published, if configured)
+    // Refresh the snapshot of the statistics read from the data-info database
+    pub(crate) async fn refresh_db_stats(&self) {
+        let db_stats = DbStats {
+            data_info: self.data_info_mgr.stats().await,
+            conflict_files: self.conflicts_status().await,
+            cold_stubs: match &self.cold_tier {
+                Some(_) => self.data_info_mgr.count_cold().await.ok(),
+                None => None,
+            },
+            whiteouts: match self.whiteouts {
+                true => self.data_info_mgr.count_whiteouts().await.ok(),
+                false => None,
+            },
+        };
+        *self.db_stats.lock().unwrap() = db_stats;
+    }
+
+    // The statistics of the storage, the ones read from the data-info database being the last snapshot of them
+    pub(crate) fn stats_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        let db_stats = self.db_stats.lock().unwrap();
+        let mut stats = serde_json::Map::new();
+        stats.insert("data_info".into(), db_stats.data_info.clone());
+        if let Some(dir_listings) = &self.dir_listings {
+            stats.insert("dir_listings".into(), dir_listings.stats());
+        }
+        stats.insert("watermark".into(), self.watermark.status_json(|_| true));
+        // checked right away, for the status to be current even without operations
+        let _ = self.check_available();
+        stats.insert("availability".into(), self.availability_status());
+        stats.insert("conflict_files".into(), db_stats.conflict_files.clone());
+        stats.insert("quarantined_files".into(), self.quarantined_count().into());
+        if let Some(tenants) = self.tenants_status() {
+            stats.insert(PROP_STORAGE_TENANTS.into(), tenants);
+        }
+        if let Some(policies) = self.policies_status() {
+            stats.insert(PROP_STORAGE_POLICIES.into(), policies);
+        }
+        if let Some(write_queue) = self.write_queue_status() {
+            stats.insert("write_queue".into(), write_queue);
+        }
+        if let Some(prefetcher) = &self.prefetcher {
+            stats.insert(PROP_STORAGE_READ_AHEAD.into(), prefetcher.status_json());
+        }
+        if let Some(mirror) = &self.mirror {
+            stats.insert("mirror".into(), mirror.status_json());
+        }
+        if let Some(cold_tier) = &self.cold_tier {
+            stats.insert(
+                "cold_tier".into(),
+                cold_tier.status_json(db_stats.cold_stubs),
+            );
+        }
+        stats
+    }
+
+    pub(crate) async fn compact_data_info(&self) -> ZResult<()> {
+        self.data_info_mgr.compact().await;
+        Ok(())
+    }
+
+    // Delete the entries older than the retention period (of their policy or tenant, if any),
+    // returning the number of deleted entries
+    pub(crate) async fn prune_expired_entries(&self) -> ZResult<usize> {
+        let is_expired = |fspath: &Path, timestamp: &Timestamp| {
+            let retention = self
+                .policy_of(fspath)
+                .and_then(|p| p.retention)
+                .or_else(|| self.tenant_of(fspath).and_then(|t| t.retention))
+                .or(self.retention);
+            let Some(retention) = retention else {
+                return false;
+            };
+            SystemTime::now()
+                .duration_since(timestamp.get_time().to_system_time())
+                .map(|age| age > retention)
+                .unwrap_or(false)
+        };
+        let mut expired = Vec::new();
+        for zfile in self.matching_files(unsafe { keyexpr::from_str_unchecked("**") }) {
+            let (_, timestamp) = self.get_encoding_and_timestamp(&zfile.fspath).await?;
+            if is_expired(&zfile.fspath, &timestamp) {
+                expired.push(zfile.zpath.into_owned());
+            }
+        }
+        for (zpath, timestamp) in self.archived_entries().await? {
+            if is_expired(&self.to_fspath(&zpath), &timestamp) {
+                expired.push(zpath);
+            }
+        }
+        for zpath in expired.iter() {
+            trace!("Delete expired entry {}", zpath);
+            self.delete_file(&self.to_zfile(zpath)).await?;
+        }
+        Ok(expired.len())
+    }
+
+    // Delete the entries under a key (including its own one, or all the entries if None) whose timestamp is older than
+    // `before`, which belong to a tenant of the storage and are accepted by `is_purgeable`. Return the numbers of
+    // deleted entries, of bytes reclaimed (the archived entries being counted as deleted, but not their bytes which
+    // remain in the archive bundles) and of entries skipped as their data-info couldn't be read.
+    pub(crate) async fn purge_older_than(
+        &self,
+        subtree: Option<&keyexpr>,
+        before: NTP64,
+        is_purgeable: impl Fn(&str) -> bool,
+    ) -> ZResult<(usize, u64, usize)> {
+        let zpath_expr = match subtree {
+            Some(key) => OwnedKeyExpr::new(format!("{key}/**"))?,
+            None => OwnedKeyExpr::new("**")?,
+        };
+        // (the root key's entry not matching "**")
+        let own = match subtree {
+            Some(key) => self.to_zfile(key.as_str()),
+            None => self.root_zfile(),
+        };
+        let own = Some(own).filter(|zfile| zfile.fspath.is_file());
+        let mut purged = Vec::new();
+        let mut bytes = 0;
+        let mut unreadable = 0;
+        for zfile in own.into_iter().chain(self.matching_files(&zpath_expr)) {
+            if !self.is_tenant_key(&zfile.zpath) || !is_purgeable(&zfile.zpath) {
+                continue;
+            }
+            let timestamp = match self.get_encoding_and_timestamp(&zfile.fspath).await {
+                Ok((_, timestamp)) => timestamp,
+                Err(e) => {
+                    warn!("Purge: skip {} whose data-info can't be read: {}", zfile, e);
+                    unreadable += 1;
+                    continue;
+                }
+            };
+            if *timestamp.get_time() < before {
+                bytes += metadata(&zfile.fspath).map(|m| m.len()).unwrap_or(0);
+                purged.push(zfile.zpath.into_owned());
+            }
+        }
+        for (zpath, timestamp) in self.archived_entries().await? {
+            if let Some(key) = subtree {
+                if zpath != key.as_str() && !zpath.starts_with(&format!("{key}/")) {
+                    continue;
+                }
+            }
+            if *timestamp.get_time() < before && self.is_tenant_key(&zpath) && is_purgeable(&zpath)
+            {
+                purged.push(zpath);
+            }
+        }
+        trace!("Purge {} entries older than {}", purged.len(), before);
+        let zfiles: Vec<_> = purged
+            .iter()
+            .map(|zpath| match zpath.as_str() {
+                ROOT_KEY => self.root_zfile(),
+                zpath => self.to_zfile(zpath),
+            })
+            .collect();
+        self.delete_files(&zfiles).await?;
+        Ok((purged.len(), bytes, unreadable))
+    }
+
+    // Read all the files, returning the number of files which couldn't be read (or are corrupted)
+    pub(crate) async fn scrub(&self) -> ZResult<usize> {
+        let mut unreadable = 0;
+        for zfile in self.matching_files(unsafe { keyexpr::from_str_unchecked("**") }) {
+            if let Some(e) = self.check_file(&zfile).await? {
+                warn!("Scrub: failed to read file {}: {}", zfile, e);
+                unreadable += 1;
+            }
+        }
+        Ok(unreadable)
+    }
+
+    // Check the integrity of a file: that it can be read and decoded (the checksum of its envelope, if any, matching),
+    // and that the data-info in its envelope (if any) are the recorded ones. Return why it's corrupted, if so.
+    pub(crate) async fn check_file(&self, zfile: &ZFile<'_>) -> ZResult<Option<String>> {
+        let _permit = self.acquire_op_permit().await?;
+        // (the stubs of the offloaded values being empty)
+        if self.is_stub(&zfile.fspath).await? {
+            return Ok(None);
+        }
+        let transforms = self.transforms_of(&zfile.fspath);
+        let result = if transforms.is_empty() {
+            // (the file being read by chunks)
+            File::open(&zfile.fspath)
+                .map_err(|e| zerror!("{}", e).into())
+                .and_then(envelope::check)
+        } else {
+            std::fs::read(&zfile.fspath)
+                .map_err(|e| zerror!("{}", e).into())
+                .and_then(|content| self.decode(content, &zfile.fspath))
+                .map(|_| ())
+        };
+        if let Err(e) = result {
+            return Ok(Some(e.to_string()));
+        }
+        let Ok(Some((encoding, timestamp))) = envelope::read_info(&zfile.fspath) else {
+            return Ok(None);
+        };
+        match self
+            .data_info_mgr
+            .get_encoding_and_timestamp(&zfile.fspath)
+            .await?
+        {
+            Some((recorded_encoding, recorded_timestamp))
+                if recorded_encoding != encoding || recorded_timestamp != timestamp =>
+            {
+                Ok(Some(format!(
+                    "its envelope's encoding and timestamp ({} {}) differ from its data-info ({} {})",
+                    encoding, timestamp, recorded_encoding, recorded_timestamp
+                )))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    // The keys under a subtree (or all the keys) whose data-info refer to a missing file (and which aren't archived)
+    pub(crate) async fn missing_entries(&self, subtree: Option<&keyexpr>) -> ZResult<Vec<String>> {
+        let mut result = Vec::new();
+        for file in self.data_info_mgr.get_missing_files().await? {
+            let Some(zpath) = self.zpath_of(&file) else {
+                continue;
+            };
+            if let Some(key) = subtree {
+                if zpath != key.as_str() && !zpath.starts_with(&format!("{key}/")) {
+                    continue;
+                }
+            }
+            result.push(zpath);
+        }
+        Ok(result)
+    }
+
+    // Wait for a slot among the max_concurrent_ops file operations allowed in parallel
+    async fn acquire_op_permit(&self) -> ZResult<SemaphorePermit<'_>> {
+        self.ops_permits
+            .acquire()
+            .await
+            .map_err(|e| zerror!("Failed to acquire a file operation slot: {}", e).into())
+    }
+
+    // Wait for a slot for a write: in the write queue (if bounded), then among the file operations
+    async fn acquire_write_permit(
+        &self,
+    ) -> ZResult<(Option<QueuedWrite<'_>>, SemaphorePermit<'_>)> {
+        match &self.write_queue {
+            Some(queue) => {
+                let mut queued = queue.enqueue().await?;
+                let permit = queued.start(self.acquire_op_permit()).await?;
+                Ok((Some(queued), permit))
+            }
+            None => Ok((None, self.acquire_op_permit().await?)),
+        }
+    }
+
+    // The zfile storing the value of the root key (i.e. the key equal to the stripped prefix)
+    pub(crate) fn root_zfile(&self) -> ZFile<'static> {
+        ZFile {
+            zpath: Cow::from(ROOT_KEY),
+            fspath: self.base_dir.join(ROOT_KEY),
+        }
+    }
+
+    pub(crate) fn to_zfile<'a>(&self, zpath: &'a str) -> ZFile<'a> {
+        let zpath = if self.canonicalize_keys {
+            canonicalize(zpath)
+        } else {
+            Cow::from(zpath)
+        };
+        let fspath = self.to_fspath(&zpath);
+        ZFile { zpath, fspath }
+    }
+
+    fn to_fspath(&self, zpath: &str) -> PathBuf {
+        let relpath = self.to_fs_relpath(zpath);
+        let mut fspath = self.stripe_root(&relpath).to_path_buf();
+        fspath.push(relpath.as_ref());
+        fspath
+    }
+
+    // The path of a zpath's file relative to its stripe's root
+    fn to_fs_relpath(&self, zpath: &str) -> Cow<'static, str> {
+        Cow::from(zpath_to_fspath(&self.names.to_fs_relpath(zpath)).into_owned())
+    }
+
+    // The root of the stripe storing a file, chosen by a hash of its relative path (base_dir, if not striped)
+    fn stripe_root(&self, relpath: &str) -> &Path {
+        if self.stripes.is_empty() {
+            return &self.base_dir;
+        }
+        let mut hasher = SipHasher13::new();
+        hasher.write(relpath.as_bytes());
+        match (hasher.finish() % (self.stripes.len() as u64 + 1)) as usize {
+            0 => &self.base_dir,
+            i => &self.stripes[i - 1],
+        }
+    }
+
+    // The roots of the stripes: base_dir, then the stripes' directories
+    fn roots(&self) -> impl Iterator<Item = &Path> {
+        std::iter::once(self.base_dir.as_path()).chain(self.stripes.iter().map(PathBuf::as_path))
+    }
+
+    // The root of the stripe containing a file (or directory)
+    fn root_of(&self, file: &Path) -> &Path {
+        self.stripes
+            .iter()
+            .find(|stripe| file.starts_with(stripe))
+            .unwrap_or(&self.base_dir)
+    }
+
+    // The path of a file relative to its stripe's root
+    fn relpath_of<'a>(&self, file: &'a Path) -> Option<&'a Path> {
+        file.strip_prefix(self.root_of(file)).ok()
+    }
+
+    // The zenoh path of the entry stored in a file
+    fn zpath_of(&self, file: &Path) -> Option<String> {
+        let relpath = fspath_to_zpath(self.relpath_of(file)?.to_str()?);
+        let coarse_zpath = self.names.to_zpath(&relpath);
+        Some(get_trimmed_keyexpr(&coarse_zpath).to_string())
+    }
+
+    // ### Behaviour in case of conflict
+    // A possible case of conflict occurs when a PUT operation operates on a prefix of another PUT.
+    // This leads to having a directory and file with the same name which is forbidden on file systems.
+    // We fix this by appending a suffix `.##z` to the conflicting file. This is dealt with internally and not exposed to the user.
+
+    pub(crate) async fn write_file(
+        &self,
+        zfile: &ZFile<'_>,
+        content: ZBuf,
+        encoding: Encoding,
+        timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        let _key = self.key_locks.lock(&zfile.zpath).await;
+        self.perform_write(zfile, content, encoding, timestamp)
+            .await
+    }
+
+    // Write a value computed from the zfile's value with the `base` timestamp (None if it had no value), failing if
+    // the zfile was updated meanwhile: the check is atomic with the write
+    pub(crate) async fn write_file_over(
+        &self,
+        zfile: &ZFile<'_>,
+        content: ZBuf,
+        encoding: Encoding,
+        timestamp: &Timestamp,
+        base: Option<&Timestamp>,
+    ) -> ZResult<()> {
+        let _key = self.key_locks.lock(&zfile.zpath).await;
+        let current = self.stored_timestamp(zfile).await?;
+        if current.as_ref() != base {
+            bail!(
+                "Failed to write {}: it was updated meanwhile (its timestamp is now {})",
+                zfile,
+                current.map_or("none".to_string(), |t| t.to_string())
+            )
+        }
+        self.perform_write(zfile, content, encoding, timestamp)
+            .await
+    }
+
+    // The same as write_file(), the key being locked
+    async fn perform_write(
+        &self,
+        zfile: &ZFile<'_>,
+        content: ZBuf,
+        encoding: Encoding,
+        timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        let _permit = self.acquire_write_permit().await?;
+        self.inject_faults("write", &zfile.fspath).await?;
+        let content = self.encode(&zfile.fspath, content, &encoding, timestamp)?;
+        self.register_names(zfile).await?;
+        let file = self.prepare_file_path(&zfile.fspath, timestamp).await?;
+        trace!("Write in file {:?}", file);
+        let old_size = metadata(&file).map(|m| m.len()).unwrap_or(0);
+        let new_size = content.len() as u64;
+        let quotas = self.quotas_of(&file);
+        if new_size > old_size {
+            for (i, quota) in quotas.iter().enumerate() {
+                if !quota.try_reserve(new_size - old_size) {
+                    for reserved in &quotas[..i] {
+                        reserved.release(new_size - old_size);
+                    }
+                    bail!(
+                        "Failed to write in file {:?}: the total size of the {} would exceed its cap ({} bytes)",
+                        file,
+                        quota.owner(),
+                        quota.max()
+                    )
+                }
+            }
+        }
+        self.handles_pool.invalidate(&file);
+        self.forget_archived(&zfile.fspath).await?;
+        self.forget_offloaded(&zfile.fspath).await?;
+        if file.exists() {
+            self.set_file_immutable(&file, false);
+        }
+        let partial_write = self.partial_write(content.len());
+        let result = self.retry.io(|| {
+            let mut f = File::create(&file)?;
+            let mut remaining = partial_write.unwrap_or(usize::MAX);
+            for slice in content.slices() {
+                let len = slice.len().min(remaining);
+                f.write_all(&slice[..len])?;
+                remaining -= len;
+            }
+            match partial_write {
+                Some(_) => Err(std::io::Error::other("injected partial write")),
+                // a network file-system client may only report the write errors on sync (or on close, ignored)
+                None if self.nfs_safe || self.verify_writes => f.sync_all(),
+                None => Ok(()),
+            }
+        });
+        if !quotas.is_empty() {
+            // adjust the reservations to the actual size of the file
+            let reserved = old_size.max(new_size);
+            let size = metadata(&file).map(|m| m.len()).unwrap_or(0);
+            for quota in quotas {
+                quota.release(reserved - size.min(reserved));
+            }
+        }
+        result.map_err(|e| zerror!("Failed to write in file {:?}: {}", file, e))?;
+        if self.verify_writes {
+            if let Err(e) = self.verify_file(&file, &content) {
+                // the corrupted file isn't kept
+                let _ = self.remove_accounted_file(&file);
+                return Err(e);
+            }
+        }
+
+        // save data-info
+        self.data_info_mgr
+            .put_data_info(&file, encoding, timestamp)
+            .await?;
+        self.set_file_immutable(&file, true);
+        self.index_put(&zfile.zpath, timestamp);
+        self.advance_watermark(&zfile.zpath, timestamp);
+        self.mirror_changed(&file);
+        self.set_whiteout(&zfile.zpath, false).await
+    }
+
+    // Encode the content of a file to write, with its transforms and in an envelope if enabled
+    fn encode(
+        &self,
+        file: &Path,
+        content: ZBuf,
+        encoding: &Encoding,
+        timestamp: &Timestamp,
+    ) -> ZResult<ZBuf> {
+        let transforms = self.transforms_of(file);
+        let content = match transforms.is_empty() {
+            true => content,
+            false => ZBuf::from(transforms.encode(content.contiguous().into_owned())?),
+        };
+        Ok(match self.envelope {
+            true => ZBuf::from(envelope::wrap(
+                &content.contiguous(),
+                encoding.clone(),
+                timestamp,
+            )?),
+            false => content,
+        })
+    }
+
+    // Check a write without performing it (in dry run): fail as the write would (e.g. exceeding a quota), or else
+    // return the conflict it would resolve, if any
+    pub(crate) fn check_write(
+        &self,
+        zfile: &ZFile<'_>,
+        content: ZBuf,
+        encoding: &Encoding,
+        timestamp: &Timestamp,
+    ) -> ZResult<Option<String>> {
+        let content = self.encode(&zfile.fspath, content, encoding, timestamp)?;
+        let conflict = match self.conflicting_path(&zfile.fspath) {
+            None => None,
+            Some(path) => match self.on_conflict {
+                OnConflict::Reject => bail!(
+                    "Failed to write in file {:?}: {:?} is {} (the conflicts are rejected)",
+                    zfile.fspath,
+                    path,
+                    if path.is_dir() {
+                        "a directory"
+                    } else {
+                        "a file"
+                    }
+                ),
+                OnConflict::Rename if path == zfile.fspath => Some(format!(
+                    "{path:?} is a directory: the value would be written as a conflict file"
+                )),
+                OnConflict::Rename => Some(format!("{path:?} would be renamed as a conflict file")),
+                // (an outdated write being ignored before)
+                OnConflict::Overwrite => Some(format!(
+                    "{path:?} would be deleted, its entries being older"
+                )),
+            },
+        };
+        let file = if self.on_conflict == OnConflict::Rename && zfile.fspath.is_dir() {
+            self.get_conflict_file(zfile.fspath.clone())
+        } else {
+            zfile.fspath.clone()
+        };
+        let old_size = metadata(&file).map(|m| m.len()).unwrap_or(0);
+        let new_size = content.len() as u64;
+        if new_size > old_size {
+            if let Some(quota) = self
+                .quotas_of(&file)
+                .into_iter()
+                .find(|quota| !quota.would_fit(new_size - old_size))
+            {
+                bail!(
+                    "Failed to write in file {:?}: the total size of the {} would exceed its cap ({} bytes)",
+                    file,
+                    quota.owner(),
+                    quota.max()
+                )
+            }
+        }
+        Ok(conflict)
+    }
+
+    // Check that a written file's content has the expected checksum
+    fn verify_file(&self, file: &Path, content: &ZBuf) -> ZResult<()> {
+        let mut expected = SipHasher13::new();
+        for slice in content.slices() {
+            expected.write(slice);
+        }
+        let mut actual = SipHasher13::new();
+        let mut size = 0;
+        self.retry
+            .io(|| {
+                actual = SipHasher13::new();
+                size = 0;
+                let mut f = File::open(file)?;
+                let mut buf = [0u8; 64 * 1024];
+                loop {
+                    match f.read(&mut buf)? {
+                        0 => return Ok(()),
+                        n => {
+                            actual.write(&buf[..n]);
+                            size += n;
+                        }
+                    }
+                }
+            })
+            .map_err(|e| zerror!("Failed to verify file {:?}: {}", file, e))?;
+        if size != content.len() || actual.finish() != expected.finish() {
+            bail!(
+                "Failed to verify file {:?}: its content read back differs from the written one ({} bytes read, {} written)",
+                file,
+                size,
+                content.len()
+            )
+        }
+        Ok(())
+    }
+
+    // Copy the value of a zfile to another one, with a new timestamp. The file is copied by the file-system
+    // (using copy_file_range or a clone where available), replacing the target's one if any.
+    // Returns false if there is no value to copy.
+    pub(crate) async fn copy_file(
+        &self,
+        from: &ZFile<'_>,
+        to: &ZFile<'_>,
+        timestamp: &Timestamp,
+    ) -> ZResult<bool> {
+        // with envelopes, the value is rewritten for its envelope to get the new timestamp
+        // (and between policies with distinct transforms, for it to be re-encoded)
+        let located = match self.envelope || !self.same_transforms(&from.fspath, &to.fspath) {
+            true => None,
+            false => self.locate_file(from).await?,
+        };
+        let (from_file, encoding) = match located {
+            Some((file, encoding, _)) => (file, encoding),
+            None => {
+                // the value may be archived (or enveloped, or re-encoded): copy it as a write
+                return match self.read_file(from).await? {
+                    Some((payload, encoding, _)) => {
+                        self.write_file(to, payload.into(), encoding, timestamp)
+                            .await?;
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                };
+            }
+        };
+        // (the stub of an offloaded value being rehydrated, to be copied)
+        self.rehydrate(&from_file).await?;
+        let _key = self.key_locks.lock(&to.zpath).await;
+        let _permit = self.acquire_op_permit().await?;
+        self.inject_faults("write", &to.fspath).await?;
+        self.register_names(to).await?;
+        let file = self.prepare_file_path(&to.fspath, timestamp).await?;
+        trace!("Copy file {:?} to {:?}", from_file, file);
+        let old_size = metadata(&file).map(|m| m.len()).unwrap_or(0);
+        let new_size = metadata(&from_file).map(|m| m.len()).unwrap_or(0);
+        let quotas = self.quotas_of(&file);
+        if new_size > old_size {
+            for (i, quota) in quotas.iter().enumerate() {
+                if !quota.try_reserve(new_size - old_size) {
+                    for reserved in &quotas[..i] {
+                        reserved.release(new_size - old_size);
+                    }
+                    bail!(
+                        "Failed to copy file {:?} to {:?}: the total size of the {} would exceed its cap ({} bytes)",
+                        from_file,
+                        file,
+                        quota.owner(),
+                        quota.max()
+                    )
+                }
+            }
+        }
+        self.handles_pool.invalidate(&file);
+        self.forget_archived(&to.fspath).await?;
+        self.forget_offloaded(&to.fspath).await?;
+        if file.exists() {
+            self.set_file_immutable(&file, false);
+        }
+        let result = copy(&from_file, &file);
+        if !quotas.is_empty() {
+            // adjust the reservations to the actual size of the file
+            let reserved = old_size.max(new_size);
+            let size = metadata(&file).map(|m| m.len()).unwrap_or(0);
+            for quota in quotas {
+                quota.release(reserved - size.min(reserved));
+            }
+        }
+        result.map_err(|e| zerror!("Failed to copy file {:?} to {:?}: {}", from_file, file, e))?;
+
+        // save data-info
+        self.data_info_mgr
+            .put_data_info(&file, encoding, timestamp)
+            .await?;
+        self.set_file_immutable(&file, true);
+        self.index_put(&to.zpath, timestamp);
+        self.advance_watermark(&to.zpath, timestamp);
+        self.mirror_changed(&file);
+        self.set_whiteout(&to.zpath, false).await?;
+        Ok(true)
+    }
+
+    // Move the value of a zfile to another one, with a new timestamp. The file is renamed (unless archived),
+    // replacing the target's one if any. Returns false if there is no value to move.
+    pub(crate) async fn move_file(
+        &self,
+        from: &ZFile<'_>,
+        to: &ZFile<'_>,
+        timestamp: &Timestamp,
+    ) -> ZResult<bool> {
+        // with envelopes, the value is rewritten for its envelope to get the new timestamp
+        // (and between policies with distinct transforms, for it to be re-encoded)
+        let located = match self.envelope || !self.same_transforms(&from.fspath, &to.fspath) {
+            true => None,
+            false => self.locate_file(from).await?,
+        };
+        let (from_file, encoding) = match located {
+            Some((file, encoding, _)) => (file, encoding),
+            None => {
+                // the value may be archived (or enveloped, or re-encoded): move it as a write then a delete
+                return match self.read_file(from).await? {
+                    Some((payload, encoding, _)) => {
+                        self.write_file(to, payload.into(), encoding, timestamp)
+                            .await?;
+                        self.delete_file(from).await?;
+                        Ok(true)
+                    }
+                    None => Ok(false),
+                };
+            }
+        };
+        // (the stub of an offloaded value being rehydrated, for the mirror to never get a stub)
+        self.rehydrate(&from_file).await?;
+        let _keys = self.key_locks.lock_all([&*from.zpath, &*to.zpath]).await;
+        let _permit = self.acquire_op_permit().await?;
+        self.inject_faults("write", &to.fspath).await?;
+        self.register_names(to).await?;
+        let to_file = self.prepare_file_path(&to.fspath, timestamp).await?;
+        trace!("Move file {:?} to {:?}", from_file, to_file);
+
+        // the size moves between the quotas of the files (e.g. from a tenant to another)
+        let size = metadata(&from_file).map(|m| m.len()).unwrap_or(0);
+        let replaced_size = metadata(&to_file).map(|m| m.len()).unwrap_or(0);
+        let from_quotas = self.quotas_of(&from_file);
+        let to_quotas = self.quotas_of(&to_file);
+        let is_shared = |quota: &QuotaShare| from_quotas.iter().any(|q| std::ptr::eq(*q, quota));
+        let growing: Vec<&QuotaShare> = if size > replaced_size {
+            to_quotas
+                .iter()
+                .copied()
+                .filter(|q| !is_shared(q))
+                .collect()
+        } else {
+            vec![]
+        };
+        for (i, quota) in growing.iter().enumerate() {
+            if !quota.try_reserve(size - replaced_size) {
+                for reserved in &growing[..i] {
+                    reserved.release(size - replaced_size);
+                }
+                bail!(
+                    "Failed to move file {:?} to {:?}: the total size of the {} would exceed its cap ({} bytes)",
+                    from_file,
+                    to_file,
+                    quota.owner(),
+                    quota.max()
+                )
+            }
+        }
+
+        self.handles_pool.invalidate(&from_file);
+        self.handles_pool.invalidate(&to_file);
+        self.forget_archived(&to.fspath).await?;
+        self.forget_offloaded(&to.fspath).await?;
+        self.set_file_immutable(&from_file, false);
+        if to_file.exists() {
+            self.set_file_immutable(&to_file, false);
+        }
+        if let Err(e) = rename(&from_file, &to_file) {
+            for reserved in &growing {
+                reserved.release(size - replaced_size);
+            }
+            self.set_file_immutable(&from_file, true);
+            bail!(
+                "Failed to move file {:?} to {:?}: {}",
+                from_file,
+                to_file,
+                e
+            )
+        }
+        for quota in to_quotas.iter() {
+            if is_shared(quota) {
+                // the replaced file is freed
+                quota.release(replaced_size);
+            } else if size <= replaced_size {
+                quota.release(replaced_size - size);
+            }
+        }
+        for quota in from_quotas
+            .iter()
+            .filter(|q| !to_quotas.iter().any(|t| std::ptr::eq(*t, **q)))
+        {
+            quota.release(size);
+        }
+        self.data_info_mgr
+            .move_data_info(&from_file, &to_file, encoding, timestamp)
+            .await?;
+        self.set_file_immutable(&to_file, true);
+        self.remove_empty_parents(&from_file);
+        self.index_delete(&from.zpath);
+        self.index_put(&to.zpath, timestamp);
+        self.advance_watermark(&to.zpath, timestamp);
+        self.mirror_changed(&from_file);
+        self.mirror_changed(&to_file);
+        self.set_whiteout(&from.zpath, true).await?;
+        self.set_whiteout(&to.zpath, false).await?;
+        Ok(true)
+    }
+
+    // Save the key chunks of the new hashed names of a zfile, to convert them back
+    async fn register_names(&self, zfile: &ZFile<'_>) -> ZResult<()> {
+        let new_names = self.names.register(&zfile.zpath);
+        if !new_names.is_empty() {
+            self.data_info_mgr.put_names(&new_names).await?;
+        }
+        Ok(())
+    }
+
+    // Create the parent directories of a file (resolving the conflict with an existing file or directory, according
+    // to `on_conflict`), and return the conflict-free path where to write it.
+    async fn prepare_file_path(&self, file: &Path, timestamp: &Timestamp) -> ZResult<PathBuf> {
+        if let Some(conflicting) = self.conflicting_path(file) {
+            self.conflicts.fetch_add(1, Ordering::Relaxed);
+            if self.on_conflict != OnConflict::Rename {
+                self.resolve_conflict(file, conflicting, timestamp).await?;
+            }
+        }
+        // Create parent directories if needed
+        let parent = file.parent().unwrap();
+        let ancestor = parent.ancestors().collect::<Vec<_>>();
+        for a in ancestor {
+            // if the ancestor is a directory, break, no need to check anything else
+            if a.exists() && a.is_dir() {
+                break;
+            }
+            // if the ancestor is a file, rename the file with a conflict suffix and update the info on rocksdb
+            if a.exists() && a.is_file() {
+                let conflict_file = self.get_conflict_file(a.to_path_buf());
+                trace!(
+                    "Conflict detected for {:?}. Writing to conflict free file {:?}",
+                    a,
+                    conflict_file
+                );
+                self.handles_pool.invalidate(a);
+                self.set_file_immutable(a, false);
+                rename(a, &conflict_file)
+                    .map_err(|e| zerror!("Failed to write in file {:?}: {}", conflict_file, e))?;
+                self.set_file_immutable(&conflict_file, true);
+                self.mirror_changed(a);
+                self.mirror_changed(&conflict_file);
+                match self.data_info_mgr.rename_key(a, &conflict_file).await {
+                    Ok(_) => None,
+                    Err(_) => {
+                        // fallback: get encoding and timestamp from file's metadata
+                        let (a_encoding, a_timestamp) = self.generate_metadata(a, timestamp);
+                        self.data_info_mgr
+                            .put_data_info(file, a_encoding, &a_timestamp)
+                            .await
+                            .ok()
+                    }
+                };
+            }
+        }
+
+        self.dir_builder
+            .create(parent)
+            .map_err(|e| zerror!("Failed to create directories for file {:?}: {}", file, e))?;
+
+        let file = if file.exists() && file.is_dir() {
+            self.get_conflict_file(file.to_path_buf())
+        } else {
+            file.to_path_buf()
+        };
+        trace!("Conflict-free file: {:?}", file);
+        Ok(file)
+    }
+
+    // The existing file or directory conflicting with a file to write, if any: its first existing ancestor if it's a
+    // file, or the file's path itself if it's a directory
+    fn conflicting_path<'a>(&self, file: &'a Path) -> Option<&'a Path> {
+        if file.is_dir() {
+            return Some(file);
+        }
+        file.parent()?
+            .ancestors()
+            .find(|a| a.exists())
+            .filter(|a| a.is_file())
+    }
+
+    // Resolve the conflict of a file to write with an existing file or directory, if not renamed: reject the write,
+    // or with `on_conflict` = "overwrite" delete the conflicting entries if they are older than the write
+    async fn resolve_conflict(
+        &self,
+        file: &Path,
+        conflicting: &Path,
+        timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        let kind = if conflicting.is_dir() {
+            "a directory"
+        } else {
+            "a file"
+        };
+        if self.on_conflict == OnConflict::Reject {
+            bail!(
+                "Failed to write in file {:?}: {:?} is {} (the conflicts are rejected)",
+                file,
+                conflicting,
+                kind
+            )
+        }
+        if self.conflicting_timestamp(conflicting).await? >= Some(*timestamp) {
+            bail!(
+                "Failed to write in file {:?}: {:?} is {} with more recent entries",
+                file,
+                conflicting,
+                kind
+            )
+        }
+        let zfiles = self.conflicting_entries(conflicting)?;
+        debug!(
+            "Conflict detected for {:?}: delete the {} older entries of {:?}",
+            file,
+            zfiles.len(),
+            conflicting
+        );
+        // (the directories left empty being removed)
+        self.remove_entries(&zfiles).await?;
+        if conflicting.is_dir() {
+            self.remove_empty_dirs(conflicting);
+        }
+        if conflicting.exists() {
+            bail!(
+                "Failed to write in file {:?}: {:?} isn't empty once its entries deleted",
+                file,
+                conflicting
+            )
+        }
+        Ok(())
+    }
+
+    // The timestamp of the entry stored in a conflicting file, or the latest one of the entries stored under a
+    // conflicting directory (if any)
+    async fn conflicting_timestamp(&self, conflicting: &Path) -> ZResult<Option<Timestamp>> {
+        let mut latest = None;
+        for zfile in self.conflicting_entries(conflicting)? {
+            let (_, timestamp) = self.get_encoding_and_timestamp(&zfile.fspath).await?;
+            latest = latest.max(Some(timestamp));
+        }
+        Ok(latest)
+    }
+
+    // The entries stored in a conflicting file, or under a conflicting directory
+    fn conflicting_entries(&self, conflicting: &Path) -> ZResult<Vec<ZFile<'static>>> {
+        let zpath = self
+            .zpath_of(conflicting)
+            .ok_or_else(|| zerror!("Failed to map {:?} to a key", conflicting))?;
+        if conflicting.is_file() {
+            return Ok(vec![ZFile {
+                zpath: Cow::Owned(zpath),
+                fspath: conflicting.to_path_buf(),
+            }]);
+        }
+        let zpath_expr = OwnedKeyExpr::autocanonize(format!("{zpath}/**"))?;
+        Ok(self
+            .matching_files(&zpath_expr)
+            // (the files of the other stripes don't conflict)
+            .filter(|zfile| zfile.fspath.starts_with(conflicting))
+            .map(|zfile| ZFile {
+                zpath: Cow::Owned(zfile.zpath.into_owned()),
+                fspath: zfile.fspath,
+            })
+            .collect())
+    }
+
+    // With `on_conflict` = "overwrite", return true if a write of a zfile conflicts with more recent entries (stored
+    // in an ancestor file, or under the zfile's directory), the write being then outdated
+    pub(crate) async fn is_outdated_by_conflict(
+        &self,
+        zfile: &ZFile<'_>,
+        timestamp: &Timestamp,
+    ) -> ZResult<bool> {
+        match self.conflicting_path(&zfile.fspath) {
+            Some(conflicting) if self.on_conflict == OnConflict::Overwrite => {
+                let outdated = self.conflicting_timestamp(conflicting).await? >= Some(*timestamp);
+                if outdated {
+                    self.conflicts.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(outdated)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn get_conflict_file(&self, file: PathBuf) -> PathBuf {
+        match file.to_str() {
+            Some(x) => PathBuf::from(get_conflict_resolved_keyexpr(x)),
+            None => file.to_path_buf(),
+        }
+    }
+
+    pub(crate) async fn delete_file(&self, zfile: &ZFile<'_>) -> ZResult<()> {
+        let _key = self.key_locks.lock(&zfile.zpath).await;
+        let _permit = self.acquire_op_permit().await?;
+        self.inject_faults("delete", &zfile.fspath).await?;
+        let file = &zfile.fspath;
+
+        let file = if file.exists() && file.is_file() {
+            file.to_path_buf()
+        } else {
+            self.get_conflict_file(file.to_path_buf())
+        };
+
+        // Delete file
+        trace!("Delete file {:?}", file);
+        self.handles_pool.invalidate(&file);
+        if file.exists() {
+            self.remove_file_and_empty_parents(&file)?;
+        }
+        self.forget_archived(&zfile.fspath).await?;
+        self.forget_offloaded(&zfile.fspath).await?;
+
+        // save timestamp in data-info (encoding is not used)
+        self.data_info_mgr.del_data_info(file).await?;
+        self.index_delete(&zfile.zpath);
+        self.set_whiteout(&zfile.zpath, true).await
+    }
+
+    // Delete several entries at once: their data-info are deleted in a single batch, and the directories left empty
+    // are removed once all the files are deleted (rather than after each file)
+    pub(crate) async fn delete_files(&self, zfiles: &[ZFile<'_>]) -> ZResult<()> {
+        let _keys = self
+            .key_locks
+            .lock_all(zfiles.iter().map(|zfile| &*zfile.zpath))
+            .await;
+        let _permit = self.acquire_op_permit().await?;
+        self.remove_entries(zfiles).await
+    }
+
+    // The same as delete_files(), within an operation already holding its permit
+    async fn remove_entries(&self, zfiles: &[ZFile<'_>]) -> ZResult<()> {
+        let mut files = Vec::with_capacity(zfiles.len());
+        let mut dirs = BTreeSet::new();
+        for zfile in zfiles {
+            self.inject_faults("delete", &zfile.fspath).await?;
+            let file = if zfile.fspath.is_file() {
+                zfile.fspath.clone()
+            } else {
+                self.get_conflict_file(zfile.fspath.clone())
+            };
+            trace!("Delete file {:?}", file);
+            self.handles_pool.invalidate(&file);
+            if file.exists() {
+                self.remove_accounted_file(&file)?;
+                if let Some(parent) = file.parent() {
+                    dirs.insert(parent.to_path_buf());
+                }
+            }
+            self.forget_archived(&zfile.fspath).await?;
+            self.forget_offloaded(&zfile.fspath).await?;
+            files.push(file);
+        }
+        self.data_info_mgr.del_data_info_batch(&files).await?;
+        for zfile in zfiles {
+            self.index_delete(&zfile.zpath);
+            self.set_whiteout(&zfile.zpath, true).await?;
+        }
+        // the deepest directories first
+        for dir in dirs.iter().rev() {
+            self.remove_empty_dirs(dir);
+        }
+        Ok(())
+    }
+
+    // If the entry stored in fspath (or its conflict file) was archived, forget it.
+    async fn forget_archived(&self, fspath: &Path) -> ZResult<()> {
+        if self.archive_mgr.is_none() {
+            return Ok(());
+        }
+        for file in [
+            fspath.to_path_buf(),
+            self.get_conflict_file(fspath.to_path_buf()),
+        ] {
+            if self.data_info_mgr.get_archive_day(&file).await?.is_some() {
+                self.data_info_mgr.del_archived_data_info(&file).await?;
+            }
+        }
+        Ok(())
+    }
+
+    // If the value stored in fspath (or its conflict file) was offloaded to the cold tier, forget it
+    async fn forget_offloaded(&self, fspath: &Path) -> ZResult<()> {
+        let Some(cold_tier) = &self.cold_tier else {
+            return Ok(());
+        };
+        let _rehydration = cold_tier.lock_rehydration().await;
+        for file in [
+            fspath.to_path_buf(),
+            self.get_conflict_file(fspath.to_path_buf()),
+        ] {
+            if let Some(relpath) = self.data_info_mgr.get_cold(&file).await? {
+                self.data_info_mgr.set_cold(&file, None).await?;
+                cold_tier.remove(&relpath);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_file_and_empty_parents(&self, file: &Path) -> ZResult<()> {
+        self.remove_accounted_file(file)?;
+        self.remove_empty_parents(file);
+        Ok(())
+    }
+
+    // Delete a file, releasing its size from the quotas
+    fn remove_accounted_file(&self, file: &Path) -> ZResult<()> {
+        let size = metadata(file).map(|m| m.len()).unwrap_or(0);
+        self.set_file_immutable(file, false);
+        remove_file(file).map_err(|e| zerror!("Failed to delete file {:?}: {}", file, e))?;
+        for quota in self.quotas_of(file) {
+            quota.release(size);
+        }
+        self.mirror_changed(file);
+        Ok(())
+    }
+
+    // Delete the parent directories of a removed file, if empty
+    fn remove_empty_parents(&self, file: &Path) {
+        if let Some(parent) = file.parent() {
+            self.remove_empty_dirs(parent);
+        }
+    }
+
+    // Delete a directory if empty, then its parents if empty
+    fn remove_empty_dirs(&self, dir: &Path) {
+        if self.passthrough {
+            return;
+        }
+        let mut dir = dir;
+        while dir != self.root_of(dir) && remove_dir(dir).is_ok() {
+            trace!("Removed empty dir: {:?}", dir);
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => break,
+            }
+        }
+    }
+
+    // Read a file and return it's content (as Vec<u8>), encoding and timestamp.
+    // Encoding and timestamp are retrieved from the data_info_mgr if file was put via zenoh.
+    // Otherwise, the encoding is guessed from the file extension, and the timestamp is computed from the file's time.
+    // With zenoh_write_only, return true if the entry is known not to be stored, from the entries index (if loaded)
+    // or else from the data-info of its file and conflict file, without accessing the file-system
+    pub(crate) async fn is_unknown(&self, zfile: &ZFile<'_>) -> ZResult<bool> {
+        if !self.zenoh_write_only {
+            return Ok(false);
+        }
+        if let Some(indexed) = self
+            .index
+            .as_ref()
+            .and_then(|index| index.contains(&zfile.zpath))
+        {
+            return Ok(!indexed);
+        }
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if self
+                .data_info_mgr
+                .get_encoding_and_timestamp(&file)
+                .await?
+                .is_some()
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub(crate) async fn read_file(
+        &self,
+        zfile: &ZFile<'_>,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        let _permit = self.acquire_op_permit().await?;
+        self.inject_faults("read", &zfile.fspath).await?;
+        let file = &zfile.fspath;
+        if let Some(x) = self.perform_read(file).await? {
+            return Ok(Some(x));
+        }
+        match self.perform_read_from_conflict(file.to_path_buf()).await? {
+            Some(x) => Ok(Some(x)),
+            None => self.read_archived(zfile).await,
+        }
+    }
+
+    // Read a value from the archive bundles (if the entry was archived)
+    async fn read_archived(
+        &self,
+        zfile: &ZFile<'_>,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        let archive_mgr = match &self.archive_mgr {
+            Some(archive_mgr) => archive_mgr,
+            None => return Ok(None),
+        };
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if let Some(day) = self.data_info_mgr.get_archive_day(&file).await? {
+                if let Some((encoding, timestamp)) =
+                    self.data_info_mgr.get_encoding_and_timestamp(&file).await?
+                {
+                    if let Some(content) =
+                        archive_mgr.read(&day, &self.archive_name(&zfile.zpath))?
+                    {
+                        let content = self.decode(content, &file)?;
+                        return Ok(Some((self.to_payload(content), encoding, timestamp)));
+                    }
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Move the aged entries into the archive bundles, returning the number of archived entries.
+    // The data-info of the archived entries are kept, for them to remain queryable.
+    pub(crate) async fn archive_aged_entries(&self) -> ZResult<usize> {
+        let archive_mgr = match &self.archive_mgr {
+            Some(archive_mgr) => archive_mgr,
+            None => return Ok(0),
+        };
+        let mut by_day: HashMap<String, Vec<ArchiveEntry>> = HashMap::new();
+        for zfile in self.matching_files(unsafe { keyexpr::from_str_unchecked("**") }) {
+            let (encoding, timestamp) = self.get_encoding_and_timestamp(&zfile.fspath).await?;
+            if archive_mgr.is_aged(&timestamp) {
+                // make sure the data-info exists, even for files not created via zenoh
+                self.data_info_mgr
+                    .put_data_info(&zfile.fspath, encoding, &timestamp)
+                    .await?;
+                by_day
+                    .entry(ArchiveMgr::day_of(&timestamp))
+                    .or_default()
+                    .push(ArchiveEntry {
+                        name: self.archive_name(&zfile.zpath),
+                        file: zfile.fspath,
+                        timestamp,
+                    });
+            }
+        }
+
+        let mut count = 0;
+        for (day, entries) in by_day {
+            archive_mgr.append(&day, &entries)?;
+            for entry in entries {
+                // skip the file if it was re-written meanwhile
+                let (_, timestamp) = self.get_encoding_and_timestamp(&entry.file).await?;
+                if timestamp != entry.timestamp {
+                    continue;
+                }
+                self.data_info_mgr.mark_archived(&entry.file, &day).await?;
+                self.handles_pool.invalidate(&entry.file);
+                self.remove_file_and_empty_parents(&entry.file)?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    // Offload the files not accessed for the cold tier's period, leaving empty stubs in their place, returning the
+    // number of offloaded files. The data-info of the offloaded files are kept, for them to remain queryable.
+    pub(crate) async fn offload_cold_entries(&self) -> ZResult<usize> {
+        let Some(cold_tier) = &self.cold_tier else {
+            return Ok(0);
+        };
+        let mut count = 0;
+        for zfile in self.matching_files(unsafe { keyexpr::from_str_unchecked("**") }) {
+            let file = zfile.fspath;
+            let size = match metadata(&file) {
+                Ok(m) if m.len() > 0 && cold_tier.is_cold(&m) => m.len(),
+                _ => continue,
+            };
+            let Some(relpath) = self
+                .relpath_of(&file)
+                .map(|p| p.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            let _permit = self.acquire_op_permit().await?;
+            // make sure the data-info exists, even for files not created via zenoh (the modification time of the
+            // stub not being the value's one)
+            let (encoding, timestamp) = self.get_encoding_and_timestamp(&file).await?;
+            self.data_info_mgr
+                .put_data_info(&file, encoding, &timestamp)
+                .await?;
+            trace!("Offload file {:?} to the cold tier", file);
+            // the cold tier may be on a slow file-system: not blocking the runtime's workers
+            let stored = {
+                let (cold_tier, file, relpath) = (cold_tier.clone(), file.clone(), relpath.clone());
+                tokio::task::spawn_blocking(move || cold_tier.store(&file, &relpath))
+                    .await
+                    .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+            };
+            if let Err(e) = stored {
+                warn!("Failed to offload file {:?} to the cold tier: {}", file, e);
+                continue;
+            }
+
+            let _rehydration = cold_tier.lock_rehydration().await;
+            // skip the file if it was re-written meanwhile
+            let (_, current) = self.get_encoding_and_timestamp(&file).await?;
+            if current != timestamp {
+                cold_tier.remove(&relpath);
+                continue;
+            }
+            self.data_info_mgr.set_cold(&file, Some(&relpath)).await?;
+            self.handles_pool.invalidate(&file);
+            self.set_file_immutable(&file, false);
+            let result = OpenOptions::new()
+                .write(true)
+                .open(&file)
+                .and_then(|f| f.set_len(0));
+            self.set_file_immutable(&file, true);
+            if let Err(e) = result {
+                warn!("Failed to offload file {:?} to the cold tier: {}", file, e);
+                self.data_info_mgr.set_cold(&file, None).await?;
+                cold_tier.remove(&relpath);
+                continue;
+            }
+            for quota in self.quotas_of(&file) {
+                quota.release(size);
+            }
+            cold_tier.count_offloaded();
+            count += 1;
+        }
+        Ok(count)
+    }
 
-        // Write file
-        trace!("Write in file {:?}", file);
-        let file = if file.exists() && file.is_dir() {
-            self.get_conflict_file(file.to_path_buf())
-        } else {
-            file.to_path_buf()
+    // Copy back the value of a file offloaded to the cold tier in place of its stub (if it's one), for it to be read
+    pub(crate) async fn rehydrate(&self, file: &Path) -> ZResult<()> {
+        let Some(cold_tier) = &self.cold_tier else {
+            return Ok(());
         };
-        trace!("Writing in conflict-free file {:?}", file);
-        let mut f = File::create(&file)
-            .map_err(|e| zerror!("Failed to write in file {:?}: {}", file, e))?;
-        for slice in content.slices() {
-            f.write_all(slice)
-                .map_err(|e| zerror!("Failed to write in file {:?}: {}", file, e))?;
+        if metadata(file).map_or(true, |m| m.len() > 0) {
+            return Ok(());
         }
-
-        // save data-info
-        self.data_info_mgr
-            .put_data_info(file, encoding, timestamp)
-            .await
+        let _rehydration = cold_tier.lock_rehydration().await;
+        // (unless rehydrated meanwhile, or an empty value)
+        let Some(relpath) = self.data_info_mgr.get_cold(file).await? else {
+            return Ok(());
+        };
+        trace!("Rehydrate file {:?} from the cold tier", file);
+        self.handles_pool.invalidate(file);
+        self.set_file_immutable(file, false);
+        let loaded = {
+            let (cold_tier, file, relpath) =
+                (cold_tier.clone(), file.to_path_buf(), relpath.clone());
+            tokio::task::spawn_blocking(move || cold_tier.load(&relpath, &file))
+                .await
+                .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+        };
+        self.set_file_immutable(file, true);
+        let size = loaded.map_err(|e| {
+            zerror!(
+                "Failed to rehydrate file {:?} from the cold tier: {}",
+                file,
+                e
+            )
+        })?;
+        // (possibly exceeding the caps: the value was already stored)
+        for quota in self.quotas_of(file) {
+            quota.account(size);
+        }
+        self.data_info_mgr.set_cold(file, None).await?;
+        cold_tier.remove(&relpath);
+        cold_tier.count_rehydrated();
+        Ok(())
     }
 
-    fn get_conflict_file(&self, file: PathBuf) -> PathBuf {
-        match file.to_str() {
-            Some(x) => PathBuf::from(get_conflict_resolved_keyexpr(x)),
-            None => file.to_path_buf(),
+    // Return true if a file is the empty stub of a value offloaded to the cold tier
+    async fn is_stub(&self, file: &Path) -> ZResult<bool> {
+        if self.cold_tier.is_none() || metadata(file).map_or(true, |m| m.len() > 0) {
+            return Ok(false);
         }
+        Ok(self.data_info_mgr.get_cold(file).await?.is_some())
     }
 
-    pub(crate) async fn delete_file(&self, zfile: &ZFile<'_>) -> ZResult<()> {
-        let file = &zfile.fspath;
+    // The name of an entry in the archive bundles (its hashed path if names are obfuscated)
+    fn archive_name(&self, zpath: &str) -> String {
+        self.names.to_fs_relpath(zpath).into_owned()
+    }
 
-        let file = if file.exists() && file.is_file() {
-            file.to_path_buf()
-        } else {
-            self.get_conflict_file(file.to_path_buf())
+    // Return the zenoh paths and timestamps of the archived entries
+    pub(crate) async fn archived_entries(&self) -> ZResult<Vec<(String, Timestamp)>> {
+        if self.archive_mgr.is_none() {
+            return Ok(vec![]);
+        }
+        Ok(self
+            .data_info_mgr
+            .get_archived_files()
+            .await?
+            .into_iter()
+            .filter_map(|(file, timestamp)| Some((self.zpath_of(&file)?, timestamp)))
+            .collect())
+    }
+
+    // Return the number of the conflict files (the files renamed with CONFLICT_SUFFIX for a directory to take their
+    // path), with the zenoh paths of the first ones
+    async fn conflicts_status(&self) -> serde_json::Value {
+        const MAX_LISTED: usize = 100;
+        let files = match self.data_info_mgr.get_conflict_files().await {
+            Ok(files) => files,
+            Err(e) => return serde_json::json!({ "error": e.to_string() }),
         };
+        let keys: Vec<String> = files
+            .iter()
+            .filter_map(|file| self.zpath_of(file))
+            .take(MAX_LISTED)
+            .collect();
+        serde_json::json!({
+            "count": files.len(),
+            "keys": keys,
+            "events": self.conflicts.load(Ordering::Relaxed),
+        })
+    }
 
-        // Delete file
-        trace!("Delete file {:?}", file);
-        if file.exists() {
-            remove_file(&file).map_err(|e| zerror!("Failed to delete file {:?}: {}", file, e))?;
-            // try to delete parent directories if empty
-            let mut f = file.as_path();
-            while let Some(parent) = f.parent() {
-                if parent != self.base_dir() && remove_dir(parent).is_ok() {
-                    trace!("Removed empty dir: {:?}", parent);
-                } else {
-                    break;
+    // The values stored in conflict files, under a key (including its own one) or all of them, in the order of
+    // their files' paths
+    pub(crate) async fn conflicts(&self, subtree: Option<&keyexpr>) -> ZResult<Vec<Conflict>> {
+        let mut result = Vec::new();
+        for file in self.data_info_mgr.get_conflict_files().await? {
+            let Some(zpath) = self.zpath_of(&file) else {
+                continue;
+            };
+            if let Some(key) = subtree {
+                if zpath != key.as_str() && !zpath.starts_with(&format!("{key}/")) {
+                    continue;
                 }
-                f = parent;
             }
+            let Some(dir) = file
+                .to_str()
+                .and_then(|s| s.strip_suffix(CONFLICT_SUFFIX))
+                .map(PathBuf::from)
+            else {
+                continue;
+            };
+            let (_, timestamp) = self.get_encoding_and_timestamp(&file).await?;
+            let relpath = |path: &Path| self.relpath_of(path).unwrap_or(path).to_path_buf();
+            result.push(Conflict {
+                zpath,
+                timestamp,
+                file: relpath(&file),
+                dir: dir.is_dir().then(|| relpath(&dir)),
+            });
         }
-
-        // save timestamp in data-info (encoding is not used)
-        self.data_info_mgr.del_data_info(file).await
+        Ok(result)
     }
 
-    // Read a file and return it's content (as Vec<u8>), encoding and timestamp.
-    // Encoding and timestamp are retrieved from the data_info_mgr if file was put via zenoh.
-    // Otherwise, the encoding is guessed from the file extension, and the timestamp is computed from the file's time.
-    pub(crate) async fn read_file(
-        &self,
-        zfile: &ZFile<'_>,
-    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
-        let file = &zfile.fspath;
-        match self.perform_read(file).await? {
-            Some(x) => Ok(Some(x)),
-            None => self.perform_read_from_conflict(file.to_path_buf()).await,
+    // Rename back the conflict files whose path is free again (the conflicting directory being removed),
+    // returning their number
+    pub(crate) async fn normalize_conflicts(&self) -> ZResult<usize> {
+        let mut count = 0;
+        for conflict_file in self.data_info_mgr.get_conflict_files().await? {
+            let Some(file) = conflict_file
+                .to_str()
+                .and_then(|s| s.strip_suffix(CONFLICT_SUFFIX))
+                .map(PathBuf::from)
+            else {
+                continue;
+            };
+            let _permit = self.acquire_op_permit().await?;
+            if file.exists() || !conflict_file.is_file() {
+                continue;
+            }
+            trace!("Normalize conflict file {:?} to {:?}", conflict_file, file);
+            self.handles_pool.invalidate(&conflict_file);
+            self.set_file_immutable(&conflict_file, false);
+            if let Err(e) = rename(&conflict_file, &file) {
+                self.set_file_immutable(&conflict_file, true);
+                warn!(
+                    "Failed to normalize conflict file {:?} to {:?}: {}",
+                    conflict_file, file, e
+                );
+                continue;
+            }
+            self.set_file_immutable(&file, true);
+            self.mirror_changed(&conflict_file);
+            self.mirror_changed(&file);
+            self.data_info_mgr.rename_key(&conflict_file, &file).await?;
+            count += 1;
         }
+        Ok(count)
     }
 
     async fn perform_read_from_conflict(
@@ -235,10 +2703,201 @@ impl FilesMgr {
         self.perform_read(&file.to_path_buf()).await
     }
 
+    // Return a range of the value of a zfile, decompressing only the frames covering it, if the zstd-seekable
+    // transform is the only one applied to its file. Otherwise (or if the range is empty, e.g. beyond the value's
+    // end) return None, the range being then taken from the whole value.
+    // The envelope's checksum isn't checked, but the checksum of each decompressed frame is.
+    pub(crate) async fn read_range(
+        &self,
+        zfile: &ZFile<'_>,
+        range: Range<u64>,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        let _permit = self.acquire_op_permit().await?;
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if !self.is_readable_file(&file) {
+                continue;
+            }
+            self.rehydrate(&file).await?;
+            let mut f =
+                File::open(&file).map_err(|e| zerror!("Error reading file {:?}: {}", file, e))?;
+            let content = envelope::skip_header(&mut f)
+                .and_then(|()| Transforms::seekable_range(&mut f, range.clone()));
+            return match content {
+                Ok(Some(content)) if !content.is_empty() => {
+                    trace!("Read range {:?} of file {:?}", range, file);
+                    let (encoding, timestamp) = self.get_encoding_and_timestamp(&file).await?;
+                    Ok(Some((self.to_payload(content), encoding, timestamp)))
+                }
+                Ok(_) => Ok(None),
+                Err(e) => {
+                    // the whole file being read instead, and quarantined if it's corrupted
+                    debug!("Failed to read range {:?} of file {:?}: {}", range, file, e);
+                    Ok(None)
+                }
+            };
+        }
+        Ok(None)
+    }
+
+    // Return the value of a zfile compressed with gzip, without decompressing it: as stored if the gzip transform is
+    // its only one, or else from the `.gz` sibling of its file (e.g. generated along with it) if not older, if any.
+    pub(crate) async fn read_compressed(
+        &self,
+        zfile: &ZFile<'_>,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        const GZIP_MAGIC: &[u8] = b"\x1f\x8b";
+        let _permit = self.acquire_op_permit().await?;
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if !self.is_readable_file(&file) {
+                continue;
+            }
+            self.rehydrate(&file).await?;
+            let content = std::fs::read(&file)
+                .map_err(|e| zerror!("Error reading file {:?}: {}", file, e))?;
+            let content = envelope::unwrap(content)
+                .map_err(|e| zerror!("Error reading file {:?}: {}", file, e))?;
+            let (encoding, timestamp) = self.get_encoding_and_timestamp(&file).await?;
+            if let Some(stream) = Transforms::gzip_stream(&content) {
+                trace!("Read file {:?} compressed", file);
+                return Ok(Some((
+                    self.to_payload(stream.to_vec()),
+                    encoding,
+                    timestamp,
+                )));
+            }
+            let sibling = self.to_fspath(&format!("{}.gz", zfile.zpath));
+            return match self.perform_read(&sibling).await? {
+                Some((payload, _, sibling_timestamp))
+                    if sibling_timestamp >= timestamp
+                        && payload.to_bytes().starts_with(GZIP_MAGIC) =>
+                {
+                    trace!("Read file {:?} compressed, from {:?}", file, sibling);
+                    Ok(Some((payload, encoding, timestamp)))
+                }
+                _ => Ok(None),
+            };
+        }
+        Ok(None)
+    }
+
+    // Return the path of the file storing zfile's value (possibly a conflict file), with its encoding and timestamp.
+    pub(crate) async fn locate_file(
+        &self,
+        zfile: &ZFile<'_>,
+    ) -> ZResult<Option<(PathBuf, Encoding, Timestamp)>> {
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if self.is_readable_file(&file) {
+                let (encoding, timestamp) = self.get_encoding_and_timestamp(&file).await?;
+                return Ok(Some((file, encoding, timestamp)));
+            }
+        }
+        Ok(None)
+    }
+
+    // Return the encoding of the value of a zfile (stored in a file or in an archive bundle), without reading it
+    pub(crate) async fn encoding_of(&self, zfile: &ZFile<'_>) -> ZResult<Option<Encoding>> {
+        if let Some((_, encoding, _)) = self.locate_file(zfile).await? {
+            return Ok(Some(encoding));
+        }
+        if self.archive_mgr.is_none() {
+            return Ok(None);
+        }
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if self.data_info_mgr.get_archive_day(&file).await?.is_some() {
+                return Ok(self
+                    .data_info_mgr
+                    .get_encoding_and_timestamp(&file)
+                    .await?
+                    .map(|(encoding, _)| encoding));
+            }
+        }
+        Ok(None)
+    }
+
+    // Return the size of the file storing the value of a zfile, if any (an archived value having none)
+    pub(crate) fn file_size(&self, zfile: &ZFile<'_>) -> Option<u64> {
+        [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ]
+        .iter()
+        .find(|file| self.is_readable_file(file))
+        .and_then(|file| metadata(file).ok())
+        .map(|m| m.len())
+    }
+
+    // The timestamp of the value stored for the zfile (in a file or in an archive bundle), if any
+    async fn stored_timestamp(&self, zfile: &ZFile<'_>) -> ZResult<Option<Timestamp>> {
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if file.is_file() {
+                return Ok(Some(self.get_encoding_and_timestamp(&file).await?.1));
+            }
+            if self.archive_mgr.is_some()
+                && self.data_info_mgr.get_archive_day(&file).await?.is_some()
+            {
+                return Ok(self
+                    .data_info_mgr
+                    .get_encoding_and_timestamp(&file)
+                    .await?
+                    .map(|(_, timestamp)| timestamp));
+            }
+        }
+        Ok(None)
+    }
+
+    // Return true if a value is stored for the zfile (in a file or in an archive bundle)
+    pub(crate) async fn exists(&self, zfile: &ZFile<'_>) -> ZResult<bool> {
+        for file in [
+            zfile.fspath.clone(),
+            self.get_conflict_file(zfile.fspath.clone()),
+        ] {
+            if file.is_file() {
+                return Ok(true);
+            }
+            if self.archive_mgr.is_some()
+                && self.data_info_mgr.get_archive_day(&file).await?.is_some()
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // consider file only is it exists, it's a file and in case of "follow_links=true" it doesn't contain symlink
+    // (or, with follow_links, not more than max_link_depth ones)
+    fn is_readable_file(&self, file: &Path) -> bool {
+        file.exists()
+            && file.is_file()
+            && match (self.follow_links, self.max_link_depth) {
+                (false, _) => !self.contains_symlink(file),
+                (true, None) => true,
+                (true, Some(max)) => self.symlinks_count(file) <= max,
+            }
+    }
+
     async fn perform_read(&self, file: &Path) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
-        // consider file only is it exists, it's a file and in case of "follow_links=true" it doesn't contain symlink
-        if file.exists() && file.is_file() && (self.follow_links || !self.contains_symlink(file)) {
-            match File::open(file) {
+        if self.is_readable_file(file) {
+            self.rehydrate(file).await?;
+            if let Some(content) = self.prefetcher.as_ref().and_then(|p| p.take(file)) {
+                trace!("Read file {:?} (prefetched)", file);
+                return self.decode_read(content, file).await;
+            }
+            match self.retry.io(|| self.handles_pool.take(file)) {
                 Ok(mut f) => {
                     // TODO: what if file is too big ??
                     let size = f.metadata().map(|m| m.len()).unwrap_or(256);
@@ -248,9 +2907,8 @@ impl FilesMgr {
                         if let Err(e) = f.read_to_end(&mut content) {
                             bail!(r#"Error reading file {:?}: {}"#, file, e)
                         } else {
-                            let (encoding, timestamp) =
-                                self.get_encoding_and_timestamp(file).await?;
-                            Ok(Some((content.into(), encoding, timestamp)))
+                            self.handles_pool.give_back(file, f);
+                            self.decode_read(content, file).await
                         }
                     } else {
                         bail!(r#"Error reading file {:?}: too big to fit in memory"#, file)
@@ -263,7 +2921,98 @@ impl FilesMgr {
         }
     }
 
-    // Search for files matching path_expr.
+    // Decode the content read from a file, returning it with its encoding and timestamp (or None if it's corrupted)
+    async fn decode_read(
+        &self,
+        content: Vec<u8>,
+        file: &Path,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        let content = match self.decode(content, file) {
+            Ok(content) => content,
+            Err(e) => {
+                self.quarantine(file, &e).await?;
+                return Ok(None);
+            }
+        };
+        let (encoding, timestamp) = self.get_encoding_and_timestamp(file).await?;
+        Ok(Some((self.to_payload(content), encoding, timestamp)))
+    }
+
+    // Record the read of a key, returning the keys whose files are to be prefetched (if it's read sequentially)
+    pub(crate) fn read_ahead_keys(&self, zpath: &str) -> Vec<String> {
+        match &self.prefetcher {
+            Some(prefetcher) => prefetcher.next_keys(zpath),
+            None => vec![],
+        }
+    }
+
+    // Read the file of a key in advance (if not too big), for it to be served from memory when requested
+    pub(crate) async fn prefetch(&self, zpath: &str) -> ZResult<()> {
+        let Some(prefetcher) = &self.prefetcher else {
+            return Ok(());
+        };
+        let _permit = self.acquire_op_permit().await?;
+        let file = self.to_fspath(zpath);
+        if !self.is_readable_file(&file) {
+            return Ok(());
+        }
+        let metadata =
+            metadata(&file).map_err(|e| zerror!("Error reading file {:?}: {}", file, e))?;
+        if metadata.len() > MAX_PREFETCHED_FILE_SIZE {
+            return Ok(());
+        }
+        let content =
+            std::fs::read(&file).map_err(|e| zerror!("Error reading file {:?}: {}", file, e))?;
+        prefetcher.insert(file, content, &metadata);
+        Ok(())
+    }
+
+    // Move a file failing to be decoded (corrupted) into the quarantine directory of its stripe, keeping its relative
+    // path, and forget its entry: it's then reported as not found, rather than failing the queries.
+    // In passthrough mode the file is left as it is, and only skipped.
+    async fn quarantine(&self, file: &Path, e: &zenoh::Error) -> ZResult<()> {
+        self.handles_pool.invalidate(file);
+        if self.passthrough || self.dry_run {
+            error!("Corrupted file {:?} skipped: {}", file, e);
+            return Ok(());
+        }
+        let root = self.root_of(file);
+        let target = match self.relpath_of(file) {
+            Some(relpath) => root.join(QUARANTINE_DIR).join(relpath),
+            None => bail!("Failed to quarantine file {:?}: not in the storage", file),
+        };
+        error!(
+            "Corrupted file {:?} moved into quarantine as {:?}: {}",
+            file, target, e
+        );
+        if let Some(parent) = target.parent() {
+            self.dir_builder
+                .create(parent)
+                .map_err(|e| zerror!("Failed to quarantine file {:?}: {}", file, e))?;
+        }
+        let size = metadata(file).map(|m| m.len()).unwrap_or(0);
+        self.set_file_immutable(file, false);
+        rename(file, &target)
+            .map_err(|e| zerror!("Failed to quarantine file {:?}: {}", file, e))?;
+        // the quarantine directory isn't accounted in the quotas (nor mirrored)
+        for quota in self.quotas_of(file) {
+            quota.release(size);
+        }
+        self.mirror_changed(file);
+        self.remove_empty_parents(file);
+        self.data_info_mgr.del_data_info(file).await?;
+        if let Some(zpath) = self.zpath_of(file) {
+            self.index_delete(&zpath);
+        }
+        self.quarantined.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub(crate) fn quarantined_count(&self) -> u64 {
+        self.quarantined.load(Ordering::Relaxed)
+    }
+
+    // Search for files matching path_expr, in the lexicographic order of the files' names in each directory.
     pub(crate) fn matching_files<'a>(&self, zpath_expr: &'a keyexpr) -> FilesIterator<'a> {
         // find the longest segment without '*' to search for files only in the corresponding
         let star_idx = zpath_expr.find('*').unwrap();
@@ -271,34 +3020,74 @@ impl FilesMgr {
             Some(i) => &zpath_expr[..i],
             None => "",
         };
-        // Directory to search for matching files is base_dir + segment converted as a file-system path
-        let search_dir = self.to_fspath(segment);
-        let base_dir_len = self.base_dir.as_os_str().len();
-
-        if !self.follow_links && self.contains_symlink(&search_dir) {
-            debug!(
-                "Don't search for files in {:?} as it's within a symbolic link",
-                search_dir
-            );
-            // return a useless FilesIterator that won't return anything (simpler than to return an Option<FilesIterator>)
-            let walkdir = WalkDir::new("");
-            FilesIterator {
-                walk_iter: walkdir.into_iter(),
-                zpath_expr,
-                base_dir_len,
-            }
-        } else {
-            debug!(
-                "For path_expr={} search matching files in {:?}",
-                zpath_expr, search_dir
-            );
-            let walkdir = WalkDir::new(search_dir).follow_links(self.follow_links);
-            FilesIterator {
-                walk_iter: walkdir.into_iter(),
-                zpath_expr,
-                base_dir_len,
-            }
-        }
+        // the files can only match at a bounded depth under the segment if the rest of the expression has no "**"
+        let rest = match segment.is_empty() {
+            true => zpath_expr.as_str(),
+            false => &zpath_expr[segment.len() + 1..],
+        };
+        let max_depth = match rest.split('/').any(|chunk| chunk == "**") {
+            true => None,
+            false => Some(rest.split('/').count()),
+        };
+        // Directory to search for matching files in each stripe is its root + segment converted as a file-system path
+        let relpath = self.to_fs_relpath(segment);
+        let stripes = self
+            .roots()
+            .filter_map(|root| {
+                let search_dir = root.join(relpath.as_ref());
+                if !self.follow_links && self.contains_symlink(&search_dir) {
+                    debug!(
+                        "Don't search for files in {:?} as it's within a symbolic link",
+                        search_dir
+                    );
+                    return None;
+                }
+                debug!(
+                    "For path_expr={} search matching files in {:?}",
+                    zpath_expr, search_dir
+                );
+                // the links followed to reach search_dir count in the depth of its entries
+                let link_depths = match self.follow_links && self.max_link_depth.is_some() {
+                    true => vec![self.symlinks_count(&search_dir)],
+                    false => vec![],
+                };
+                // (walkdir detecting the loops of symbolic links, when followed without bound)
+                let walk = match &self.dir_listings {
+                    Some(listings) if max_depth.is_some() || !self.follow_links => {
+                        Walk::Listed(ListedWalk {
+                            listings: listings.clone(),
+                            follow_links: self.follow_links,
+                            max_depth: max_depth.unwrap_or(usize::MAX),
+                            search_dir: Some(search_dir),
+                            pending: None,
+                            stack: vec![],
+                        })
+                    }
+                    _ => {
+                        let mut walkdir = WalkDir::new(search_dir)
+                            .follow_links(self.follow_links)
+                            .sort_by_file_name();
+                        if let Some(max_depth) = max_depth {
+                            walkdir = walkdir.max_depth(max_depth);
+                        }
+                        Walk::Dir(walkdir.into_iter())
+                    }
+                };
+                let files = StripeFiles {
+                    walk,
+                    zpath_expr,
+                    root: root.to_path_buf(),
+                    names: self.names.clone(),
+                    canonicalize_keys: self.canonicalize_keys,
+                    follow_links: self.follow_links,
+                    max_link_depth: self.max_link_depth.filter(|_| self.follow_links),
+                    link_depths,
+                    skip_silly_renames: self.nfs_safe,
+                };
+                Some((files, None))
+            })
+            .collect();
+        FilesIterator { stripes }
     }
 
     fn generate_metadata(&self, file: &Path, timestamp: &Timestamp) -> (Encoding, Timestamp) {
@@ -315,6 +3104,14 @@ impl FilesMgr {
         match self.data_info_mgr.get_encoding_and_timestamp(&file).await? {
             Some((encoding, timestamp)) => Ok((encoding, timestamp)),
             None => {
+                // the data-info may be lost: recover them from the file's envelope, if any
+                if let Ok(Some((encoding, timestamp))) = envelope::read_info(file) {
+                    trace!(
+                        "data-info for {:?} not found; recovered from envelope",
+                        file
+                    );
+                    return Ok((encoding, timestamp));
+                }
                 trace!("data-info for {:?} not found; fallback to metadata", file);
                 let encoding = self.guess_encoding(file);
                 // fallback: get timestamp from file's metadata
@@ -325,13 +3122,40 @@ impl FilesMgr {
         }
     }
 
+    // The encoding of a zfile's value if it had no data-info
+    pub(crate) fn guess_encoding_of(&self, zfile: &ZFile<'_>) -> Encoding {
+        self.guess_encoding(&zfile.fspath)
+    }
+
     fn guess_encoding(&self, file: &Path) -> Encoding {
-        if self.keep_mime {
-            // fallback: guess mime type from file extension
-            let mime_type = mime_guess::from_path(file).first_or_octet_stream();
-            Encoding::from(mime_type.essence_str().to_string())
+        // the encoding configured for the key's longest prefix, if any
+        if !self.encoding_overrides.is_empty() {
+            if let Some((_, encoding)) = self.zpath_of(file).and_then(|zpath| {
+                self.encoding_overrides.iter().find(|(prefix, _)| {
+                    zpath
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+                })
+            }) {
+                return encoding.clone();
+            }
+        }
+        // fallback: guess mime type from file extension
+        let encoding = if self.keep_mime {
+            mime_type_of(file)
         } else {
-            Encoding::APPLICATION_OCTET_STREAM
+            None
+        };
+        let encoding = encoding.unwrap_or_else(|| self.default_encoding.clone());
+        // add the schema configured for the file's directory
+        let relative = self.relpath_of(file).unwrap_or(file);
+        match self
+            .encoding_schemas
+            .iter()
+            .find(|(dir, _)| relative.parent().is_some_and(|p| p.starts_with(dir)))
+        {
+            Some((_, schema)) => encoding.with_schema(schema.as_str()),
+            None => encoding,
         }
     }
 
@@ -348,10 +3172,22 @@ impl FilesMgr {
             .or_else(|_| metadata.accessed())
             .or_else(|_| metadata.created())
             .unwrap_or_else(|_| SystemTime::now());
-        Ok(Timestamp::new(
-            sys_time.duration_since(UNIX_EPOCH).unwrap().into(),
-            TimestampId::try_from([1]).unwrap(),
-        ))
+        let time = sys_time.duration_since(UNIX_EPOCH).unwrap();
+        // the sub-second part of the modification times isn't reliable on network file-systems (e.g. 2s on SMB)
+        let time = match self.nfs_safe {
+            true => Duration::from_secs(time.as_secs()),
+            false => time,
+        };
+        Ok(Timestamp::new(time.into(), self.timestamp_id))
+    }
+
+    // Count the segments of a Path which are symbolic links (up-to its stripe's root)
+    fn symlinks_count(&self, path: &Path) -> usize {
+        let root = self.root_of(path);
+        path.ancestors()
+            .take_while(|p| *p != root)
+            .filter(|p| is_symlink(p))
+            .count()
     }
 
     // Check if a Path contains a segment which is a symbolic link
@@ -360,10 +3196,11 @@ impl FilesMgr {
             return true;
         }
 
+        let root = self.root_of(path.as_ref());
         let mut current = path.as_ref();
         while let Some(parent) = current.parent() {
-            // check only up-to base_dir, and don't mind if it's itself a symbolic link
-            if parent == self.base_dir() {
+            // check only up-to the stripe's root, and don't mind if it's itself a symbolic link
+            if parent == root {
                 return false;
             } else if is_symlink(parent) {
                 return true;
@@ -374,23 +3211,28 @@ impl FilesMgr {
     }
 }
 
+// The fallback of shutdown(), if it didn't complete (e.g. the runtime being shut down meanwhile, or for a FilesMgr
+// dropped without being closed): best-effort, as the destructor may run in an async context where blocking on the
+// runtime would panic or deadlock
 impl Drop for FilesMgr {
     fn drop(&mut self) {
+        if self.closed.load(Ordering::Relaxed) {
+            return;
+        }
         debug!("Closing File System Storage on {:?}", self.base_dir);
+        self.handles_pool.clear();
         match self.on_closure {
             OnClosure::DeleteAll => {
-                // Close data_info_mgr at first
-                blockon_runtime(async move {
-                    self.data_info_mgr
-                        .close()
-                        .await
-                        .unwrap_or_else(|e| warn!("{}", e));
-                    remove_dir_all(&self.base_dir).unwrap_or_else(|err| {
-                        warn!("Failed to cleanup directory {:?}; {}", self.base_dir, err)
-                    });
-                });
+                // close the data-info database at first
+                self.data_info_mgr
+                    .close_now()
+                    .unwrap_or_else(|e| warn!("{}", e));
+                self.remove_roots();
             }
             OnClosure::DoNothing => {
+                self.data_info_mgr
+                    .flush_now()
+                    .unwrap_or_else(|e| warn!("{}", e));
                 debug!(
                     "Close File System Storage, keeping directory {:?} as it is",
                     self.base_dir
@@ -401,68 +3243,321 @@ impl Drop for FilesMgr {
 }
 
 pub(crate) struct FilesIterator<'a> {
-    walk_iter: IntoIter,
-    zpath_expr: &'a keyexpr,
-    base_dir_len: usize,
+    // the files of each stripe, with the next one (if already walked)
+    stripes: Vec<(StripeFiles<'a>, Option<ZFile<'a>>)>,
 }
 
 impl<'a> Iterator for FilesIterator<'a> {
     type Item = ZFile<'a>;
+    // the files of all the stripes, in the order of their paths relative to their stripe's root
+    fn next(&mut self) -> Option<Self::Item> {
+        for (files, next) in self.stripes.iter_mut() {
+            if next.is_none() {
+                *next = files.next();
+            }
+        }
+        let (i, _) = self
+            .stripes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (files, next))| {
+                let zfile = next.as_ref()?;
+                Some((
+                    i,
+                    zfile
+                        .fspath
+                        .strip_prefix(&files.root)
+                        .unwrap_or(&zfile.fspath),
+                ))
+            })
+            .min_by(|(_, a), (_, b)| a.cmp(b))?;
+        self.stripes[i].1.take()
+    }
+}
+
+// The walk of the files matching a key expression in a stripe (base_dir, if not striped)
+struct StripeFiles<'a> {
+    walk: Walk,
+    zpath_expr: &'a keyexpr,
+    root: PathBuf,
+    names: Arc<NamesMapper>,
+    canonicalize_keys: bool,
+    follow_links: bool,
+    // the maximum number of symbolic links followed in an entry's path, if bounded
+    max_link_depth: Option<usize>,
+    // if bounded, the number of symbolic links followed to reach each directory of the current path
+    // (the search directory first)
+    link_depths: Vec<usize>,
+    // skip the files renamed by an NFS client when removed while open
+    skip_silly_renames: bool,
+}
+
+// The walk of the search directory of a stripe: with walkdir, or from the cached listings of the directories (if
+// enabled, unless following the symbolic links down to an unbounded depth)
+enum Walk {
+    Dir(IntoIter),
+    Listed(ListedWalk),
+}
+
+// An entry of the walk
+struct WalkEntry {
+    path: PathBuf,
+    // the depth under the search directory (0 for the search directory itself)
+    depth: usize,
+    // the type of the entry, or of the target of the symbolic link if followed
+    is_dir: bool,
+    is_symlink: bool,
+}
+
+impl Walk {
+    // Skip the content of the last returned entry, if it's a directory
+    fn skip_current_dir(&mut self) {
+        match self {
+            Walk::Dir(walk_iter) => walk_iter.skip_current_dir(),
+            Walk::Listed(walk) => walk.pending = None,
+        }
+    }
+}
+
+// The walk of the directories from their cached listings, in the same order as walkdir with `sort_by_file_name`
+// (each directory being followed by its entries, sorted by name), down to max_depth
+struct ListedWalk {
+    listings: Arc<DirListings>,
+    follow_links: bool,
+    max_depth: usize,
+    // the search directory, until it's returned
+    search_dir: Option<PathBuf>,
+    // the last returned directory, with its depth, to be walked next unless skipped
+    pending: Option<(PathBuf, usize)>,
+    // the listing of each directory of the current path, the index of its next entry and the depth of its entries
+    stack: Vec<(PathBuf, Arc<Vec<DirChild>>, usize, usize)>,
+}
+
+impl Iterator for ListedWalk {
+    type Item = WalkEntry;
     fn next(&mut self) -> Option<Self::Item> {
+        if let Some(dir) = self.search_dir.take() {
+            let metadata = match dir.metadata() {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    debug!("Possible issue walking {:?}: {}", dir, e);
+                    return None;
+                }
+            };
+            if metadata.is_dir() {
+                self.pending = Some((dir.clone(), 0));
+            }
+            return Some(WalkEntry {
+                is_symlink: is_symlink(&dir),
+                path: dir,
+                depth: 0,
+                is_dir: metadata.is_dir(),
+            });
+        }
+        if let Some((dir, depth)) = self.pending.take() {
+            if depth < self.max_depth {
+                match self.listings.list(&dir) {
+                    Ok(children) => self.stack.push((dir, children, 0, depth + 1)),
+                    Err(e) => debug!("Possible issue walking {:?}: {}", dir, e),
+                }
+            }
+        }
         loop {
-            match self.walk_iter.next() {
-                Some(Ok(e)) => {
-                    if e.file_type().is_dir() {
-                        // skip content of DataInfoMgr::DB_FILENAME directory
-                        if e.file_name().to_str().unwrap_or_default() == DataInfoMgr::DB_FILENAME {
-                            self.walk_iter.skip_current_dir();
-                        }
-                    } else {
-                        let fspath = e.into_path();
-                        if let Some(s) = fspath.to_str() {
-                            // coarse_zpath is the file's absolute path stripped from base_dir and converted as zenoh path
-                            let coarse_zpath = fspath_to_zpath(&s[self.base_dir_len..]);
-                            // zpath trims away the CONFLICT_SUFFIX if present
-                            let zpath = get_trimmed_keyexpr(&coarse_zpath);
-                            let zpath_as_ke = match keyexpr::new(zpath) {
-                                Ok(ke) => ke,
-                                Err(e) => {
-                                    tracing::error!(
-                                        "Couldn't convert `{}` into a key expression: {}",
-                                        &zpath,
-                                        e
-                                    );
-                                    continue;
-                                }
-                            };
-                            // convert it to zenoh path for matching test with zpath_expr
-                            if self.zpath_expr.intersects(zpath_as_ke) {
-                                // matching file; return a ZFile
-                                let zfile = ZFile {
-                                    zpath: zpath.to_string().into(),
-                                    fspath: fspath.clone(),
-                                };
-                                return Some(zfile);
-                            }
-                        } else {
-                            debug!(
-                                "Looking for files matching {}: ignore {:?} as non UTF-8 filename",
-                                self.zpath_expr, fspath
-                            );
-                        };
+            let (dir, children, next, depth) = self.stack.last_mut()?;
+            let Some(child) = children.get(*next) else {
+                self.stack.pop();
+                continue;
+            };
+            *next += 1;
+            let path = dir.join(&child.name);
+            let is_dir = match child.is_symlink && self.follow_links {
+                true => match path.metadata() {
+                    Ok(metadata) => metadata.is_dir(),
+                    Err(e) => {
+                        debug!("Possible issue walking {:?}: {}", path, e);
+                        continue;
                     }
-                    continue;
+                },
+                false => child.is_dir,
+            };
+            let depth = *depth;
+            if is_dir {
+                self.pending = Some((path.clone(), depth));
+            }
+            return Some(WalkEntry {
+                path,
+                depth,
+                is_dir,
+                is_symlink: child.is_symlink,
+            });
+        }
+    }
+}
+
+impl StripeFiles<'_> {
+    // Check that the number of symbolic links followed to reach an entry doesn't exceed max_link_depth
+    fn within_link_depth(&mut self, e: &WalkEntry) -> bool {
+        let Some(max) = self.max_link_depth else {
+            return true;
+        };
+        self.link_depths.truncate(e.depth.max(1));
+        let depth = self.link_depths.last().copied().unwrap_or(0)
+            + usize::from(e.depth > 0 && e.is_symlink);
+        if depth > max {
+            warn!(
+                "Looking for files matching {}: skip {:?} as more than {} symbolic links are followed to reach it",
+                self.zpath_expr, e.path, max
+            );
+            return false;
+        }
+        if e.depth > 0 && e.is_dir {
+            self.link_depths.push(depth);
+        }
+        true
+    }
+
+    // Return false if none of the keys under a directory can match the key expression, for its walk to be skipped
+    fn may_contain_matches(&self, dir: &Path) -> bool {
+        if self.zpath_expr.as_str() == "**" {
+            return true;
+        }
+        let Some(s) = dir.to_str() else {
+            return true;
+        };
+        let relpath = fspath_to_zpath(&s[self.root.as_os_str().len()..]);
+        let zpath = self.names.to_zpath(&relpath);
+        let zpath = zpath.strip_prefix('/').unwrap_or(&zpath);
+        // (the directory names which aren't valid key chunks being walked)
+        match OwnedKeyExpr::new(format!("{zpath}/**")) {
+            Ok(prefix) => self.zpath_expr.intersects(&prefix),
+            Err(_) => true,
+        }
+    }
+
+    // The next entry of the walk, skipping (with a log) the ones which can't be read
+    fn next_entry(&mut self) -> Option<WalkEntry> {
+        loop {
+            let walk_iter = match &mut self.walk {
+                Walk::Dir(walk_iter) => walk_iter,
+                Walk::Listed(walk) => return walk.next(),
+            };
+            match walk_iter.next()? {
+                Ok(e) => {
+                    let is_dir = e.file_type().is_dir();
+                    return Some(WalkEntry {
+                        depth: e.depth(),
+                        is_dir,
+                        is_symlink: e.path_is_symlink() || (is_dir && is_unreported_link(e.path())),
+                        path: e.into_path(),
+                    });
+                }
+                Err(err) if err.loop_ancestor().is_some() => {
+                    // with follow_links, a symbolic link to one of its ancestors: walkdir doesn't enter it
+                    warn!(
+                        "Looking for files matching {}: skip {:?} as a symbolic link loop",
+                        self.zpath_expr,
+                        err.path().unwrap_or(Path::new(""))
+                    );
                 }
-                None => return None,
-                Some(Err(err)) => {
+                Err(err) => {
                     // Cannot read file or dir... that might be normal (or not...) ignore it
                     debug!(
                         "Possible issue looking for files matching {} : {}",
                         self.zpath_expr, err
                     );
-                    continue;
                 }
-            };
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for StripeFiles<'a> {
+    type Item = ZFile<'a>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let e = self.next_entry()?;
+            if !self.within_link_depth(&e) {
+                if e.is_dir {
+                    self.walk.skip_current_dir();
+                }
+            } else if e.is_dir && e.is_symlink && e.depth > 0 && !self.follow_links {
+                // a link which walkdir didn't report as a symbolic link (on Windows)
+                debug!("Don't search for files in {:?} as it's a link", e.path);
+                self.walk.skip_current_dir();
+            } else if e.is_dir && e.is_symlink && e.depth > 0 && is_loop(&e.path) {
+                // (not detected by walkdir with the cached listings, nor with the links it didn't report)
+                warn!(
+                    "Looking for files matching {}: skip {:?} as a symbolic link loop",
+                    self.zpath_expr, e.path
+                );
+                self.walk.skip_current_dir();
+            } else if e.is_dir {
+                // skip content of DataInfoMgr::DB_FILENAME, ArchiveMgr::DIR_NAME and QUARANTINE_DIR
+                // directories
+                let name = e
+                    .path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .unwrap_or_default();
+                if name == DataInfoMgr::DB_FILENAME
+                    || name == ArchiveMgr::DIR_NAME
+                    || name == QUARANTINE_DIR
+                    || (e.depth > 0 && !self.may_contain_matches(&e.path))
+                {
+                    self.walk.skip_current_dir();
+                }
+            } else if self.skip_silly_renames
+                && e.path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(NFS_SILLY_RENAME_PREFIX))
+            {
+                trace!("Skip NFS silly-renamed file {:?}", e.path);
+            } else {
+                let fspath = e.path;
+                if let Some(s) = fspath.to_str() {
+                    // coarse_zpath is the file's absolute path stripped from root and converted as zenoh path
+                    let relpath = fspath_to_zpath(&s[self.root.as_os_str().len()..]);
+                    if get_trimmed_keyexpr(&relpath) == ROOT_KEY {
+                        // the root key's file (retrieved via root_zfile())
+                        continue;
+                    }
+                    let coarse_zpath = self.names.to_zpath(&relpath);
+                    // zpath trims away the CONFLICT_SUFFIX if present
+                    let zpath = get_trimmed_keyexpr(&coarse_zpath);
+                    let zpath = if self.canonicalize_keys {
+                        canonicalize(zpath)
+                    } else {
+                        Cow::from(zpath)
+                    };
+                    let zpath_as_ke = match keyexpr::new(zpath.as_ref()) {
+                        Ok(ke) => ke,
+                        Err(e) => {
+                            tracing::error!(
+                                "Couldn't convert `{}` into a key expression: {}",
+                                &zpath,
+                                e
+                            );
+                            continue;
+                        }
+                    };
+                    // convert it to zenoh path for matching test with zpath_expr
+                    // ("**" doesn't intersect the keys with verbatim chunks, starting with '@', but it's used to search all the files)
+                    if self.zpath_expr.as_str() == "**" || self.zpath_expr.intersects(zpath_as_ke) {
+                        // matching file; return a ZFile
+                        let zfile = ZFile {
+                            zpath: zpath.to_string().into(),
+                            fspath: fspath.clone(),
+                        };
+                        return Some(zfile);
+                    }
+                } else {
+                    debug!(
+                        "Looking for files matching {}: ignore {:?} as non UTF-8 filename",
+                        self.zpath_expr, fspath
+                    );
+                };
+            }
         }
     }
 }
@@ -479,6 +3574,11 @@ pub(crate) fn zpath_to_fspath(zpath: &str) -> Cow<'_, str> {
     Cow::from(zpath.replace('/', WIN_SEP))
 }
 
+// The top-level prefix of a zpath: its first chunk
+pub(crate) fn top_level_prefix(zpath: &str) -> Option<&str> {
+    zpath.split('/').next().filter(|chunk| !chunk.is_empty())
+}
+
 #[cfg(unix)]
 #[inline(always)]
 pub(crate) fn fspath_to_zpath(fspath: &str) -> Cow<'_, str> {
@@ -491,11 +3591,17 @@ pub(crate) fn fspath_to_zpath(fspath: &str) -> Cow<'_, str> {
     Cow::from(fspath.replace(std::path::MAIN_SEPARATOR, ZENOH_SEP))
 }
 
-fn is_symlink<P: AsRef<Path>>(path: P) -> bool {
-    match path.as_ref().symlink_metadata() {
-        Ok(metadata) => metadata.file_type().is_symlink(),
-        Err(_) => false,
-    }
+#[cfg(feature = "mime-guess")]
+fn mime_type_of(file: &Path) -> Option<Encoding> {
+    mime_guess::from_path(file)
+        .first()
+        .map(|mime_type| Encoding::from(mime_type.essence_str().to_string()))
+}
+
+// Built without the mime types table: the files get the default encoding
+#[cfg(not(feature = "mime-guess"))]
+fn mime_type_of(_file: &Path) -> Option<Encoding> {
+    None
 }
 
 pub(crate) fn get_trimmed_keyexpr(keyexpr: &str) -> &str {
@@ -507,6 +3613,17 @@ pub(crate) fn get_trimmed_keyexpr(keyexpr: &str) -> &str {
     k.strip_prefix('/').unwrap_or(k)
 }
 
+// The canonical form of a key expression (or the key expression itself, if it's invalid)
+fn canonicalize(zpath: &str) -> Cow<'_, str> {
+    if keyexpr::new(zpath).is_ok() {
+        return Cow::from(zpath);
+    }
+    match OwnedKeyExpr::autocanonize(zpath.to_string()) {
+        Ok(ke) => Cow::from(ke.to_string()),
+        Err(_) => Cow::from(zpath),
+    }
+}
+
 pub(crate) fn get_conflict_resolved_keyexpr(keyexpr: &str) -> String {
     format!("{keyexpr}{CONFLICT_SUFFIX}")
 }
@@ -0,0 +1,412 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{collections::BTreeMap, num::NonZeroUsize};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zenoh::{internal::bail, Result as ZResult};
+
+use crate::{
+    config::{PropertyKind, STORAGE_PROPERTIES},
+    *,
+};
+
+/// The configuration of a File System Backend's volume, as set in the `volumes` section of the storage manager's
+/// configuration. See the README for the description of each property.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FsVolumeConfig {
+    /// The root directory of the storages (relative to `${ZENOH_BACKEND_FS_ROOT}` if not absolute).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root: Option<String>,
+    /// The maximum size (in bytes) of all the files stored in the volume.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<NonZeroUsize>,
+    /// The properties of the storages which don't set them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage_defaults: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+impl FsVolumeConfig {
+    /// Parse the configuration of a volume, checking the type of each property (the unknown ones being ignored).
+    pub fn from_json(from: &serde_json::Map<String, serde_json::Value>) -> ZResult<Self> {
+        match serde_json::from_value(from.clone().into()) {
+            Ok(config) => Ok(config),
+            Err(e) => {
+                let Some((name, _)) = invalid_property::<Self>(from) else {
+                    bail!("Invalid File System Backend configuration: {}", e)
+                };
+                let expected = match name {
+                    PROP_BACKEND_ROOT => "a non-empty string",
+                    PROP_BACKEND_MAX_TOTAL_SIZE => "a positive integer",
+                    _ => "an object of storage properties",
+                };
+                bail!(
+                    r#"Invalid value for File System Backend property "{}": it must be {}"#,
+                    name,
+                    expected
+                )
+            }
+        }
+    }
+
+    /// The configuration as a JSON object, with only the properties which are set.
+    pub fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        to_json_object(self)
+    }
+}
+
+/// The volume-specific configuration of a File System Storage, as set in the `volume` section of a storage in the
+/// storage manager's configuration (or built with a [`FileSystemStorageBuilder`]).
+/// See the README for the description of each property.
+///
+/// The properties with a structured value (e.g. `acl`, `tenants` or `maintenance`) have their own types, whose unknown
+/// fields are rejected. For instance:
+/// ```ignore
+/// let config = FsStorageConfig {
+///     follow_links: Some(true),
+///     open_files_cache_size: NonZeroUsize::new(64),
+///     ..Default::default()
+/// };
+/// let storage = FileSystemStorageBuilder::new("demo", "demo/example/**".try_into()?, "example")
+///     .config(&config)
+///     .build(&volume)
+///     .await?;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FsStorageConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only_behavior: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worm: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub set_immutable: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_closure: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_conflict: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub empty_payload: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skewed_timestamps: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_future_skew_secs: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_past_skew_secs: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscate_names: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub obfuscation_salt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonicalize_keys: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zenoh_write_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entries_index: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest_interval_secs: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub control_prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub export_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_roots: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_unpack_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_patched_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stripes: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mirror_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failover_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lower_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_links: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_link_depth: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_mime_types: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding_schemas: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding_overrides: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_transforms: Option<Vec<FsTransformConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub envelope: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub passthrough: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_on_start: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lazy_init: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_ops: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_queue_depth: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_queue_overload: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dedicated_runtime_threads: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_files_cache_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_ahead: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nfs_safe: Option<bool>,
+    /// (0 disabling the retries)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_attempts: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff_ms: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_errors: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_writes: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dry_run: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_ops: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_info_cache_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir_listings_cache_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bloom_filter: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark_by_prefix: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_pool_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shm_threshold: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fault_injection: Option<FsFaultInjectionConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expose_fs_path: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_after_days: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cold_tier_dir: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cold_after_days: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenants: Option<BTreeMap<String, FsTenantConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policies: Option<BTreeMap<String, FsPolicyConfig>>,
+    /// (the operations allowed on the keys matching each key expression)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acl: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_put: Option<FsCommandConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_delete: Option<FsCommandConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interceptors: Option<Vec<FsInterceptorConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_key_expr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_connect: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats_key_expr: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats_interval_secs: Option<NonZeroUsize>,
+    /// (the cron-like schedule of each maintenance job)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance: Option<BTreeMap<String, String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+impl FsStorageConfig {
+    /// Parse the volume-specific configuration of a storage, checking the type of each property (the unknown ones
+    /// being ignored). The values are further validated on the storage's creation.
+    pub fn from_json(from: &serde_json::Map<String, serde_json::Value>) -> ZResult<Self> {
+        match serde_json::from_value(from.clone().into()) {
+            Ok(config) => Ok(config),
+            Err(e) => {
+                let Some((name, e)) = invalid_property::<Self>(from) else {
+                    bail!("Invalid File System Storage configuration: {}", e)
+                };
+                let kind = STORAGE_PROPERTIES
+                    .iter()
+                    .find(|p| p.name == name)
+                    .map(|p| &p.kind);
+                let expected = match kind {
+                    _ if name == PROP_STORAGE_RETRY_ATTEMPTS => "an unsigned integer",
+                    Some(PropertyKind::Bool) => "a boolean",
+                    Some(PropertyKind::PositiveInt) => "a positive integer",
+                    Some(PropertyKind::String | PropertyKind::Enum(_)) => "a string",
+                    Some(PropertyKind::StringArray) => "an array of strings",
+                    Some(PropertyKind::StringMap) => "an object with string values",
+                    Some(PropertyKind::Command) => "a non-empty string or array of strings",
+                    Some(PropertyKind::Transforms) => {
+                        "an array of payload transform types, or of objects with their type and options"
+                    }
+                    // (the structured values being explained by serde's error, e.g. an unknown field)
+                    Some(_) => bail!(
+                        r#"Invalid value for File System Storage configuration: `{}`: {}"#,
+                        name,
+                        e
+                    ),
+                    None => bail!("Invalid File System Storage configuration: {}", e),
+                };
+                bail!(
+                    r#"Invalid value for File System Storage configuration: `{}` must be {}"#,
+                    name,
+                    expected
+                )
+            }
+        }
+    }
+
+    /// The configuration as a JSON object, with only the properties which are set.
+    pub fn to_json(&self) -> serde_json::Map<String, serde_json::Value> {
+        to_json_object(self)
+    }
+}
+
+/// The configuration of a tenant, in the `tenants` property of a [`FsStorageConfig`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FsTenantConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<NonZeroUsize>,
+}
+
+/// The policy of the keys under a prefix, in the `policies` property of a [`FsStorageConfig`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FsPolicyConfig {
+    /// (replacing the storage's ones, an empty array meaning no transform)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_transforms: Option<Vec<FsTransformConfig>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_size: Option<NonZeroUsize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<NonZeroUsize>,
+}
+
+/// A built-in payload transform, in the `payload_transforms` property of a [`FsStorageConfig`]: its type (with its
+/// default options), or an object with its type and options.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FsTransformConfig {
+    Type(String),
+    Options(FsTransformOptions),
+}
+
+/// The type and options of a built-in payload transform.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum FsTransformOptions {
+    #[serde(rename = "gzip")]
+    Gzip {
+        /// (from 0 to 9)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level: Option<u32>,
+    },
+    #[serde(rename = "zstd-seekable")]
+    ZstdSeekable {
+        /// (from 1 to 22)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        level: Option<i32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        frame_size: Option<NonZeroUsize>,
+    },
+    #[serde(rename = "aes-256-gcm")]
+    AesGcm { key_file: String },
+}
+
+/// A built-in interceptor, in the `interceptors` property of a [`FsStorageConfig`]: its type and options.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", deny_unknown_fields)]
+pub enum FsInterceptorConfig {
+    #[serde(rename = "audit")]
+    Audit {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        file: Option<String>,
+    },
+    #[serde(rename = "validate")]
+    Validate {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key_regex: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_payload_size: Option<usize>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        encodings: Option<Vec<String>>,
+    },
+    #[serde(rename = "rewrite")]
+    Rewrite { from: String, to: String },
+}
+
+/// The faults injected into the file operations of a storage, in the `fault_injection` property of a
+/// [`FsStorageConfig`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FsFaultInjectionConfig {
+    /// (from 0 to 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub io_error_rate: Option<f64>,
+    /// (from 0 to 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_write_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
+}
+
+/// A command run by a hook, in the `on_put` and `on_delete` properties of a [`FsStorageConfig`]: its program, or its
+/// program and first arguments.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FsCommandConfig {
+    Program(String),
+    Args(Vec<String>),
+}
+
+// The first property whose value is invalid, with its error (serde's errors not naming the field)
+fn invalid_property<T: DeserializeOwned>(
+    from: &serde_json::Map<String, serde_json::Value>,
+) -> Option<(&str, serde_json::Error)> {
+    from.iter().find_map(|(name, value)| {
+        let mut single = serde_json::Map::new();
+        single.insert(name.to_string(), value.clone());
+        serde_json::from_value::<T>(single.into())
+            .err()
+            .map(|e| (name.as_str(), e))
+    })
+}
+
+fn to_json_object(config: &impl Serialize) -> serde_json::Map<String, serde_json::Value> {
+    match serde_json::to_value(config) {
+        Ok(serde_json::Value::Object(obj)) => obj,
+        _ => serde_json::Map::new(),
+    }
+}
@@ -0,0 +1,135 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+#[cfg(target_os = "linux")]
+use std::{
+    ffi::CString,
+    fs::File,
+    os::unix::{ffi::OsStrExt, io::AsRawFd},
+    path::PathBuf,
+};
+use std::{io, path::Path};
+
+// The type and the capacity (in bytes) of the file-system of a directory
+pub(crate) struct FsInfo {
+    // as in /proc/mounts (e.g. "ext4", "nfs4" or "fuse.s3fs"), if found
+    fs_type: Option<String>,
+    total: u64,
+    free: u64,
+    // the free space available to unprivileged users (excluding the reserved blocks)
+    available: u64,
+}
+
+impl FsInfo {
+    #[cfg(target_os = "linux")]
+    pub(crate) fn of(dir: &Path) -> io::Result<Self> {
+        let path = CString::new(dir.as_os_str().as_bytes())?;
+        // SAFETY: `path` is a valid C string, and `stat` a valid pointer for statvfs to fill
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // (the types of the fields depend on the platform)
+        #[allow(clippy::unnecessary_cast)]
+        let (block_size, blocks, free, available) = (
+            stat.f_frsize as u64,
+            stat.f_blocks as u64,
+            stat.f_bfree as u64,
+            stat.f_bavail as u64,
+        );
+        Ok(FsInfo {
+            fs_type: mount_type(dir),
+            total: blocks * block_size,
+            free: free * block_size,
+            available: available * block_size,
+        })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn of(_dir: &Path) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the file-system information is only supported on Linux",
+        ))
+    }
+
+    // Add the information to an admin status
+    pub(crate) fn insert_into(&self, status: &mut serde_json::Map<String, serde_json::Value>) {
+        status.insert("fs_type".into(), self.fs_type.clone().into());
+        status.insert("total_space".into(), self.total.into());
+        status.insert("free_space".into(), self.free.into());
+        status.insert("available_space".into(), self.available.into());
+    }
+}
+
+// Flush the file-system of a directory to its disk (all of its written files, not only the storage's ones)
+#[cfg(target_os = "linux")]
+pub(crate) fn sync_fs(dir: &Path) -> io::Result<()> {
+    let dir = File::open(dir)?;
+    // SAFETY: the fd is valid while `dir` lives
+    if unsafe { libc::syncfs(dir.as_raw_fd()) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// (only the directory's entries being synced)
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn sync_fs(dir: &Path) -> io::Result<()> {
+    match std::fs::File::open(dir) {
+        Ok(dir) => dir.sync_all(),
+        // e.g. on Windows, where a directory can't be opened as a file
+        Err(_) => Ok(()),
+    }
+}
+
+// The type of the file-system mounted on the longest mount point containing a (canonical) directory
+#[cfg(target_os = "linux")]
+fn mount_type(dir: &Path) -> Option<String> {
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ').skip(1);
+            let mount_point = PathBuf::from(unescape(fields.next()?));
+            let fs_type = fields.next()?;
+            dir.starts_with(&mount_point)
+                .then(|| (mount_point.as_os_str().len(), fs_type.to_string()))
+        })
+        // (the last of the longest ones being the one mounted over the others)
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fs_type)| fs_type)
+}
+
+// A field of /proc/mounts, whose spaces, tabs, newlines and backslashes are escaped in octal (e.g. "\040")
+#[cfg(target_os = "linux")]
+fn unescape(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(i) = rest.find('\\') {
+        result.push_str(&rest[..i]);
+        let escaped = rest.get(i + 1..i + 4);
+        match escaped.and_then(|code| u8::from_str_radix(code, 8).ok()) {
+            Some(c) => {
+                result.push(c as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
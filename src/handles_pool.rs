@@ -0,0 +1,85 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tracing::trace;
+
+// A bounded pool keeping the handles of the recently read files open,
+// so repeated reads of the same files don't pay the open/close syscalls each time.
+// A handle is exclusively used by the reader which took it, until it's given back.
+pub(crate) struct HandlesPool {
+    capacity: usize,
+    // the most recently used handles are at the back
+    handles: Mutex<VecDeque<(PathBuf, File)>>,
+}
+
+impl HandlesPool {
+    // A pool with a 0 capacity never keeps any handle open
+    pub(crate) fn new(capacity: usize) -> Self {
+        HandlesPool {
+            capacity,
+            handles: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    // Take the handle of `file` out of the pool (rewound to the start of the file), or open it.
+    pub(crate) fn take(&self, file: &Path) -> std::io::Result<File> {
+        let cached = {
+            let mut handles = self.handles.lock().unwrap();
+            handles
+                .iter()
+                .position(|(p, _)| p == file)
+                .and_then(|i| handles.remove(i))
+        };
+        match cached {
+            Some((_, mut f)) => {
+                trace!("Reuse open handle for file {:?}", file);
+                f.seek(SeekFrom::Start(0))?;
+                Ok(f)
+            }
+            None => File::open(file),
+        }
+    }
+
+    // Give back the handle of `file` to the pool, closing the least recently used one if the pool is full.
+    pub(crate) fn give_back(&self, file: &Path, f: File) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut handles = self.handles.lock().unwrap();
+        handles.retain(|(p, _)| p != file);
+        handles.push_back((file.to_path_buf(), f));
+        while handles.len() > self.capacity {
+            handles.pop_front();
+        }
+    }
+
+    // Close the handle of `file` if present (to be called when the file is written, renamed or deleted).
+    pub(crate) fn invalidate(&self, file: &Path) {
+        if self.capacity > 0 {
+            self.handles.lock().unwrap().retain(|(p, _)| p != file);
+        }
+    }
+
+    // Close all the handles.
+    pub(crate) fn clear(&self) {
+        self.handles.lock().unwrap().clear();
+    }
+}
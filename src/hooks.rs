@@ -0,0 +1,96 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    path::Path,
+    process::Command,
+    sync::mpsc::{sync_channel, SyncSender, TrySendError},
+};
+
+use tracing::{debug, warn};
+
+// The max number of hook executions waiting for the previous ones to complete
+const QUEUE_SIZE: usize = 1024;
+
+// A host command run after an operation on the storage (the program, and its first arguments)
+pub(crate) type HookCommand = Vec<String>;
+
+// Runs the hooks configured for the operations of a storage, with the key and the file path as last arguments.
+// The commands are run one after the other by a dedicated thread, not to delay the operations.
+pub(crate) struct Hooks {
+    on_put: Option<HookCommand>,
+    on_delete: Option<HookCommand>,
+    queue: SyncSender<Vec<String>>,
+}
+
+impl Hooks {
+    pub(crate) fn new(
+        storage: &str,
+        on_put: Option<HookCommand>,
+        on_delete: Option<HookCommand>,
+    ) -> Option<Self> {
+        if on_put.is_none() && on_delete.is_none() {
+            return None;
+        }
+        let (queue, commands) = sync_channel::<Vec<String>>(QUEUE_SIZE);
+        let storage = storage.to_string();
+        // the thread ends when the Hooks (and so the queue) are dropped
+        std::thread::spawn(move || {
+            for command in commands {
+                debug!("Storage {}: run hook {:?}", storage, command);
+                match Command::new(&command[0]).args(&command[1..]).status() {
+                    Ok(status) if status.success() => (),
+                    Ok(status) => {
+                        warn!("Storage {}: hook {:?} failed: {}", storage, command, status)
+                    }
+                    Err(e) => warn!(
+                        "Storage {}: failed to run hook {:?}: {}",
+                        storage, command, e
+                    ),
+                }
+            }
+        });
+        Some(Hooks {
+            on_put,
+            on_delete,
+            queue,
+        })
+    }
+
+    pub(crate) fn on_put(&self, key: &str, file: &Path) {
+        if let Some(command) = &self.on_put {
+            self.run(command, key, file);
+        }
+    }
+
+    pub(crate) fn on_delete(&self, key: &str, file: &Path) {
+        if let Some(command) = &self.on_delete {
+            self.run(command, key, file);
+        }
+    }
+
+    fn run(&self, command: &HookCommand, key: &str, file: &Path) {
+        let mut command = command.clone();
+        command.push(key.to_string());
+        command.push(file.to_string_lossy().into_owned());
+        match self.queue.try_send(command) {
+            Ok(()) => (),
+            Err(TrySendError::Full(command)) => {
+                warn!("Too many hooks waiting: {:?} skipped", command)
+            }
+            Err(TrySendError::Disconnected(command)) => {
+                warn!("Hooks thread stopped: {:?} skipped", command)
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::{fs::File, os::unix::io::AsRawFd};
+
+// The "immutable" inode flag (see `chattr +i`), from linux/fs.h
+#[cfg(target_os = "linux")]
+const FS_IMMUTABLE_FL: libc::c_int = 0x0000_0010;
+
+// Set or clear the immutable attribute of a file.
+// Setting it requires the CAP_LINUX_IMMUTABLE capability, and a file-system supporting it.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_immutable(path: &Path, immutable: bool) -> std::io::Result<()> {
+    let f = File::open(path)?;
+    let mut flags: libc::c_int = 0;
+    // SAFETY: the fd is valid while `f` lives, and `flags` is a valid pointer for those ioctls
+    if unsafe { libc::ioctl(f.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) } < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let new_flags = if immutable {
+        flags | FS_IMMUTABLE_FL
+    } else {
+        flags & !FS_IMMUTABLE_FL
+    };
+    if new_flags != flags
+        && unsafe { libc::ioctl(f.as_raw_fd(), libc::FS_IOC_SETFLAGS, &new_flags) } < 0
+    {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_immutable(_path: &Path, _immutable: bool) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "the immutable attribute is only supported on Linux",
+    ))
+}
@@ -0,0 +1,237 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::SystemTime,
+};
+
+use tokio::sync::mpsc::{channel, Sender};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+use zenoh::{internal::zerror, key_expr::OwnedKeyExpr, time::Timestamp, Result as ZResult};
+
+use crate::{
+    acl::{Acl, AclOp},
+    files_mgt::FilesMgr,
+    spawn_storage_task,
+    tarball::for_each_tar_entry,
+};
+
+// The number of files read ahead of their storage
+const READ_AHEAD: usize = 16;
+
+// The import of an external directory (or tarball) of the host, as entries under a key prefix.
+// The files are read in a dedicated thread and stored in background, all with the timestamp of the request.
+pub(crate) struct Import {
+    source: PathBuf,
+    prefix: String,
+    // the ACL of the storage, checked for each imported key
+    acl: Option<Arc<Acl>>,
+    // write-once storage: the existing entries are skipped
+    worm: bool,
+    // the empty files are skipped (if `empty_payload` isn't "store")
+    skip_empty: bool,
+    started: SystemTime,
+    imported: AtomicUsize,
+    skipped: AtomicUsize,
+    // set at the end of the import: Ok, or the error which interrupted it
+    result: Mutex<Option<Result<(), String>>>,
+}
+
+impl Import {
+    pub(crate) fn new(
+        source: PathBuf,
+        prefix: String,
+        acl: Option<Arc<Acl>>,
+        worm: bool,
+        skip_empty: bool,
+    ) -> Self {
+        Import {
+            source,
+            prefix,
+            acl,
+            worm,
+            skip_empty,
+            started: SystemTime::now(),
+            imported: AtomicUsize::new(0),
+            skipped: AtomicUsize::new(0),
+            result: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.result.lock().unwrap().is_none()
+    }
+
+    // Import the files in background
+    pub(crate) fn spawn(self: &Arc<Self>, to: Weak<FilesMgr>, timestamp: Timestamp) {
+        let import = self.clone();
+        spawn_storage_task(to, |to| async move {
+            let result = import.import_all(&to, &timestamp).await;
+            match &result {
+                Ok(()) => info!(
+                    "Import of {:?} complete: {} entries imported, {} skipped",
+                    import.source,
+                    import.imported.load(Ordering::Relaxed),
+                    import.skipped.load(Ordering::Relaxed)
+                ),
+                Err(e) => warn!("Import of {:?} failed: {}", import.source, e),
+            }
+            *import.result.lock().unwrap() = Some(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    async fn import_all(&self, to: &Weak<FilesMgr>, timestamp: &Timestamp) -> ZResult<()> {
+        let (tx, mut rx) = channel(READ_AHEAD);
+        let source = self.source.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = read_source(&source, &tx) {
+                // the import stops if the receiver is dropped meanwhile
+                let _ = tx.blocking_send(Err(e));
+            }
+        });
+        // (waiting for the next file read from the source, unless the storage is closed meanwhile)
+        while let Some(entry) = FilesMgr::idle(to, rx.recv())
+            .await
+            .ok_or_else(|| zerror!("the storage was closed"))?
+        {
+            let (path, content) = entry?;
+            // stop if the storage was closed meanwhile
+            let to = FilesMgr::upgrade(to).ok_or_else(|| zerror!("the storage was closed"))?;
+            if self.import_file(&to, &path, content, timestamp).await? {
+                self.imported.fetch_add(1, Ordering::Relaxed);
+            } else {
+                self.skipped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    // Store a file under the import's prefix, returning false if skipped
+    async fn import_file(
+        &self,
+        to: &FilesMgr,
+        path: &str,
+        content: Vec<u8>,
+        timestamp: &Timestamp,
+    ) -> ZResult<bool> {
+        let key = if self.prefix.is_empty() {
+            path.to_string()
+        } else {
+            format!("{}/{}", self.prefix, path)
+        };
+        let ke = match OwnedKeyExpr::new(key.as_str()) {
+            Ok(ke) if !ke.is_wild() => ke,
+            _ => {
+                warn!("Import: skip {} which isn't a valid key", key);
+                return Ok(false);
+            }
+        };
+        if let Some(Err(e)) = self
+            .acl
+            .as_ref()
+            .map(|acl| acl.check(AclOp::Write, Some(&ke)))
+        {
+            warn!("Import: skip {}: {}", key, e);
+            return Ok(false);
+        }
+        if content.is_empty() && self.skip_empty {
+            debug!("Import: skip empty {}", key);
+            return Ok(false);
+        }
+        let zfile = to.to_zfile(&key);
+        if !to.is_tenant_key(&zfile.zpath) {
+            warn!(
+                "Import: skip {} which doesn't belong to any tenant of the storage",
+                key
+            );
+            return Ok(false);
+        }
+        if self.worm && to.exists(&zfile).await? {
+            debug!("Import: skip existing {} in write-once storage", key);
+            return Ok(false);
+        }
+        let encoding = to.guess_encoding_of(&zfile);
+        to.write_file(&zfile, content.into(), encoding, timestamp)
+            .await?;
+        Ok(true)
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let mut status = serde_json::Map::new();
+        status.insert("source".into(), self.source.to_string_lossy().into());
+        status.insert("prefix".into(), self.prefix.clone().into());
+        status.insert(
+            "started".into(),
+            humantime::format_rfc3339_seconds(self.started)
+                .to_string()
+                .into(),
+        );
+        status.insert(
+            "imported".into(),
+            self.imported.load(Ordering::Relaxed).into(),
+        );
+        status.insert(
+            "skipped".into(),
+            self.skipped.load(Ordering::Relaxed).into(),
+        );
+        match &*self.result.lock().unwrap() {
+            None => status.insert("state".into(), "in_progress".into()),
+            Some(Ok(())) => status.insert("state".into(), "complete".into()),
+            Some(Err(e)) => {
+                status.insert("error".into(), e.clone().into());
+                status.insert("state".into(), "failed".into())
+            }
+        };
+        status.into()
+    }
+}
+
+// Send the relative path (with '/' as separator) and the content of each regular file of a directory or a tarball
+fn read_source(source: &Path, tx: &Sender<ZResult<(String, Vec<u8>)>>) -> ZResult<()> {
+    let send = |path: String, content: Vec<u8>| {
+        tx.blocking_send(Ok((path, content)))
+            .map_err(|_| zerror!("the import was interrupted").into())
+    };
+    if source.is_file() {
+        let file = File::open(source).map_err(|e| zerror!("Failed to open {:?}: {}", source, e))?;
+        // (the tarball being a file of the host, under one of the `import_roots`)
+        return for_each_tar_entry(BufReader::new(file), u64::MAX, send);
+    }
+    for entry in WalkDir::new(source) {
+        let entry = entry.map_err(|e| zerror!("Failed to read {:?}: {}", source, e))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(source).unwrap_or(entry.path());
+        let path = relative
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(chunk) => Some(chunk.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let content = std::fs::read(entry.path())
+            .map_err(|e| zerror!("Failed to read {:?}: {}", entry.path(), e))?;
+        send(path, content)?;
+    }
+    Ok(())
+}
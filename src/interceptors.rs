@@ -0,0 +1,336 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use regex::Regex;
+use tracing::{info, warn};
+use zenoh::{
+    bytes::{Encoding, ZBytes},
+    internal::{bail, zerror},
+    key_expr::{keyexpr, OwnedKeyExpr},
+    time::Timestamp,
+    Result as ZResult,
+};
+use zenoh_backend_traits::{StorageInsertionResult, StoredData};
+
+use crate::{FsInterceptorConfig, PROP_STORAGE_INTERCEPTORS};
+
+/// An interceptor of the operations of a File System Storage, e.g. for auditing, validation or key rewriting.
+/// The storages can chain several interceptors, configured with the `interceptors` property for the built-in ones,
+/// or added with [`FileSystemStorageBuilder::interceptor`](crate::FileSystemStorageBuilder::interceptor).
+///
+/// The `before_*` methods are called in the order of the chain, before the storage processes the operation: they can
+/// rewrite the operation's key (relative to the storage's `strip_prefix`, `None` being the prefix itself), or reject
+/// the operation by returning an error. The `after_*` methods are called in the reverse order with the operation's
+/// result, which `after_get` can modify. The key passed to the `after_*` methods is the rewritten one.
+///
+/// The methods are called on the storage's task: they must not block (e.g. a slow audit sink should be fed through a
+/// channel). All of them do nothing by default.
+pub trait StorageInterceptor: Send + Sync {
+    fn before_put(
+        &self,
+        _key: &mut Option<OwnedKeyExpr>,
+        _payload: &ZBytes,
+        _encoding: &Encoding,
+        _timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        Ok(())
+    }
+
+    fn after_put(
+        &self,
+        _key: Option<&keyexpr>,
+        _payload: &ZBytes,
+        _result: &ZResult<StorageInsertionResult>,
+    ) {
+    }
+
+    fn before_delete(
+        &self,
+        _key: &mut Option<OwnedKeyExpr>,
+        _timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        Ok(())
+    }
+
+    fn after_delete(&self, _key: Option<&keyexpr>, _result: &ZResult<StorageInsertionResult>) {}
+
+    fn before_get(&self, _key: &mut Option<OwnedKeyExpr>, _parameters: &str) -> ZResult<()> {
+        Ok(())
+    }
+
+    fn after_get(&self, _key: Option<&keyexpr>, _result: &mut ZResult<Vec<StoredData>>) {}
+}
+
+/// An audit log of the operations: a JSON line per operation, appended to a file or logged (at the info level, with
+/// the `zenoh_backend_fs::audit` target).
+pub struct AuditInterceptor {
+    storage: String,
+    file: Option<Mutex<File>>,
+}
+
+impl AuditInterceptor {
+    pub const TYPE: &'static str = "audit";
+
+    /// An audit log of the operations of `storage`, appended to `file` if set.
+    pub fn new(storage: impl Into<String>, file: Option<&Path>) -> ZResult<Self> {
+        let file = match file {
+            Some(path) => Some(Mutex::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| zerror!("Failed to open audit log {:?}: {}", path, e))?,
+            )),
+            None => None,
+        };
+        Ok(AuditInterceptor {
+            storage: storage.into(),
+            file,
+        })
+    }
+
+    fn log<T>(&self, op: &str, key: Option<&keyexpr>, bytes: Option<usize>, result: &ZResult<T>) {
+        let mut record = serde_json::json!({
+            "time": humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            "storage": self.storage,
+            "op": op,
+            "key": key.map(|k| k.as_str()),
+        });
+        if let Some(bytes) = bytes {
+            record["bytes"] = bytes.into();
+        }
+        match result {
+            Ok(_) => record["result"] = "ok".into(),
+            Err(e) => record["error"] = e.to_string().into(),
+        }
+        match &self.file {
+            Some(file) => {
+                if let Err(e) = writeln!(file.lock().unwrap(), "{record}") {
+                    warn!("Storage {}: failed to write audit log: {}", self.storage, e);
+                }
+            }
+            None => info!(target: "zenoh_backend_fs::audit", "{}", record),
+        }
+    }
+}
+
+impl StorageInterceptor for AuditInterceptor {
+    fn after_put(
+        &self,
+        key: Option<&keyexpr>,
+        payload: &ZBytes,
+        result: &ZResult<StorageInsertionResult>,
+    ) {
+        self.log("put", key, Some(payload.len()), result);
+    }
+
+    fn after_delete(&self, key: Option<&keyexpr>, result: &ZResult<StorageInsertionResult>) {
+        self.log("delete", key, None, result);
+    }
+
+    fn after_get(&self, key: Option<&keyexpr>, result: &mut ZResult<Vec<StoredData>>) {
+        let bytes = match result {
+            Ok(data) => Some(data.iter().map(|d| d.payload.len()).sum()),
+            Err(_) => None,
+        };
+        self.log("get", key, bytes, result);
+    }
+}
+
+/// The validation of the PUTs: the keys must match a regular expression, the payloads must not exceed a size and
+/// their encoding must be among the allowed ones (each constraint being optional). The other PUTs are rejected.
+pub struct ValidateInterceptor {
+    key_regex: Option<Regex>,
+    max_payload_size: Option<usize>,
+    encodings: Option<Vec<String>>,
+}
+
+impl ValidateInterceptor {
+    pub const TYPE: &'static str = "validate";
+
+    pub fn new(
+        key_regex: Option<Regex>,
+        max_payload_size: Option<usize>,
+        encodings: Option<Vec<String>>,
+    ) -> Self {
+        ValidateInterceptor {
+            key_regex,
+            max_payload_size,
+            encodings,
+        }
+    }
+}
+
+impl StorageInterceptor for ValidateInterceptor {
+    fn before_put(
+        &self,
+        key: &mut Option<OwnedKeyExpr>,
+        payload: &ZBytes,
+        encoding: &Encoding,
+        _timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        let key_str = key.as_ref().map(|k| k.as_str()).unwrap_or("");
+        if let Some(regex) = &self.key_regex {
+            if !regex.is_match(key_str) {
+                bail!(
+                    "PUT on {:?} rejected: the key doesn't match {}",
+                    key_str,
+                    regex
+                )
+            }
+        }
+        if let Some(max) = self.max_payload_size {
+            if payload.len() > max {
+                bail!(
+                    "PUT on {:?} rejected: the payload size ({} bytes) exceeds {} bytes",
+                    key_str,
+                    payload.len(),
+                    max
+                )
+            }
+        }
+        if let Some(encodings) = &self.encodings {
+            // the encoding without its schema
+            let encoding = encoding.to_string();
+            let encoding = encoding.split(';').next().unwrap_or_default();
+            if !encodings.iter().any(|e| e == encoding) {
+                bail!(
+                    "PUT on {:?} rejected: encoding {} is not allowed",
+                    key_str,
+                    encoding
+                )
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The rewriting of the keys starting with a prefix (i.e. equal to the prefix, or followed by a '/'), replacing the
+/// prefix with another one, for the PUTs, DELETEs and GETs.
+pub struct RewriteInterceptor {
+    from: OwnedKeyExpr,
+    to: OwnedKeyExpr,
+}
+
+impl RewriteInterceptor {
+    pub const TYPE: &'static str = "rewrite";
+
+    pub fn new(from: OwnedKeyExpr, to: OwnedKeyExpr) -> Self {
+        RewriteInterceptor { from, to }
+    }
+
+    fn rewrite(&self, key: &mut Option<OwnedKeyExpr>) -> ZResult<()> {
+        let Some(k) = key else {
+            return Ok(());
+        };
+        let rest = match k.as_str().strip_prefix(self.from.as_str()) {
+            Some("") => "",
+            Some(rest) if rest.starts_with('/') => rest,
+            _ => return Ok(()),
+        };
+        *key = Some(OwnedKeyExpr::try_from(format!("{}{}", self.to, rest))?);
+        Ok(())
+    }
+}
+
+impl StorageInterceptor for RewriteInterceptor {
+    fn before_put(
+        &self,
+        key: &mut Option<OwnedKeyExpr>,
+        _payload: &ZBytes,
+        _encoding: &Encoding,
+        _timestamp: &Timestamp,
+    ) -> ZResult<()> {
+        self.rewrite(key)
+    }
+
+    fn before_delete(&self, key: &mut Option<OwnedKeyExpr>, _timestamp: &Timestamp) -> ZResult<()> {
+        self.rewrite(key)
+    }
+
+    fn before_get(&self, key: &mut Option<OwnedKeyExpr>, _parameters: &str) -> ZResult<()> {
+        self.rewrite(key)
+    }
+}
+
+// Build the interceptors of the `interceptors` property: the built-in interceptors, each one with its type and options
+pub(crate) fn extract_interceptors(
+    storage: &str,
+    configs: &[FsInterceptorConfig],
+) -> ZResult<Vec<Arc<dyn StorageInterceptor>>> {
+    let mut result: Vec<Arc<dyn StorageInterceptor>> = Vec::with_capacity(configs.len());
+    for config in configs {
+        let invalid = |interceptor: &str, option: &str, expected: &str| {
+            zerror!(
+                r#"Invalid value for File System Storage configuration: `{}` of "{}" in `{}` must be {}"#,
+                option,
+                interceptor,
+                PROP_STORAGE_INTERCEPTORS,
+                expected
+            )
+        };
+        let key_expr =
+            |option: &str, value: &str| match OwnedKeyExpr::autocanonize(value.to_string()) {
+                Ok(ke) if !ke.is_wild() => Ok(ke),
+                _ => Err(invalid(
+                    RewriteInterceptor::TYPE,
+                    option,
+                    "a key expression without wildcard",
+                )),
+            };
+        match config {
+            FsInterceptorConfig::Audit { file } => {
+                result.push(Arc::new(AuditInterceptor::new(
+                    storage,
+                    file.as_deref().map(Path::new),
+                )?));
+            }
+            FsInterceptorConfig::Validate {
+                key_regex,
+                max_payload_size,
+                encodings,
+            } => {
+                let key_regex = match key_regex {
+                    Some(r) => Some(Regex::new(r).map_err(|e| {
+                        invalid(
+                            ValidateInterceptor::TYPE,
+                            "key_regex",
+                            &format!("a valid regular expression ({e})"),
+                        )
+                    })?),
+                    None => None,
+                };
+                result.push(Arc::new(ValidateInterceptor::new(
+                    key_regex,
+                    *max_payload_size,
+                    encodings.clone(),
+                )));
+            }
+            FsInterceptorConfig::Rewrite { from, to } => {
+                result.push(Arc::new(RewriteInterceptor::new(
+                    key_expr("from", from)?,
+                    key_expr("to", to)?,
+                )));
+            }
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,110 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+// The locks of the keys being updated, for an update to be atomic with the checks of the entry's current value
+// preceding it. A lock only exists while it's held or awaited.
+// To avoid deadlocks, the keys' locks are always taken before the file operations' permits, and several keys' locks
+// in the order of the keys.
+#[derive(Default)]
+pub(crate) struct KeyLocks {
+    locks: Mutex<HashMap<String, Arc<AsyncMutex<()>>>>,
+}
+
+impl KeyLocks {
+    pub(crate) async fn lock(&self, key: &str) -> KeyGuard<'_> {
+        let lock = self
+            .locks
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+        KeyGuard {
+            locks: self,
+            key: key.to_string(),
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+
+    // Lock several keys (possibly with duplicates)
+    pub(crate) async fn lock_all<'a, I>(&self, keys: I) -> Vec<KeyGuard<'_>>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut keys: Vec<&str> = keys.into_iter().collect();
+        keys.sort_unstable();
+        keys.dedup();
+        let mut guards = Vec::with_capacity(keys.len());
+        for key in keys {
+            guards.push(self.lock(key).await);
+        }
+        guards
+    }
+}
+
+pub(crate) struct KeyGuard<'a> {
+    locks: &'a KeyLocks,
+    key: String,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for KeyGuard<'_> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.locks.lock().unwrap();
+        drop(self.guard.take());
+        // forget the lock if no other task holds or awaits it
+        if locks
+            .get(&self.key)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn key_locks() {
+        let locks = Arc::new(KeyLocks::default());
+        let guard = locks.lock("a").await;
+        // another key isn't blocked
+        drop(locks.lock("b").await);
+        let task = tokio::spawn({
+            let locks = locks.clone();
+            async move {
+                let _guard = locks.lock("a").await;
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!task.is_finished());
+        drop(guard);
+        task.await.unwrap();
+        assert!(locks.locks.lock().unwrap().is_empty());
+        let guards = locks.lock_all(["b", "a", "b"]).await;
+        assert_eq!(guards.len(), 2);
+        drop(guards);
+        assert!(locks.locks.lock().unwrap().is_empty());
+    }
+}
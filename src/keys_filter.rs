@@ -0,0 +1,137 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    hash::Hasher,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        RwLock,
+    },
+};
+
+use siphasher::sip::SipHasher13;
+
+// The number of bits per key and of hashes of the filter, for a false positive rate of about 1%
+const BITS_PER_KEY: usize = 10;
+const HASHES: usize = 7;
+
+// The minimum number of keys a filter is sized for
+const MIN_CAPACITY: usize = 1024;
+
+// A Bloom filter of the keys of the data-info database, for the lookups of the files without data-info (e.g. the
+// absent keys, or the files created without zenoh) to be answered without locking and querying the database.
+// It has no false negative, as each key written in the database is added to it, but the deleted keys remain in it
+// (as false positives): it's rebuilt from the database when it holds more keys than it was sized for.
+pub(crate) struct KeysFilter {
+    bloom: RwLock<Bloom>,
+    // the lookups answered by the filter, and the ones passed to the database
+    negatives: AtomicU64,
+    positives: AtomicU64,
+}
+
+struct Bloom {
+    bits: Vec<u64>,
+    // the number of keys added, and the number it was sized for
+    count: usize,
+    capacity: usize,
+}
+
+impl Bloom {
+    fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY);
+        Bloom {
+            bits: vec![0; (capacity * BITS_PER_KEY).div_ceil(64)],
+            count: 0,
+            capacity,
+        }
+    }
+
+    // The positions of the bits of a key (by double hashing)
+    fn positions(&self, key: &str) -> [usize; HASHES] {
+        let mut hasher = SipHasher13::new();
+        hasher.write(key.as_bytes());
+        let hash = hasher.finish();
+        let (h1, h2) = (hash & 0xffff_ffff, (hash >> 32) | 1);
+        let nbits = (self.bits.len() * 64) as u64;
+        std::array::from_fn(|i| (h1.wrapping_add((i as u64).wrapping_mul(h2)) % nbits) as usize)
+    }
+
+    fn insert(&mut self, key: &str) {
+        for i in self.positions(key) {
+            self.bits[i / 64] |= 1 << (i % 64);
+        }
+        self.count += 1;
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.positions(key)
+            .into_iter()
+            .all(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+}
+
+impl KeysFilter {
+    // A filter of the given keys, sized for twice their number (for the keys added later)
+    pub(crate) fn new(keys: &[String]) -> Self {
+        KeysFilter {
+            bloom: RwLock::new(Self::build(keys)),
+            negatives: AtomicU64::new(0),
+            positives: AtomicU64::new(0),
+        }
+    }
+
+    fn build(keys: &[String]) -> Bloom {
+        let mut bloom = Bloom::new(2 * keys.len());
+        for key in keys {
+            bloom.insert(key);
+        }
+        bloom
+    }
+
+    pub(crate) fn insert(&self, key: &str) {
+        self.bloom.write().unwrap().insert(key);
+    }
+
+    // Return false if the key is surely not in the database
+    pub(crate) fn may_contain(&self, key: &str) -> bool {
+        let contained = self.bloom.read().unwrap().contains(key);
+        match contained {
+            true => self.positives.fetch_add(1, Ordering::Relaxed),
+            false => self.negatives.fetch_add(1, Ordering::Relaxed),
+        };
+        contained
+    }
+
+    // Return true if more keys were added than the filter was sized for (its false positive rate growing)
+    pub(crate) fn is_outgrown(&self) -> bool {
+        let bloom = self.bloom.read().unwrap();
+        bloom.count > bloom.capacity
+    }
+
+    // Replace the filter by one of the given keys (the database's current ones, while it's locked for the keys written
+    // meanwhile not to be missed)
+    pub(crate) fn rebuild(&self, keys: &[String]) {
+        *self.bloom.write().unwrap() = Self::build(keys);
+    }
+
+    pub(crate) fn stats(&self) -> serde_json::Value {
+        let bloom = self.bloom.read().unwrap();
+        serde_json::json!({
+            "capacity": bloom.capacity,
+            "keys": bloom.count,
+            "bytes": bloom.bits.len() * 8,
+            "negatives": self.negatives.load(Ordering::Relaxed),
+            "positives": self.positives.load(Ordering::Relaxed),
+        })
+    }
+}
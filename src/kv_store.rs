@@ -0,0 +1,584 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+
+// The key-value store of the data-info records: a RocksDB database, or (if the backend is built without the
+// "rocksdb" feature, e.g. for the targets where librocksdb-sys doesn't build) a sidecar log file.
+#[cfg(feature = "rocksdb")]
+pub(crate) use rocks::KvStore;
+#[cfg(not(feature = "rocksdb"))]
+pub(crate) use sidecar::KvStore;
+
+// The update of a record's value (None if there is no record) by a merge operand, given to KvStore::open for the
+// merges of KvStore::merge
+pub(crate) type MergeFn = fn(Option<&[u8]>, &[u8]) -> Vec<u8>;
+
+// A batch of puts and deletes, written at once and atomically by KvStore::write (applied in their order)
+#[derive(Default)]
+pub(crate) struct KvBatch {
+    ops: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl KvBatch {
+    pub(crate) fn put(&mut self, key: &[u8], value: &[u8]) {
+        self.ops.push((key.to_vec(), Some(value.to_vec())));
+    }
+
+    pub(crate) fn delete(&mut self, key: &[u8]) {
+        self.ops.push((key.to_vec(), None));
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
+
+#[cfg(feature = "rocksdb")]
+mod rocks {
+    use std::path::Path;
+
+    use rocksdb::{
+        properties, Direction, Error, IteratorMode, MergeOperands, Options, WriteBatch, DB,
+    };
+    use tracing::warn;
+    use zenoh::{internal::zerror, Result as ZResult};
+
+    use super::{KvBatch, MergeFn};
+    use crate::retry::{ErrorClass, RetryPolicy};
+
+    // The name of the merge operator (checked by RocksDB against the one of the database's merges)
+    const MERGE_OPERATOR: &str = "zenoh_backend_fs_merge";
+
+    pub(crate) struct KvStore {
+        db: DB,
+        retry: RetryPolicy,
+    }
+
+    impl KvStore {
+        pub(crate) fn open(path: &Path, merge: MergeFn) -> ZResult<Self> {
+            let mut opts = Options::default();
+            opts.create_if_missing(true);
+            // the operands are applied by RocksDB on reads and compactions, in the order of the merges
+            opts.set_merge_operator(
+                MERGE_OPERATOR,
+                move |_: &[u8], existing: Option<&[u8]>, operands: &MergeOperands| {
+                    let mut value = existing.map(|v| v.to_vec());
+                    for operand in operands.iter() {
+                        value = Some(merge(value.as_deref(), operand));
+                    }
+                    value
+                },
+                // the operands aren't combined together, but only with a value
+                |_: &[u8], _: Option<&[u8]>, _: &MergeOperands| None,
+            );
+            let db = DB::open(&opts, path).map_err(|e| zerror!("{}", e))?;
+            Ok(KvStore {
+                db,
+                retry: RetryPolicy::none(),
+            })
+        }
+
+        pub(crate) fn set_retry(&mut self, retry: RetryPolicy) {
+            self.retry = retry;
+        }
+
+        // Run an operation of the database, retried on the transient errors
+        fn retried<T>(&self, op: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+            self.retry
+                .run(op, |e: &Error| ErrorClass::of_db(&e.to_string()))
+        }
+
+        // Flush and delete the store
+        pub(crate) fn destroy(&mut self) -> ZResult<()> {
+            self.db
+                .flush()
+                .and_then(|()| DB::destroy(&rocksdb::Options::default(), self.db.path()))
+                .map_err(|e| zerror!("{}", e).into())
+        }
+
+        // Flush the memtables and sync the write-ahead log
+        pub(crate) fn flush(&mut self) -> ZResult<()> {
+            self.db
+                .flush()
+                .and_then(|()| self.db.flush_wal(true))
+                .map_err(|e| zerror!("{}", e).into())
+        }
+
+        pub(crate) fn get(&self, key: &[u8]) -> ZResult<Option<Vec<u8>>> {
+            match self.retried(|| self.db.get_pinned(key)) {
+                Ok(value) => Ok(value.map(|v| v.to_vec())),
+                Err(e) => Err(zerror!("{}", e).into()),
+            }
+        }
+
+        pub(crate) fn put(&mut self, key: &[u8], value: &[u8]) -> ZResult<()> {
+            self.retried(|| self.db.put(key, value))
+                .map_err(|e| zerror!("{}", e).into())
+        }
+
+        pub(crate) fn delete(&mut self, key: &[u8]) -> ZResult<()> {
+            self.retried(|| self.db.delete(key))
+                .map_err(|e| zerror!("{}", e).into())
+        }
+
+        // Update the value of a record with an operand, without reading it (applied by the merge operator)
+        pub(crate) fn merge(&mut self, key: &[u8], operand: &[u8]) -> ZResult<()> {
+            self.retried(|| self.db.merge(key, operand))
+                .map_err(|e| zerror!("{}", e).into())
+        }
+
+        // Write a batch of puts and deletes, in a single RocksDB write batch
+        pub(crate) fn write(&mut self, batch: &KvBatch) -> ZResult<()> {
+            if batch.is_empty() {
+                return Ok(());
+            }
+            self.retried(|| {
+                let mut write_batch = WriteBatch::default();
+                for (key, value) in &batch.ops {
+                    match value {
+                        Some(value) => write_batch.put(key, value),
+                        None => write_batch.delete(key),
+                    }
+                }
+                self.db.write(write_batch)
+            })
+            .map_err(|e| zerror!("{}", e).into())
+        }
+
+        // Call `f` with each record whose key starts with `prefix`, in the order of the keys
+        pub(crate) fn scan<F>(&self, prefix: &[u8], mut f: F) -> ZResult<()>
+        where
+            F: FnMut(&[u8], &[u8]) -> ZResult<()>,
+        {
+            for item in self
+                .db
+                .iterator(IteratorMode::From(prefix, Direction::Forward))
+            {
+                let (key, value) = item.map_err(|e| zerror!("{}", e))?;
+                if !key.starts_with(prefix) {
+                    break;
+                }
+                f(&key, &value)?;
+            }
+            Ok(())
+        }
+
+        // Metrics of the database, for the admin status
+        pub(crate) fn stats(&self) -> serde_json::Value {
+            let mut stats = serde_json::Map::new();
+            for (name, property) in [
+                ("estimated_keys", properties::ESTIMATE_NUM_KEYS),
+                ("sst_files_size", properties::TOTAL_SST_FILES_SIZE),
+                ("memtables_size", properties::CUR_SIZE_ALL_MEM_TABLES),
+                ("compaction_pending", properties::COMPACTION_PENDING),
+                (
+                    "pending_compaction_bytes",
+                    properties::ESTIMATE_PENDING_COMPACTION_BYTES,
+                ),
+                ("running_compactions", properties::NUM_RUNNING_COMPACTIONS),
+            ] {
+                match self.db.property_int_value(property) {
+                    Ok(Some(value)) => {
+                        stats.insert(name.into(), value.into());
+                    }
+                    Ok(None) => (),
+                    Err(e) => warn!("Failed to get data-info metric {}: {}", name, e),
+                }
+            }
+            stats.into()
+        }
+
+        pub(crate) fn compact(&mut self) {
+            self.db.compact_range::<&[u8], &[u8]>(None, None);
+        }
+    }
+}
+
+#[cfg(not(feature = "rocksdb"))]
+mod sidecar {
+    use std::{
+        collections::BTreeMap,
+        fs::{self, File, OpenOptions},
+        io::{BufWriter, Write},
+        path::{Path, PathBuf},
+    };
+
+    use tracing::warn;
+    use zenoh::{internal::zerror, Result as ZResult};
+
+    // The name of the log file, in the store's directory
+    const LOG_FILENAME: &str = "records.log";
+
+    const OP_PUT: u8 = 1;
+    const OP_DELETE: u8 = 2;
+    // a batch of puts and deletes, as a length-prefixed sequence of their records
+    const OP_BATCH: u8 = 3;
+
+    use super::{KvBatch, MergeFn};
+    use crate::retry::RetryPolicy;
+
+    // The records are kept in memory, and persisted in an append-only log of the puts and deletes.
+    // The log is rewritten with only the current records on opening and on compaction.
+    pub(crate) struct KvStore {
+        dir: PathBuf,
+        records: BTreeMap<Vec<u8>, Vec<u8>>,
+        log: File,
+        merge: MergeFn,
+    }
+
+    impl KvStore {
+        pub(crate) fn open(path: &Path, merge: MergeFn) -> ZResult<Self> {
+            fs::create_dir_all(path).map_err(|e| zerror!("{}", e))?;
+            let log_path = path.join(LOG_FILENAME);
+            let mut records = BTreeMap::new();
+            match fs::read(&log_path) {
+                Ok(log) => replay(&log, &mut records, &log_path),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+                Err(e) => return Err(zerror!("Failed to read {:?}: {}", log_path, e).into()),
+            }
+            let log = write_log(path, &records)?;
+            Ok(KvStore {
+                dir: path.to_path_buf(),
+                records,
+                log,
+                merge,
+            })
+        }
+
+        // The appends to the log aren't retried, as a failed one may have been partially written
+        pub(crate) fn set_retry(&mut self, _retry: RetryPolicy) {}
+
+        // Delete the store
+        pub(crate) fn destroy(&mut self) -> ZResult<()> {
+            self.records.clear();
+            fs::remove_dir_all(&self.dir).map_err(|e| zerror!("{}", e).into())
+        }
+
+        // Sync the log
+        pub(crate) fn flush(&mut self) -> ZResult<()> {
+            self.log.sync_all().map_err(|e| zerror!("{}", e).into())
+        }
+
+        pub(crate) fn get(&self, key: &[u8]) -> ZResult<Option<Vec<u8>>> {
+            Ok(self.records.get(key).cloned())
+        }
+
+        pub(crate) fn put(&mut self, key: &[u8], value: &[u8]) -> ZResult<()> {
+            let mut record = Vec::with_capacity(9 + key.len() + value.len());
+            record.push(OP_PUT);
+            encode(&mut record, key);
+            encode(&mut record, value);
+            self.log.write_all(&record).map_err(|e| zerror!("{}", e))?;
+            self.records.insert(key.to_vec(), value.to_vec());
+            Ok(())
+        }
+
+        pub(crate) fn delete(&mut self, key: &[u8]) -> ZResult<()> {
+            if self.records.remove(key).is_some() {
+                let mut record = Vec::with_capacity(5 + key.len());
+                record.push(OP_DELETE);
+                encode(&mut record, key);
+                self.log.write_all(&record).map_err(|e| zerror!("{}", e))?;
+            }
+            Ok(())
+        }
+
+        // Update the value of a record with an operand: the records being in memory, it's logged as a put of the
+        // updated value
+        pub(crate) fn merge(&mut self, key: &[u8], operand: &[u8]) -> ZResult<()> {
+            let value = (self.merge)(self.records.get(key).map(|v| v.as_slice()), operand);
+            self.put(key, &value)
+        }
+
+        // Write a batch of puts and deletes, in a single batch record of the log (replayed entirely or not at all)
+        pub(crate) fn write(&mut self, batch: &KvBatch) -> ZResult<()> {
+            if batch.is_empty() {
+                return Ok(());
+            }
+            let mut ops = Vec::new();
+            for (key, value) in &batch.ops {
+                match value {
+                    Some(value) => {
+                        ops.push(OP_PUT);
+                        encode(&mut ops, key);
+                        encode(&mut ops, value);
+                    }
+                    None => {
+                        ops.push(OP_DELETE);
+                        encode(&mut ops, key);
+                    }
+                }
+            }
+            let mut record = Vec::with_capacity(5 + ops.len());
+            record.push(OP_BATCH);
+            encode(&mut record, &ops);
+            self.log.write_all(&record).map_err(|e| zerror!("{}", e))?;
+            for (key, value) in &batch.ops {
+                match value {
+                    Some(value) => self.records.insert(key.clone(), value.clone()),
+                    None => self.records.remove(key),
+                };
+            }
+            Ok(())
+        }
+
+        // Call `f` with each record whose key starts with `prefix`, in the order of the keys
+        pub(crate) fn scan<F>(&self, prefix: &[u8], mut f: F) -> ZResult<()>
+        where
+            F: FnMut(&[u8], &[u8]) -> ZResult<()>,
+        {
+            for (key, value) in self.records.range(prefix.to_vec()..) {
+                if !key.starts_with(prefix) {
+                    break;
+                }
+                f(key, value)?;
+            }
+            Ok(())
+        }
+
+        // Metrics of the store, for the admin status
+        pub(crate) fn stats(&self) -> serde_json::Value {
+            let log_size = self.log.metadata().map(|m| m.len()).unwrap_or(0);
+            serde_json::json!({
+                "keys": self.records.len(),
+                "log_size": log_size,
+            })
+        }
+
+        pub(crate) fn compact(&mut self) {
+            match write_log(&self.dir, &self.records) {
+                Ok(log) => self.log = log,
+                Err(e) => warn!("Failed to compact data-info log in {:?}: {}", self.dir, e),
+            }
+        }
+    }
+
+    // Append a length-prefixed (as u32 little-endian) byte string
+    fn encode(record: &mut Vec<u8>, bytes: &[u8]) {
+        record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        record.extend_from_slice(bytes);
+    }
+
+    fn decode<'a>(log: &mut &'a [u8]) -> Option<&'a [u8]> {
+        let len = u32::from_le_bytes(log.get(..4)?.try_into().ok()?) as usize;
+        let bytes = log.get(4..4 + len)?;
+        *log = &log[4 + len..];
+        Some(bytes)
+    }
+
+    // Apply the records of a log, ignoring an incomplete last one (e.g. after a crash)
+    fn replay(mut log: &[u8], records: &mut BTreeMap<Vec<u8>, Vec<u8>>, path: &Path) {
+        while let Some((op, mut rest)) = log.split_first() {
+            let complete = match *op {
+                OP_BATCH => decode(&mut rest)
+                    .and_then(decode_batch)
+                    .map(|ops| {
+                        for (key, value) in ops {
+                            match value {
+                                Some(value) => records.insert(key.to_vec(), value.to_vec()),
+                                None => records.remove(key),
+                            };
+                        }
+                    })
+                    .is_some(),
+                _ => decode_op(*op, &mut rest)
+                    .map(|(key, value)| match value {
+                        Some(value) => records.insert(key.to_vec(), value.to_vec()),
+                        None => records.remove(key),
+                    })
+                    .is_some(),
+            };
+            if !complete {
+                warn!(
+                    "Ignored the invalid end of data-info log {:?} ({} bytes)",
+                    path,
+                    log.len()
+                );
+                return;
+            }
+            log = rest;
+        }
+    }
+
+    // Decode the key (and value, if a put) of a put or delete record
+    fn decode_op<'a>(op: u8, rest: &mut &'a [u8]) -> Option<(&'a [u8], Option<&'a [u8]>)> {
+        match op {
+            OP_PUT => decode(rest).zip(decode(rest)).map(|(k, v)| (k, Some(v))),
+            OP_DELETE => decode(rest).map(|k| (k, None)),
+            _ => None,
+        }
+    }
+
+    // Decode all the records of a batch, or none if one is invalid
+    fn decode_batch(mut ops: &[u8]) -> Option<Vec<(&[u8], Option<&[u8]>)>> {
+        let mut decoded = Vec::new();
+        while let Some((op, mut rest)) = ops.split_first() {
+            decoded.push(decode_op(*op, &mut rest)?);
+            ops = rest;
+        }
+        Some(decoded)
+    }
+
+    // (Re)write the log with the current records, returning it opened for the next appends
+    fn write_log(dir: &Path, records: &BTreeMap<Vec<u8>, Vec<u8>>) -> ZResult<File> {
+        let path = dir.join(LOG_FILENAME);
+        let tmp_path = dir.join(format!("{LOG_FILENAME}.tmp"));
+        let result = File::create(&tmp_path).and_then(|file| {
+            let mut writer = BufWriter::new(file);
+            for (key, value) in records {
+                let mut record = vec![OP_PUT];
+                encode(&mut record, key);
+                encode(&mut record, value);
+                writer.write_all(&record)?;
+            }
+            writer.into_inner()?.sync_all()?;
+            fs::rename(&tmp_path, &path)
+        });
+        result.map_err(|e| zerror!("Failed to write {:?}: {}", path, e))?;
+        OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map_err(|e| zerror!("Failed to open {:?}: {}", path, e).into())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        // A merge operator appending the operand to the value
+        fn append(value: Option<&[u8]>, operand: &[u8]) -> Vec<u8> {
+            [value.unwrap_or_default(), operand].concat()
+        }
+
+        fn records(store: &KvStore) -> Vec<(Vec<u8>, Vec<u8>)> {
+            let mut records = Vec::new();
+            store
+                .scan(b"", |key, value| {
+                    records.push((key.to_vec(), value.to_vec()));
+                    Ok(())
+                })
+                .unwrap();
+            records
+        }
+
+        // Append some bytes to the log, as if written before a crash
+        fn append_to_log(dir: &Path, bytes: &[u8]) {
+            let mut log = OpenOptions::new()
+                .append(true)
+                .open(dir.join(LOG_FILENAME))
+                .unwrap();
+            log.write_all(bytes).unwrap();
+        }
+
+        #[test]
+        fn replay_truncated_record() {
+            let dir = tempfile::tempdir().unwrap();
+            let mut store = KvStore::open(dir.path(), append).unwrap();
+            store.put(b"a", b"1").unwrap();
+            store.put(b"b", b"2").unwrap();
+            store.delete(b"a").unwrap();
+            drop(store);
+            // a put whose value is cut
+            let mut record = vec![OP_PUT];
+            encode(&mut record, b"c");
+            encode(&mut record, b"3333");
+            append_to_log(dir.path(), &record[..record.len() - 2]);
+            let mut store = KvStore::open(dir.path(), append).unwrap();
+            assert_eq!(records(&store), vec![(b"b".to_vec(), b"2".to_vec())]);
+            // the log was rewritten without the truncated record, the next appends being replayed
+            store.put(b"d", b"4").unwrap();
+            drop(store);
+            let store = KvStore::open(dir.path(), append).unwrap();
+            assert_eq!(
+                records(&store),
+                vec![
+                    (b"b".to_vec(), b"2".to_vec()),
+                    (b"d".to_vec(), b"4".to_vec())
+                ]
+            );
+        }
+
+        #[test]
+        fn replay_partial_batch() {
+            let dir = tempfile::tempdir().unwrap();
+            let mut store = KvStore::open(dir.path(), append).unwrap();
+            store.put(b"a", b"1").unwrap();
+            let mut batch = KvBatch::default();
+            batch.put(b"b", b"2");
+            batch.delete(b"a");
+            store.write(&batch).unwrap();
+            assert_eq!(records(&store), vec![(b"b".to_vec(), b"2".to_vec())]);
+            drop(store);
+            // a batch cut after its first operation: none of its operations is applied
+            let mut ops = vec![OP_PUT];
+            encode(&mut ops, b"c");
+            encode(&mut ops, b"3");
+            let first_op_len = ops.len();
+            ops.push(OP_DELETE);
+            encode(&mut ops, b"b");
+            let mut record = vec![OP_BATCH];
+            encode(&mut record, &ops);
+            append_to_log(dir.path(), &record[..5 + first_op_len]);
+            let store = KvStore::open(dir.path(), append).unwrap();
+            assert_eq!(records(&store), vec![(b"b".to_vec(), b"2".to_vec())]);
+            // a batch with an invalid operation isn't decoded
+            assert!(decode_batch(&ops).is_some());
+            assert!(decode_batch(&ops[..ops.len() - 1]).is_none());
+            assert!(decode_batch(&[OP_BATCH, 0, 0, 0, 0]).is_none());
+        }
+
+        #[test]
+        fn merge() {
+            let dir = tempfile::tempdir().unwrap();
+            let mut store = KvStore::open(dir.path(), append).unwrap();
+            store.merge(b"a", b"x").unwrap();
+            store.merge(b"a", b"y").unwrap();
+            store.put(b"b", b"1").unwrap();
+            store.merge(b"b", b"2").unwrap();
+            assert_eq!(store.get(b"a").unwrap(), Some(b"xy".to_vec()));
+            assert_eq!(store.get(b"b").unwrap(), Some(b"12".to_vec()));
+            drop(store);
+            // the merges are logged as puts of the merged values
+            let store = KvStore::open(dir.path(), append).unwrap();
+            assert_eq!(store.get(b"a").unwrap(), Some(b"xy".to_vec()));
+            assert_eq!(store.get(b"b").unwrap(), Some(b"12".to_vec()));
+        }
+
+        #[test]
+        fn compact_and_reopen() {
+            let dir = tempfile::tempdir().unwrap();
+            let mut store = KvStore::open(dir.path(), append).unwrap();
+            for i in 0..100u32 {
+                store.put(&i.to_be_bytes(), b"value").unwrap();
+            }
+            for i in 10..100u32 {
+                store.delete(&i.to_be_bytes()).unwrap();
+            }
+            let expected = records(&store);
+            assert_eq!(expected.len(), 10);
+            let log_size = || fs::metadata(dir.path().join(LOG_FILENAME)).unwrap().len();
+            let size = log_size();
+            store.compact();
+            assert!(log_size() < size);
+            assert_eq!(records(&store), expected);
+            // the appends after the compaction go to the rewritten log
+            store.put(b"z", b"last").unwrap();
+            drop(store);
+            let store = KvStore::open(dir.path(), append).unwrap();
+            let mut all = expected;
+            all.push((b"z".to_vec(), b"last".to_vec()));
+            assert_eq!(records(&store), all);
+            assert!(!dir.path().join(format!("{LOG_FILENAME}.tmp")).exists());
+        }
+    }
+}
@@ -12,17 +12,27 @@
 //   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
 //
 
-use std::{collections::HashMap, fs::DirBuilder, future::Future, io::prelude::*, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::DirBuilder,
+    future::Future,
+    io::prelude::*,
+    num::NonZeroUsize,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use async_trait::async_trait;
 use tempfile::tempfile_in;
-use tracing::{debug, warn};
+use tracing::{debug, debug_span, field, info, warn, Instrument, Span};
 use zenoh::{
     bytes::{Encoding, ZBytes},
     internal::{bail, zenoh_home, zerror},
     key_expr::{keyexpr, OwnedKeyExpr},
     query::Parameters,
-    time::Timestamp,
+    time::{Timestamp, TimestampId, NTP64},
     try_init_log_from_env, Result as ZResult,
 };
 use zenoh_backend_traits::{
@@ -32,9 +42,103 @@ use zenoh_backend_traits::{
 };
 use zenoh_plugin_trait::{plugin_long_version, plugin_version, Plugin};
 
+mod acl;
+mod archive_mgt;
+mod availability;
+mod builder;
+mod cold_tier;
+mod conditional;
+mod config;
+mod data_info_cache;
 mod data_info_mgt;
+mod delta;
+mod digest;
+mod dir_listings;
+mod dry_run;
+mod entries_index;
+mod envelope;
+mod failover;
+#[cfg(feature = "fault-injection")]
+mod faults;
 mod files_mgt;
+mod fs_config;
+mod fs_info;
+mod handles_pool;
+mod hooks;
+mod immutable;
+mod import;
+mod interceptors;
+mod key_locks;
+mod keys_filter;
+mod kv_store;
+mod links;
+mod maintenance;
+mod manifest;
+mod migration;
+mod mirror;
+mod names;
+mod notifier;
+mod overlay;
+mod permissions;
+mod policies;
+mod prefetch;
+mod progress;
+mod quota;
+mod retry;
+mod scrub;
+#[cfg(feature = "shared-memory")]
+mod shm;
+mod storage_dirs;
+mod storage_log;
+mod storage_runtime;
+mod tarball;
+mod tenants;
+mod timestamp_skew;
+mod transforms;
+mod tree;
+mod unpack;
+mod watermark;
+mod write_queue;
+use acl::{Acl, AclOp};
+use builder::CustomExtensions;
+pub use builder::FileSystemStorageBuilder;
+use cold_tier::ColdTier;
+use conditional::Conditions;
+use delta::Delta;
+use dry_run::DryRun;
+use failover::Failover;
 use files_mgt::*;
+pub use fs_config::{
+    FsCommandConfig, FsFaultInjectionConfig, FsInterceptorConfig, FsPolicyConfig, FsStorageConfig,
+    FsTenantConfig, FsTransformConfig, FsTransformOptions, FsVolumeConfig,
+};
+use fs_info::FsInfo;
+use hooks::{HookCommand, Hooks};
+use import::Import;
+pub use interceptors::{
+    AuditInterceptor, RewriteInterceptor, StorageInterceptor, ValidateInterceptor,
+};
+use maintenance::{CronSchedule, Job, MaintenanceScheduler};
+use manifest::ManifestEntry;
+use migration::Migration;
+use mirror::Mirror;
+use notifier::{Notifier, StatsPublisher};
+use overlay::Overlay;
+use policies::PolicyConfig;
+use progress::InitProgress;
+use quota::DiskQuota;
+use retry::{ErrorClass, RetryPolicy};
+use scrub::Scrub;
+use storage_dirs::{StorageDirGuard, StorageDirs};
+use storage_log::StorageLog;
+use storage_runtime::{run_on, StorageRuntime};
+use tarball::{tar_of, TarFile};
+use tenants::TenantConfig;
+use timestamp_skew::{SkewPolicy, TimestampSkew};
+pub use transforms::{AesGcmTransform, GzipTransform, PayloadTransform, ZstdSeekableTransform};
+use tree::TreeSummary;
+use unpack::archive_entries;
+use write_queue::OverloadPolicy;
 
 const WORKER_THREAD_NUM: usize = 2;
 const MAX_BLOCK_THREAD_NUM: usize = 50;
@@ -47,21 +151,56 @@ lazy_static::lazy_static! {
                .build()
                .expect("Unable to create runtime");
 }
-#[inline(always)]
-fn blockon_runtime<F: Future>(task: F) -> F::Output {
-    // Check whether able to get the current runtime
-    match tokio::runtime::Handle::try_current() {
-        Ok(rt) => {
-            // Able to get the current runtime (standalone binary), spawn on the current runtime
-            tokio::task::block_in_place(|| rt.block_on(task))
-        }
-        Err(_) => {
-            // Unable to get the current runtime (dynamic plugins), spawn on the global runtime
-            tokio::task::block_in_place(|| TOKIO_RUNTIME.block_on(task))
+// The runtime of the background tasks of a storage (given its FilesMgr): its dedicated runtime if any, else the current
+// runtime, or the global runtime if unable to get it (dynamic plugins)
+fn runtime_of(files_mgr: &FilesMgr) -> tokio::runtime::Handle {
+    match files_mgr.runtime() {
+        Some(runtime) => runtime.clone(),
+        None => {
+            tokio::runtime::Handle::try_current().unwrap_or_else(|_| TOKIO_RUNTIME.handle().clone())
         }
     }
 }
 
+// Spawn a background task of a storage (given its FilesMgr), awaited by the storage's closure: the task must stop at
+// its next step once the storage is being closed (FilesMgr::upgrade() failing), its pauses between the steps being
+// interrupted with FilesMgr::idle()
+fn spawn_storage_task<F, T>(files_mgr: Weak<FilesMgr>, task: T)
+where
+    T: FnOnce(Weak<FilesMgr>) -> F,
+    F: Future<Output = ()> + Send + 'static,
+{
+    let Some(mgr) = FilesMgr::upgrade(&files_mgr) else {
+        // the storage is dropped or being closed
+        return;
+    };
+    let task = mgr.log().scope(task(files_mgr));
+    mgr.spawn_task(task, &runtime_of(&mgr));
+}
+
+// The interval at which the statistics read from the data-info database are refreshed, for the admin status
+const DB_STATS_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+// Refresh in background the statistics of a storage (given its FilesMgr) read from its data-info database, the admin
+// status reading their last snapshot without blocking
+fn spawn_db_stats_refresh(files_mgr: Weak<FilesMgr>) {
+    spawn_storage_task(files_mgr, |files_mgr| async move {
+        loop {
+            let Some(mgr) = FilesMgr::upgrade(&files_mgr) else {
+                break;
+            };
+            mgr.refresh_db_stats().await;
+            drop(mgr);
+            if FilesMgr::idle(&files_mgr, tokio::time::sleep(DB_STATS_REFRESH_INTERVAL))
+                .await
+                .is_none()
+            {
+                break;
+            }
+        }
+    });
+}
+
 /// The environement variable used to configure the root of all storages managed by this FileSystemBackend.
 pub const SCOPE_ENV_VAR: &str = "ZENOH_BACKEND_FS_ROOT";
 
@@ -69,18 +208,280 @@ pub const SCOPE_ENV_VAR: &str = "ZENOH_BACKEND_FS_ROOT";
 pub const DEFAULT_ROOT_DIR: &str = "zenoh_backend_fs";
 
 // Properies used by the Backend
-//  - None
+pub const PROP_BACKEND_MAX_TOTAL_SIZE: &str = "max_total_size";
+pub const PROP_BACKEND_STORAGE_DEFAULTS: &str = "storage_defaults";
+pub const PROP_BACKEND_ROOT: &str = "root";
 
 // Properies used by the Storage
 pub const PROP_STORAGE_READ_ONLY: &str = "read_only";
+pub const PROP_STORAGE_READ_ONLY_BEHAVIOR: &str = "read_only_behavior";
+pub const PROP_STORAGE_WORM: &str = "worm";
+pub const PROP_STORAGE_SET_IMMUTABLE: &str = "set_immutable";
+pub const PROP_STORAGE_TENANTS: &str = "tenants";
+pub const PROP_STORAGE_ACL: &str = "acl";
+pub const PROP_TENANT_MAX_TOTAL_SIZE: &str = "max_total_size";
+pub const PROP_TENANT_RETENTION_DAYS: &str = "retention_days";
+pub const PROP_STORAGE_POLICIES: &str = "policies";
+pub const PROP_POLICY_MAX_TOTAL_SIZE: &str = "max_total_size";
+pub const PROP_POLICY_RETENTION_DAYS: &str = "retention_days";
 pub const PROP_STORAGE_DIR: &str = "dir";
+pub const PROP_STORAGE_STRIPES: &str = "stripes";
+pub const PROP_STORAGE_MIRROR_DIR: &str = "mirror_dir";
+pub const PROP_STORAGE_FAILOVER_DIR: &str = "failover_dir";
+pub const PROP_STORAGE_LOWER_DIR: &str = "lower_dir";
 pub const PROP_STORAGE_ON_CLOSURE: &str = "on_closure";
+pub const PROP_STORAGE_ON_CONFLICT: &str = "on_conflict";
 pub const PROP_STORAGE_FOLLOW_LINK: &str = "follow_links";
+pub const PROP_STORAGE_MAX_LINK_DEPTH: &str = "max_link_depth";
 pub const PROP_STORAGE_KEEP_MIME: &str = "keep_mime_types";
+pub const PROP_STORAGE_VERIFY_ON_START: &str = "verify_on_start";
+pub const PROP_STORAGE_LAZY_INIT: &str = "lazy_init";
+pub const PROP_STORAGE_DEDICATED_RUNTIME_THREADS: &str = "dedicated_runtime_threads";
+pub const PROP_STORAGE_MAX_CONCURRENT_OPS: &str = "max_concurrent_ops";
+pub const PROP_STORAGE_WRITE_QUEUE_DEPTH: &str = "write_queue_depth";
+pub const PROP_STORAGE_WRITE_QUEUE_OVERLOAD: &str = "write_queue_overload";
+pub const PROP_STORAGE_OPEN_FILES_CACHE_SIZE: &str = "open_files_cache_size";
+pub const PROP_STORAGE_READ_AHEAD: &str = "read_ahead";
+pub const PROP_STORAGE_NFS_SAFE: &str = "nfs_safe";
+pub const PROP_STORAGE_RETRY_ATTEMPTS: &str = "retry_attempts";
+pub const PROP_STORAGE_RETRY_BACKOFF_MS: &str = "retry_backoff_ms";
+pub const PROP_STORAGE_RETRY_ERRORS: &str = "retry_errors";
+pub const PROP_STORAGE_VERIFY_WRITES: &str = "verify_writes";
+pub const PROP_STORAGE_LOG_LEVEL: &str = "log_level";
+pub const PROP_STORAGE_TRACE_OPS: &str = "trace_ops";
+pub const PROP_STORAGE_DRY_RUN: &str = "dry_run";
+pub const PROP_STORAGE_DATA_INFO_CACHE_SIZE: &str = "data_info_cache_size";
+pub const PROP_STORAGE_DIR_LISTINGS_CACHE_SIZE: &str = "dir_listings_cache_size";
+pub const PROP_STORAGE_BLOOM_FILTER: &str = "bloom_filter";
+pub const PROP_STORAGE_WATERMARK_BY_PREFIX: &str = "watermark_by_prefix";
+pub const PROP_STORAGE_SHM_POOL_SIZE: &str = "shm_pool_size";
+pub const PROP_STORAGE_SHM_THRESHOLD: &str = "shm_threshold";
+pub const PROP_STORAGE_FAULT_INJECTION: &str = "fault_injection";
+pub const PROP_STORAGE_EXPOSE_FS_PATH: &str = "expose_fs_path";
+pub const PROP_STORAGE_ENTRIES_INDEX: &str = "entries_index";
+pub const PROP_STORAGE_DIGEST_INTERVAL_SECS: &str = "digest_interval_secs";
+pub const PROP_STORAGE_ARCHIVE_AFTER_DAYS: &str = "archive_after_days";
+pub const PROP_STORAGE_RETENTION_DAYS: &str = "retention_days";
+pub const PROP_STORAGE_COLD_TIER_DIR: &str = "cold_tier_dir";
+pub const PROP_STORAGE_COLD_AFTER_DAYS: &str = "cold_after_days";
+pub const PROP_STORAGE_MAINTENANCE: &str = "maintenance";
+pub const PROP_STORAGE_STRICT: &str = "strict";
+pub const PROP_STORAGE_DEFAULT_ENCODING: &str = "default_encoding";
+pub const PROP_STORAGE_ENCODING_SCHEMAS: &str = "encoding_schemas";
+pub const PROP_STORAGE_ENCODING_OVERRIDES: &str = "encoding_overrides";
+pub const PROP_STORAGE_TIMESTAMP_ID: &str = "timestamp_id";
+pub const PROP_STORAGE_PAYLOAD_TRANSFORMS: &str = "payload_transforms";
+pub const PROP_STORAGE_ENVELOPE: &str = "envelope";
+pub const PROP_STORAGE_PASSTHROUGH: &str = "passthrough";
+pub const PROP_STORAGE_EMPTY_PAYLOAD: &str = "empty_payload";
+pub const PROP_STORAGE_SKEWED_TIMESTAMPS: &str = "skewed_timestamps";
+pub const PROP_STORAGE_MAX_FUTURE_SKEW_SECS: &str = "max_future_skew_secs";
+pub const PROP_STORAGE_MAX_PAST_SKEW_SECS: &str = "max_past_skew_secs";
+pub const PROP_STORAGE_OBFUSCATE_NAMES: &str = "obfuscate_names";
+pub const PROP_STORAGE_OBFUSCATION_SALT: &str = "obfuscation_salt";
+pub const PROP_STORAGE_CANONICALIZE_KEYS: &str = "canonicalize_keys";
+pub const PROP_STORAGE_ZENOH_WRITE_ONLY: &str = "zenoh_write_only";
+pub const PROP_STORAGE_CONTROL_PREFIX: &str = "control_prefix";
+pub const PROP_STORAGE_EXPORT_DIR: &str = "export_dir";
+pub const PROP_STORAGE_IMPORT_ROOTS: &str = "import_roots";
+pub const PROP_STORAGE_MAX_UNPACK_SIZE: &str = "max_unpack_size";
+pub const PROP_STORAGE_MAX_PATCHED_SIZE: &str = "max_patched_size";
+pub const PROP_STORAGE_ON_PUT: &str = "on_put";
+pub const PROP_STORAGE_ON_DELETE: &str = "on_delete";
+pub const PROP_STORAGE_INTERCEPTORS: &str = "interceptors";
+pub const PROP_STORAGE_NOTIFICATION_KEY_EXPR: &str = "notification_key_expr";
+pub const PROP_STORAGE_NOTIFICATION_CONNECT: &str = "notification_connect";
+pub const PROP_STORAGE_STATS_KEY_EXPR: &str = "stats_key_expr";
+pub const PROP_STORAGE_STATS_INTERVAL_SECS: &str = "stats_interval_secs";
+
+/// The control operation moving the storage to another directory (given as payload, relative to the volume's root).
+pub const CONTROL_MIGRATE: &str = "migrate";
+
+/// The control operation moving an entry to another key (given as a JSON payload: `{"from": <key>, "to": <key>}`).
+pub const CONTROL_MOVE: &str = "move";
+
+/// The control operation copying an entry to another key (given as a JSON payload: `{"from": <key>, "to": <key>}`).
+pub const CONTROL_COPY: &str = "copy";
+
+/// The control operation deleting several entries at once (given as a JSON payload: a list of keys).
+pub const CONTROL_DELETE: &str = "delete";
+
+/// The control operation importing an external directory or tarball of the host (whose absolute path, under one of the
+/// `import_roots`, is given as payload) as entries under a key prefix (given as `<control_prefix>/import/<prefix>`).
+pub const CONTROL_IMPORT: &str = "import";
+
+/// The control operation writing the inventory manifest of the entries under a key prefix
+/// (given as `<control_prefix>/manifest/<prefix>`) to a file of the `export_dir` (whose relative path is given as
+/// payload).
+pub const CONTROL_MANIFEST: &str = "manifest";
+
+/// The control operation synchronizing the entries under a key prefix (given as `<control_prefix>/sync/<prefix>`)
+/// with the manifest of a remote storage (given as payload, in JSON).
+pub const CONTROL_SYNC: &str = "sync";
+
+/// The control operation updating an entry (given as `<control_prefix>/patch/<key>`) with a delta against its current
+/// value (given as payload).
+pub const CONTROL_PATCH: &str = "patch";
+
+/// The control operation changing the logging of the storage (given as a JSON payload:
+/// `{"level": <level or null>, "trace_ops": <bool>}`, both optional).
+pub const CONTROL_LOG: &str = "log";
+
+/// The control operation checking the integrity of the entries under a key prefix (given as
+/// `<control_prefix>/scrub/<prefix>`), at most a number of files per second (given as payload, or
+/// DEFAULT_SCRUB_MAX_RATE if empty).
+pub const CONTROL_SCRUB: &str = "scrub";
+
+/// The control operation deleting the entries under a key prefix (given as `<control_prefix>/purge/<prefix>`) older
+/// than a bound (given as payload: a timestamp, an RFC 3339 date-time or an age such as `30days`).
+pub const CONTROL_PURGE: &str = "purge";
+
+/// The default schedule of the archival, retention and offload jobs, if not specified in the `maintenance` property
+/// (hourly).
+pub const DEFAULT_MAINTENANCE_SCHEDULE: &str = "0 * * * *";
+
+/// The default number of days without access after which an entry is offloaded to the cold tier.
+pub const DEFAULT_COLD_AFTER_DAYS: usize = 30;
+
+/// The default minimal size (in bytes) of a file to be served through shared-memory.
+pub const DEFAULT_SHM_THRESHOLD: usize = 64 * 1024;
+
+/// The default maximum size (in bytes) of the files unpacked from the archive of a PUT with `_unpack=true`.
+pub const DEFAULT_MAX_UNPACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// The default maximum size (in bytes) of a value built by the `patch` control operation.
+pub const DEFAULT_MAX_PATCHED_SIZE: usize = 64 * 1024 * 1024;
+
+/// The directory (in the volume's root) of the data-info databases of the storages in passthrough mode,
+/// which have nothing added to their own directory.
+pub const PASSTHROUGH_DATA_INFO_DIR: &str = ".zenoh_passthrough";
+
+/// The directory (in the volume's root) of the data-info databases of the replica directories the storages' reads
+/// fail over to, which are only read.
+pub const FAILOVER_DATA_INFO_DIR: &str = ".zenoh_failover";
+
+/// The directory (in the volume's root) of the data-info databases of the lower layers of the storages, which are
+/// only read.
+pub const OVERLAY_DATA_INFO_DIR: &str = ".zenoh_overlay";
+
+// Query parameters handled by the Storage
+pub const PARAM_FS_PATH: &str = "_fs_path";
+pub const PARAM_FORMAT: &str = "_format";
+pub const PARAM_SORT: &str = "_sort";
+pub const PARAM_CHANGED_SINCE: &str = "_changed_since";
+pub const PARAM_ACCEPT_ENCODING: &str = "_accept_encoding";
+pub const PARAM_IF_NONE_MATCH: &str = "_if_none_match";
+pub const PARAM_IF_MODIFIED_SINCE: &str = "_if_modified_since";
+pub const PARAM_RANGE: &str = "_range";
+
+/// The parameter of a PUT's encoding (e.g. `application/x-tar;_unpack=true`) requesting its payload, a tarball
+/// (possibly gzipped) or a zip file, to be unpacked as entries under the PUT's key. The parameters are given in the
+/// encoding's schema, as the PUTs received by a storage have no selector parameters.
+pub const PARAM_UNPACK: &str = "_unpack";
+
+/// The value of the `_accept_encoding` parameter of a GET accepting a value compressed with gzip (possibly among
+/// other comma-separated values, as in an HTTP `Accept-Encoding` header).
+pub const ACCEPT_GZIP: &str = "gzip";
+
+/// The encoding schema of the replies carrying a value compressed with gzip, appended to the schema of the entry's
+/// encoding (if any) with a `+`.
+pub const GZIP_SCHEMA: &str = "gzip";
+
+/// The encoding schema of the empty replies to the conditional GETs (with `_if_none_match` or `_if_modified_since`)
+/// whose querier already has the value, the reply's timestamp being the value's one.
+pub const NOT_MODIFIED_SCHEMA: &str = "not_modified";
+
+/// The values of the `_sort` parameter of a GET with a `_format`: the entries are ordered by key (the default),
+/// or by timestamp (then by key).
+pub const SORT_KEY: &str = "key";
+pub const SORT_TIMESTAMP: &str = "timestamp";
+
+/// The value of the `_format` parameter of a GET replying a tarball of the entries under the key.
+pub const FORMAT_TAR: &str = "tar";
+
+/// The encoding of the tarball replied to a GET with `_format=tar`.
+pub const TAR_ENCODING: &str = "application/x-tar";
+
+/// The values of the `_format` parameter of a GET replying the inventory manifest of the entries under the key,
+/// as JSON or as CSV.
+pub const FORMAT_MANIFEST: &str = "manifest";
+pub const FORMAT_MANIFEST_CSV: &str = "manifest.csv";
+
+/// The value of the `_format` parameter of a GET replying the replication digest of the entries under the key.
+pub const FORMAT_DIGEST: &str = "digest";
+
+/// The value of the `_format` parameter of a GET replying the summaries of the direct children of the key (the number
+/// of their children and of the entries under them, their total size and their most recent timestamp).
+pub const FORMAT_TREE: &str = "tree";
+
+/// The value of the `_format` parameter of a GET replying the number of entries under the key and their total size,
+/// per encoding.
+pub const FORMAT_ENCODINGS: &str = "encodings";
+
+/// The value of the `_format` parameter of a GET replying the entries under the key stored in conflict files, with
+/// the paths of their file and of the conflicting directory.
+pub const FORMAT_CONFLICTS: &str = "conflicts";
+
+/// The value of the `_format` parameter of a GET replying the watermark of the storage (on its root) or of a top-level
+/// prefix: the most recent timestamp of the values stored (under it).
+pub const FORMAT_WATERMARK: &str = "watermark";
+
+/// The default duration (in seconds) of the time intervals of the replication digest.
+pub const DEFAULT_DIGEST_INTERVAL_SECS: usize = 10;
+
+/// The default interval (in seconds) between the publications of the statistics of a storage.
+pub const DEFAULT_STATS_INTERVAL_SECS: usize = 60;
+
+/// The default maximum number of files checked per second by the `scrub` control operation.
+pub const DEFAULT_SCRUB_MAX_RATE: usize = 100;
+
+/// The default tolerance (in seconds) on the timestamps ahead of the current time.
+pub const DEFAULT_MAX_FUTURE_SKEW_SECS: usize = 60;
+
+/// The encoding schema of the replies carrying a file's absolute path instead of its content.
+pub const FS_PATH_SCHEMA: &str = "fs_path";
 
 // Special key for None (when the prefix being stripped exactly matches the key)
 pub const ROOT_KEY: &str = "@root";
 
+/// The JSON Schema of the volume-specific configuration of the storages (i.e. of their `volume` section),
+/// allowing to validate a zenohd configuration before deployment.
+/// It's also exposed in the volume's admin status, as `config_schema`.
+pub fn storage_config_schema() -> serde_json::Value {
+    config::storage_config_schema()
+}
+
+// What to do on a PUT with an empty payload
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum EmptyPayload {
+    // store it as an empty file
+    Store,
+    // reject the PUT
+    Reject,
+    // process the PUT as a DELETE (an empty file is then considered as a missing file)
+    Delete,
+}
+
+// The order of the entries returned by `entries()`
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SortOrder {
+    // lexicographic by key, the root key first
+    Key,
+    // by timestamp, then by key
+    Timestamp,
+}
+
+// What to do on a PUT or DELETE received by a read-only storage
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ReadOnlyBehavior {
+    // log a warning and return an error
+    Error,
+    // ignore it, without any log
+    SilentlyIgnore,
+    // ignore it, logging a warning only for the first one
+    LogOnce,
+}
+
 pub struct FileSystemBackend {}
 
 #[cfg(feature = "dynamic_plugin")]
@@ -94,335 +495,3519 @@ impl Plugin for FileSystemBackend {
     const PLUGIN_VERSION: &'static str = plugin_version!();
     const PLUGIN_LONG_VERSION: &'static str = plugin_long_version!();
 
-    fn start(_name: &str, _config: &Self::StartArgs) -> ZResult<Self::Instance> {
+    fn start(name: &str, config: &Self::StartArgs) -> ZResult<Self::Instance> {
         try_init_log_from_env();
-        debug!("FileSystem backend {}", Self::PLUGIN_VERSION);
+        debug!(
+            "FileSystem backend {} for volume {}",
+            Self::PLUGIN_VERSION,
+            name
+        );
 
-        let root_path = if let Some(dir) = std::env::var_os(SCOPE_ENV_VAR) {
+        let default_root = if let Some(dir) = std::env::var_os(SCOPE_ENV_VAR) {
             PathBuf::from(dir)
         } else {
             let mut dir = PathBuf::from(zenoh_home());
             dir.push(DEFAULT_ROOT_DIR);
             dir
         };
-        if let Err(e) = std::fs::create_dir_all(&root_path) {
+        let volume_config = FsVolumeConfig::from_json(&config.rest)?;
+        // (an absolute root replacing the default one)
+        let root_path = match volume_config.root {
+            Some(dir) if !dir.is_empty() => default_root.join(dir),
+            None => default_root,
+            Some(_) => bail!(
+                r#"Invalid value for File System Backend property "{}" of volume {}: it must be a non-empty string"#,
+                PROP_BACKEND_ROOT,
+                name
+            ),
+        };
+        let max_total_size = volume_config.max_total_size.map(NonZeroUsize::get);
+        let storage_defaults = volume_config.storage_defaults.unwrap_or_default();
+        Ok(Box::new(
+            FileSystemVolume::new(&root_path, max_total_size)?
+                .with_storage_defaults(storage_defaults)?,
+        ))
+    }
+}
+
+pub struct FileSystemVolume {
+    admin_status: serde_json::Value,
+    root: PathBuf,
+    storage_dirs: StorageDirs,
+    quota: Option<Arc<DiskQuota>>,
+    // the properties inherited by the storages which don't set them
+    storage_defaults: serde_json::Map<String, serde_json::Value>,
+    // the handles of the storages of the volume, to count and close them
+    storages: Mutex<Vec<Weak<StorageHandle>>>,
+    // the progress of the storages being created, per name
+    initializing: Mutex<HashMap<String, Arc<InitProgress>>>,
+}
+
+impl FileSystemVolume {
+    /// Create a volume whose storages are in the `root` directory (created if missing), as the one of
+    /// `${ZENOH_BACKEND_FS_ROOT}` (or the volume's `root` property) when the backend is loaded by the storage manager.
+    /// The directories of the storages can't be nested with each other, even across volumes with nested roots.
+    /// If `max_total_size` is set, it caps the total size in bytes of the files of all the storages.
+    pub fn new(root: &Path, max_total_size: Option<usize>) -> ZResult<Self> {
+        if let Err(e) = std::fs::create_dir_all(root) {
             bail!(
-                r#"Failed to create directory ${{{}}}={}: {}"#,
-                SCOPE_ENV_VAR,
-                root_path.display(),
-                e
+                r#"Failed to create root directory {} of the volume: {}{}"#,
+                root.display(),
+                e,
+                permissions::diagnose(root, &e, true)
             );
         }
-        let root = match dunce::canonicalize(&root_path) {
+        let root = match dunce::canonicalize(root) {
             Ok(dir) => dir,
             Err(e) => bail!(
-                r#"Invalid path for ${{{}}}={}: {}"#,
-                SCOPE_ENV_VAR,
-                root_path.display(),
+                r#"Invalid root directory {} of the volume: {}"#,
+                root.display(),
                 e
             ),
         };
         debug!("Using root dir: {}", root.display());
 
+        let quota = max_total_size.map(|max| Arc::new(DiskQuota::new("volume".into(), max as u64)));
+
         let mut parameters = Parameters::default();
         parameters.insert::<String, String>("root".into(), root.to_string_lossy().into());
-        parameters.insert::<String, String>("version".into(), Self::PLUGIN_VERSION.into());
+        parameters
+            .insert::<String, String>("version".into(), FileSystemBackend::PLUGIN_VERSION.into());
 
-        let admin_status = HashMap::from(parameters)
-            .into_iter()
-            .map(|(k, v)| (k, serde_json::Value::String(v)))
-            .collect();
-        Ok(Box::new(FileSystemVolume { admin_status, root }))
+        let mut admin_status: serde_json::Map<String, serde_json::Value> =
+            HashMap::from(parameters)
+                .into_iter()
+                .map(|(k, v)| (k, serde_json::Value::String(v)))
+                .collect();
+        admin_status.insert("config_schema".into(), storage_config_schema());
+        Ok(FileSystemVolume {
+            admin_status: admin_status.into(),
+            root,
+            storage_dirs: StorageDirs::shared(),
+            quota,
+            storage_defaults: serde_json::Map::new(),
+            storages: Mutex::new(Vec::new()),
+            initializing: Mutex::new(HashMap::new()),
+        })
     }
-}
 
-pub struct FileSystemVolume {
-    admin_status: serde_json::Value,
-    root: PathBuf,
+    /// Set the default properties of the storages of the volume: each storage inherits the ones it doesn't set
+    /// itself (a property set by a storage replacing the default one as a whole, even for an object).
+    pub fn with_storage_defaults(
+        mut self,
+        mut defaults: serde_json::Map<String, serde_json::Value>,
+    ) -> ZResult<Self> {
+        config::check_storage_defaults(&mut defaults)?;
+        if let (false, Some(obj)) = (defaults.is_empty(), self.admin_status.as_object_mut()) {
+            obj.insert(
+                PROP_BACKEND_STORAGE_DEFAULTS.into(),
+                defaults.clone().into(),
+            );
+        }
+        self.storage_defaults = defaults;
+        Ok(self)
+    }
 }
 
-fn extract_bool(
-    from: &serde_json::Map<String, serde_json::Value>,
-    key: &str,
-    default: bool,
-) -> ZResult<bool> {
-    match from.get(key) {
-        Some(serde_json::Value::Bool(s)) => Ok(*s),
-        None => Ok(default),
-        _ => bail!(
-            r#"Invalid value for File System Storage configuration: `{}` must be a boolean"#,
-            key
+// The time of the `_changed_since` or `_if_modified_since` parameter: a zenoh timestamp (e.g. the one of the last
+// received entry), or an RFC 3339 date-time (e.g. "2024-06-01T12:00:00Z")
+pub(crate) fn parse_time(param: &str, since: &str) -> ZResult<NTP64> {
+    if let Ok(timestamp) = since.parse::<Timestamp>() {
+        return Ok(*timestamp.get_time());
+    }
+    match humantime::parse_rfc3339_weak(since) {
+        Ok(time) => Ok(NTP64::from(
+            time.duration_since(UNIX_EPOCH).unwrap_or_default(),
+        )),
+        Err(_) => bail!(
+            "Invalid value for `{}` parameter: {} - must be a timestamp or an RFC 3339 date-time",
+            param,
+            since
         ),
     }
 }
 
-#[async_trait]
-impl Volume for FileSystemVolume {
-    fn get_admin_status(&self) -> serde_json::Value {
-        self.admin_status.clone()
-    }
-
-    fn get_capability(&self) -> Capability {
-        Capability {
-            persistence: Persistence::Durable,
-            history: History::Latest,
+// The byte range of the `_range` parameter: "<first>-<last>" (the last byte included, as in an HTTP `Range` header),
+// or "<first>-" for the bytes from the first one to the value's end
+fn parse_range(range: &str) -> ZResult<Range<u64>> {
+    let parsed = range.split_once('-').and_then(|(first, last)| {
+        let first = first.trim().parse::<u64>().ok()?;
+        match last.trim() {
+            "" => Some(first..u64::MAX),
+            last => last
+                .parse::<u64>()
+                .ok()
+                .filter(|last| *last >= first)
+                .map(|last| first..last.saturating_add(1)),
         }
+    });
+    match parsed {
+        Some(range) => Ok(range),
+        None => bail!(
+            "Invalid value for `{}` parameter: {} - must be <first>-<last> or <first>- (byte offsets, the last one included)",
+            PARAM_RANGE,
+            range
+        ),
     }
+}
 
-    async fn create_storage(&self, mut config: StorageConfig) -> ZResult<Box<dyn Storage>> {
-        let volume_cfg = match config.volume_cfg.as_object() {
-            Some(v) => v,
-            None => bail!("fs backed volumes require volume-specific configuration"),
-        };
+// The bytes of a value in a range, truncated to the value's size
+fn slice_payload(payload: ZBytes, range: &Range<u64>) -> ZBytes {
+    let bytes = payload.to_bytes();
+    let end = range.end.min(bytes.len() as u64) as usize;
+    let start = range.start.min(end as u64) as usize;
+    bytes[start..end].to_vec().into()
+}
 
-        let read_only = extract_bool(volume_cfg, PROP_STORAGE_READ_ONLY, false)?;
-        let follow_links = extract_bool(volume_cfg, PROP_STORAGE_FOLLOW_LINK, false)?;
-        let keep_mime = extract_bool(volume_cfg, PROP_STORAGE_KEEP_MIME, true)?;
-        let on_closure = match config.volume_cfg.get(PROP_STORAGE_ON_CLOSURE) {
-            Some(serde_json::Value::String(s)) if s == "delete_all" => OnClosure::DeleteAll,
-            Some(serde_json::Value::String(s)) if s == "do_nothing" => OnClosure::DoNothing,
-            None => OnClosure::DoNothing,
-            Some(s) => {
-                bail!(
-                    r#"Unsupported value {:?} for `on_closure` property: must be either "delete_all" or "do_nothing". Default is "do_nothing""#,
-                    s
-                )
-            }
-        };
+// The encoding of a value compressed with gzip: the entry's encoding, with GZIP_SCHEMA appended to its schema
+fn gzip_encoding(encoding: Encoding) -> Encoding {
+    let schema = match encoding.schema() {
+        Some(schema) => format!("{}+{}", String::from_utf8_lossy(schema), GZIP_SCHEMA),
+        None => GZIP_SCHEMA.to_string(),
+    };
+    encoding.with_schema(schema)
+}
 
-        let base_dir =
-            if let Some(serde_json::Value::String(dir)) = config.volume_cfg.get(PROP_STORAGE_DIR) {
-                let dir_path = PathBuf::from(dir.as_str());
-                if dir_path.is_absolute() {
-                    bail!(
-                        r#"Invalid property "{}"="{}": the path must be relative"#,
-                        PROP_STORAGE_DIR,
-                        dir
-                    );
-                }
-                if dir_path
-                    .components()
-                    .any(|c| c == std::path::Component::ParentDir)
-                {
-                    bail!(
-                        r#"Invalid property "{}"="{}": the path must not contain any '..'"#,
-                        PROP_STORAGE_DIR,
-                        dir
-                    );
-                }
+// Whether a PUT's encoding has the `_unpack=true` parameter in its schema
+fn is_unpack_request(encoding: &Encoding) -> bool {
+    encoding.schema().is_some_and(|schema| {
+        Parameters::from(String::from_utf8_lossy(schema).as_ref()).get(PARAM_UNPACK) == Some("true")
+    })
+}
 
-                // prepend base_dir with self.root
-                let mut base_dir = self.root.clone();
-                base_dir.push(dir_path);
-                base_dir
-            } else {
-                bail!(
-                    r#"Missing required property for File System Storage: "{}""#,
-                    PROP_STORAGE_DIR
-                )
-            };
+// The program and first arguments of a command, none of them being empty
+fn extract_command(command: Option<&FsCommandConfig>, key: &str) -> ZResult<Option<HookCommand>> {
+    let args = match command {
+        Some(FsCommandConfig::Program(program)) => vec![program.clone()],
+        Some(FsCommandConfig::Args(args)) => args.clone(),
+        None => return Ok(None),
+    };
+    if args.is_empty() || args.iter().any(String::is_empty) {
+        bail!(
+            r#"Invalid value for File System Storage configuration: `{}` must be a non-empty string or array of strings"#,
+            key
+        )
+    }
+    Ok(Some(args))
+}
 
-        // check if base_dir exists and is readable (and writeable if not "read_only" mode)
-        let mut dir_builder = DirBuilder::new();
-        dir_builder.recursive(true);
-        let base_dir_path = PathBuf::from(&base_dir);
-        if !base_dir_path.exists() {
-            if let Err(err) = dir_builder.create(&base_dir) {
-                bail!(
-                    r#"Cannot create File System Storage on "dir"={:?} : {}"#,
-                    base_dir,
-                    err
-                )
-            }
-        } else if !base_dir_path.is_dir() {
+// Parse the `encoding_schemas` property: an object with the encoding schema of the files
+// created without zenoh in each directory (relative to the storage's directory).
+fn extract_encoding_schemas(
+    schemas: Option<&BTreeMap<String, String>>,
+) -> ZResult<Vec<(PathBuf, String)>> {
+    let Some(schemas) = schemas else {
+        return Ok(vec![]);
+    };
+    let mut result = Vec::with_capacity(schemas.len());
+    for (dir, schema) in schemas {
+        let dir_path = PathBuf::from(dir.as_str());
+        if dir_path.is_absolute()
+            || dir_path
+                .components()
+                .any(|c| c == std::path::Component::ParentDir)
+        {
             bail!(
-                r#"Cannot create File System Storage on "dir"={:?} : this is not a directory"#,
-                base_dir
+                r#"Invalid directory "{}" in `{}`: the path must be relative, without any '..'"#,
+                dir,
+                PROP_STORAGE_ENCODING_SCHEMAS
             )
-        } else if let Err(err) = base_dir_path.read_dir() {
+        }
+        result.push((dir_path, schema.clone()));
+    }
+    Ok(result)
+}
+
+// Parse the `encoding_overrides` property: an object with the encoding of the files created without zenoh under
+// each key prefix (relative to the stripped prefix, a trailing "/**" being optional).
+fn extract_encoding_overrides(
+    overrides: Option<&BTreeMap<String, String>>,
+) -> ZResult<Vec<(String, Encoding)>> {
+    let Some(overrides) = overrides else {
+        return Ok(vec![]);
+    };
+    let mut result = Vec::with_capacity(overrides.len());
+    for (prefix, encoding) in overrides {
+        let trimmed = prefix.strip_suffix("/**").unwrap_or(prefix);
+        if !keyexpr::new(trimmed).is_ok_and(|ke| !ke.is_wild() && !trimmed.starts_with('@')) {
             bail!(
-                r#"Cannot create File System Storage on "dir"={:?} : {}"#,
-                base_dir,
-                err
+                r#"Invalid prefix "{}" in `{}`: must be a key expression without wildcard (except a trailing "/**") nor leading '@'"#,
+                prefix,
+                PROP_STORAGE_ENCODING_OVERRIDES
             )
-        } else if !read_only {
-            // try to write a random file
-            let _ = tempfile_in(&base_dir)
-                .map(|mut f| writeln!(f, "test"))
-                .map_err(|err| {
-                    zerror!(
-                        r#"Cannot create writeable File System Storage on "dir"={:?} : {}"#,
-                        base_dir,
-                        err
-                    )
-                })?;
         }
+        result.push((trimmed.to_string(), Encoding::from(encoding.as_str())));
+    }
+    Ok(result)
+}
 
-        config
-            .volume_cfg
-            .as_object_mut()
-            .unwrap()
-            .insert("dir_full_path".into(), base_dir.to_string_lossy().into());
+// The retry policy of a storage's operations: retried on the network file-systems by default
+fn extract_retry_policy(cfg: &FsStorageConfig, nfs_safe: bool) -> ZResult<RetryPolicy> {
+    let default = match nfs_safe {
+        true => RetryPolicy::network(),
+        false => RetryPolicy::none(),
+    };
+    let retries = match cfg.retry_attempts {
+        Some(n) => n,
+        None if cfg.retry_backoff_ms.is_none() && cfg.retry_errors.is_none() => return Ok(default),
+        None => RetryPolicy::network().retries(),
+    };
+    let backoff = cfg
+        .retry_backoff_ms
+        .map(|ms| Duration::from_millis(ms.get() as u64))
+        .unwrap_or(RetryPolicy::DEFAULT_BACKOFF);
+    let classes = match &cfg.retry_errors {
+        Some(names) => names
+            .iter()
+            .map(|name| match ErrorClass::from_name(name) {
+                Some(class) => Ok(class),
+                None => bail!(
+                    r#"Invalid value "{}" in `{}`: must be one of {}"#,
+                    name,
+                    PROP_STORAGE_RETRY_ERRORS,
+                    ErrorClass::ALL.join(", ")
+                ),
+            })
+            .collect::<ZResult<Vec<_>>>()?,
+        None => ErrorClass::ALL
+            .iter()
+            .filter_map(|name| ErrorClass::from_name(name))
+            .collect(),
+    };
+    Ok(RetryPolicy::new(retries, backoff, classes))
+}
 
-        tracing::debug!(
-            "Storage on {} will store files in {}",
-            config.key_expr,
-            base_dir.display()
+// The directory of a storage: `dir` relative to the volume's root
+fn storage_dir(root: &Path, dir: &str) -> ZResult<PathBuf> {
+    let dir_path = PathBuf::from(dir);
+    if dir_path.is_absolute() {
+        bail!(
+            r#"Invalid property "{}"="{}": the path must be relative"#,
+            PROP_STORAGE_DIR,
+            dir
+        );
+    }
+    if dir_path
+        .components()
+        .any(|c| c == std::path::Component::ParentDir)
+    {
+        bail!(
+            r#"Invalid property "{}"="{}": the path must not contain any '..'"#,
+            PROP_STORAGE_DIR,
+            dir
         );
+    }
+    Ok(root.join(dir_path))
+}
 
-        let files_mgr = FilesMgr::new(base_dir, follow_links, keep_mime, on_closure).await?;
-        Ok(Box::new(FileSystemStorage {
-            config,
-            files_mgr,
-            read_only,
-        }))
+// Create the directory of a storage if needed, checking it's readable (and writeable if not "read_only" mode).
+// A permission error is explained (which directory lacks which permission for which user).
+fn prepare_storage_dir(base_dir: &Path, read_only: bool) -> ZResult<()> {
+    let mut dir_builder = DirBuilder::new();
+    dir_builder.recursive(true);
+    if !base_dir.exists() {
+        if let Err(err) = dir_builder.create(base_dir) {
+            bail!(
+                r#"Cannot create File System Storage on "dir"={:?} : {}{}"#,
+                base_dir,
+                err,
+                permissions::diagnose(base_dir, &err, true)
+            )
+        }
+    } else if !base_dir.is_dir() {
+        bail!(
+            r#"Cannot create File System Storage on "dir"={:?} : this is not a directory"#,
+            base_dir
+        )
+    } else if let Err(err) = base_dir.read_dir() {
+        bail!(
+            r#"Cannot create File System Storage on "dir"={:?} : {}{}"#,
+            base_dir,
+            err,
+            permissions::diagnose(base_dir, &err, false)
+        )
+    } else if !read_only {
+        // try to write a random file
+        tempfile_in(base_dir)
+            .and_then(|mut f| writeln!(f, "test"))
+            .map_err(|err| {
+                zerror!(
+                    r#"Cannot create writeable File System Storage on "dir"={:?} : {}{}"#,
+                    base_dir,
+                    err,
+                    permissions::diagnose(base_dir, &err, true)
+                )
+            })?;
     }
+    Ok(())
 }
 
-struct FileSystemStorage {
-    config: StorageConfig,
-    files_mgr: FilesMgr,
-    read_only: bool,
+// The key prefix given to a control operation as `<op>/<prefix>` (empty if only `<op>`), if `op` is `name`
+fn op_prefix<'a>(op: &'a str, name: &str) -> Option<&'a str> {
+    match op.strip_prefix(name)? {
+        "" => Some(""),
+        rest => rest.strip_prefix('/'),
+    }
 }
 
-#[async_trait]
-impl Storage for FileSystemStorage {
-    fn get_admin_status(&self) -> serde_json::Value {
-        self.config.to_json_value()
+// Return true if the string is a single key chunk, without wildcard nor leading '@'
+fn is_plain_chunk(s: &str) -> bool {
+    keyexpr::new(s)
+        .map(|ke| !ke.is_wild() && !s.contains('/') && !s.starts_with('@'))
+        .unwrap_or(false)
+}
+
+// Check the `tenants` property: an object with the configuration of each tenant
+fn extract_tenants(
+    tenants: Option<&BTreeMap<String, FsTenantConfig>>,
+) -> ZResult<Option<Vec<TenantConfig>>> {
+    let Some(tenants) = tenants else {
+        return Ok(None);
+    };
+    let mut result = Vec::with_capacity(tenants.len());
+    for (name, config) in tenants {
+        // the tenant's name is the first chunk of its keys
+        if !is_plain_chunk(name) {
+            bail!(
+                r#"Invalid tenant name "{}" in `{}`: must be a single key chunk, without wildcard nor leading '@'"#,
+                name,
+                PROP_STORAGE_TENANTS
+            )
+        }
+        result.push(TenantConfig {
+            name: name.clone(),
+            max_total_size: config.max_total_size.map(|n| n.get() as u64),
+            retention_days: config.retention_days.map(|n| n.get() as u64),
+        });
     }
+    Ok(Some(result))
+}
 
-    async fn put(
-        &mut self,
-        key: Option<OwnedKeyExpr>,
-        payload: ZBytes,
-        encoding: Encoding,
-        timestamp: Timestamp,
-    ) -> ZResult<StorageInsertionResult> {
-        if !self.read_only {
-            if let Some(k) = key {
-                let k = k.as_str();
-                let zfile = self.files_mgr.to_zfile(k);
-                // write file
-                self.files_mgr
-                    .write_file(&zfile, payload.into(), encoding, &timestamp)
-                    .await?;
-                Ok(StorageInsertionResult::Inserted)
-            } else {
-                let zfile = self.files_mgr.to_zfile(ROOT_KEY);
-                // write file
-                self.files_mgr
-                    .write_file(&zfile, payload.into(), encoding, &timestamp)
-                    .await?;
-                Ok(StorageInsertionResult::Inserted)
-            }
-        } else {
-            warn!(
-                "Received PUT for read-only Files System Storage on {:?} - ignored",
-                self.files_mgr.base_dir()
-            );
-            Err("Received update for read-only File System Storage".into())
+// Check the `policies` property: an object with the policy of the keys under each prefix
+fn extract_policies(
+    policies: Option<&BTreeMap<String, FsPolicyConfig>>,
+) -> ZResult<Vec<PolicyConfig>> {
+    let Some(policies) = policies else {
+        return Ok(vec![]);
+    };
+    let mut result = Vec::with_capacity(policies.len());
+    for (prefix, config) in policies {
+        if !keyexpr::new(prefix.as_str()).is_ok_and(|ke| !ke.is_wild() && !prefix.starts_with('@'))
+        {
+            bail!(
+                r#"Invalid prefix "{}" in `{}`: must be a key expression without wildcard nor leading '@'"#,
+                prefix,
+                PROP_STORAGE_POLICIES
+            )
         }
+        result.push(PolicyConfig {
+            prefix: prefix.clone(),
+            transforms: match &config.payload_transforms {
+                Some(transforms) => Some(transforms::extract_transforms(transforms)?),
+                None => None,
+            },
+            max_total_size: config.max_total_size.map(|n| n.get() as u64),
+            retention_days: config.retention_days.map(|n| n.get() as u64),
+        });
     }
+    Ok(result)
+}
 
-    /// Function called for each incoming delete request to this storage.
-    async fn delete(
-        &mut self,
-        key: Option<OwnedKeyExpr>,
-        _timestamp: Timestamp,
-    ) -> ZResult<StorageInsertionResult> {
-        if !self.read_only {
-            if let Some(k) = key {
-                let k = k.as_str();
-                let zfile = self.files_mgr.to_zfile(k);
-                // delete file
-                self.files_mgr.delete_file(&zfile).await?;
-                Ok(StorageInsertionResult::Deleted)
-            } else {
-                let zfile = self.files_mgr.to_zfile(ROOT_KEY);
-                // delete file
-                self.files_mgr.delete_file(&zfile).await?;
-                Ok(StorageInsertionResult::Deleted)
+// Check the `maintenance` property: an object with the schedule of each job.
+// The archival, retention and offload jobs are scheduled by default when their period (or cold tier) is configured.
+fn extract_maintenance_jobs(
+    schedules: Option<&BTreeMap<String, String>>,
+    archive: bool,
+    retention: bool,
+    offload: bool,
+) -> ZResult<Vec<(Job, CronSchedule)>> {
+    let no_schedules = BTreeMap::new();
+    let schedules = schedules.unwrap_or(&no_schedules);
+    if let Some(name) = schedules
+        .keys()
+        .find(|name| !Job::ALL.iter().any(|job| job.name() == *name))
+    {
+        bail!(
+            r#"Invalid value for File System Storage configuration: unknown maintenance job `{}` (supported jobs: {})"#,
+            name,
+            Job::ALL.map(|job| job.name()).join(", ")
+        )
+    }
+    let mut jobs = Vec::new();
+    for job in Job::ALL {
+        let schedule = match schedules.get(job.name()) {
+            Some(s) => CronSchedule::parse(s)?,
+            None if (job == Job::Archival && archive)
+                || (job == Job::Retention && retention)
+                || (job == Job::Offload && offload) =>
+            {
+                CronSchedule::parse(DEFAULT_MAINTENANCE_SCHEDULE)?
             }
-        } else {
+            None => continue,
+        };
+        if job == Job::Archival && !archive {
+            bail!(
+                r#"The `{}` maintenance job requires the `{}` property"#,
+                job,
+                PROP_STORAGE_ARCHIVE_AFTER_DAYS
+            )
+        }
+        if job == Job::Retention && !retention {
+            bail!(
+                r#"The `{}` maintenance job requires the `{}` property"#,
+                job,
+                PROP_STORAGE_RETENTION_DAYS
+            )
+        }
+        if job == Job::Offload && !offload {
+            bail!(
+                r#"The `{}` maintenance job requires the `{}` property"#,
+                job,
+                PROP_STORAGE_COLD_TIER_DIR
+            )
+        }
+        jobs.push((job, schedule));
+    }
+    Ok(jobs)
+}
+
+// Scan the files of a storage at its creation: account them in the quotas, and verify the data-info if requested
+async fn scan_on_start(
+    files_mgr: &FilesMgr,
+    progress: &InitProgress,
+    verify_on_start: bool,
+) -> ZResult<()> {
+    progress.phase("quota");
+    files_mgr.account_existing_files(progress);
+    progress.phase("watermark");
+    files_mgr.init_watermark().await?;
+    if verify_on_start {
+        progress.phase("verify_on_start");
+        let removed = files_mgr.verify_data_info(Some(progress)).await?;
+        if removed > 0 {
             warn!(
-                "Received DELETE for read-only Files System Storage on {:?} - ignored",
-                self.files_mgr.base_dir()
+                "Storage on {:?}: removed {} corrupted or orphan data-info entries",
+                files_mgr.base_dir(),
+                removed
+            );
+        } else {
+            debug!(
+                "Storage on {:?}: all data-info entries verified",
+                files_mgr.base_dir()
             );
-            Err("Received update for read-only File System Storage".into())
         }
     }
+    Ok(())
+}
 
-    /// Function to retrieve the sample associated with a single key.
-    async fn get(
-        &mut self,
-        key: Option<OwnedKeyExpr>,
-        _parameters: &str,
-    ) -> ZResult<Vec<StoredData>> {
-        if key.is_some() {
-            let k = key.clone().unwrap();
-            let k = k.as_str();
-            let zfile = self.files_mgr.to_zfile(k);
-            match self.files_mgr.read_file(&zfile).await {
-                Ok(Some((payload, encoding, timestamp))) => Ok(vec![StoredData {
-                    payload,
-                    encoding,
-                    timestamp,
-                }]),
-                Ok(None) => Ok(vec![]),
-                Err(e) => {
-                    Err(format!("Get key {:?} : failed to read file {} : {}", key, zfile, e).into())
-                }
+// The initialization of a storage with `lazy_init`, in background once the storage is created: the scans of
+// `scan_on_start`, then the loading of the entries index (if enabled).
+async fn warm_up(
+    files_mgr: Weak<FilesMgr>,
+    progress: &InitProgress,
+    verify_on_start: bool,
+    empty_as_deleted: bool,
+) -> ZResult<()> {
+    let Some(mgr) = FilesMgr::upgrade(&files_mgr) else {
+        return Ok(());
+    };
+    scan_on_start(&mgr, progress, verify_on_start).await?;
+    if mgr.begin_index_load() {
+        progress.phase("entries_index");
+        // all the entries are indexed, the ACL being checked on listing
+        let walked = walk_entries(&mgr, None, empty_as_deleted, |_| true).await;
+        mgr.end_index_load(walked.as_deref().ok());
+        walked?;
+    }
+    progress.complete();
+    Ok(())
+}
+
+// Walk the files (and the archive bundles) of a FilesMgr, returning the zpath and timestamp of each entry
+// (under `subtree` if set and readable), the root entry being returned as ROOT_KEY
+async fn walk_entries(
+    files_mgr: &FilesMgr,
+    subtree: Option<&keyexpr>,
+    empty_as_deleted: bool,
+    is_readable: impl Fn(Option<&keyexpr>) -> bool + Sync,
+) -> ZResult<Vec<(String, Timestamp)>> {
+    let mut result = Vec::new();
+    let selector = match subtree {
+        Some(key) => OwnedKeyExpr::try_from(format!("{key}/**"))?,
+        None => OwnedKeyExpr::try_from("**")?,
+    };
+    // Add the root entry if it exists.
+    // Root key can't be acuired from `matching_files` call, as its file name ("@root") is reserved:
+    // the keys with chunks starting with '@' are stored in files with an extra leading '@'
+    if subtree.is_none() {
+        if let Some((payload, _, timestamp)) = files_mgr.read_file(&files_mgr.root_zfile()).await? {
+            if !(empty_as_deleted && payload.is_empty())
+                && files_mgr.is_tenant_key(ROOT_KEY)
+                && is_readable(None)
+            {
+                result.push((ROOT_KEY.to_string(), timestamp));
             }
-        } else {
-            let zfile = self.files_mgr.to_zfile(ROOT_KEY);
-            match self.files_mgr.read_file(&zfile).await {
-                Ok(Some((payload, encoding, timestamp))) => Ok(vec![StoredData {
-                    payload,
-                    encoding,
-                    timestamp,
-                }]),
-                Ok(None) => Ok(vec![]),
-                Err(e) => {
-                    Err(format!("Get key {:?} : failed to read file {} : {}", key, zfile, e).into())
-                }
+        }
+    }
+    // Get all files in the filesystem (the root key file being skipped)
+    for zfile in files_mgr
+        .matching_files(&selector)
+        .filter(|zfile| files_mgr.is_tenant_key(&zfile.zpath))
+        .filter(|zfile| keyexpr::new(zfile.zpath.as_ref()).is_ok_and(|ke| is_readable(Some(ke))))
+    {
+        let trimmed_zpath = get_trimmed_keyexpr(zfile.zpath.as_ref());
+        let trimmed_zfile = files_mgr.to_zfile(trimmed_zpath);
+        match files_mgr.read_file(&trimmed_zfile).await {
+            Ok(Some((payload, _, _))) if (empty_as_deleted && payload.is_empty()) => (),
+            Ok(Some((_, _, timestamp))) => {
+                result.push((zfile.zpath.into_owned(), timestamp));
             }
+            Ok(None) => (), // file not found, do nothing
+            Err(e) => warn!(
+                "Getting all entries : failed to read file {} : {}",
+                zfile, e
+            ),
         }
     }
-
-    async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
-        let mut result = Vec::new();
-        // Add the root entry if it exists.
-        // Root key can't be acuired from `matching_files` call
-        // because it's name is specially chosen to be not allowed as key value ("@root")
-        if let Some((_, _, timestamp)) = self
-            .files_mgr
-            .read_file(&self.files_mgr.to_zfile(ROOT_KEY))
-            .await?
+    // Add the entries moved into archive bundles
+    for (zpath, timestamp) in files_mgr.archived_entries().await? {
+        if files_mgr.is_tenant_key(&zpath)
+            && keyexpr::new(zpath.as_str())
+                .is_ok_and(|ke| selector.intersects(ke) && is_readable(Some(ke)))
         {
-            result.push((None, timestamp));
+            result.push((zpath, timestamp));
         }
-        // Get all files in the filesystem.
-        // Also skip the root key file which was already added above.
-        // This is just for completeness, it's skipped anyway due to it's name starting from '@'
-        for zfile in self
+    }
+    Ok(result)
+}
+
+#[async_trait]
+impl Volume for FileSystemVolume {
+    fn get_admin_status(&self) -> serde_json::Value {
+        let mut status = self.admin_status.clone();
+        if let (Some(quota), Some(obj)) = (&self.quota, status.as_object_mut()) {
+            obj.insert(PROP_BACKEND_MAX_TOTAL_SIZE.into(), quota.max().into());
+            obj.insert("total_size".into(), quota.used().into());
+        }
+        if let Some(obj) = status.as_object_mut() {
+            obj.insert("storages".into(), self.open_storages().len().into());
+            match FsInfo::of(&self.root) {
+                Ok(info) => info.insert_into(obj),
+                Err(e) => debug!(
+                    "Failed to get the file-system information of the volume: {}",
+                    e
+                ),
+            }
+        }
+        let initializing = self.initializing.lock().unwrap();
+        if let (false, Some(obj)) = (initializing.is_empty(), status.as_object_mut()) {
+            obj.insert(
+                "initializing".into(),
+                initializing
+                    .iter()
+                    .map(|(name, progress)| (name.clone(), progress.status_json()))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into(),
+            );
+        }
+        status
+    }
+
+    fn get_capability(&self) -> Capability {
+        Capability {
+            persistence: Persistence::Durable,
+            history: History::Latest,
+        }
+    }
+
+    async fn create_storage(&self, config: StorageConfig) -> ZResult<Box<dyn Storage>> {
+        self.create_custom_storage(config, CustomExtensions::default())
+            .await
+    }
+}
+
+impl FileSystemVolume {
+    /// Close the storages of the volume which weren't dropped yet, as on the unload of the backend: wait (for a
+    /// while) for their background tasks and their mirror, then flush them (or delete them if `on_closure` is
+    /// "delete_all"). The storages dropped without being closed are only closed on a best effort.
+    pub async fn close(&self) {
+        for handle in self.open_storages() {
+            handle.close().await;
+        }
+    }
+
+    // The handles of the storages which weren't dropped yet
+    fn open_storages(&self) -> Vec<Arc<StorageHandle>> {
+        let mut storages = self.storages.lock().unwrap();
+        storages.retain(|handle| handle.strong_count() > 0);
+        storages.iter().filter_map(Weak::upgrade).collect()
+    }
+
+    // Create a storage, with custom payload transforms and interceptors chained after the configured ones
+    pub(crate) async fn create_custom_storage(
+        &self,
+        config: StorageConfig,
+        custom: CustomExtensions,
+    ) -> ZResult<Box<dyn Storage>> {
+        // the progress is reported in the volume's admin status until the storage is created
+        let name = config.name.clone();
+        let progress = Arc::new(InitProgress::new(&name));
+        self.initializing
+            .lock()
+            .unwrap()
+            .insert(name.clone(), progress.clone());
+        let result = self.init_storage(config, custom, progress).await;
+        self.initializing.lock().unwrap().remove(&name);
+        result
+    }
+
+    async fn init_storage(
+        &self,
+        mut config: StorageConfig,
+        custom: CustomExtensions,
+        progress: Arc<InitProgress>,
+    ) -> ZResult<Box<dyn Storage>> {
+        match config.volume_cfg.as_object_mut() {
+            Some(v) => {
+                config::resolve_deprecated_properties(&config.name, v)?;
+                for (key, value) in &self.storage_defaults {
+                    if !v.contains_key(key) {
+                        debug!(
+                            "File System Storage {}: property \"{}\" inherited from the volume",
+                            config.name, key
+                        );
+                        v.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            None => bail!("fs backed volumes require volume-specific configuration"),
+        };
+        let volume_cfg = match config.volume_cfg.as_object() {
+            Some(v) => v,
+            None => bail!("fs backed volumes require volume-specific configuration"),
+        };
+        let cfg = FsStorageConfig::from_json(volume_cfg)?;
+
+        let strict = cfg.strict.unwrap_or(true);
+        config::check_properties(&config.name, volume_cfg, strict)?;
+
+        let read_only = cfg.read_only.unwrap_or(false);
+        let worm = cfg.worm.unwrap_or(false);
+        let set_immutable = cfg.set_immutable.unwrap_or(false);
+        let follow_links = cfg.follow_links.unwrap_or(false);
+        let max_link_depth = cfg.max_link_depth.map(NonZeroUsize::get);
+        if max_link_depth.is_some() && !follow_links {
+            warn!(
+                "`{}` ignored: the symbolic links aren't followed (`{}` isn't true)",
+                PROP_STORAGE_MAX_LINK_DEPTH, PROP_STORAGE_FOLLOW_LINK
+            );
+        }
+        let keep_mime = cfg.keep_mime_types.unwrap_or(true);
+        #[cfg(not(feature = "mime-guess"))]
+        if cfg.keep_mime_types == Some(true) {
+            warn!(
+                "`{}` ignored: this backend was built without the \"mime-guess\" feature",
+                PROP_STORAGE_KEEP_MIME
+            );
+        }
+        let verify_on_start = cfg.verify_on_start.unwrap_or(false);
+        let dry_run = cfg.dry_run.unwrap_or(false);
+        if dry_run && verify_on_start {
+            warn!(
+                "`{}` ignored in dry run, as it may remove data-info",
+                PROP_STORAGE_VERIFY_ON_START
+            );
+        }
+        let verify_on_start = verify_on_start && !dry_run;
+        let lazy_init = cfg.lazy_init.unwrap_or(false);
+        let dedicated_runtime_threads = cfg.dedicated_runtime_threads.map(NonZeroUsize::get);
+        let max_concurrent_ops = cfg.max_concurrent_ops.map(NonZeroUsize::get);
+        let open_files_cache_size = cfg.open_files_cache_size.map(NonZeroUsize::get);
+        let read_ahead = cfg.read_ahead.map(NonZeroUsize::get);
+        let dir_listings_cache_size = cfg.dir_listings_cache_size.map(NonZeroUsize::get);
+        let nfs_safe = cfg.nfs_safe.unwrap_or(false);
+        let retry = extract_retry_policy(&cfg, nfs_safe)?;
+        let verify_writes = cfg.verify_writes.unwrap_or(false);
+        let log_level = match &cfg.log_level {
+            Some(s) => match storage_log::level_from_name(s) {
+                Some(level) => Some(level),
+                None => bail!(
+                    r#"Unsupported value {:?} for `{}` property: must be one of "{}". Default is zenohd's log level"#,
+                    s,
+                    PROP_STORAGE_LOG_LEVEL,
+                    storage_log::LEVELS.join(r#"", ""#)
+                ),
+            },
+            None => None,
+        };
+        let trace_ops = cfg.trace_ops.unwrap_or(false);
+        if nfs_safe {
+            // the operations unsupported on network file-systems
+            let unsupported = [
+                (PROP_STORAGE_SET_IMMUTABLE, set_immutable),
+                (
+                    PROP_STORAGE_OPEN_FILES_CACHE_SIZE,
+                    open_files_cache_size.is_some(),
+                ),
+                (
+                    PROP_STORAGE_DIR_LISTINGS_CACHE_SIZE,
+                    dir_listings_cache_size.is_some(),
+                ),
+            ];
+            if let Some((name, _)) = unsupported.iter().find(|(_, set)| *set) {
+                bail!(
+                    r#"File System Storage configuration: `{}` can't be set with `{}`=true, as it's unsafe on network file-systems"#,
+                    name,
+                    PROP_STORAGE_NFS_SAFE
+                )
+            }
+        }
+        let data_info_cache_size = cfg.data_info_cache_size.map(NonZeroUsize::get);
+        let bloom_filter = cfg.bloom_filter.unwrap_or(false);
+        let watermark_by_prefix = cfg.watermark_by_prefix.unwrap_or(false);
+        let expose_fs_path = cfg.expose_fs_path.unwrap_or(false);
+        let entries_index = cfg.entries_index.unwrap_or(false);
+        let digest_interval = Duration::from_secs(
+            cfg.digest_interval_secs
+                .map(NonZeroUsize::get)
+                .unwrap_or(DEFAULT_DIGEST_INTERVAL_SECS) as u64,
+        );
+        let archive_after_days = cfg.archive_after_days.map(NonZeroUsize::get);
+        let retention_days = cfg.retention_days.map(NonZeroUsize::get);
+        let cold_tier_dir = match &cfg.cold_tier_dir {
+            Some(s) if Path::new(s).is_absolute() => Some(PathBuf::from(s)),
+            None => None,
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an absolute path"#,
+                PROP_STORAGE_COLD_TIER_DIR
+            ),
+        };
+        let cold_after_days = cfg.cold_after_days.map(NonZeroUsize::get);
+        if cold_after_days.is_some() && cold_tier_dir.is_none() {
+            warn!(
+                "`{}` ignored: `{}` is not set",
+                PROP_STORAGE_COLD_AFTER_DAYS, PROP_STORAGE_COLD_TIER_DIR
+            );
+        }
+        if cold_tier_dir.is_some() && archive_after_days.is_some() {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`, as the aged entries are moved into archive bundles"#,
+                PROP_STORAGE_COLD_TIER_DIR,
+                PROP_STORAGE_ARCHIVE_AFTER_DAYS
+            )
+        }
+        if cold_tier_dir.is_some() && dry_run {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`=true, as the offloaded entries are copied back on read"#,
+                PROP_STORAGE_COLD_TIER_DIR,
+                PROP_STORAGE_DRY_RUN
+            )
+        }
+        let tenants = extract_tenants(cfg.tenants.as_ref())?;
+        let policies = extract_policies(cfg.policies.as_ref())?;
+        let acl = match &cfg.acl {
+            Some(acl) => Some(Arc::new(Acl::new(
+                PROP_STORAGE_ACL,
+                acl,
+                config.strip_prefix.clone(),
+            )?)),
+            None => None,
+        };
+        let tenants_retention = tenants.iter().flatten().any(|t| t.retention_days.is_some())
+            || policies.iter().any(|p| p.retention_days.is_some());
+        if worm && (retention_days.is_some() || tenants_retention) {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`=true, as the entries can't be deleted"#,
+                PROP_STORAGE_RETENTION_DAYS,
+                PROP_STORAGE_WORM
+            )
+        }
+        let maintenance_jobs = extract_maintenance_jobs(
+            cfg.maintenance.as_ref(),
+            archive_after_days.is_some(),
+            retention_days.is_some() || tenants_retention,
+            cold_tier_dir.is_some(),
+        )?;
+        let shm_pool_size = cfg.shm_pool_size.map(NonZeroUsize::get);
+        #[cfg(feature = "shared-memory")]
+        let shm_threshold = cfg
+            .shm_threshold
+            .map(NonZeroUsize::get)
+            .unwrap_or(DEFAULT_SHM_THRESHOLD);
+        #[cfg(feature = "fault-injection")]
+        let faults = match &cfg.fault_injection {
+            Some(v) => Some(Arc::new(faults::Faults::from_config(v)?)),
+            None => None,
+        };
+        #[cfg(not(feature = "fault-injection"))]
+        if cfg.fault_injection.is_some() {
+            warn!(
+                "`{}` ignored: this backend was built without the \"fault-injection\" feature",
+                PROP_STORAGE_FAULT_INJECTION
+            );
+        }
+        let default_encoding = cfg.default_encoding.as_deref().map(Encoding::from);
+        let encoding_schemas = extract_encoding_schemas(cfg.encoding_schemas.as_ref())?;
+        let encoding_overrides = extract_encoding_overrides(cfg.encoding_overrides.as_ref())?;
+        let timestamp_id = match &cfg.timestamp_id {
+            Some(s) => match s.parse::<TimestampId>() {
+                Ok(id) => Some(id),
+                Err(_) => bail!(
+                    r#"Invalid value for File System Storage configuration: `{}` must be a non-zero hexadecimal id of 1 to 16 bytes, without leading 0"#,
+                    PROP_STORAGE_TIMESTAMP_ID
+                ),
+            },
+            None => None,
+        };
+        let envelope = cfg.envelope.unwrap_or(false);
+        let passthrough = cfg.passthrough.unwrap_or(false);
+        let mut payload_transforms =
+            transforms::extract_transforms(cfg.payload_transforms.as_deref().unwrap_or_default())?;
+        payload_transforms.extend(custom.transforms);
+        let mut interceptors = interceptors::extract_interceptors(
+            &config.name,
+            cfg.interceptors.as_deref().unwrap_or_default(),
+        )?;
+        interceptors.extend(custom.interceptors);
+        let obfuscate_names = cfg.obfuscate_names.unwrap_or(false);
+        let canonicalize_keys = cfg.canonicalize_keys.unwrap_or(false);
+        let zenoh_write_only = cfg.zenoh_write_only.unwrap_or(false);
+        let control_prefix = match &cfg.control_prefix {
+            Some(s) if is_plain_chunk(s) => Some(s.clone()),
+            None => None,
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be a single key chunk, without wildcard nor leading '@'"#,
+                PROP_STORAGE_CONTROL_PREFIX
+            ),
+        };
+        let export_dir = match &cfg.export_dir {
+            Some(s) if Path::new(s).is_absolute() => Some(PathBuf::from(s)),
+            None => None,
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an absolute path"#,
+                PROP_STORAGE_EXPORT_DIR
+            ),
+        };
+        let import_roots: Vec<PathBuf> = match &cfg.import_roots {
+            Some(dirs) if dirs.iter().all(|s| Path::new(s).is_absolute()) => {
+                dirs.iter().map(PathBuf::from).collect()
+            }
+            None => vec![],
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an array of absolute paths"#,
+                PROP_STORAGE_IMPORT_ROOTS
+            ),
+        };
+        let max_unpack_size = cfg
+            .max_unpack_size
+            .map(NonZeroUsize::get)
+            .unwrap_or(DEFAULT_MAX_UNPACK_SIZE) as u64;
+        let max_patched_size = cfg
+            .max_patched_size
+            .map(NonZeroUsize::get)
+            .unwrap_or(DEFAULT_MAX_PATCHED_SIZE);
+        let notification_key_expr = match &cfg.notification_key_expr {
+            Some(s) => match OwnedKeyExpr::autocanonize(s.clone()) {
+                Ok(ke) if !ke.is_wild() => Some(ke),
+                _ => bail!(
+                    r#"Invalid value for File System Storage configuration: `{}` must be a key expression without wildcard"#,
+                    PROP_STORAGE_NOTIFICATION_KEY_EXPR
+                ),
+            },
+            None => None,
+        };
+        let stats_key_expr = match &cfg.stats_key_expr {
+            Some(s) => match OwnedKeyExpr::autocanonize(s.clone()) {
+                Ok(ke) if !ke.is_wild() => Some(ke),
+                _ => bail!(
+                    r#"Invalid value for File System Storage configuration: `{}` must be a key expression without wildcard"#,
+                    PROP_STORAGE_STATS_KEY_EXPR
+                ),
+            },
+            None => None,
+        };
+        let stats_interval = Duration::from_secs(
+            cfg.stats_interval_secs
+                .map(NonZeroUsize::get)
+                .unwrap_or(DEFAULT_STATS_INTERVAL_SECS) as u64,
+        );
+        let notification_connect = match &cfg.notification_connect {
+            Some(endpoints) if !endpoints.is_empty() => endpoints.clone(),
+            None => vec![],
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be a non-empty array of strings"#,
+                PROP_STORAGE_NOTIFICATION_CONNECT
+            ),
+        };
+        // (no endpoint is assumed, the publications being otherwise silently lost if nothing listens on it)
+        for (prop, is_set) in [
+            (
+                PROP_STORAGE_NOTIFICATION_KEY_EXPR,
+                notification_key_expr.is_some(),
+            ),
+            (PROP_STORAGE_STATS_KEY_EXPR, stats_key_expr.is_some()),
+        ] {
+            if is_set && notification_connect.is_empty() {
+                bail!(
+                    r#"File System Storage configuration: `{}` requires `{}` to be set to the endpoints of a zenoh router to publish to, e.g. ["tcp/localhost:7447"]"#,
+                    prop,
+                    PROP_STORAGE_NOTIFICATION_CONNECT
+                )
+            }
+        }
+        let on_put = extract_command(cfg.on_put.as_ref(), PROP_STORAGE_ON_PUT)?;
+        let on_delete = extract_command(cfg.on_delete.as_ref(), PROP_STORAGE_ON_DELETE)?;
+        let obfuscation_salt = cfg.obfuscation_salt.clone();
+        if obfuscation_salt.is_some() && !obfuscate_names {
+            warn!(
+                "`{}` ignored: `{}` is not set to true",
+                PROP_STORAGE_OBFUSCATION_SALT, PROP_STORAGE_OBFUSCATE_NAMES
+            );
+        }
+        let on_closure = match cfg.on_closure.as_deref() {
+            Some("delete_all") => OnClosure::DeleteAll,
+            Some("do_nothing") => OnClosure::DoNothing,
+            None => OnClosure::DoNothing,
+            Some(s) => {
+                bail!(
+                    r#"Unsupported value {:?} for `on_closure` property: must be either "delete_all" or "do_nothing". Default is "do_nothing""#,
+                    s
+                )
+            }
+        };
+        let on_conflict = match &cfg.on_conflict {
+            Some(s) => match OnConflict::from_name(s) {
+                Some(on_conflict) => on_conflict,
+                None => bail!(
+                    r#"Unsupported value {:?} for `{}` property: must be either "rename", "reject" or "overwrite". Default is "rename""#,
+                    s,
+                    PROP_STORAGE_ON_CONFLICT
+                ),
+            },
+            // in passthrough mode, the existing files are never renamed
+            None if passthrough => OnConflict::Reject,
+            None => OnConflict::Rename,
+        };
+        if passthrough {
+            let altering = [
+                (PROP_STORAGE_ENVELOPE, envelope),
+                (
+                    PROP_STORAGE_PAYLOAD_TRANSFORMS,
+                    !payload_transforms.is_empty()
+                        || policies
+                            .iter()
+                            .any(|p| p.transforms.as_ref().is_some_and(|t| !t.is_empty())),
+                ),
+                (PROP_STORAGE_OBFUSCATE_NAMES, obfuscate_names),
+                (
+                    PROP_STORAGE_ARCHIVE_AFTER_DAYS,
+                    archive_after_days.is_some(),
+                ),
+                (PROP_STORAGE_SET_IMMUTABLE, set_immutable),
+                (PROP_STORAGE_COLD_TIER_DIR, cold_tier_dir.is_some()),
+                (
+                    PROP_STORAGE_ON_CLOSURE,
+                    matches!(on_closure, OnClosure::DeleteAll),
+                ),
+                (PROP_STORAGE_ON_CONFLICT, on_conflict != OnConflict::Reject),
+            ];
+            if let Some((name, _)) = altering.iter().find(|(_, set)| *set) {
+                bail!(
+                    r#"File System Storage configuration: `{}` can't be set with `{}`=true, as it alters the files"#,
+                    name,
+                    PROP_STORAGE_PASSTHROUGH
+                )
+            }
+        }
+        let stripes: Vec<PathBuf> = match &cfg.stripes {
+            Some(dirs) if dirs.iter().all(|s| Path::new(s).is_absolute()) => {
+                dirs.iter().map(PathBuf::from).collect()
+            }
+            None => vec![],
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an array of absolute paths"#,
+                PROP_STORAGE_STRIPES
+            ),
+        };
+        if passthrough && !stripes.is_empty() {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`=true, as the files are written by other means"#,
+                PROP_STORAGE_STRIPES,
+                PROP_STORAGE_PASSTHROUGH
+            )
+        }
+        let mirror_dir = match &cfg.mirror_dir {
+            Some(s) if Path::new(s).is_absolute() => Some(PathBuf::from(s)),
+            None => None,
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an absolute path"#,
+                PROP_STORAGE_MIRROR_DIR
+            ),
+        };
+        if passthrough && mirror_dir.is_some() {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`=true, as the files are written by other means"#,
+                PROP_STORAGE_MIRROR_DIR,
+                PROP_STORAGE_PASSTHROUGH
+            )
+        }
+        let failover_dir = match &cfg.failover_dir {
+            Some(s) if Path::new(s).is_absolute() => Some(PathBuf::from(s)),
+            None => None,
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an absolute path"#,
+                PROP_STORAGE_FAILOVER_DIR
+            ),
+        };
+        let lower_dir = match &cfg.lower_dir {
+            Some(s) if Path::new(s).is_absolute() => Some(PathBuf::from(s)),
+            None => None,
+            _ => bail!(
+                r#"Invalid value for File System Storage configuration: `{}` must be an absolute path"#,
+                PROP_STORAGE_LOWER_DIR
+            ),
+        };
+        if obfuscate_names && failover_dir.is_some() {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`=true, as the names of the files are mapped in the storage's data-info"#,
+                PROP_STORAGE_FAILOVER_DIR,
+                PROP_STORAGE_OBFUSCATE_NAMES
+            )
+        }
+        if passthrough && zenoh_write_only {
+            bail!(
+                r#"File System Storage configuration: `{}` can't be set with `{}`=true, as the files are written by other means"#,
+                PROP_STORAGE_ZENOH_WRITE_ONLY,
+                PROP_STORAGE_PASSTHROUGH
+            )
+        }
+
+        let read_only_behavior = match cfg.read_only_behavior.as_deref() {
+            Some("error") => ReadOnlyBehavior::Error,
+            Some("silently_ignore") => ReadOnlyBehavior::SilentlyIgnore,
+            Some("log_once") => ReadOnlyBehavior::LogOnce,
+            None => ReadOnlyBehavior::Error,
+            Some(s) => {
+                bail!(
+                    r#"Unsupported value {:?} for `{}` property: must be either "error", "silently_ignore" or "log_once". Default is "error""#,
+                    s,
+                    PROP_STORAGE_READ_ONLY_BEHAVIOR
+                )
+            }
+        };
+        let empty_payload = match cfg.empty_payload.as_deref() {
+            Some("store") => EmptyPayload::Store,
+            Some("reject") => EmptyPayload::Reject,
+            Some("delete") => EmptyPayload::Delete,
+            None => EmptyPayload::Store,
+            Some(s) => {
+                bail!(
+                    r#"Unsupported value {:?} for `{}` property: must be either "store", "reject" or "delete". Default is "store""#,
+                    s,
+                    PROP_STORAGE_EMPTY_PAYLOAD
+                )
+            }
+        };
+        let skew_policy = match &cfg.skewed_timestamps {
+            Some(s) => match SkewPolicy::from_name(s) {
+                Some(policy) => policy,
+                None => bail!(
+                    r#"Unsupported value {:?} for `{}` property: must be either "accept", "clamp_to_now" or "reject". Default is "accept""#,
+                    s,
+                    PROP_STORAGE_SKEWED_TIMESTAMPS
+                ),
+            },
+            None => SkewPolicy::Accept,
+        };
+        let write_queue_overload = match &cfg.write_queue_overload {
+            Some(s) => match OverloadPolicy::from_name(s) {
+                Some(policy) => policy,
+                None => bail!(
+                    r#"Unsupported value {:?} for `{}` property: must be either "block", "drop_oldest" or "error". Default is "block""#,
+                    s,
+                    PROP_STORAGE_WRITE_QUEUE_OVERLOAD
+                ),
+            },
+            None => OverloadPolicy::Block,
+        };
+        let write_queue = cfg
+            .write_queue_depth
+            .map(NonZeroUsize::get)
+            .map(|depth| (depth, write_queue_overload));
+        let timestamp_skew = TimestampSkew::new(
+            skew_policy,
+            Duration::from_secs(
+                cfg.max_future_skew_secs
+                    .map(NonZeroUsize::get)
+                    .unwrap_or(DEFAULT_MAX_FUTURE_SKEW_SECS) as u64,
+            ),
+            cfg.max_past_skew_secs
+                .map(NonZeroUsize::get)
+                .map(|secs| Duration::from_secs(secs as u64)),
+        );
+
+        let base_dir = match &cfg.dir {
+            Some(dir) => storage_dir(&self.root, dir)?,
+            _ => bail!(
+                r#"Missing required property for File System Storage: "{}""#,
+                PROP_STORAGE_DIR
+            ),
+        };
+        prepare_storage_dir(&base_dir, read_only)?;
+        // in passthrough mode, the data-info database is kept out of the storage's directory
+        let passthrough = match passthrough {
+            true => {
+                let dbs_dir = self.root.join(PASSTHROUGH_DATA_INFO_DIR);
+                if dbs_dir.starts_with(&base_dir) || base_dir.starts_with(&dbs_dir) {
+                    bail!(
+                        r#"Invalid property "{}" with `{}`=true: the directory must not contain (nor be in) {:?}"#,
+                        PROP_STORAGE_DIR,
+                        PROP_STORAGE_PASSTHROUGH,
+                        dbs_dir
+                    )
+                }
+                Some(dbs_dir.join(base_dir.strip_prefix(&self.root).unwrap_or(&base_dir)))
+            }
+            false => None,
+        };
+
+        if let Some(dir) = &lower_dir {
+            if dir.starts_with(&base_dir) || base_dir.starts_with(dir) {
+                bail!(
+                    r#"Invalid property "{}": {:?} must not contain (nor be in) the storage's directory"#,
+                    PROP_STORAGE_LOWER_DIR,
+                    dir
+                )
+            }
+        }
+        let failover = match failover_dir {
+            Some(dir) if dir.starts_with(&base_dir) || base_dir.starts_with(&dir) => bail!(
+                r#"Invalid property "{}": {:?} must not contain (nor be in) the storage's directory"#,
+                PROP_STORAGE_FAILOVER_DIR,
+                dir
+            ),
+            Some(dir) => {
+                let data_info_dir = self
+                    .root
+                    .join(FAILOVER_DATA_INFO_DIR)
+                    .join(base_dir.strip_prefix(&self.root).unwrap_or(&base_dir));
+                Some(Failover::new(dir, data_info_dir))
+            }
+            None => None,
+        };
+
+        // reject a directory nested with the one of another storage
+        let dir_guard = self.storage_dirs.register(&config.name, &base_dir)?;
+        // (or with another stripe)
+        let mut stripe_guards = Vec::with_capacity(stripes.len());
+        let stripes = stripes
+            .iter()
+            .map(|dir| {
+                prepare_storage_dir(dir, read_only)?;
+                stripe_guards.push(self.storage_dirs.register(&config.name, dir)?);
+                dunce::canonicalize(dir)
+                    .map_err(|e| zerror!("Invalid stripe {:?}: {}", dir, e).into())
+            })
+            .collect::<ZResult<Vec<PathBuf>>>()?;
+        // (or with the mirror directory)
+        let mirror = match mirror_dir {
+            Some(dir) => {
+                prepare_storage_dir(&dir, false)?;
+                stripe_guards.push(self.storage_dirs.register(&config.name, &dir)?);
+                let dir = dunce::canonicalize(&dir)
+                    .map_err(|e| zerror!("Invalid mirror directory {:?}: {}", dir, e))?;
+                Some(Arc::new(Mirror::new(dir)))
+            }
+            None => None,
+        };
+        // (or with the cold tier's directory)
+        let cold_tier = match cold_tier_dir {
+            Some(dir) => {
+                prepare_storage_dir(&dir, false)?;
+                stripe_guards.push(self.storage_dirs.register(&config.name, &dir)?);
+                let dir = dunce::canonicalize(&dir)
+                    .map_err(|e| zerror!("Invalid cold tier directory {:?}: {}", dir, e))?;
+                let after_days = cold_after_days.unwrap_or(DEFAULT_COLD_AFTER_DAYS);
+                Some(Arc::new(ColdTier::new(dir, after_days as u64)))
+            }
+            None => None,
+        };
+
+        config
+            .volume_cfg
+            .as_object_mut()
+            .unwrap()
+            .insert("dir_full_path".into(), base_dir.to_string_lossy().into());
+
+        tracing::debug!(
+            "Storage on {} will store files in {}",
+            config.key_expr,
+            base_dir.display()
+        );
+
+        #[cfg(not(feature = "shared-memory"))]
+        if shm_pool_size.is_some() {
+            warn!(
+                "`{}` ignored: this backend was built without the \"shared-memory\" feature",
+                PROP_STORAGE_SHM_POOL_SIZE
+            );
+        }
+        let runtime = match dedicated_runtime_threads {
+            Some(threads) => Some(Arc::new(StorageRuntime::new(&config.name, threads)?)),
+            None => None,
+        };
+        let files_mgr_settings = FilesMgrSettings {
+            follow_links,
+            max_link_depth,
+            stripes,
+            keep_mime,
+            on_closure,
+            on_conflict,
+            max_concurrent_ops,
+            open_files_cache_size,
+            read_ahead,
+            data_info_cache_size,
+            dir_listings_cache_size,
+            bloom_filter,
+            watermark_by_prefix,
+            obfuscate_names,
+            obfuscation_salt,
+            #[cfg(feature = "shared-memory")]
+            shm: shm_pool_size.map(|pool_size| (pool_size, shm_threshold)),
+            archive_after_days,
+            retention_days,
+            quota: self.quota.clone(),
+            tenants,
+            policies,
+            canonicalize_keys,
+            zenoh_write_only,
+            default_encoding,
+            timestamp_id,
+            immutable: set_immutable && !read_only,
+            encoding_schemas,
+            encoding_overrides,
+            entries_index,
+            payload_transforms,
+            envelope,
+            passthrough,
+            #[cfg(feature = "fault-injection")]
+            faults,
+            runtime: runtime.as_ref().map(|runtime| runtime.handle()),
+            write_queue,
+            nfs_safe,
+            retry,
+            verify_writes,
+            dry_run,
+            read_only,
+            mirror,
+            whiteouts: lower_dir.is_some(),
+            cold_tier,
+            log: Arc::new(StorageLog::new(&config.name, log_level, trace_ops)),
+        };
+        let overlay = match lower_dir {
+            Some(dir) => {
+                let data_info_dir = self
+                    .root
+                    .join(OVERLAY_DATA_INFO_DIR)
+                    .join(base_dir.strip_prefix(&self.root).unwrap_or(&base_dir));
+                Some(Overlay::new(dir, data_info_dir, &files_mgr_settings).await?)
+            }
+            None => None,
+        };
+        let files_mgr = Arc::new(files_mgr_settings.build(base_dir).await?);
+        if let Some(mirror) = &files_mgr_settings.mirror {
+            mirror.spawn(Arc::downgrade(&files_mgr));
+        }
+        spawn_db_stats_refresh(Arc::downgrade(&files_mgr));
+        if lazy_init {
+            // answer the queries right away, the scans running in background
+            let progress = progress.clone();
+            let name = config.name.clone();
+            let empty_as_deleted = empty_payload == EmptyPayload::Delete;
+            spawn_storage_task(Arc::downgrade(&files_mgr), |files_mgr| async move {
+                if let Err(e) =
+                    warm_up(files_mgr, &progress, verify_on_start, empty_as_deleted).await
+                {
+                    warn!(
+                        "Storage {}: initialization in background failed: {}",
+                        name, e
+                    );
+                }
+            });
+        } else {
+            // scan the files before the storage starts answering queries
+            scan_on_start(&files_mgr, &progress, verify_on_start).await?;
+        }
+        let maintenance = Arc::new(MaintenanceScheduler::new(maintenance_jobs));
+        if !maintenance.is_empty() && dry_run {
+            warn!(
+                "`{}` ignored in dry run: the maintenance jobs modify the storage's files",
+                PROP_STORAGE_MAINTENANCE
+            );
+        } else if !maintenance.is_empty() {
+            maintenance.spawn(Arc::downgrade(&files_mgr));
+        }
+        let hooks = Hooks::new(&config.name, on_put, on_delete);
+        // the session publishing the notifications and statistics, if any
+        let session = match notification_key_expr.is_some() || stats_key_expr.is_some() {
+            true => Some(
+                notifier::open_session(&notification_connect)
+                    .await
+                    .map_err(|e| {
+                        zerror!(
+                            "Failed to open the session of the publishers of File System Storage {}: {}",
+                            config.name,
+                            e
+                        )
+                    })?,
+            ),
+            false => None,
+        };
+        let notifier = match (notification_key_expr, &session) {
+            (Some(ke), Some(session)) => Some(Notifier::new(session, ke).await.map_err(|e| {
+                zerror!(
+                    "Failed to create the notifications publisher of File System Storage {}: {}",
+                    config.name,
+                    e
+                )
+            })?),
+            _ => None,
+        };
+        let stats_publisher = match (stats_key_expr, &session) {
+            (Some(ke), Some(session)) => {
+                let stats_publisher = StatsPublisher::new(
+                    &config.name,
+                    session,
+                    ke,
+                    stats_interval,
+                    progress.clone(),
+                    maintenance.clone(),
+                )
+                .await
+                .map_err(|e| {
+                    zerror!(
+                        "Failed to create the statistics publisher of File System Storage {}: {}",
+                        config.name,
+                        e
+                    )
+                })?;
+                let stats_publisher = Arc::new(stats_publisher);
+                stats_publisher.spawn(Arc::downgrade(&files_mgr));
+                Some(stats_publisher)
+            }
+            _ => None,
+        };
+        if !lazy_init {
+            progress.complete();
+        }
+        let dry_run = dry_run.then(|| DryRun::new(&config.name));
+        let handle = Arc::new(StorageHandle {
+            name: config.name.clone(),
+            files_mgr: Mutex::new(Arc::downgrade(&files_mgr)),
+        });
+        self.storages.lock().unwrap().push(Arc::downgrade(&handle));
+        Ok(Box::new(FileSystemStorage {
+            config,
+            root: self.root.clone(),
+            storage_dirs: self.storage_dirs.clone(),
+            handle,
+            files_mgr_settings,
+            files_mgr,
+            migration: None,
+            import: None,
+            scrub: None,
+            last_sync: None,
+            last_purge: None,
+            hooks,
+            notifier,
+            stats_publisher,
+            interceptors,
+            dry_run,
+            failover,
+            overlay,
+            maintenance,
+            dir_guard,
+            stripe_guards,
+            read_only,
+            read_only_behavior,
+            read_only_warned: false,
+            worm,
+            acl,
+            empty_payload,
+            timestamp_skew,
+            expose_fs_path,
+            digest_interval,
+            control_prefix,
+            export_dir,
+            import_roots,
+            max_unpack_size,
+            max_patched_size,
+            init_progress: progress,
+            runtime,
+        }))
+    }
+}
+
+struct FileSystemStorage {
+    config: StorageConfig,
+    // the volume's root and storages directories, to migrate the storage to another directory
+    root: PathBuf,
+    storage_dirs: StorageDirs,
+    // (registered in the volume, for the volume to count and close its storages)
+    handle: Arc<StorageHandle>,
+    files_mgr_settings: FilesMgrSettings,
+    files_mgr: Arc<FilesMgr>,
+    // the migration from a former directory, until all its entries are copied
+    migration: Option<Arc<Migration>>,
+    // the last import of an external directory or tarball
+    import: Option<Arc<Import>>,
+    // the last scrub requested by a control operation
+    scrub: Option<Arc<Scrub>>,
+    // the result of the last synchronization with a remote storage
+    last_sync: Option<serde_json::Value>,
+    // the result of the last purge of the entries older than a bound
+    last_purge: Option<serde_json::Value>,
+    // the commands run after the PUTs and DELETEs, if any
+    hooks: Option<Hooks>,
+    // the publisher of the notifications of the PUTs and DELETEs, if any
+    notifier: Option<Notifier>,
+    // the periodic publisher of the storage's statistics, if any
+    stats_publisher: Option<Arc<StatsPublisher>>,
+    // the interceptors of the PUTs, DELETEs and GETs, in their order
+    interceptors: Vec<Arc<dyn StorageInterceptor>>,
+    // in dry run, the updates are only checked and counted, the disk being never modified
+    dry_run: Option<DryRun>,
+    // the failover of the reads to a replica directory while the storage's directories are unavailable, if any
+    failover: Option<Failover>,
+    // the read-only lower layer of the storage, if any (its directory being the writable upper layer)
+    overlay: Option<Overlay>,
+    maintenance: Arc<MaintenanceScheduler>,
+    dir_guard: StorageDirGuard,
+    // the guards of the stripes' directories (if striped), of the mirror directory (if mirrored) and of the cold
+    // tier's directory (if any)
+    stripe_guards: Vec<StorageDirGuard>,
+    read_only: bool,
+    read_only_behavior: ReadOnlyBehavior,
+    // true once a warning was logged for an update on the read-only storage
+    read_only_warned: bool,
+    // write-once mode: the existing entries can't be overwritten nor deleted
+    worm: bool,
+    // if set, the operations allowed per key expression
+    acl: Option<Arc<Acl>>,
+    empty_payload: EmptyPayload,
+    // the policy for the timestamps too far in the future or in the past, and their counts
+    timestamp_skew: TimestampSkew,
+    expose_fs_path: bool,
+    // the duration of the time intervals of the replication digest
+    digest_interval: Duration,
+    // if set, a PUT on `<control_prefix>/<job>` runs a maintenance job
+    control_prefix: Option<String>,
+    // if set, the directory of the host the manifests are written to
+    export_dir: Option<PathBuf>,
+    // the directories of the host the imported directories and tarballs must be under (none being allowed if empty)
+    import_roots: Vec<PathBuf>,
+    // the maximum size of the files unpacked from the archive of a PUT with `_unpack=true`
+    max_unpack_size: u64,
+    // the maximum size of a value built by the patch control operation
+    max_patched_size: usize,
+    // the progress of the storage's initialization, once completed
+    init_progress: Arc<InitProgress>,
+    // the runtime dedicated to the storage, if any (declared last, for the FilesMgr to be dropped before it)
+    runtime: Option<Arc<StorageRuntime>>,
+}
+
+// The maximum duration of the closure of a storage, waiting for its background tasks and its mirror
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// The handle of a storage registered in its volume, for the volume to close it
+struct StorageHandle {
+    name: String,
+    // the FilesMgr of the storage (replaced on a migration)
+    files_mgr: Mutex<Weak<FilesMgr>>,
+}
+
+impl StorageHandle {
+    async fn close(&self) {
+        let files_mgr = self.files_mgr.lock().unwrap().upgrade();
+        if let Some(files_mgr) = files_mgr {
+            close_storage(&self.name, files_mgr).await;
+        }
+    }
+}
+
+// Close a storage in order (e.g. on the unload of the backend): stop its background tasks, wait (up to CLOSE_TIMEOUT)
+// for the steps they are running (e.g. a maintenance job) and for the mirror to catch up, then flush the cached file
+// handles, the data-info database and the files (or delete everything if `on_closure` is "delete_all"). The storage
+// itself keeps its directories registered until it's dropped, for a storage not to be created on them meanwhile.
+async fn close_storage(name: &str, files_mgr: Arc<FilesMgr>) {
+    let deadline = Instant::now() + CLOSE_TIMEOUT;
+    if !files_mgr.begin_close() {
+        // (being closed by another call)
+        let _ = tokio::time::timeout_at(deadline.into(), files_mgr.closed()).await;
+        return;
+    }
+    debug!("Closing File System Storage {}", name);
+    let stopped = async {
+        files_mgr.stopped_tasks().await;
+        files_mgr.mirror_caught_up().await;
+    };
+    if tokio::time::timeout_at(deadline.into(), stopped)
+        .await
+        .is_err()
+    {
+        warn!(
+            "File System Storage {}: closed without waiting any longer for its background tasks ({} changes not mirrored)",
+            name,
+            files_mgr.mirror_pending()
+        );
+    }
+    files_mgr.shutdown().await;
+    debug!("File System Storage {} closed", name);
+}
+
+impl FileSystemStorage {
+    // Return true if the ACL (if any) allows to read the key
+    fn is_readable_key(&self, key: Option<&keyexpr>) -> bool {
+        match &self.acl {
+            Some(acl) => acl.allows(AclOp::Read, key),
+            None => true,
+        }
+    }
+
+    // Process an update received by the read-only storage, according to `read_only_behavior`.
+    // An ignored update is reported as outdated.
+    fn reject_update(&mut self, op: &str) -> ZResult<StorageInsertionResult> {
+        match self.read_only_behavior {
+            ReadOnlyBehavior::Error => {
+                warn!(
+                    "Received {} for read-only Files System Storage on {:?} - ignored",
+                    op,
+                    self.files_mgr.base_dir()
+                );
+                Err("Received update for read-only File System Storage".into())
+            }
+            ReadOnlyBehavior::SilentlyIgnore => Ok(StorageInsertionResult::Outdated),
+            ReadOnlyBehavior::LogOnce => {
+                if !self.read_only_warned {
+                    warn!(
+                        "Received {} for read-only Files System Storage on {:?} - ignored (further updates will be ignored silently)",
+                        op,
+                        self.files_mgr.base_dir()
+                    );
+                    self.read_only_warned = true;
+                }
+                Ok(StorageInsertionResult::Outdated)
+            }
+        }
+    }
+
+    // The control operation requested by a PUT, if its key is in the control namespace
+    fn control_op<'a>(&self, key: Option<&'a keyexpr>) -> Option<&'a str> {
+        match (&self.control_prefix, key) {
+            (Some(prefix), Some(key)) => key
+                .as_str()
+                .strip_prefix(prefix.as_str())
+                .and_then(|op| op.strip_prefix('/')),
+            _ => None,
+        }
+    }
+
+    // Run a control operation (a maintenance job, a migration or a move), returning a short report
+    async fn run_control_op(
+        &mut self,
+        op: &str,
+        payload: &ZBytes,
+        timestamp: &Timestamp,
+    ) -> ZResult<String> {
+        // (the scrub only reading the files)
+        if self.dry_run.is_some() && op != CONTROL_LOG && op_prefix(op, CONTROL_SCRUB).is_none() {
+            bail!("The {} operation isn't allowed in dry run", op)
+        }
+        if op == CONTROL_MIGRATE {
+            if self.files_mgr_settings.passthrough.is_some() {
+                bail!("The {} operation isn't allowed in passthrough mode", op)
+            }
+            if self.overlay.is_some() {
+                // the whiteouts are recorded in the data-info of the storage's directory
+                bail!("The {} operation isn't allowed with a lower layer", op)
+            }
+            let dir = payload
+                .try_to_string()
+                .map_err(|e| zerror!("Invalid directory for {} operation: {}", op, e))?;
+            return self.migrate(dir.trim()).await;
+        }
+        if op == CONTROL_MOVE {
+            let (from, to) = self.extract_keys_pair(op, payload)?;
+            return self.move_entry(from, to, timestamp).await;
+        }
+        if op == CONTROL_COPY {
+            let (from, to) = self.extract_keys_pair(op, payload)?;
+            return self.copy_entry(from, to, timestamp).await;
+        }
+        if op == CONTROL_DELETE {
+            return self.delete_entries(payload).await;
+        }
+        if let Some(prefix) = op_prefix(op, CONTROL_IMPORT) {
+            let source = payload
+                .try_to_string()
+                .map_err(|e| zerror!("Invalid path for {} operation: {}", CONTROL_IMPORT, e))?;
+            return self.import(prefix, source.trim(), timestamp);
+        }
+        if let Some(prefix) = op_prefix(op, CONTROL_MANIFEST) {
+            let path = payload
+                .try_to_string()
+                .map_err(|e| zerror!("Invalid path for {} operation: {}", CONTROL_MANIFEST, e))?;
+            return self.write_manifest(prefix, path.trim()).await;
+        }
+        if let Some(prefix) = op_prefix(op, CONTROL_SYNC) {
+            return self.sync(prefix, payload).await;
+        }
+        if let Some(key) = op_prefix(op, CONTROL_PATCH) {
+            return self.patch(key, payload, timestamp).await;
+        }
+        if op == CONTROL_LOG {
+            return self.set_log(payload);
+        }
+        // (rather than the scrub maintenance job, which checks all the files at once)
+        if let Some(prefix) = op_prefix(op, CONTROL_SCRUB) {
+            return self.scrub(prefix, payload);
+        }
+        // (rather than the retention job, which deletes the entries older than their retention period)
+        if let Some(prefix) = op_prefix(op, CONTROL_PURGE) {
+            return self.purge(prefix, payload).await;
+        }
+        match Job::from_name(op) {
+            Some(job) => self.maintenance.run(job, &self.files_mgr).await,
+            None => bail!(
+                "Unknown control operation \"{}\" - must be one of: {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}",
+                op,
+                Job::ALL.map(|job| job.name()).join(", "),
+                CONTROL_MIGRATE,
+                CONTROL_MOVE,
+                CONTROL_COPY,
+                CONTROL_DELETE,
+                CONTROL_IMPORT,
+                CONTROL_MANIFEST,
+                CONTROL_SYNC,
+                CONTROL_PATCH,
+                CONTROL_LOG,
+                CONTROL_SCRUB,
+                CONTROL_PURGE
+            ),
+        }
+    }
+
+    // Change the level of the storage's logs (null to follow zenohd's one again) and the logging of its operations
+    fn set_log(&self, payload: &ZBytes) -> ZResult<String> {
+        let invalid = |e: &dyn std::fmt::Display| {
+            zerror!(
+                r#"Invalid payload for {} operation (expected {{"level": <level or null>, "trace_ops": <bool>}}): {}"#,
+                CONTROL_LOG,
+                e
+            )
+        };
+        let value: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_slice(&payload.to_bytes()).map_err(|e| invalid(&e))?;
+        let log = self.files_mgr.log();
+        match value.get("level") {
+            Some(serde_json::Value::Null) => log.set_level(None),
+            Some(level) => match level.as_str().and_then(storage_log::level_from_name) {
+                Some(level) => log.set_level(Some(level)),
+                None => return Err(invalid(&format!("unknown level {level}")).into()),
+            },
+            None => (),
+        }
+        match value.get("trace_ops") {
+            Some(serde_json::Value::Bool(trace_ops)) => log.set_trace_ops(*trace_ops),
+            Some(v) => return Err(invalid(&format!("invalid trace_ops {v}")).into()),
+            None => (),
+        }
+        Ok(format!(
+            "Logging of storage {}: {}",
+            self.config.name,
+            log.status_json()
+        ))
+    }
+
+    // Convert a (full) key received in a control payload to the key of a storage's entry (None for the root key)
+    fn to_storage_key(&self, key: &str) -> ZResult<Option<OwnedKeyExpr>> {
+        let ke = keyexpr::new(key)?;
+        if ke.is_wild() || !ke.intersects(&self.config.key_expr) {
+            bail!("{} is not a key of storage {}", key, self.config.name)
+        }
+        let Some(prefix) = &self.config.strip_prefix else {
+            return Ok(Some(ke.into()));
+        };
+        if key == prefix.as_str() {
+            return Ok(None);
+        }
+        match key
+            .strip_prefix(prefix.as_str())
+            .and_then(|k| k.strip_prefix('/'))
+        {
+            Some(stripped) => Ok(Some(OwnedKeyExpr::new(stripped)?)),
+            None => bail!(
+                "{} is not a key of storage {} (not prefixed with {})",
+                key,
+                self.config.name,
+                prefix
+            ),
+        }
+    }
+
+    // Extract the `from` and `to` keys of a control operation's JSON payload, as keys of storage's entries
+    fn extract_keys_pair(
+        &self,
+        op: &str,
+        payload: &ZBytes,
+    ) -> ZResult<(Option<OwnedKeyExpr>, Option<OwnedKeyExpr>)> {
+        let value: serde_json::Value = serde_json::from_slice(&payload.to_bytes())
+            .map_err(|e| zerror!("Invalid payload for {} operation: {}", op, e))?;
+        let key = |name: &str| match value.get(name).and_then(|v| v.as_str()) {
+            Some(key) => self.to_storage_key(key),
+            None => bail!(
+                r#"Invalid payload for {} operation: expected {{"from": <key>, "to": <key>}}"#,
+                op
+            ),
+        };
+        Ok((key("from")?, key("to")?))
+    }
+
+    // Delete several entries (given as a JSON list of full keys) at once. All the keys are checked before any deletion.
+    async fn delete_entries(&mut self, payload: &ZBytes) -> ZResult<String> {
+        if self.worm {
+            bail!("Entries can't be deleted in a write-once File System Storage")
+        }
+        if self.migration.is_some() {
+            bail!("Entries can't be deleted at once while a migration is in progress")
+        }
+        let keys: Vec<String> = serde_json::from_slice(&payload.to_bytes()).map_err(|e| {
+            zerror!(
+                "Invalid payload for {} operation (expected a list of keys): {}",
+                CONTROL_DELETE,
+                e
+            )
+        })?;
+        let keys = keys
+            .iter()
+            .map(|key| self.to_storage_key(key))
+            .collect::<ZResult<Vec<_>>>()?;
+        let mut zfiles = Vec::with_capacity(keys.len());
+        for key in &keys {
+            if let Some(acl) = &self.acl {
+                acl.check(AclOp::Delete, key.as_deref())?;
+            }
+            let zfile = match key {
+                Some(k) => self.files_mgr.to_zfile(k.as_str()),
+                None => self.files_mgr.root_zfile(),
+            };
+            if !self.files_mgr.is_tenant_key(&zfile.zpath) {
+                bail!(
+                    "Can't delete {} which doesn't belong to any tenant of the storage",
+                    zfile.zpath
+                )
+            }
+            zfiles.push(zfile);
+        }
+        self.files_mgr.delete_files(&zfiles).await?;
+        Ok(format!("{} entries deleted", zfiles.len()))
+    }
+
+    // Move an entry to another key, renaming its file and its data-info (with the control PUT's timestamp)
+    // rather than copying its value. The entry previously stored under the new key (if any) is replaced.
+    async fn move_entry(
+        &mut self,
+        from: Option<OwnedKeyExpr>,
+        to: Option<OwnedKeyExpr>,
+        timestamp: &Timestamp,
+    ) -> ZResult<String> {
+        if let Some(acl) = &self.acl {
+            acl.check(AclOp::Read, from.as_deref())?;
+            acl.check(AclOp::Delete, from.as_deref())?;
+            acl.check(AclOp::Write, to.as_deref())?;
+        }
+        if self.worm {
+            bail!("Entries can't be moved in a write-once File System Storage")
+        }
+        if self.migration.is_some() {
+            bail!("Entries can't be moved while a migration is in progress")
+        }
+        let (from_zfile, to_zfile) = self.to_zfiles_pair(CONTROL_MOVE, &from, &to)?;
+        if !self
+            .files_mgr
+            .move_file(&from_zfile, &to_zfile, timestamp)
+            .await?
+        {
+            bail!("No entry to move at {}", from_zfile.zpath)
+        }
+        Ok(format!("{} moved to {}", from_zfile.zpath, to_zfile.zpath))
+    }
+
+    // Copy an entry to another key, with the control PUT's timestamp. The file is copied by the file-system
+    // (sharing its blocks where supported). The entry previously stored under the new key (if any) is replaced.
+    async fn copy_entry(
+        &mut self,
+        from: Option<OwnedKeyExpr>,
+        to: Option<OwnedKeyExpr>,
+        timestamp: &Timestamp,
+    ) -> ZResult<String> {
+        if let Some(acl) = &self.acl {
+            acl.check(AclOp::Read, from.as_deref())?;
+            acl.check(AclOp::Write, to.as_deref())?;
+        }
+        if self.migration.is_some() {
+            bail!("Entries can't be copied while a migration is in progress")
+        }
+        let (from_zfile, to_zfile) = self.to_zfiles_pair(CONTROL_COPY, &from, &to)?;
+        if self.worm && self.files_mgr.exists(&to_zfile).await? {
+            bail!(
+                "Can't copy to existing {} in a write-once File System Storage",
+                to_zfile.zpath
+            )
+        }
+        if !self
+            .files_mgr
+            .copy_file(&from_zfile, &to_zfile, timestamp)
+            .await?
+        {
+            bail!("No entry to copy at {}", from_zfile.zpath)
+        }
+        Ok(format!("{} copied to {}", from_zfile.zpath, to_zfile.zpath))
+    }
+
+    // Store the files of an archive (a tarball or a zip file) as entries under the key of a PUT with `_unpack=true`
+    // (the storage's root if None), all with the PUT's timestamp. The keys of all the files are checked before storing
+    // any of them.
+    async fn unpack(
+        &self,
+        prefix: Option<&keyexpr>,
+        payload: &ZBytes,
+        timestamp: &Timestamp,
+    ) -> ZResult<String> {
+        let entries = archive_entries(&payload.to_bytes(), self.max_unpack_size)?;
+        let mut keys = Vec::with_capacity(entries.len());
+        for (path, _) in &entries {
+            let key = match prefix {
+                Some(prefix) => format!("{prefix}/{path}"),
+                None => path.clone(),
+            };
+            let ke = OwnedKeyExpr::new(key.as_str())
+                .map_err(|e| zerror!("Invalid key {} for unpacked file {}: {}", key, path, e))?;
+            if ke.is_wild() {
+                bail!(
+                    "Invalid key {} for unpacked file {}: contains wildcards",
+                    key,
+                    path
+                )
+            }
+            if let Some(acl) = &self.acl {
+                acl.check(AclOp::Write, Some(&ke))?;
+            }
+            keys.push(ke);
+        }
+        let mut skipped = 0;
+        for (key, (_, content)) in keys.iter().zip(entries) {
+            let encoding = self
+                .files_mgr
+                .guess_encoding_of(&self.files_mgr.to_zfile(key));
+            if let StorageInsertionResult::Outdated = self
+                .put_entry(Some(key), content.into(), encoding, timestamp)
+                .await?
+            {
+                skipped += 1;
+            }
+        }
+        Ok(format!(
+            "{} entries unpacked under \"{}\" ({} skipped)",
+            keys.len() - skipped,
+            prefix.map_or("", |k| k.as_str()),
+            skipped
+        ))
+    }
+
+    // Import an external directory or tarball of the host as entries under a key prefix (the storage's root if empty),
+    // all with the control PUT's timestamp. The files are imported in background, the progress being reported in the
+    // storage's admin status.
+    fn import(&mut self, prefix: &str, source: &str, timestamp: &Timestamp) -> ZResult<String> {
+        if self.import.as_ref().is_some_and(|i| i.is_running()) {
+            bail!("An import is already in progress")
+        }
+        if self.migration.is_some() {
+            bail!("Can't import while a migration is in progress")
+        }
+        let source = PathBuf::from(source);
+        if !source.is_absolute() {
+            bail!(
+                "Invalid path for {} operation: {:?} must be absolute",
+                CONTROL_IMPORT,
+                source
+            )
+        }
+        let source = dunce::canonicalize(&source).map_err(|e| {
+            zerror!(
+                "Invalid path for {} operation: {:?}: {}",
+                CONTROL_IMPORT,
+                source,
+                e
+            )
+        })?;
+        // (the roots being canonicalized too, for a symbolic link in the source's path not to lead out of them)
+        if !self
+            .import_roots
+            .iter()
+            .any(|root| dunce::canonicalize(root).is_ok_and(|root| source.starts_with(root)))
+        {
+            bail!(
+                "Invalid path for {} operation: {:?} is not under any of the `{}`",
+                CONTROL_IMPORT,
+                source,
+                PROP_STORAGE_IMPORT_ROOTS
+            )
+        }
+        if source.starts_with(self.files_mgr.base_dir())
+            || self.files_mgr.base_dir().starts_with(&source)
+        {
+            bail!(
+                "Invalid path for {} operation: {:?} overlaps the storage's directory",
+                CONTROL_IMPORT,
+                source
+            )
+        }
+        let import = Arc::new(Import::new(
+            source.clone(),
+            prefix.to_string(),
+            self.acl.clone(),
+            self.worm,
+            self.empty_payload != EmptyPayload::Store,
+        ));
+        import.spawn(Arc::downgrade(&self.files_mgr), *timestamp);
+        self.import = Some(import);
+        Ok(format!(
+            "import of {:?} under \"{}\" started",
+            source, prefix
+        ))
+    }
+
+    // Check the integrity of the entries under a key prefix (all the entries if empty), at most `max_rate` files per
+    // second (given as payload). The files are checked in background, the progress and the corrupted or missing
+    // entries being reported in the storage's admin status.
+    fn scrub(&mut self, prefix: &str, payload: &ZBytes) -> ZResult<String> {
+        if self.scrub.as_ref().is_some_and(|s| s.is_running()) {
+            bail!("A scrub is already in progress")
+        }
+        if !prefix.is_empty() && keyexpr::new(prefix).map_or(true, |ke| ke.is_wild()) {
+            bail!(
+                "Invalid prefix for {} operation: {} must be a key without wildcard",
+                CONTROL_SCRUB,
+                prefix
+            )
+        }
+        let max_rate = match payload.try_to_string() {
+            Ok(s) if s.trim().is_empty() => DEFAULT_SCRUB_MAX_RATE,
+            Ok(s) => match s.trim().parse::<usize>() {
+                Ok(rate) if rate > 0 => rate,
+                _ => bail!(
+                    "Invalid payload for {} operation: {} must be a positive number of files per second",
+                    CONTROL_SCRUB,
+                    s
+                ),
+            },
+            Err(e) => bail!("Invalid payload for {} operation: {}", CONTROL_SCRUB, e),
+        };
+        let scrub = Arc::new(Scrub::new(prefix.to_string(), max_rate));
+        scrub.spawn(Arc::downgrade(&self.files_mgr));
+        self.scrub = Some(scrub);
+        Ok(format!(
+            "scrub of \"{}\" started, at most {} files per second",
+            prefix, max_rate
+        ))
+    }
+
+    // Delete the entries under a key prefix (all the entries if empty) whose timestamp is older than a bound (given as
+    // payload), returning the numbers of deleted entries and of bytes reclaimed
+    async fn purge(&mut self, prefix: &str, payload: &ZBytes) -> ZResult<String> {
+        if self.worm {
+            bail!("Entries can't be purged in a write-once File System Storage")
+        }
+        if self.migration.is_some() {
+            bail!("Entries can't be purged while a migration is in progress")
+        }
+        let subtree = match prefix.is_empty() {
+            true => None,
+            false => match OwnedKeyExpr::new(prefix) {
+                Ok(ke) if !ke.is_wild() => Some(ke),
+                _ => bail!(
+                    "Invalid prefix for {} operation: {} must be a key without wildcard",
+                    CONTROL_PURGE,
+                    prefix
+                ),
+            },
+        };
+        let bound = payload
+            .try_to_string()
+            .map_err(|e| zerror!("Invalid payload for {} operation: {}", CONTROL_PURGE, e))?;
+        let bound = bound.trim();
+        let before = match humantime::parse_duration(bound) {
+            Ok(age) => NTP64::from(
+                SystemTime::now()
+                    .checked_sub(age)
+                    .unwrap_or(UNIX_EPOCH)
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default(),
+            ),
+            Err(_) => parse_time(CONTROL_PURGE, bound).map_err(|_| {
+                zerror!(
+                    "Invalid payload for {} operation: {} - must be a timestamp, an RFC 3339 date-time or an age",
+                    CONTROL_PURGE,
+                    bound
+                )
+            })?,
+        };
+        // (only the entries whose deletion is allowed by the ACL)
+        let is_purgeable = |zpath: &str| match &self.acl {
+            Some(acl) if zpath == ROOT_KEY => acl.allows(AclOp::Delete, None),
+            Some(acl) => keyexpr::new(zpath).is_ok_and(|key| acl.allows(AclOp::Delete, Some(key))),
+            None => true,
+        };
+        let (count, bytes, unreadable) = self
+            .files_mgr
+            .purge_older_than(subtree.as_deref(), before, is_purgeable)
+            .await?;
+        self.last_purge = Some(serde_json::json!({
+            "prefix": prefix,
+            "before": humantime::format_rfc3339_seconds(before.to_system_time()).to_string(),
+            "time": humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            "purged": count,
+            "bytes": bytes,
+            "unreadable": unreadable,
+        }));
+        Ok(format!(
+            "{} entries purged under \"{}\" ({} bytes reclaimed, {} entries skipped as unreadable)",
+            count, prefix, bytes, unreadable
+        ))
+    }
+
+    // Write the inventory manifest of the entries under a key prefix (all the entries if empty) to a file of the
+    // export directory (given by its path relative to it), as CSV if its extension is ".csv", or as JSON otherwise.
+    // The file is replaced once the manifest is complete.
+    async fn write_manifest(&self, prefix: &str, path: &str) -> ZResult<String> {
+        let Some(export_dir) = &self.export_dir else {
+            bail!(
+                "The {} operation requires `{}` to be set",
+                CONTROL_MANIFEST,
+                PROP_STORAGE_EXPORT_DIR
+            )
+        };
+        let relpath = Path::new(path);
+        let Some(file_name) = relpath.file_name() else {
+            bail!(
+                "Invalid path for {} operation: {:?} must be the path of a file",
+                CONTROL_MANIFEST,
+                relpath
+            )
+        };
+        if !relpath
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)))
+        {
+            bail!(
+                "Invalid path for {} operation: {:?} must be relative to `{}`, without any '..'",
+                CONTROL_MANIFEST,
+                relpath,
+                PROP_STORAGE_EXPORT_DIR
+            )
+        }
+        // (a symbolic link in the path mustn't lead out of the export directory)
+        let export_dir = dunce::canonicalize(export_dir)
+            .map_err(|e| zerror!("Failed to write manifest in {:?}: {}", export_dir, e))?;
+        let dir = export_dir.join(relpath.parent().unwrap_or(Path::new("")));
+        let dir = dunce::canonicalize(&dir)
+            .map_err(|e| zerror!("Failed to write manifest in {:?}: {}", dir, e))?;
+        if !dir.starts_with(&export_dir) {
+            bail!(
+                "Invalid path for {} operation: {:?} leads out of `{}`",
+                CONTROL_MANIFEST,
+                relpath,
+                PROP_STORAGE_EXPORT_DIR
+            )
+        }
+        let path = dir.join(file_name);
+        let subtree = if prefix.is_empty() {
+            None
+        } else {
+            Some(OwnedKeyExpr::new(prefix)?)
+        };
+        let entries = self
+            .manifest(subtree.as_deref(), SortOrder::Key, None)
+            .await?;
+        let manifest = if path.extension().is_some_and(|ext| ext == "csv") {
+            manifest::to_csv(&entries)
+        } else {
+            manifest::to_json(&entries)
+        };
+        let mut file = tempfile::NamedTempFile::new_in(&dir)
+            .map_err(|e| zerror!("Failed to write manifest in {:?}: {}", path, e))?;
+        file.write_all(manifest.as_bytes())
+            .map_err(|e| zerror!("Failed to write manifest in {:?}: {}", path, e))?;
+        file.persist(&path)
+            .map_err(|e| zerror!("Failed to write manifest in {:?}: {}", path, e))?;
+        Ok(format!(
+            "manifest of {} entries written in {:?}",
+            entries.len(),
+            path
+        ))
+    }
+
+    // Synchronize the entries under a key prefix (all the entries if empty) with the manifest of a remote storage:
+    // the local entries missing remotely are deleted, and the keys to fetch from the remote storage (missing locally,
+    // or with a different and more recent value) are listed in the storage's admin status.
+    async fn sync(&mut self, prefix: &str, payload: &ZBytes) -> ZResult<String> {
+        if self.worm {
+            bail!("Entries can't be synchronized in a write-once File System Storage")
+        }
+        if self.migration.is_some() {
+            bail!("Entries can't be synchronized while a migration is in progress")
+        }
+        let subtree = if prefix.is_empty() {
+            None
+        } else {
+            Some(OwnedKeyExpr::new(prefix)?)
+        };
+        let selector = match &subtree {
+            Some(subtree) => Some(OwnedKeyExpr::try_from(format!("{subtree}/**"))?),
+            None => None,
+        };
+        let remote = manifest::from_json(&payload.to_bytes())?;
+        for entry in &remote {
+            let key = self.to_storage_key(entry.key())?;
+            let in_subtree = match (&selector, &key) {
+                (None, _) => true,
+                (Some(selector), Some(key)) => selector.includes(key),
+                (Some(_), None) => false,
+            };
+            if !in_subtree {
+                bail!("Invalid manifest: {} is not under {}", entry.key(), prefix)
+            }
+        }
+        let local = self
+            .manifest(subtree.as_deref(), SortOrder::Key, None)
+            .await?;
+        let delta = manifest::delta(&local, &remote);
+        let keys = delta
+            .to_delete
+            .iter()
+            .map(|full_key| self.to_storage_key(full_key))
+            .collect::<ZResult<Vec<_>>>()?;
+        let mut zfiles = Vec::with_capacity(keys.len());
+        for key in &keys {
+            if let Some(acl) = &self.acl {
+                acl.check(AclOp::Delete, key.as_deref())?;
+            }
+            let zfile = match key {
+                Some(k) => self.files_mgr.to_zfile(k.as_str()),
+                None => self.files_mgr.root_zfile(),
+            };
+            if !self.files_mgr.is_tenant_key(&zfile.zpath) {
+                bail!(
+                    "Can't delete {} which doesn't belong to any tenant of the storage",
+                    zfile.zpath
+                )
+            }
+            zfiles.push(zfile);
+        }
+        self.files_mgr.delete_files(&zfiles).await?;
+        let report = format!(
+            "{} entries deleted, {} entries to fetch",
+            delta.to_delete.len(),
+            delta.to_fetch.len()
+        );
+        self.last_sync = Some(serde_json::json!({
+            "prefix": prefix,
+            "time": humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+            "deleted": delta.to_delete,
+            "to_fetch": delta.to_fetch,
+        }));
+        Ok(report)
+    }
+
+    // Update an entry with a delta against its current value, which must have the delta's base timestamp.
+    // The new value keeps the encoding of the current one, and gets the control PUT's timestamp.
+    async fn patch(&self, key: &str, payload: &ZBytes, timestamp: &Timestamp) -> ZResult<String> {
+        let key = OwnedKeyExpr::new(key)?;
+        if let Some(acl) = &self.acl {
+            acl.check(AclOp::Write, Some(&key))?;
+        }
+        let bytes = payload.to_bytes();
+        let delta = Delta::parse(&bytes)?;
+        let zfile = self.files_mgr.to_zfile(key.as_str());
+        // the base value is stored by files_mgr, else it's read from the migration's former directory or the overlay:
+        // it's checked to be still the stored one (or to still be none) when writing the new value
+        let (read, stored) = match self.files_mgr.read_file(&zfile).await? {
+            Some(read) => {
+                let stored = Some(read.2);
+                (Some(read), stored)
+            }
+            None => (self.read_entry(Some(&key), &zfile).await?, None),
+        };
+        let (base, encoding) = match read {
+            Some((base, encoding, base_timestamp)) if base_timestamp == *delta.base() => {
+                (base, encoding)
+            }
+            Some((_, _, base_timestamp)) => bail!(
+                "Can't patch {}: its timestamp is {}, not the delta's base one ({})",
+                key,
+                base_timestamp,
+                delta.base()
+            ),
+            None => bail!("Can't patch {}: no value stored", key),
+        };
+        let value = delta.apply(&base.to_bytes(), self.max_patched_size)?;
+        let size = value.len();
+        match self
+            .put_entry_over(
+                Some(&key),
+                value.into(),
+                encoding,
+                timestamp,
+                Some(stored.as_ref()),
+            )
+            .await?
+        {
+            StorageInsertionResult::Outdated => Ok(format!("patch of {} denied", key)),
+            _ => Ok(format!(
+                "{} patched with a {} bytes delta ({} bytes value)",
+                key,
+                bytes.len(),
+                size
+            )),
+        }
+    }
+
+    // The files of 2 distinct entries of the storage's tenants, for an operation between them
+    fn to_zfiles_pair<'a>(
+        &self,
+        op: &str,
+        from: &'a Option<OwnedKeyExpr>,
+        to: &'a Option<OwnedKeyExpr>,
+    ) -> ZResult<(ZFile<'a>, ZFile<'a>)> {
+        let [from_zfile, to_zfile] = [from, to].map(|key| match key {
+            Some(k) => self.files_mgr.to_zfile(k.as_str()),
+            None => self.files_mgr.root_zfile(),
+        });
+        for zfile in [&from_zfile, &to_zfile] {
+            if !self.files_mgr.is_tenant_key(&zfile.zpath) {
+                bail!(
+                    "{} doesn't belong to any tenant of the storage",
+                    zfile.zpath
+                )
+            }
+        }
+        if from_zfile.zpath == to_zfile.zpath {
+            bail!("Can't {} {} to itself", op, from_zfile.zpath)
+        }
+        Ok((from_zfile, to_zfile))
+    }
+
+    // Move the storage to another directory (relative to the volume's root): the updates are applied in the new
+    // directory right away, while the existing entries are copied in background.
+    async fn migrate(&mut self, dir: &str) -> ZResult<String> {
+        let base_dir = storage_dir(&self.root, dir)?;
+        if !self.stripe_guards.is_empty() {
+            bail!("Can't migrate a storage striped over several directories")
+        }
+        if self.import.as_ref().is_some_and(|i| i.is_running()) {
+            bail!("Can't migrate while an import is in progress")
+        }
+        if let Some(migration) = &self.migration {
+            if migration.is_failed() && base_dir == self.files_mgr.base_dir() {
+                migration.spawn(Arc::downgrade(&self.files_mgr));
+                return Ok(format!("migration to {:?} resumed", base_dir));
+            }
+            bail!(
+                "A migration from {:?} to {:?} is already in progress",
+                migration.former().base_dir(),
+                self.files_mgr.base_dir()
+            )
+        }
+        prepare_storage_dir(&base_dir, false)?;
+        let dir_guard = self.storage_dirs.register(&self.config.name, &base_dir)?;
+        let files_mgr = Arc::new(self.files_mgr_settings.build(base_dir.clone()).await?);
+        files_mgr.account_existing_files(&InitProgress::new(&self.config.name));
+        // switch the updates to the new directory
+        let from = std::mem::replace(&mut self.files_mgr, files_mgr);
+        *self.handle.files_mgr.lock().unwrap() = Arc::downgrade(&self.files_mgr);
+        let from_guard = std::mem::replace(&mut self.dir_guard, dir_guard);
+        // stop the background tasks of the former directory (its maintenance jobs and statistics, respawned
+        // on the new one below), its entries being still read until they are copied
+        from.begin_close();
+        let migration = Arc::new(Migration::new(from, from_guard));
+        migration.spawn(Arc::downgrade(&self.files_mgr));
+        if !self.maintenance.is_empty() {
+            self.maintenance.spawn(Arc::downgrade(&self.files_mgr));
+        }
+        spawn_db_stats_refresh(Arc::downgrade(&self.files_mgr));
+        if let Some(stats_publisher) = &self.stats_publisher {
+            stats_publisher.spawn(Arc::downgrade(&self.files_mgr));
+        }
+        self.migration = Some(migration);
+        if let Some(volume_cfg) = self.config.volume_cfg.as_object_mut() {
+            volume_cfg.insert(PROP_STORAGE_DIR.into(), dir.into());
+            volume_cfg.insert("dir_full_path".into(), base_dir.to_string_lossy().into());
+        }
+        Ok(format!("migration to {:?} started", base_dir))
+    }
+
+    // Return true if the entry is in the former directory of a migration in progress (i.e. possibly not copied yet)
+    async fn exists_in_former_dir(&self, key: Option<&keyexpr>) -> ZResult<bool> {
+        match &self.migration {
+            Some(migration) => migration.exists_former(key.map(|k| k.as_str())).await,
+            None => Ok(false),
+        }
+    }
+
+    // The keys and timestamps of all the entries stored by a FilesMgr (of the storage's directory, or of a migration's former one)
+    // If `subtree` is set, only the entries under this key are returned.
+    async fn entries_of(
+        &self,
+        files_mgr: &FilesMgr,
+        subtree: Option<&keyexpr>,
+    ) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        let selector = match subtree {
+            Some(key) => OwnedKeyExpr::try_from(format!("{key}/**"))?,
+            None => OwnedKeyExpr::try_from("**")?,
+        };
+        let zpaths = match files_mgr.indexed_entries() {
+            Some(entries) => entries,
+            None if files_mgr.begin_index_load() => {
+                // walk all the entries, to load the index
+                let walked = self.walk_entries(files_mgr, None).await;
+                files_mgr.end_index_load(walked.as_deref().ok());
+                walked?
+            }
+            None => self.walk_entries(files_mgr, subtree).await?,
+        };
+        let mut result = Vec::new();
+        for (zpath, timestamp) in zpaths {
+            if zpath == ROOT_KEY {
+                if subtree.is_none()
+                    && files_mgr.is_tenant_key(ROOT_KEY)
+                    && self.is_readable_key(None)
+                    && self.is_storage_key(None)
+                {
+                    result.push((None, timestamp));
+                }
+                continue;
+            }
+            match OwnedKeyExpr::new(zpath.as_str()) {
+                Ok(ke)
+                    if selector.intersects(&ke)
+                        && files_mgr.is_tenant_key(&zpath)
+                        && self.is_readable_key(Some(&ke))
+                        && self.is_storage_key(Some(&ke)) =>
+                {
+                    result.push((Some(ke), timestamp))
+                }
+                Ok(_) => (),
+                Err(e) => warn!("Getting all entries : invalid key {} : {}", zpath, e),
+            }
+        }
+        Ok(result)
+    }
+
+    // Walk the files (and the archive bundles) of a FilesMgr, returning the zpath and timestamp of each entry
+    // readable by the storage's ACL (under `subtree` if set), the root entry being returned as ROOT_KEY
+    async fn walk_entries(
+        &self,
+        files_mgr: &FilesMgr,
+        subtree: Option<&keyexpr>,
+    ) -> ZResult<Vec<(String, Timestamp)>> {
+        walk_entries(
+            files_mgr,
+            subtree,
+            self.empty_payload == EmptyPayload::Delete,
+            |key| self.is_readable_key(key),
+        )
+        .await
+    }
+
+    // The keys and timestamps of the entries of the storage (only the ones under `subtree` if set),
+    // including the ones not copied yet by a migration in progress, in the given order
+    async fn entries(
+        &self,
+        subtree: Option<&keyexpr>,
+        order: SortOrder,
+    ) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        let mut result = self.entries_of(&self.files_mgr, subtree).await?;
+        if let Some(migration) = &self.migration {
+            // add the entries not copied yet from the former directory
+            let keys: HashSet<_> = result.iter().map(|(key, _)| key.clone()).collect();
+            for entry in self.entries_of(migration.former(), subtree).await? {
+                if !keys.contains(&entry.0) {
+                    result.push(entry);
+                }
+            }
+        }
+        if let Some(overlay) = &self.overlay {
+            // add the entries of the lower layer, unless overwritten or deleted in the upper one
+            let keys: HashSet<_> = result.iter().map(|(key, _)| key.clone()).collect();
+            for entry in self.entries_of(overlay.lower(), subtree).await? {
+                let zpath = entry.0.as_ref().map_or(ROOT_KEY, |k| k.as_str());
+                if !keys.contains(&entry.0) && !self.files_mgr.is_whiteout(zpath).await? {
+                    result.push(entry);
+                }
+            }
+        }
+        let by_key = |a: &(Option<OwnedKeyExpr>, Timestamp),
+                      b: &(Option<OwnedKeyExpr>, Timestamp)| {
+            a.0.as_ref()
+                .map(|k| k.as_str())
+                .cmp(&b.0.as_ref().map(|k| k.as_str()))
+        };
+        match order {
+            SortOrder::Key => result.sort_by(by_key),
+            SortOrder::Timestamp => result.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| by_key(a, b))),
+        }
+        Ok(result)
+    }
+
+    // Read the value of an entry, from the former directory of a migration in progress if not copied yet, or from the
+    // lower layer if missing in the storage's directory
+    async fn read_entry(
+        &self,
+        key: Option<&keyexpr>,
+        zfile: &ZFile<'_>,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        let read = self.files_mgr.read_file(zfile).await?;
+        if let (None, Some(migration)) = (&read, &self.migration) {
+            // the entry may not be copied yet
+            let _guard = migration.lock().await;
+            return migration.read_former(key.map(|k| k.as_str())).await;
+        }
+        if let (None, Some(overlay)) = (&read, &self.overlay) {
+            return overlay.read(&self.files_mgr, key, &zfile.zpath).await;
+        }
+        Ok(read)
+    }
+
+    // Call `f` with the key and the value of each entry under `subtree` (of all the entries if None), in the given order.
+    // If `changed_since` is set, only the entries with a more recent timestamp are read.
+    async fn for_each_entry<F>(
+        &self,
+        subtree: Option<&keyexpr>,
+        order: SortOrder,
+        changed_since: Option<NTP64>,
+        mut f: F,
+    ) -> ZResult<()>
+    where
+        F: FnMut(Option<&keyexpr>, ZBytes, Encoding, Timestamp) + Send,
+    {
+        for (key, timestamp) in self.entries(subtree, order).await? {
+            if changed_since.is_some_and(|since| *timestamp.get_time() <= since) {
+                continue;
+            }
+            let zfile = match &key {
+                Some(k) => self.files_mgr.to_zfile(k.as_str()),
+                None => self.files_mgr.root_zfile(),
+            };
+            match self.read_entry(key.as_deref(), &zfile).await? {
+                Some((payload, _, _)) if self.is_deleted_payload(&payload) => (),
+                Some((payload, encoding, timestamp)) => {
+                    f(key.as_deref(), payload, encoding, timestamp)
+                }
+                None => (),
+            }
+        }
+        Ok(())
+    }
+
+    // Return true if the full key of an entry matches the storage's key expression (the files of a shared or
+    // pre-populated directory may not)
+    fn is_storage_key(&self, key: Option<&keyexpr>) -> bool {
+        keyexpr::new(self.full_key(key).as_str())
+            .is_ok_and(|ke| self.config.key_expr.intersects(ke))
+    }
+
+    // The full key of an entry (i.e. prefixed with `strip_prefix`)
+    fn full_key(&self, key: Option<&keyexpr>) -> String {
+        match (&self.config.strip_prefix, key) {
+            (Some(prefix), Some(key)) => format!("{prefix}/{key}"),
+            (Some(prefix), None) => prefix.to_string(),
+            (None, Some(key)) => key.to_string(),
+            (None, None) => String::new(),
+        }
+    }
+
+    // The inventory manifest of the entries under `subtree` (of all the entries if None), with their full keys
+    async fn manifest(
+        &self,
+        subtree: Option<&keyexpr>,
+        order: SortOrder,
+        changed_since: Option<NTP64>,
+    ) -> ZResult<Vec<ManifestEntry>> {
+        let mut entries = Vec::new();
+        self.for_each_entry(
+            subtree,
+            order,
+            changed_since,
+            |key, payload, encoding, timestamp| {
+                entries.push(ManifestEntry::new(
+                    self.full_key(key),
+                    &payload.to_bytes(),
+                    encoding,
+                    timestamp,
+                ))
+            },
+        )
+        .await?;
+        Ok(entries)
+    }
+
+    // The inventory manifest of the entries under a key, replied as a single value (as JSON, or as CSV if `csv`)
+    async fn export_manifest(
+        &self,
+        key: Option<&keyexpr>,
+        csv: bool,
+        order: SortOrder,
+        changed_since: Option<NTP64>,
+    ) -> ZResult<Vec<StoredData>> {
+        let entries = self.manifest(key, order, changed_since).await?;
+        let Some(timestamp) = entries.iter().map(|e| *e.timestamp()).max() else {
+            return Ok(vec![]);
+        };
+        let (payload, encoding) = if csv {
+            (manifest::to_csv(&entries), Encoding::TEXT_CSV)
+        } else {
+            (manifest::to_json(&entries), Encoding::APPLICATION_JSON)
+        };
+        Ok(vec![StoredData {
+            payload: payload.into(),
+            encoding,
+            timestamp,
+        }])
+    }
+
+    // The replication digest of the entries under a key, replied as a single value (as JSON)
+    // with the timestamp of the most recent entry
+    async fn export_digest(&self, key: Option<&keyexpr>) -> ZResult<Vec<StoredData>> {
+        let entries: Vec<(String, Timestamp)> = self
+            .entries(key, SortOrder::Key)
+            .await?
+            .into_iter()
+            .map(|(k, timestamp)| (self.full_key(k.as_deref()), timestamp))
+            .collect();
+        let Some(timestamp) = entries.iter().map(|(_, t)| *t).max() else {
+            return Ok(vec![]);
+        };
+        let digest = digest::digest(&entries, self.digest_interval, SystemTime::now());
+        Ok(vec![StoredData {
+            payload: digest.to_string().into(),
+            encoding: Encoding::APPLICATION_JSON,
+            timestamp,
+        }])
+    }
+
+    // The summaries of the direct children of a key (of their subtrees), replied as a single value (as JSON) with the
+    // timestamp of the most recent entry. Only the entries' timestamps and the files' sizes are used: no file is read.
+    async fn export_tree(
+        &self,
+        key: Option<&keyexpr>,
+        changed_since: Option<NTP64>,
+    ) -> ZResult<Vec<StoredData>> {
+        let prefix = key.map(|k| format!("{k}/")).unwrap_or_default();
+        let mut tree = TreeSummary::default();
+        for (entry_key, timestamp) in self.entries(key, SortOrder::Key).await? {
+            if changed_since.is_some_and(|since| *timestamp.get_time() <= since) {
+                continue;
+            }
+            // the entry of the key itself (or the root key) isn't a child
+            let Some(entry_key) = entry_key else {
+                continue;
+            };
+            let Some(relpath) = entry_key.as_str().strip_prefix(prefix.as_str()) else {
+                continue;
+            };
+            let size = self
+                .files_mgr
+                .file_size(&self.files_mgr.to_zfile(entry_key.as_str()))
+                .unwrap_or(0);
+            tree.add(relpath, size, timestamp);
+        }
+        let Some(timestamp) = tree.latest() else {
+            return Ok(vec![]);
+        };
+        let base = self.full_key(key);
+        let payload = tree.to_json(|child| match base.is_empty() {
+            true => child.to_string(),
+            false => format!("{base}/{child}"),
+        });
+        Ok(vec![StoredData {
+            payload: payload.into(),
+            encoding: Encoding::APPLICATION_JSON,
+            timestamp,
+        }])
+    }
+
+    // The number of entries under a key (including its own one) and their total size, per encoding, from their
+    // data-info (without reading the files), as a JSON array (the most frequent encodings first). The reply's
+    // timestamp is the one of the most recent entry.
+    async fn export_encodings(
+        &self,
+        key: Option<&keyexpr>,
+        changed_since: Option<NTP64>,
+    ) -> ZResult<Vec<StoredData>> {
+        let mut encodings: HashMap<String, (usize, u64)> = HashMap::new();
+        let mut latest: Option<Timestamp> = None;
+        for (entry_key, timestamp) in self.entries(key, SortOrder::Key).await? {
+            if changed_since.is_some_and(|since| *timestamp.get_time() <= since) {
+                continue;
+            }
+            // (the entry may not be copied yet by a migration, or be read from the lower layer)
+            let files_mgrs = std::iter::once(self.files_mgr.as_ref())
+                .chain(self.migration.as_ref().map(|m| m.former()))
+                .chain(self.overlay.as_ref().map(|o| o.lower()));
+            let mut found = None;
+            for files_mgr in files_mgrs {
+                let zfile = match &entry_key {
+                    Some(k) => files_mgr.to_zfile(k.as_str()),
+                    None => files_mgr.root_zfile(),
+                };
+                if let Some(encoding) = files_mgr.encoding_of(&zfile).await? {
+                    found = Some((encoding, files_mgr.file_size(&zfile)));
+                    break;
+                }
+            }
+            let Some((encoding, size)) = found else {
+                continue;
+            };
+            let counts = encodings.entry(encoding.to_string()).or_default();
+            counts.0 += 1;
+            counts.1 += size.unwrap_or(0);
+            latest = latest.max(Some(timestamp));
+        }
+        let Some(timestamp) = latest else {
+            return Ok(vec![]);
+        };
+        let mut encodings: Vec<(String, (usize, u64))> = encodings.into_iter().collect();
+        encodings.sort_by(|(e1, (n1, _)), (e2, (n2, _))| n2.cmp(n1).then_with(|| e1.cmp(e2)));
+        let payload = serde_json::Value::from_iter(encodings.into_iter().map(
+            |(encoding, (entries, size))| {
+                serde_json::json!({ "encoding": encoding, "entries": entries, "size": size })
+            },
+        ))
+        .to_string();
+        Ok(vec![StoredData {
+            payload: payload.into(),
+            encoding: Encoding::APPLICATION_JSON,
+            timestamp,
+        }])
+    }
+
+    // The entries under a key stored in conflict files, with the paths (relative to their stripe's root) of their file
+    // and of the conflicting directory, replied as a single value (as JSON) with the timestamp of the most recent one
+    async fn export_conflicts(&self, key: Option<&keyexpr>) -> ZResult<Vec<StoredData>> {
+        let mut entries = Vec::new();
+        let mut latest: Option<Timestamp> = None;
+        for conflict in self.files_mgr.conflicts(key).await? {
+            let Ok(ke) = keyexpr::new(conflict.zpath.as_str()) else {
+                continue;
+            };
+            if !self.files_mgr.is_tenant_key(&conflict.zpath) || !self.is_readable_key(Some(ke)) {
+                continue;
+            }
+            entries.push(serde_json::json!({
+                "key": self.full_key(Some(ke)),
+                "timestamp": conflict.timestamp.to_string(),
+                "file": conflict.file.to_string_lossy(),
+                "dir": conflict.dir.map(|dir| dir.to_string_lossy().into_owned()),
+            }));
+            latest = latest.max(Some(conflict.timestamp));
+        }
+        let Some(timestamp) = latest else {
+            return Ok(vec![]);
+        };
+        Ok(vec![StoredData {
+            payload: serde_json::Value::from(entries).to_string().into(),
+            encoding: Encoding::APPLICATION_JSON,
+            timestamp,
+        }])
+    }
+
+    // The watermark of the storage (on its root, with the ones of the readable top-level prefixes if tracked) or of a
+    // top-level prefix, replied as a single value (as JSON) with the watermark's timestamp. Nothing is replied if no
+    // value was stored (under the prefix), nor on the other keys.
+    fn export_watermark(&self, key: Option<&keyexpr>) -> ZResult<Vec<StoredData>> {
+        if !self.is_readable_key(key) {
+            return Ok(vec![]);
+        }
+        let watermark = self.files_mgr.watermark();
+        let (payload, timestamp) = match key {
+            None => {
+                let Some(latest) = watermark.latest(None) else {
+                    return Ok(vec![]);
+                };
+                let status = watermark.status_json(|prefix| {
+                    keyexpr::new(prefix).is_ok_and(|ke| self.is_readable_key(Some(ke)))
+                });
+                (status, latest)
+            }
+            Some(key) if !key.contains('/') => {
+                let Some(latest) = watermark.latest(Some(key.as_str())) else {
+                    return Ok(vec![]);
+                };
+                (watermark::to_json(&latest), latest)
+            }
+            Some(_) => return Ok(vec![]),
+        };
+        Ok(vec![StoredData {
+            payload: payload.to_string().into(),
+            encoding: Encoding::APPLICATION_JSON,
+            timestamp,
+        }])
+    }
+
+    // A tarball of all the entries under a key (with their paths relative to this key), replied as a single value
+    // with the timestamp of the most recent entry. The encoding and timestamp of each entry are set as PAX headers.
+    async fn export_tar(
+        &self,
+        key: Option<&keyexpr>,
+        order: SortOrder,
+        changed_since: Option<NTP64>,
+    ) -> ZResult<Vec<StoredData>> {
+        let prefix = key.map(|k| format!("{k}/")).unwrap_or_default();
+        let mut files = Vec::new();
+        let mut latest: Option<Timestamp> = None;
+        self.for_each_entry(
+            key,
+            order,
+            changed_since,
+            |entry_key, payload, encoding, timestamp| {
+                // the entry of the key itself (or the root key) has no path in the tarball
+                if let Some(path) = entry_key.and_then(|k| k.as_str().strip_prefix(prefix.as_str()))
+                {
+                    latest = latest.max(Some(timestamp));
+                    files.push(TarFile {
+                        path: path.to_string(),
+                        content: payload.to_bytes().into_owned(),
+                        encoding,
+                        timestamp,
+                    });
+                }
+            },
+        )
+        .await?;
+        let Some(timestamp) = latest else {
+            return Ok(vec![]);
+        };
+        Ok(vec![StoredData {
+            payload: tar_of(&files)?.into(),
+            encoding: Encoding::from(TAR_ENCODING),
+            timestamp,
+        }])
+    }
+
+    // Store the value of an entry (received via a PUT, or unpacked from a batch)
+    async fn put_entry(
+        &self,
+        key: Option<&keyexpr>,
+        payload: ZBytes,
+        encoding: Encoding,
+        timestamp: &Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        self.put_entry_over(key, payload, encoding, timestamp, None)
+            .await
+    }
+
+    // The same as put_entry(), but if `base` is set, the value is only written if the timestamp of the stored one is
+    // still the base one (or if there's still no stored value for a None base)
+    async fn put_entry_over(
+        &self,
+        key: Option<&keyexpr>,
+        payload: ZBytes,
+        encoding: Encoding,
+        timestamp: &Timestamp,
+        base: Option<Option<&Timestamp>>,
+    ) -> ZResult<StorageInsertionResult> {
+        let zfile = match key {
+            Some(k) => self.files_mgr.to_zfile(k.as_str()),
+            None => self.files_mgr.root_zfile(),
+        };
+        if !self.files_mgr.is_tenant_key(&zfile.zpath) {
+            bail!(
+                "Received PUT on {} which doesn't belong to any tenant of the storage - rejected",
+                zfile.zpath
+            )
+        }
+        // don't let the migration copy the entry meanwhile
+        let _migration_guard = match &self.migration {
+            Some(migration) => Some(migration.lock().await),
+            None => None,
+        };
+        if self.worm
+            && (self.files_mgr.exists(&zfile).await? || self.exists_in_former_dir(key).await?)
+        {
+            warn!(
+                "Received PUT on existing {} for write-once File System Storage - denied",
+                zfile
+            );
+            return Ok(StorageInsertionResult::Outdated);
+        }
+        if payload.is_empty() {
+            match self.empty_payload {
+                EmptyPayload::Store => (),
+                EmptyPayload::Reject => {
+                    bail!("Received PUT with empty payload for {} - rejected", zfile)
+                }
+                EmptyPayload::Delete => {
+                    if let Some(dry_run) = &self.dry_run {
+                        dry_run.delete(&zfile.zpath, self.files_mgr.exists(&zfile).await?);
+                        return Ok(StorageInsertionResult::Outdated);
+                    }
+                    // delete file
+                    self.files_mgr.delete_file(&zfile).await?;
+                    if let Some(migration) = &self.migration {
+                        migration.delete_former(key.map(|k| k.as_str())).await?;
+                    }
+                    self.after_delete(key, &zfile, timestamp).await;
+                    return Ok(StorageInsertionResult::Deleted);
+                }
+            }
+        }
+        if self
             .files_mgr
-            .matching_files(unsafe { keyexpr::from_str_unchecked("**") })
-            .filter(|zfile| zfile.zpath != ROOT_KEY)
+            .is_outdated_by_conflict(&zfile, timestamp)
+            .await?
         {
-            let trimmed_zpath = get_trimmed_keyexpr(zfile.zpath.as_ref());
-            let trimmed_zfile = self.files_mgr.to_zfile(trimmed_zpath);
-            match self.files_mgr.read_file(&trimmed_zfile).await {
-                Ok(Some((_, _, timestamp))) => {
-                    let zpath = Some(zfile.zpath.as_ref().try_into().unwrap());
-                    result.push((zpath, timestamp));
+            debug!(
+                "Received PUT on {} conflicting with more recent entries - ignored",
+                zfile
+            );
+            return Ok(StorageInsertionResult::Outdated);
+        }
+        if let Some(dry_run) = &self.dry_run {
+            let size = payload.len();
+            let conflict =
+                self.files_mgr
+                    .check_write(&zfile, payload.into(), &encoding, timestamp)?;
+            dry_run.put(&zfile.zpath, size, conflict.as_deref());
+            return Ok(StorageInsertionResult::Outdated);
+        }
+        // write file
+        let size = payload.len();
+        match base {
+            Some(base) => {
+                self.files_mgr
+                    .write_file_over(&zfile, payload.into(), encoding, timestamp, base)
+                    .await?
+            }
+            None => {
+                self.files_mgr
+                    .write_file(&zfile, payload.into(), encoding, timestamp)
+                    .await?
+            }
+        }
+        self.after_put(key, &zfile, size, timestamp).await;
+        Ok(StorageInsertionResult::Inserted)
+    }
+
+    // Run the hook and publish the notification (if configured) of a successful PUT
+    async fn after_put(
+        &self,
+        key: Option<&keyexpr>,
+        zfile: &ZFile<'_>,
+        size: usize,
+        timestamp: &Timestamp,
+    ) {
+        let full_key = self.full_key(key);
+        if let Some(hooks) = &self.hooks {
+            hooks.on_put(&full_key, zfile.fspath());
+        }
+        if let Some(notifier) = &self.notifier {
+            notifier
+                .notify(&full_key, "put", timestamp, Some(size))
+                .await;
+        }
+    }
+
+    // Run the hook and publish the notification (if configured) of a successful DELETE
+    async fn after_delete(&self, key: Option<&keyexpr>, zfile: &ZFile<'_>, timestamp: &Timestamp) {
+        let full_key = self.full_key(key);
+        if let Some(hooks) = &self.hooks {
+            hooks.on_delete(&full_key, zfile.fspath());
+        }
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(&full_key, "delete", timestamp, None).await;
+        }
+    }
+
+    // Forget the migration once all the entries were copied, releasing the former directory
+    fn end_complete_migration(&mut self) {
+        if self.migration.as_ref().is_some_and(|m| m.is_complete()) {
+            debug!(
+                "Storage {} now only uses {:?}",
+                self.config.name,
+                self.files_mgr.base_dir()
+            );
+            self.migration = None;
+        }
+    }
+
+    // With `empty_payload` set to "delete", an empty file is considered as missing
+    fn is_deleted_payload(&self, payload: &ZBytes) -> bool {
+        payload.is_empty() && self.empty_payload == EmptyPayload::Delete
+    }
+
+    // Prefetch in background the files of the keys following a key read sequentially (if read-ahead is enabled)
+    fn read_ahead(&self, zpath: &str) {
+        let zpaths = self.files_mgr.read_ahead_keys(zpath);
+        if zpaths.is_empty() {
+            return;
+        }
+        spawn_storage_task(Arc::downgrade(&self.files_mgr), |files_mgr| async move {
+            for zpath in zpaths {
+                let Some(mgr) = FilesMgr::upgrade(&files_mgr) else {
+                    return;
+                };
+                if let Err(e) = mgr.prefetch(&zpath).await {
+                    debug!("Failed to prefetch the file of {}: {}", zpath, e);
+                }
+            }
+        });
+    }
+
+    // The span of an operation on the storage, identified by its name and directory.
+    // The byte counts (or entries count for the traversal) are recorded once known.
+    fn op_span(&self, op: &'static str, key: Option<&keyexpr>) -> Span {
+        debug_span!(
+            "fs_storage",
+            op,
+            storage = %self.config.name,
+            dir = ?self.files_mgr.base_dir(),
+            key = key.map(|k| k.as_str()).unwrap_or(ROOT_KEY),
+            bytes = field::Empty,
+            entries = field::Empty,
+        )
+    }
+}
+
+// The operations of the storage, once intercepted
+impl FileSystemStorage {
+    async fn put_op(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        payload: ZBytes,
+        encoding: Encoding,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        let span = self.op_span("put", key.as_deref());
+        span.record("bytes", payload.len());
+        async move {
+            self.end_complete_migration();
+            self.files_mgr.check_available()?;
+            if let Some(acl) = &self.acl {
+                acl.check(AclOp::Write, key.as_deref())?;
+            }
+            if self.read_only {
+                return self.reject_update("PUT");
+            }
+            let timestamp = self.timestamp_skew.check(
+                timestamp,
+                "PUT",
+                key.as_ref().map_or("", |k| k.as_str()),
+            )?;
+            if let Some(op) = self.control_op(key.as_deref()) {
+                // the control PUT isn't stored
+                let report = self.run_control_op(op, &payload, &timestamp).await?;
+                info!(
+                    "{} operation requested on storage {}: {}",
+                    op, self.config.name, report
+                );
+                return Ok(StorageInsertionResult::Outdated);
+            }
+            if is_unpack_request(&encoding) {
+                // only the files of the archive are stored, not the PUT itself
+                let report = self.unpack(key.as_deref(), &payload, &timestamp).await?;
+                info!(
+                    "Unpack requested on storage {}: {}",
+                    self.config.name, report
+                );
+                return Ok(StorageInsertionResult::Outdated);
+            }
+            self.put_entry(key.as_deref(), payload, encoding, &timestamp)
+                .await
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn delete_op(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        let span = self.op_span("delete", key.as_deref());
+        async move {
+            self.end_complete_migration();
+            self.files_mgr.check_available()?;
+            if let Some(acl) = &self.acl {
+                acl.check(AclOp::Delete, key.as_deref())?;
+            }
+            if self.read_only {
+                return self.reject_update("DELETE");
+            }
+            let timestamp =
+                self.timestamp_skew
+                    .check(timestamp, "DELETE", key.as_ref().map_or("", |k| k.as_str()))?;
+            let zfile = match &key {
+                Some(k) => self.files_mgr.to_zfile(k.as_str()),
+                None => self.files_mgr.root_zfile(),
+            };
+            if !self.files_mgr.is_tenant_key(&zfile.zpath) {
+                bail!(
+                    "Received DELETE on {} which doesn't belong to any tenant of the storage - rejected",
+                    zfile.zpath
+                )
+            }
+            if self.worm {
+                warn!(
+                    "Received DELETE on {} for write-once File System Storage - denied",
+                    zfile
+                );
+                return Ok(StorageInsertionResult::Outdated);
+            }
+            if let Some(dry_run) = &self.dry_run {
+                dry_run.delete(&zfile.zpath, self.files_mgr.exists(&zfile).await?);
+                return Ok(StorageInsertionResult::Outdated);
+            }
+            // don't let the migration copy the entry meanwhile
+            let _migration_guard = match &self.migration {
+                Some(migration) => Some(migration.lock().await),
+                None => None,
+            };
+            // delete file
+            self.files_mgr.delete_file(&zfile).await?;
+            if let Some(migration) = &self.migration {
+                // for the entry not to be copied afterwards
+                migration
+                    .delete_former(key.as_deref().map(|k| k.as_str()))
+                    .await?;
+            }
+            self.after_delete(key.as_deref(), &zfile, &timestamp).await;
+            Ok(StorageInsertionResult::Deleted)
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn get_op(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        let Some(failover) = &mut self.failover else {
+            return self.read_op(key, parameters).await;
+        };
+        let e = match self.files_mgr.check_available() {
+            Ok(()) => {
+                failover.fail_back(&self.config.name);
+                return self.read_op(key, parameters).await;
+            }
+            Err(e) => e,
+        };
+        let replica = failover
+            .fail_over(&self.config.name, &e.to_string(), &self.files_mgr_settings)
+            .await
+            .map_err(|e2| zerror!("{} (and the failover failed: {})", e, e2))?;
+        // the query is answered from the replica, with the same settings
+        let primary = std::mem::replace(&mut self.files_mgr, replica);
+        let result = self.read_op(key, parameters).await;
+        self.files_mgr = primary;
+        result
+    }
+
+    async fn read_op(
+        &mut self,
+        key: Option<OwnedKeyExpr>,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        let span = self.op_span("get", key.as_deref());
+        self.end_complete_migration();
+        let result = async {
+            self.files_mgr.check_available()?;
+            // with a format, the ACL and the tenants are checked for each entry
+            let parameters = Parameters::from(parameters);
+            let order = match parameters.get(PARAM_SORT) {
+                None | Some(SORT_KEY) => SortOrder::Key,
+                Some(SORT_TIMESTAMP) => SortOrder::Timestamp,
+                Some(sort) => bail!(
+                    "Unknown value for `{}` parameter: {} - must be one of: {}, {}",
+                    PARAM_SORT,
+                    sort,
+                    SORT_KEY,
+                    SORT_TIMESTAMP
+                ),
+            };
+            let changed_since = match parameters.get(PARAM_CHANGED_SINCE) {
+                Some(since) => Some(parse_time(PARAM_CHANGED_SINCE, since)?),
+                None => None,
+            };
+            let conditions = Conditions::from_parameters(&parameters)?;
+            let range = match parameters.get(PARAM_RANGE) {
+                Some(range) => Some(parse_range(range)?),
+                None => None,
+            };
+            match parameters.get(PARAM_FORMAT) {
+                Some(FORMAT_TAR) => {
+                    return self.export_tar(key.as_deref(), order, changed_since).await
                 }
-                Ok(None) => (), // file not found, do nothing
-                Err(e) => warn!(
-                    "Getting all entries : failed to read file {} : {}",
-                    zfile, e
+                Some(FORMAT_MANIFEST) => {
+                    return self
+                        .export_manifest(key.as_deref(), false, order, changed_since)
+                        .await
+                }
+                Some(FORMAT_MANIFEST_CSV) => {
+                    return self
+                        .export_manifest(key.as_deref(), true, order, changed_since)
+                        .await
+                }
+                Some(FORMAT_DIGEST) => return self.export_digest(key.as_deref()).await,
+                Some(FORMAT_TREE) => return self.export_tree(key.as_deref(), changed_since).await,
+                Some(FORMAT_ENCODINGS) => {
+                    return self.export_encodings(key.as_deref(), changed_since).await
+                }
+                Some(FORMAT_CONFLICTS) => return self.export_conflicts(key.as_deref()).await,
+                Some(FORMAT_WATERMARK) => return self.export_watermark(key.as_deref()),
+                Some(format) => bail!(
+                    "Unknown value for `{}` parameter: {} - must be one of: {}, {}, {}, {}, {}, {}, {}, {}",
+                    PARAM_FORMAT,
+                    format,
+                    FORMAT_TAR,
+                    FORMAT_MANIFEST,
+                    FORMAT_MANIFEST_CSV,
+                    FORMAT_DIGEST,
+                    FORMAT_TREE,
+                    FORMAT_ENCODINGS,
+                    FORMAT_CONFLICTS,
+                    FORMAT_WATERMARK
                 ),
+                None => (),
+            }
+            let zfile = match &key {
+                Some(k) => self.files_mgr.to_zfile(k.as_str()),
+                None => self.files_mgr.root_zfile(),
+            };
+            if !self.files_mgr.is_tenant_key(&zfile.zpath) {
+                return Ok(vec![]);
+            }
+            if let Some(acl) = &self.acl {
+                acl.check(AclOp::Read, key.as_deref())?;
+            }
+            if self.expose_fs_path && parameters.contains_key(PARAM_FS_PATH) {
+                // reply with the absolute path of the file, for local consumers to open it directly
+                return match self.files_mgr.locate_file(&zfile).await {
+                    Ok(Some((path, _, timestamp))) => {
+                        // (the stub of an offloaded value being rehydrated, to be read)
+                        self.files_mgr.rehydrate(&path).await?;
+                        Ok(vec![StoredData {
+                            payload: path.to_string_lossy().into_owned().into(),
+                            encoding: Encoding::ZENOH_STRING.with_schema(FS_PATH_SCHEMA),
+                            timestamp,
+                        }])
+                    }
+                    Ok(None) => Ok(vec![]),
+                    Err(e) => Err(format!(
+                        "Get key {:?} : failed to locate file {} : {}",
+                        key, zfile, e
+                    )
+                    .into()),
+                };
+            }
+            if let Some(since) = changed_since {
+                // answered from data-info, without reading the file
+                if let Ok(Some((_, _, timestamp))) = self.files_mgr.locate_file(&zfile).await {
+                    if *timestamp.get_time() <= since {
+                        return Ok(vec![]);
+                    }
+                }
+            }
+            if let Some(conditions) = &conditions {
+                // answered from data-info, without reading the file
+                if let Ok(Some((_, _, timestamp))) = self.files_mgr.locate_file(&zfile).await {
+                    if conditions.not_modified(&timestamp) {
+                        return Ok(vec![Conditions::not_modified_reply(timestamp)]);
+                    }
+                }
+            }
+            if self.migration.is_none()
+                && self.overlay.is_none()
+                && self.files_mgr.is_unknown(&zfile).await?
+            {
+                // never written (the former directory of a migration being checked by read_entry)
+                return Ok(vec![]);
+            }
+            self.read_ahead(&zfile.zpath);
+            let accepts_gzip = parameters
+                .get(PARAM_ACCEPT_ENCODING)
+                .is_some_and(|accepted| accepted.split(',').any(|e| e.trim() == ACCEPT_GZIP));
+            if accepts_gzip && range.is_none() {
+                // reply with the compressed bytes if available, saving their decompression (and recompression by
+                // the consumer), or else with the value as usual
+                match self.files_mgr.read_compressed(&zfile).await {
+                    Ok(Some((_, _, timestamp)))
+                        if changed_since.is_some_and(|since| *timestamp.get_time() <= since) =>
+                    {
+                        return Ok(vec![])
+                    }
+                    Ok(Some((payload, encoding, timestamp))) => {
+                        return Ok(vec![StoredData {
+                            payload,
+                            encoding: gzip_encoding(encoding),
+                            timestamp,
+                        }])
+                    }
+                    Ok(None) => (),
+                    Err(e) => {
+                        return Err(format!(
+                            "Get key {:?} : failed to read file {} : {}",
+                            key, zfile, e
+                        )
+                        .into())
+                    }
+                }
+            }
+            // a range is read from the frames covering it if possible, or else taken from the whole value
+            let (read, to_slice) = match &range {
+                Some(range) => match self.files_mgr.read_range(&zfile, range.clone()).await {
+                    Ok(None) => (self.read_entry(key.as_deref(), &zfile).await, Some(range)),
+                    read => (read, None),
+                },
+                None => (self.read_entry(key.as_deref(), &zfile).await, None),
+            };
+            match read {
+                Ok(Some((payload, _, _))) if self.is_deleted_payload(&payload) => Ok(vec![]),
+                Ok(Some((_, _, timestamp)))
+                    if changed_since.is_some_and(|since| *timestamp.get_time() <= since) =>
+                {
+                    Ok(vec![])
+                }
+                // (an archived value, or read from the lower layer)
+                Ok(Some((_, _, timestamp)))
+                    if conditions
+                        .as_ref()
+                        .is_some_and(|c| c.not_modified(&timestamp)) =>
+                {
+                    Ok(vec![Conditions::not_modified_reply(timestamp)])
+                }
+                Ok(Some((payload, encoding, timestamp))) => Ok(vec![StoredData {
+                    payload: match to_slice {
+                        Some(range) => slice_payload(payload, range),
+                        None => payload,
+                    },
+                    encoding,
+                    timestamp,
+                }]),
+                Ok(None) => Ok(vec![]),
+                Err(e) => {
+                    Err(format!("Get key {:?} : failed to read file {} : {}", key, zfile, e).into())
+                }
             }
         }
-        Ok(result)
+        .instrument(span.clone())
+        .await;
+        if let Ok(data) = &result {
+            span.record("bytes", data.iter().map(|d| d.payload.len()).sum::<usize>());
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Storage for FileSystemStorage {
+    fn get_admin_status(&self) -> serde_json::Value {
+        let mut status = self.config.to_json_value();
+        if let Some(obj) = status.as_object_mut() {
+            obj.extend(self.files_mgr.stats_json());
+            obj.insert("initialization".into(), self.init_progress.status_json());
+            obj.insert("log".into(), self.files_mgr.log().status_json());
+            if let Some(dry_run) = &self.dry_run {
+                obj.insert(PROP_STORAGE_DRY_RUN.into(), dry_run.status_json());
+            }
+            if let Some(failover) = &self.failover {
+                obj.insert("failover".into(), failover.status_json());
+            }
+            if let Some(overlay) = &self.overlay {
+                obj.insert("overlay".into(), overlay.status_json(&self.files_mgr));
+            }
+            let maintenance = self.maintenance.status_json();
+            if maintenance.as_object().is_some_and(|jobs| !jobs.is_empty()) {
+                obj.insert(PROP_STORAGE_MAINTENANCE.into(), maintenance);
+            }
+            if let Some(migration) = &self.migration {
+                obj.insert(
+                    "migration".into(),
+                    migration.status_json(self.files_mgr.base_dir()),
+                );
+            }
+            if let Some(import) = &self.import {
+                obj.insert("import".into(), import.status_json());
+            }
+            if let Some(scrub) = &self.scrub {
+                obj.insert(CONTROL_SCRUB.into(), scrub.status_json());
+            }
+            if let Some(sync) = &self.last_sync {
+                obj.insert(CONTROL_SYNC.into(), sync.clone());
+            }
+            if let Some(purge) = &self.last_purge {
+                obj.insert(CONTROL_PURGE.into(), purge.clone());
+            }
+            obj.insert(
+                PROP_STORAGE_SKEWED_TIMESTAMPS.into(),
+                self.timestamp_skew.status_json(),
+            );
+        }
+        status
+    }
+
+    async fn put(
+        &mut self,
+        mut key: Option<OwnedKeyExpr>,
+        payload: ZBytes,
+        encoding: Encoding,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        let start = Instant::now();
+        let interceptors = self.interceptors.clone();
+        let mut result = Ok(());
+        for interceptor in &interceptors {
+            result = interceptor.before_put(&mut key, &payload, &encoding, &timestamp);
+            if result.is_err() {
+                break;
+            }
+        }
+        let result = match result {
+            Ok(()) => {
+                let runtime = self.runtime.clone();
+                let log = self.files_mgr.log().clone();
+                run_on(
+                    runtime.as_deref(),
+                    log.scope(self.put_op(key.clone(), payload.clone(), encoding, timestamp)),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        for interceptor in interceptors.iter().rev() {
+            interceptor.after_put(key.as_deref(), &payload, &result);
+        }
+        self.files_mgr
+            .log()
+            .op("put", key.as_deref(), start, &result);
+        if let (Some(dry_run), Err(_)) = (&self.dry_run, &result) {
+            dry_run.reject();
+        }
+        result
+    }
+
+    /// Function called for each incoming delete request to this storage.
+    async fn delete(
+        &mut self,
+        mut key: Option<OwnedKeyExpr>,
+        timestamp: Timestamp,
+    ) -> ZResult<StorageInsertionResult> {
+        let start = Instant::now();
+        let interceptors = self.interceptors.clone();
+        let mut result = Ok(());
+        for interceptor in &interceptors {
+            result = interceptor.before_delete(&mut key, &timestamp);
+            if result.is_err() {
+                break;
+            }
+        }
+        let result = match result {
+            Ok(()) => {
+                let runtime = self.runtime.clone();
+                let log = self.files_mgr.log().clone();
+                run_on(
+                    runtime.as_deref(),
+                    log.scope(self.delete_op(key.clone(), timestamp)),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        for interceptor in interceptors.iter().rev() {
+            interceptor.after_delete(key.as_deref(), &result);
+        }
+        self.files_mgr
+            .log()
+            .op("delete", key.as_deref(), start, &result);
+        if let (Some(dry_run), Err(_)) = (&self.dry_run, &result) {
+            dry_run.reject();
+        }
+        result
+    }
+
+    /// Function to retrieve the sample associated with a single key.
+    async fn get(
+        &mut self,
+        mut key: Option<OwnedKeyExpr>,
+        parameters: &str,
+    ) -> ZResult<Vec<StoredData>> {
+        let start = Instant::now();
+        let interceptors = self.interceptors.clone();
+        let mut result = Ok(());
+        for interceptor in &interceptors {
+            result = interceptor.before_get(&mut key, parameters);
+            if result.is_err() {
+                break;
+            }
+        }
+        let mut result = match result {
+            Ok(()) => {
+                let runtime = self.runtime.clone();
+                let log = self.files_mgr.log().clone();
+                run_on(
+                    runtime.as_deref(),
+                    log.scope(self.get_op(key.clone(), parameters)),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        for interceptor in interceptors.iter().rev() {
+            interceptor.after_get(key.as_deref(), &mut result);
+        }
+        self.files_mgr
+            .log()
+            .op("get", key.as_deref(), start, &result);
+        result
+    }
+
+    async fn get_all_entries(&self) -> ZResult<Vec<(Option<OwnedKeyExpr>, Timestamp)>> {
+        let span = self.op_span(
+            "get_all_entries",
+            Some(unsafe { keyexpr::from_str_unchecked("**") }),
+        );
+        let task = async {
+            self.files_mgr.check_available()?;
+            let result = self.entries(None, SortOrder::Key).await?;
+            span.record("entries", result.len());
+            Ok(result)
+        };
+        let task = self.files_mgr.log().scope(task.instrument(span.clone()));
+        run_on(self.runtime.as_deref(), task).await
+    }
+}
+
+// The storage is expected to be closed by its volume (see FileSystemVolume::close). Otherwise, its destructor (which
+// may run in an async context, e.g. on the router's shutdown, and must not block) only stops its background tasks, the
+// destructor of the FilesMgr flushing it on a best effort once they release it
+impl Drop for FileSystemStorage {
+    fn drop(&mut self) {
+        if self.files_mgr.begin_close() {
+            warn!(
+                "File System Storage {} dropped without being closed: closed on a best effort, without waiting for its background tasks nor its mirror",
+                self.config.name
+            );
+        }
     }
 }
@@ -0,0 +1,83 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{fs::Metadata, path::Path};
+
+// Check if a path is a link (not followed): a symbolic link, or on Windows also an NTFS junction, a mount point or
+// another reparse point redirecting the path, all being followed like the symbolic links with `follow_links`
+pub(crate) fn is_symlink<P: AsRef<Path>>(path: P) -> bool {
+    match path.as_ref().symlink_metadata() {
+        Ok(metadata) => is_link(path.as_ref(), &metadata),
+        Err(_) => false,
+    }
+}
+
+// Check if an entry is a link, from its metadata (the link not being followed)
+#[cfg(unix)]
+#[inline(always)]
+pub(crate) fn is_link(_path: &Path, metadata: &Metadata) -> bool {
+    metadata.file_type().is_symlink()
+}
+
+#[cfg(windows)]
+pub(crate) fn is_link(path: &Path, metadata: &Metadata) -> bool {
+    use std::os::windows::fs::{FileTypeExt, MetadataExt};
+
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() || file_type.is_symlink_dir() || file_type.is_symlink_file() {
+        return true;
+    }
+    if metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT == 0 {
+        return false;
+    }
+    // another reparse point: a junction or a mount point whose tag isn't recognized as a link, or a reparse point
+    // which doesn't redirect the path (e.g. the placeholder of a cloud file, or a deduplicated file) and isn't a link
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    match (dunce::canonicalize(path), dunce::canonicalize(parent)) {
+        // (the names being case-insensitive)
+        (Ok(target), Ok(parent)) => !target
+            .as_os_str()
+            .eq_ignore_ascii_case(parent.join(name).as_os_str()),
+        // a dangling junction
+        (Err(_), Ok(_)) => true,
+        (_, Err(_)) => false,
+    }
+}
+
+// Check if a directory reached by a walk which doesn't follow the symbolic links is a link nonetheless: on Windows,
+// the reparse points redirecting the path which aren't reported as symbolic links
+#[cfg(unix)]
+#[inline(always)]
+pub(crate) fn is_unreported_link(_dir: &Path) -> bool {
+    false
+}
+
+#[cfg(windows)]
+pub(crate) fn is_unreported_link(dir: &Path) -> bool {
+    is_symlink(dir)
+}
+
+// Check if a followed link to a directory makes a loop: its target being one of its ancestors
+pub(crate) fn is_loop(link: &Path) -> bool {
+    match (
+        dunce::canonicalize(link),
+        link.parent().map(dunce::canonicalize),
+    ) {
+        (Ok(target), Some(Ok(parent))) => parent.starts_with(&target),
+        _ => false,
+    }
+}
@@ -0,0 +1,447 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fmt,
+    sync::{Arc, Mutex, Weak},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{debug, warn};
+use zenoh::{internal::bail, Result as ZResult};
+
+use crate::{files_mgt::FilesMgr, spawn_storage_task};
+
+// The maintenance jobs which can be scheduled on a storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Job {
+    // compact the data-info database
+    Compaction,
+    // remove the data-info of missing files
+    Gc,
+    // delete the entries older than the retention period
+    Retention,
+    // check that all the files can be read
+    Scrub,
+    // move the aged entries into archive bundles
+    Archival,
+    // rename back the conflict files whose path is free again
+    Normalize,
+    // offload the entries not accessed for a while to the cold tier
+    Offload,
+}
+
+impl Job {
+    pub(crate) const ALL: [Job; 7] = [
+        Job::Compaction,
+        Job::Gc,
+        Job::Retention,
+        Job::Scrub,
+        Job::Archival,
+        Job::Normalize,
+        Job::Offload,
+    ];
+
+    pub(crate) fn from_name(name: &str) -> Option<Job> {
+        Job::ALL.into_iter().find(|job| job.name() == name)
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Job::Compaction => "compaction",
+            Job::Gc => "gc",
+            Job::Retention => "retention",
+            Job::Scrub => "scrub",
+            Job::Archival => "archival",
+            Job::Normalize => "normalize",
+            Job::Offload => "offload",
+        }
+    }
+
+    // Run the job, returning a short report
+    pub(crate) async fn run(&self, files_mgr: &FilesMgr) -> ZResult<String> {
+        match self {
+            Job::Compaction => files_mgr
+                .compact_data_info()
+                .await
+                .map(|()| "data-info compacted".into()),
+            Job::Gc => files_mgr
+                .verify_data_info(None)
+                .await
+                .map(|n| format!("{n} data-info entries removed")),
+            Job::Retention => files_mgr
+                .prune_expired_entries()
+                .await
+                .map(|n| format!("{n} entries deleted")),
+            Job::Scrub => files_mgr
+                .scrub()
+                .await
+                .map(|n| format!("{n} unreadable files")),
+            Job::Archival => files_mgr
+                .archive_aged_entries()
+                .await
+                .map(|n| format!("{n} entries archived")),
+            Job::Normalize => files_mgr
+                .normalize_conflicts()
+                .await
+                .map(|n| format!("{n} conflict files normalized")),
+            Job::Offload => files_mgr
+                .offload_cold_entries()
+                .await
+                .map(|n| format!("{n} entries offloaded")),
+        }
+    }
+}
+
+impl fmt::Display for Job {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+// A broken-down UTC time, at the minute precision
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UtcMinute {
+    minute: u32,
+    hour: u32,
+    day: u32,
+    month: u32,
+    weekday: u32,
+}
+
+impl UtcMinute {
+    fn from_system_time(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let days = (secs / 86400) as i64;
+        let secs_of_day = secs % 86400;
+        // civil date from days since epoch (see http://howardhinnant.github.io/date_algorithms.html)
+        let doe = (days + 719468).rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        UtcMinute {
+            minute: ((secs_of_day / 60) % 60) as u32,
+            hour: (secs_of_day / 3600) as u32,
+            day,
+            month,
+            // 1970-01-01 was a Thursday
+            weekday: ((days + 4).rem_euclid(7)) as u32,
+        }
+    }
+}
+
+// A cron-like schedule: "<minute> <hour> <day of month> <month> <day of week>" (in UTC).
+// Each field is either `*`, a value, a range `a-b`, a list `a,b,c`, optionally with a step (`*/15`, `0-30/10`).
+// As in cron, if both day of month and day of week are restricted, a time matching either of them matches.
+#[derive(Debug, Clone)]
+pub(crate) struct CronSchedule {
+    expr: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub(crate) fn parse(expr: &str) -> ZResult<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            bail!(
+                r#"Invalid schedule "{}": expecting 5 fields "<minute> <hour> <day of month> <month> <day of week>""#,
+                expr
+            )
+        }
+        Ok(CronSchedule {
+            expr: expr.into(),
+            minutes: parse_field(expr, fields[0], 0, 59)?,
+            hours: parse_field(expr, fields[1], 0, 23)?,
+            days: parse_field(expr, fields[2], 1, 31)?,
+            months: parse_field(expr, fields[3], 1, 12)?,
+            // accept 7 for Sunday, as cron does
+            weekdays: {
+                let w = parse_field(expr, fields[4], 0, 7)?;
+                (w | (w >> 7)) & 0x7f
+            },
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        })
+    }
+
+    fn matches(&self, t: &UtcMinute) -> bool {
+        let day_match = self.days & (1 << t.day) != 0;
+        let weekday_match = self.weekdays & (1 << t.weekday) != 0;
+        let day_ok = match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday_match,
+            (false, true) => day_match,
+            (false, false) => day_match || weekday_match,
+        };
+        self.minutes & (1 << t.minute) != 0
+            && self.hours & (1 << t.hour) != 0
+            && self.months & (1 << t.month) != 0
+            && day_ok
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.expr)
+    }
+}
+
+// Parse a cron field into a bitmask of the allowed values
+fn parse_field(expr: &str, field: &str, min: u32, max: u32) -> ZResult<u64> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, Some(step)),
+                _ => bail!(r#"Invalid schedule "{}": invalid step in "{}""#, expr, part),
+            },
+            None => (part, None),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            match (a.parse::<u32>(), b.parse::<u32>()) {
+                (Ok(a), Ok(b)) => (a, b),
+                _ => bail!(r#"Invalid schedule "{}": invalid range "{}""#, expr, range),
+            }
+        } else {
+            match range.parse::<u32>() {
+                // as in cron, "a/n" means from a to the max, every n
+                Ok(v) if step.is_some() => (v, max),
+                Ok(v) => (v, v),
+                _ => bail!(r#"Invalid schedule "{}": invalid value "{}""#, expr, range),
+            }
+        };
+        if start < min || end > max || start > end {
+            bail!(
+                r#"Invalid schedule "{}": "{}" is out of range {}-{}"#,
+                expr,
+                part,
+                min,
+                max
+            )
+        }
+        for v in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+// Status of the last run of a job
+struct JobStatus {
+    last_run: SystemTime,
+    duration: Duration,
+    // the report, or the error
+    result: Result<String, String>,
+}
+
+// Runs the maintenance jobs of a storage according to their schedules
+pub(crate) struct MaintenanceScheduler {
+    jobs: Vec<(Job, CronSchedule)>,
+    status: Mutex<Vec<(Job, JobStatus)>>,
+}
+
+impl MaintenanceScheduler {
+    pub(crate) fn new(jobs: Vec<(Job, CronSchedule)>) -> Self {
+        MaintenanceScheduler {
+            jobs,
+            status: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    // Run the scheduled jobs at each minute matching their schedule, until the storage is closed
+    pub(crate) fn spawn(self: &Arc<Self>, files_mgr: Weak<FilesMgr>) {
+        let scheduler = self.clone();
+        spawn_storage_task(files_mgr, |files_mgr| async move {
+            loop {
+                // wait for the beginning of the next minute
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let next_minute = tokio::time::sleep(Duration::from_secs(60 - now.as_secs() % 60));
+                if FilesMgr::idle(&files_mgr, next_minute).await.is_none() {
+                    break;
+                }
+
+                let files_mgr = match FilesMgr::upgrade(&files_mgr) {
+                    Some(files_mgr) => files_mgr,
+                    None => break,
+                };
+                // paused while the storage's directories are unavailable
+                if let Err(e) = files_mgr.check_available() {
+                    debug!(
+                        "Maintenance jobs paused on {:?}: {}",
+                        files_mgr.base_dir(),
+                        e
+                    );
+                    continue;
+                }
+                let now = UtcMinute::from_system_time(SystemTime::now());
+                for (job, schedule) in scheduler.jobs.iter() {
+                    if schedule.matches(&now) {
+                        let _ = scheduler.run(*job, &files_mgr).await;
+                    }
+                }
+            }
+        });
+    }
+
+    // Run a job (scheduled or not), recording its status
+    pub(crate) async fn run(&self, job: Job, files_mgr: &FilesMgr) -> ZResult<String> {
+        // e.g. the gc job would remove all the data-info
+        files_mgr.check_available()?;
+        debug!("Run {} job on {:?}", job, files_mgr.base_dir());
+        let last_run = SystemTime::now();
+        let start = Instant::now();
+        let result = job.run(files_mgr).await;
+        match &result {
+            Ok(report) => debug!("{} job on {:?}: {}", job, files_mgr.base_dir(), report),
+            Err(e) => warn!("{} job on {:?} failed: {}", job, files_mgr.base_dir(), e),
+        }
+        let status = JobStatus {
+            last_run,
+            duration: start.elapsed(),
+            result: result
+                .as_ref()
+                .map(|report| report.clone())
+                .map_err(|e| e.to_string()),
+        };
+        let mut all_status = self.status.lock().unwrap();
+        all_status.retain(|(j, _)| *j != job);
+        all_status.push((job, status));
+        result
+    }
+
+    // The schedules and last-run status of the jobs (scheduled or which have run), for the admin status
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let all_status = self.status.lock().unwrap();
+        let mut result = serde_json::Map::new();
+        for job in Job::ALL {
+            let schedule = self.jobs.iter().find(|(j, _)| *j == job).map(|(_, s)| s);
+            let status = all_status.iter().find(|(j, _)| *j == job).map(|(_, s)| s);
+            if schedule.is_none() && status.is_none() {
+                continue;
+            }
+            let mut job_status = serde_json::Map::new();
+            if let Some(schedule) = schedule {
+                job_status.insert("schedule".into(), schedule.to_string().into());
+            }
+            if let Some(status) = status {
+                job_status.insert(
+                    "last_run".into(),
+                    humantime::format_rfc3339_seconds(status.last_run)
+                        .to_string()
+                        .into(),
+                );
+                job_status.insert(
+                    "duration_ms".into(),
+                    (status.duration.as_millis() as u64).into(),
+                );
+                match &status.result {
+                    Ok(report) => job_status.insert("result".into(), report.clone().into()),
+                    Err(e) => job_status.insert("error".into(), e.clone().into()),
+                };
+            }
+            result.insert(job.name().into(), job_status.into());
+        }
+        result.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Saturday 2024-06-01 12:30 UTC
+    fn saturday() -> UtcMinute {
+        UtcMinute::from_system_time(UNIX_EPOCH + Duration::from_secs(1717245000))
+    }
+
+    #[test]
+    fn utc_minute() {
+        assert_eq!(
+            saturday(),
+            UtcMinute {
+                minute: 30,
+                hour: 12,
+                day: 1,
+                month: 6,
+                weekday: 6
+            }
+        );
+    }
+
+    #[test]
+    fn schedule_matches() {
+        let matches = |expr: &str| CronSchedule::parse(expr).unwrap().matches(&saturday());
+        assert!(matches("30 12 * * *"));
+        assert!(matches("*/15 * * * *"));
+        assert!(matches("0-59/30 12 1 6 *"));
+        assert!(matches("0,30 8,12 1-7 * *"));
+        // the day of month or the day of week matching
+        assert!(matches("30 12 15 * 6"));
+        assert!(!matches("30 12 15 * 1"));
+        assert!(!matches("0 12 * * *"));
+        assert!(!matches("30 12 * 7 *"));
+        assert!(!matches("30 12 * * 0"));
+        // 7 for Sunday
+        let sunday = UtcMinute::from_system_time(UNIX_EPOCH + Duration::from_secs(1717329600));
+        assert_eq!(sunday.weekday, 0);
+        assert!(CronSchedule::parse("* * * * 7").unwrap().matches(&sunday));
+    }
+
+    #[test]
+    fn schedule_display() {
+        let schedule = CronSchedule::parse("*/5 3 * * 1-5").unwrap();
+        assert_eq!(schedule.to_string(), "*/5 3 * * 1-5");
+    }
+
+    #[test]
+    fn schedule_malformed() {
+        for expr in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "*/a * * * *",
+            "5-3 * * * *",
+            "a * * * *",
+            "1-b * * * *",
+            "1,,2 * * * *",
+        ] {
+            assert!(CronSchedule::parse(expr).is_err(), "{expr:?} was accepted");
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::collections::{HashMap, HashSet};
+
+use sha1::{Digest, Sha1};
+use zenoh::{
+    bytes::Encoding,
+    internal::{bail, zerror},
+    time::Timestamp,
+    Result as ZResult,
+};
+
+const CSV_HEADER: &str = "key,timestamp,size,encoding,sha1";
+
+// An entry of the inventory manifest of a storage
+pub(crate) struct ManifestEntry {
+    key: String,
+    timestamp: Timestamp,
+    size: usize,
+    encoding: Encoding,
+    sha1: String,
+}
+
+impl ManifestEntry {
+    pub(crate) fn new(
+        key: String,
+        content: &[u8],
+        encoding: Encoding,
+        timestamp: Timestamp,
+    ) -> Self {
+        ManifestEntry {
+            key,
+            timestamp,
+            size: content.len(),
+            encoding,
+            sha1: format!("{:x}", Sha1::digest(content)),
+        }
+    }
+
+    // An entry of a manifest in JSON
+    fn from_json(value: &serde_json::Value) -> ZResult<Self> {
+        let field = |name: &str| match value.get(name) {
+            Some(serde_json::Value::String(s)) => Ok(s.as_str()),
+            _ => Err(zerror!(
+                "Invalid manifest entry {}: missing `{}` string",
+                value,
+                name
+            )),
+        };
+        Ok(ManifestEntry {
+            key: field("key")?.to_string(),
+            timestamp: field("timestamp")?.parse().map_err(|e| {
+                zerror!(
+                    "Invalid manifest entry {}: invalid timestamp: {:?}",
+                    value,
+                    e
+                )
+            })?,
+            size: value.get("size").and_then(|s| s.as_u64()).unwrap_or(0) as usize,
+            encoding: Encoding::from(field("encoding").unwrap_or_default().to_string()),
+            sha1: field("sha1")?.to_string(),
+        })
+    }
+
+    pub(crate) fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub(crate) fn timestamp(&self) -> &Timestamp {
+        &self.timestamp
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "key": self.key,
+            "timestamp": self.timestamp.to_string(),
+            "size": self.size,
+            "encoding": self.encoding.to_string(),
+            "sha1": self.sha1,
+        })
+    }
+
+    fn to_csv(&self) -> String {
+        [
+            csv_field(&self.key),
+            csv_field(&self.timestamp.to_string()),
+            self.size.to_string(),
+            csv_field(&self.encoding.to_string()),
+            self.sha1.clone(),
+        ]
+        .join(",")
+    }
+}
+
+// The manifest as a JSON array
+pub(crate) fn to_json(entries: &[ManifestEntry]) -> String {
+    serde_json::Value::from_iter(entries.iter().map(ManifestEntry::to_json)).to_string()
+}
+
+// Parse a manifest in JSON (as produced by `to_json`)
+pub(crate) fn from_json(json: &[u8]) -> ZResult<Vec<ManifestEntry>> {
+    match serde_json::from_slice(json) {
+        Ok(serde_json::Value::Array(entries)) => {
+            entries.iter().map(ManifestEntry::from_json).collect()
+        }
+        Ok(_) => bail!("Invalid manifest: must be a JSON array"),
+        Err(e) => bail!("Invalid manifest: {}", e),
+    }
+}
+
+// The differences of a local set of entries with a remote one, to be synchronized with it
+#[derive(Default)]
+pub(crate) struct Delta {
+    // the keys missing locally, or with a different and older value
+    pub(crate) to_fetch: Vec<String>,
+    // the keys missing remotely
+    pub(crate) to_delete: Vec<String>,
+}
+
+pub(crate) fn delta(local: &[ManifestEntry], remote: &[ManifestEntry]) -> Delta {
+    let local: HashMap<&str, &ManifestEntry> = local.iter().map(|e| (e.key(), e)).collect();
+    let mut delta = Delta::default();
+    for entry in remote {
+        match local.get(entry.key()) {
+            Some(l) if l.sha1 == entry.sha1 || l.timestamp >= entry.timestamp => (),
+            _ => delta.to_fetch.push(entry.key.clone()),
+        }
+    }
+    let remote_keys: HashSet<&str> = remote.iter().map(|e| e.key()).collect();
+    for key in local.keys() {
+        if !remote_keys.contains(key) {
+            delta.to_delete.push(key.to_string());
+        }
+    }
+    delta.to_delete.sort();
+    delta
+}
+
+// The manifest as CSV, with a header line
+pub(crate) fn to_csv(entries: &[ManifestEntry]) -> String {
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for entry in entries {
+        csv.push_str(&entry.to_csv());
+        csv.push('\n');
+    }
+    csv
+}
+
+// Quote a CSV field if it contains a separator, a quote or a line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
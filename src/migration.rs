@@ -0,0 +1,207 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::SystemTime,
+};
+
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard};
+use tracing::{debug, info, warn};
+use zenoh::{
+    bytes::{Encoding, ZBytes},
+    internal::zerror,
+    key_expr::keyexpr,
+    time::Timestamp,
+    Result as ZResult,
+};
+
+use crate::{files_mgt::FilesMgr, spawn_storage_task, storage_dirs::StorageDirGuard};
+
+// The migration of a storage to a new directory.
+// The updates are applied in the new directory as soon as the migration starts, while the entries of the former
+// directory are copied in background. Meanwhile, the entries not copied yet are read from the former directory.
+pub(crate) struct Migration {
+    // the files of the former directory
+    from: Arc<FilesMgr>,
+    // keeps the former directory registered until the end of the migration
+    _from_guard: StorageDirGuard,
+    // serializes the copy of an entry with the updates of the storage
+    lock: AsyncMutex<()>,
+    started: SystemTime,
+    total: AtomicUsize,
+    copied: AtomicUsize,
+    // set at the end of the copy: Ok, or the error which interrupted it
+    result: Mutex<Option<Result<(), String>>>,
+}
+
+impl Migration {
+    pub(crate) fn new(from: Arc<FilesMgr>, from_guard: StorageDirGuard) -> Self {
+        Migration {
+            from,
+            _from_guard: from_guard,
+            lock: AsyncMutex::new(()),
+            started: SystemTime::now(),
+            total: AtomicUsize::new(0),
+            copied: AtomicUsize::new(0),
+            result: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn former(&self) -> &FilesMgr {
+        &self.from
+    }
+
+    // Return true once all the entries were copied
+    pub(crate) fn is_complete(&self) -> bool {
+        matches!(*self.result.lock().unwrap(), Some(Ok(())))
+    }
+
+    pub(crate) fn is_failed(&self) -> bool {
+        matches!(*self.result.lock().unwrap(), Some(Err(_)))
+    }
+
+    // Wait for the copy of the current entry, preventing the next one until the guard is dropped
+    pub(crate) async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().await
+    }
+
+    // Copy the entries of the former directory which are missing in the new one, in background
+    pub(crate) fn spawn(self: &Arc<Self>, to: Weak<FilesMgr>) {
+        *self.result.lock().unwrap() = None;
+        let migration = self.clone();
+        spawn_storage_task(to, |to| async move {
+            let result = migration.copy_all(&to).await;
+            match &result {
+                Ok(()) => info!(
+                    "Migration from {:?} complete: {} entries copied",
+                    migration.from.base_dir(),
+                    migration.copied.load(Ordering::Relaxed)
+                ),
+                Err(e) => warn!(
+                    "Migration from {:?} failed: {}",
+                    migration.from.base_dir(),
+                    e
+                ),
+            }
+            *migration.result.lock().unwrap() = Some(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    async fn copy_all(&self, to: &Weak<FilesMgr>) -> ZResult<()> {
+        let mut zpaths: Vec<Option<String>> = vec![None];
+        zpaths.extend(
+            self.from
+                .matching_files(unsafe { keyexpr::from_str_unchecked("**") })
+                .map(|zfile| Some(zfile.zpath.into_owned())),
+        );
+        zpaths.extend(
+            self.from
+                .archived_entries()
+                .await?
+                .into_iter()
+                .map(|(zpath, _)| Some(zpath)),
+        );
+        self.total.store(zpaths.len(), Ordering::Relaxed);
+        for zpath in zpaths {
+            // stop if the storage was closed meanwhile
+            let to = FilesMgr::upgrade(to).ok_or_else(|| zerror!("the storage was closed"))?;
+            if self.copy_entry(&to, zpath.as_deref()).await? {
+                self.copied.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        Ok(())
+    }
+
+    // Copy an entry (None for the root key) if it's missing in the new directory, returning true if copied
+    async fn copy_entry(&self, to: &FilesMgr, zpath: Option<&str>) -> ZResult<bool> {
+        let _guard = self.lock().await;
+        let to_zfile = match zpath {
+            Some(zpath) => to.to_zfile(zpath),
+            None => to.root_zfile(),
+        };
+        if to.exists(&to_zfile).await? {
+            // updated since the migration started
+            return Ok(false);
+        }
+        match self.read_former(zpath).await? {
+            Some((payload, encoding, timestamp)) => {
+                debug!("Migration: copy {}", to_zfile.zpath);
+                to.write_file(&to_zfile, payload.into(), encoding, &timestamp)
+                    .await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    // Read an entry (None for the root key) from the former directory
+    pub(crate) async fn read_former(
+        &self,
+        zpath: Option<&str>,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        let zfile = match zpath {
+            Some(zpath) => self.from.to_zfile(zpath),
+            None => self.from.root_zfile(),
+        };
+        self.from.read_file(&zfile).await
+    }
+
+    // Return true if an entry (None for the root key) is in the former directory
+    pub(crate) async fn exists_former(&self, zpath: Option<&str>) -> ZResult<bool> {
+        let zfile = match zpath {
+            Some(zpath) => self.from.to_zfile(zpath),
+            None => self.from.root_zfile(),
+        };
+        self.from.exists(&zfile).await
+    }
+
+    // Delete an entry (None for the root key) from the former directory, for it not to be copied
+    pub(crate) async fn delete_former(&self, zpath: Option<&str>) -> ZResult<()> {
+        let zfile = match zpath {
+            Some(zpath) => self.from.to_zfile(zpath),
+            None => self.from.root_zfile(),
+        };
+        if self.from.exists(&zfile).await? {
+            self.from.delete_file(&zfile).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn status_json(&self, to: &Path) -> serde_json::Value {
+        let mut status = serde_json::Map::new();
+        status.insert("from".into(), self.from.base_dir().to_string_lossy().into());
+        status.insert("to".into(), to.to_string_lossy().into());
+        status.insert(
+            "started".into(),
+            humantime::format_rfc3339_seconds(self.started)
+                .to_string()
+                .into(),
+        );
+        status.insert("total".into(), self.total.load(Ordering::Relaxed).into());
+        status.insert("copied".into(), self.copied.load(Ordering::Relaxed).into());
+        match &*self.result.lock().unwrap() {
+            None => status.insert("state".into(), "in_progress".into()),
+            Some(Ok(())) => status.insert("state".into(), "complete".into()),
+            Some(Err(e)) => {
+                status.insert("error".into(), e.clone().into());
+                status.insert("state".into(), "failed".into())
+            }
+        };
+        status.into()
+    }
+}
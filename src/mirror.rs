@@ -0,0 +1,205 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    fs::{remove_dir, remove_file, DirBuilder},
+    io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Instant,
+};
+
+use tempfile::NamedTempFile;
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender},
+    Notify,
+};
+use tracing::{trace, warn};
+
+use crate::{files_mgt::FilesMgr, runtime_of};
+
+// A file of the storage to replicate in the mirror: its path, and its path relative to its stripe's root
+struct MirrorOp {
+    file: PathBuf,
+    relpath: PathBuf,
+    queued: Instant,
+}
+
+// The replication of the storage's files to a mirror directory, in background. Each file written, renamed or deleted
+// is synchronized with its current state: copied if it exists, or else deleted from the mirror.
+pub(crate) struct Mirror {
+    dir: PathBuf,
+    sender: UnboundedSender<MirrorOp>,
+    // taken by the replication task, once spawned
+    receiver: Mutex<Option<UnboundedReceiver<MirrorOp>>>,
+    stats: Arc<MirrorStats>,
+}
+
+struct MirrorStats {
+    pending: AtomicU64,
+    // notified when there's no pending change anymore
+    caught_up: Notify,
+    copied: AtomicU64,
+    deleted: AtomicU64,
+    failures: AtomicU64,
+    // the time between a file's change and its replication, for the last one and at most
+    last_lag_ms: AtomicU64,
+    max_lag_ms: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl MirrorStats {
+    // Account a scheduled change as processed
+    fn done(&self) {
+        if self.pending.fetch_sub(1, Ordering::Relaxed) == 1 {
+            self.caught_up.notify_waiters();
+        }
+    }
+}
+
+impl Mirror {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        let (sender, receiver) = unbounded_channel();
+        Mirror {
+            dir,
+            sender,
+            receiver: Mutex::new(Some(receiver)),
+            stats: Arc::new(MirrorStats {
+                pending: AtomicU64::new(0),
+                caught_up: Notify::new(),
+                copied: AtomicU64::new(0),
+                deleted: AtomicU64::new(0),
+                failures: AtomicU64::new(0),
+                last_lag_ms: AtomicU64::new(0),
+                max_lag_ms: AtomicU64::new(0),
+                last_error: Mutex::new(None),
+            }),
+        }
+    }
+
+    // Replicate the changes in background (on the storage's runtime, given its FilesMgr), until the mirror is dropped.
+    // As the mirror is shared by the storage's successive FilesMgrs (e.g. on a migration), the task isn't one of the
+    // FilesMgr's: the closure of the storage rather waits for the mirror to catch up.
+    pub(crate) fn spawn(&self, files_mgr: Weak<FilesMgr>) {
+        let Some(mgr) = FilesMgr::upgrade(&files_mgr) else {
+            return;
+        };
+        let Some(mut receiver) = self.receiver.lock().unwrap().take() else {
+            // already spawned
+            return;
+        };
+        let dir = self.dir.clone();
+        let stats = self.stats.clone();
+        let task = mgr.log().scope(async move {
+            while let Some(op) = receiver.recv().await {
+                let target = dir.join(&op.relpath);
+                let dir = dir.clone();
+                // the mirror may be on a slow file-system: not blocking the runtime's workers
+                let result =
+                    tokio::task::spawn_blocking(move || sync_file(&op.file, &target, &dir))
+                        .await
+                        .unwrap_or_else(|e| Err(io::Error::other(e)));
+                stats.done();
+                match result {
+                    Ok(true) => stats.copied.fetch_add(1, Ordering::Relaxed),
+                    Ok(false) => stats.deleted.fetch_add(1, Ordering::Relaxed),
+                    Err(e) => {
+                        warn!("Failed to mirror {:?}: {}", op.relpath, e);
+                        *stats.last_error.lock().unwrap() =
+                            Some(format!("{:?}: {}", op.relpath, e));
+                        stats.failures.fetch_add(1, Ordering::Relaxed)
+                    }
+                };
+                let lag = op.queued.elapsed().as_millis() as u64;
+                stats.last_lag_ms.store(lag, Ordering::Relaxed);
+                stats.max_lag_ms.fetch_max(lag, Ordering::Relaxed);
+            }
+        });
+        runtime_of(&mgr).spawn(task);
+    }
+
+    // Schedule the replication of a file which was written, renamed or deleted
+    pub(crate) fn changed(&self, file: &Path, relpath: &Path) {
+        let op = MirrorOp {
+            file: file.to_path_buf(),
+            relpath: relpath.to_path_buf(),
+            queued: Instant::now(),
+        };
+        self.stats.pending.fetch_add(1, Ordering::Relaxed);
+        if self.sender.send(op).is_err() {
+            self.stats.done();
+        }
+    }
+
+    pub(crate) fn pending(&self) -> u64 {
+        self.stats.pending.load(Ordering::Relaxed)
+    }
+
+    // Wait for all the scheduled changes to be replicated
+    pub(crate) async fn caught_up(&self) {
+        loop {
+            // (created before the check, for a notification not to be missed)
+            let notified = self.stats.caught_up.notified();
+            if self.pending() == 0 {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "dir": self.dir,
+            "pending": self.stats.pending.load(Ordering::Relaxed),
+            "copied": self.stats.copied.load(Ordering::Relaxed),
+            "deleted": self.stats.deleted.load(Ordering::Relaxed),
+            "failures": self.stats.failures.load(Ordering::Relaxed),
+            "last_lag_ms": self.stats.last_lag_ms.load(Ordering::Relaxed),
+            "max_lag_ms": self.stats.max_lag_ms.load(Ordering::Relaxed),
+            "last_error": *self.stats.last_error.lock().unwrap(),
+        })
+    }
+}
+
+// Synchronize the mirror's copy of a file with its current state, returning true if it was copied, false if deleted
+fn sync_file(file: &Path, target: &Path, dir: &Path) -> io::Result<bool> {
+    if file.is_file() {
+        trace!("Mirror file {:?} to {:?}", file, target);
+        let parent = target.parent().unwrap_or(dir);
+        DirBuilder::new().recursive(true).create(parent)?;
+        // copied aside then renamed, for the mirror to never have a partial file
+        let tmp = NamedTempFile::new_in(parent)?;
+        std::fs::copy(file, tmp.path())?;
+        tmp.persist(target).map_err(|e| e.error)?;
+        Ok(true)
+    } else {
+        trace!("Delete mirrored file {:?}", target);
+        match remove_file(target) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e),
+        }
+        // remove the directories left empty
+        let mut parent = target.parent();
+        while let Some(p) = parent.filter(|p| *p != dir && p.starts_with(dir)) {
+            if remove_dir(p).is_err() {
+                break;
+            }
+            parent = p.parent();
+        }
+        Ok(false)
+    }
+}
@@ -0,0 +1,160 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    borrow::Cow,
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::RwLock,
+};
+
+use siphasher::sip128::SipHasher13;
+
+use crate::files_mgt::CONFLICT_SUFFIX;
+
+// The max length of a file name on most file-systems, minus the CONFLICT_SUFFIX that may be appended
+const MAX_NAME_LEN: usize = 255 - CONFLICT_SUFFIX.len();
+
+// Maps the chunks of the zenoh keys to the names of the files and directories storing them.
+// A chunk is stored under its own name, unless names are obfuscated or it's too long for a file name:
+// it's then stored under a (salted) hash of itself. The chunks of the hashed names are kept to convert the paths back to keys.
+// A chunk starting with '@' is escaped with an extra '@', for the names starting with a single '@' to remain
+// reserved to the storage (e.g. the ROOT_KEY file).
+pub(crate) struct NamesMapper {
+    // the key of the salted hash, if names are obfuscated
+    obfuscation_key: Option<(u64, u64)>,
+    // the chunk of each hashed name
+    chunks: RwLock<HashMap<String, String>>,
+}
+
+impl NamesMapper {
+    // A mapper with the already known chunks of the hashed names, obfuscating the names with the hash keyed by `salt` if set
+    pub(crate) fn new(salt: Option<&str>, chunks: HashMap<String, String>) -> Self {
+        NamesMapper {
+            obfuscation_key: salt.map(salt_key),
+            chunks: RwLock::new(chunks),
+        }
+    }
+
+    pub(crate) fn is_obfuscated(&self) -> bool {
+        self.obfuscation_key.is_some()
+    }
+
+    fn is_hashed(&self, chunk: &str) -> bool {
+        self.is_obfuscated() || chunk.len() + usize::from(is_escaped(chunk)) > MAX_NAME_LEN
+    }
+
+    // Return true if the chunk isn't stored under its own name
+    fn is_mapped(&self, chunk: &str) -> bool {
+        is_escaped(chunk) || self.is_hashed(chunk)
+    }
+
+    // The name of the file or directory storing a key chunk
+    pub(crate) fn fs_name<'a>(&self, chunk: &'a str) -> Cow<'a, str> {
+        if !self.is_hashed(chunk) {
+            return if is_escaped(chunk) {
+                Cow::from(format!("@{chunk}"))
+            } else {
+                Cow::from(chunk)
+            };
+        }
+        let (k0, k1) = self.obfuscation_key.unwrap_or_default();
+        Cow::from(format!(
+            "{:032x}",
+            SipHasher13::new_with_keys(k0, k1)
+                .hash(chunk.as_bytes())
+                .as_u128()
+        ))
+    }
+
+    // Convert a zenoh path to the relative path of the file storing it (with '/' as separator)
+    pub(crate) fn to_fs_relpath<'a>(&self, zpath: &'a str) -> Cow<'a, str> {
+        if zpath.is_empty() || !zpath.split('/').any(|chunk| self.is_mapped(chunk)) {
+            return Cow::from(zpath);
+        }
+        Cow::from(
+            zpath
+                .split('/')
+                .map(|chunk| self.fs_name(chunk))
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    }
+
+    // Convert the relative path of a file (with '/' as separator) back to a zenoh path.
+    // The unknown names (e.g. of files not created via zenoh) are kept as they are.
+    pub(crate) fn to_zpath<'a>(&self, relpath: &'a str) -> Cow<'a, str> {
+        let chunks = self.chunks.read().unwrap();
+        if chunks.is_empty() && !relpath.contains("@@") {
+            return Cow::from(relpath);
+        }
+        Cow::from(
+            relpath
+                .split('/')
+                .map(|name| {
+                    // the conflict suffix is appended to the hashed name of a conflicting file
+                    let (name, suffix) = match name.strip_suffix(CONFLICT_SUFFIX) {
+                        Some(name) => (name, CONFLICT_SUFFIX),
+                        None => (name, ""),
+                    };
+                    match chunks.get(name) {
+                        Some(chunk) => format!("{chunk}{suffix}"),
+                        None => match name.strip_prefix("@@") {
+                            Some(escaped) => format!("@{escaped}{suffix}"),
+                            None => format!("{name}{suffix}"),
+                        },
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("/"),
+        )
+    }
+
+    // Register the hashed names of a zenoh path's chunks, returning the ones which were unknown
+    pub(crate) fn register(&self, zpath: &str) -> Vec<(String, String)> {
+        if !zpath.split('/').any(|chunk| self.is_hashed(chunk)) {
+            return vec![];
+        }
+        let mut new_names = Vec::new();
+        let mut chunks = self.chunks.write().unwrap();
+        for chunk in zpath.split('/').filter(|chunk| self.is_hashed(chunk)) {
+            let name = self.fs_name(chunk).into_owned();
+            if !chunks.contains_key(&name) {
+                chunks.insert(name.clone(), chunk.to_string());
+                new_names.push((name, chunk.to_string()));
+            }
+        }
+        new_names
+    }
+}
+
+// A random salt, for the storages with obfuscated names but without a configured salt
+pub(crate) fn random_salt() -> String {
+    let state = RandomState::new();
+    let mut salt = String::new();
+    for i in 0..2u8 {
+        let mut hasher = state.build_hasher();
+        hasher.write_u8(i);
+        salt.push_str(&format!("{:016x}", hasher.finish()));
+    }
+    salt
+}
+
+// The chunks starting with '@' (e.g. verbatim chunks) are stored with an extra leading '@'
+fn is_escaped(chunk: &str) -> bool {
+    chunk.starts_with('@')
+}
+
+fn salt_key(salt: &str) -> (u64, u64) {
+    SipHasher13::new().hash(salt.as_bytes()).as_u64()
+}
@@ -0,0 +1,165 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    sync::{Arc, Weak},
+    time::{Duration, SystemTime},
+};
+
+use tracing::warn;
+use zenoh::{
+    bytes::Encoding, key_expr::OwnedKeyExpr, pubsub::Publisher, time::Timestamp, Config,
+    Result as ZResult, Session,
+};
+
+use crate::{
+    files_mgt::FilesMgr, maintenance::MaintenanceScheduler, progress::InitProgress,
+    spawn_storage_task, PROP_STORAGE_MAINTENANCE,
+};
+
+// Open the session publishing the notifications and statistics of a storage.
+// As a storage has no access to the zenoh runtime hosting it, they are published via a dedicated session, connected
+// as a client to the configured endpoints (e.g. the router's).
+pub(crate) async fn open_session(connect: &[String]) -> ZResult<Session> {
+    let mut config = Config::default();
+    config.insert_json5("mode", r#""client""#)?;
+    config.insert_json5("connect/endpoints", &serde_json::to_string(connect)?)?;
+    // don't wait for the router, which may not be listening yet
+    config.insert_json5("connect/timeout_ms", "0")?;
+    config.insert_json5("connect/exit_on_failure", "false")?;
+    config.insert_json5("scouting/multicast/enabled", "false")?;
+    zenoh::open(config).await
+}
+
+// Publishes a small JSON notification for each update of a storage.
+pub(crate) struct Notifier {
+    publisher: Publisher<'static>,
+    // the session of the publisher, kept open until the storage is dropped
+    _session: Session,
+}
+
+impl Notifier {
+    pub(crate) async fn new(session: &Session, key_expr: OwnedKeyExpr) -> ZResult<Self> {
+        let publisher = session
+            .declare_publisher(key_expr)
+            .encoding(Encoding::APPLICATION_JSON)
+            .await?;
+        Ok(Notifier {
+            publisher,
+            _session: session.clone(),
+        })
+    }
+
+    // Notify an update: `op` is "put" or "delete", and `size` the size of the put value
+    pub(crate) async fn notify(
+        &self,
+        key: &str,
+        op: &str,
+        timestamp: &Timestamp,
+        size: Option<usize>,
+    ) {
+        let notification = serde_json::json!({
+            "key": key,
+            "op": op,
+            "timestamp": timestamp.to_string(),
+            "size": size,
+        });
+        if let Err(e) = self.publisher.put(notification.to_string()).await {
+            warn!(
+                "Failed to publish notification on {}: {}",
+                self.publisher.key_expr(),
+                e
+            );
+        }
+    }
+}
+
+// Publishes the statistics of a storage (as reported in its admin status) periodically, for them to be collected with
+// a subscriber rather than by polling the admin space
+pub(crate) struct StatsPublisher {
+    storage: String,
+    publisher: Publisher<'static>,
+    interval: Duration,
+    init_progress: Arc<InitProgress>,
+    maintenance: Arc<MaintenanceScheduler>,
+    // the session of the publisher, kept open until the storage is dropped
+    _session: Session,
+}
+
+impl StatsPublisher {
+    pub(crate) async fn new(
+        storage: &str,
+        session: &Session,
+        key_expr: OwnedKeyExpr,
+        interval: Duration,
+        init_progress: Arc<InitProgress>,
+        maintenance: Arc<MaintenanceScheduler>,
+    ) -> ZResult<Self> {
+        let publisher = session
+            .declare_publisher(key_expr)
+            .encoding(Encoding::APPLICATION_JSON)
+            .await?;
+        Ok(StatsPublisher {
+            storage: storage.to_string(),
+            publisher,
+            interval,
+            init_progress,
+            maintenance,
+            _session: session.clone(),
+        })
+    }
+
+    // Publish the statistics of the storage (given its FilesMgr) at each interval, until it's dropped
+    pub(crate) fn spawn(self: &Arc<Self>, files_mgr: Weak<FilesMgr>) {
+        let stats_publisher = self.clone();
+        spawn_storage_task(files_mgr, |files_mgr| async move {
+            loop {
+                let interval = tokio::time::sleep(stats_publisher.interval);
+                if FilesMgr::idle(&files_mgr, interval).await.is_none() {
+                    break;
+                }
+                let files_mgr = match FilesMgr::upgrade(&files_mgr) {
+                    Some(files_mgr) => files_mgr,
+                    None => break,
+                };
+                files_mgr.refresh_db_stats().await;
+                let stats = files_mgr.stats_json();
+                drop(files_mgr);
+                stats_publisher.publish(stats).await;
+            }
+        });
+    }
+
+    async fn publish(&self, stats: serde_json::Map<String, serde_json::Value>) {
+        let mut message = serde_json::json!({
+            "storage": self.storage,
+            "time": humantime::format_rfc3339_millis(SystemTime::now()).to_string(),
+            "initialization": self.init_progress.status_json(),
+        });
+        let maintenance = self.maintenance.status_json();
+        if maintenance.as_object().is_some_and(|jobs| !jobs.is_empty()) {
+            message[PROP_STORAGE_MAINTENANCE] = maintenance;
+        }
+        if let Some(message) = message.as_object_mut() {
+            message.extend(stats);
+        }
+        if let Err(e) = self.publisher.put(message.to_string()).await {
+            warn!(
+                "Failed to publish the statistics of storage {} on {}: {}",
+                self.storage,
+                self.publisher.key_expr(),
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::path::PathBuf;
+
+use zenoh::{
+    bytes::{Encoding, ZBytes},
+    internal::zerror,
+    key_expr::keyexpr,
+    time::Timestamp,
+    Result as ZResult,
+};
+
+use crate::files_mgt::{FilesMgr, FilesMgrSettings};
+
+// The read-only lower layer of a storage (e.g. a shipped dataset), the storage's directory being its writable upper
+// layer. The entries missing in the upper layer are read from the lower one, unless deleted: the deletions are
+// recorded as whiteouts in the upper layer, hiding the lower layer's values.
+pub(crate) struct Overlay {
+    lower: FilesMgr,
+}
+
+impl Overlay {
+    // Open the lower layer in `dir`, with its data-info database in data_info_dir. Its files are plain: they're read
+    // without the storage's transforms, envelopes or obfuscated names.
+    pub(crate) async fn new(
+        dir: PathBuf,
+        data_info_dir: PathBuf,
+        settings: &FilesMgrSettings,
+    ) -> ZResult<Self> {
+        std::fs::create_dir_all(&data_info_dir).map_err(|e| {
+            zerror!(
+                "Failed to create the data-info directory of the lower layer {:?}: {}",
+                dir,
+                e
+            )
+        })?;
+        let settings = FilesMgrSettings {
+            obfuscate_names: false,
+            payload_transforms: vec![],
+            envelope: false,
+            policies: vec![],
+            ..settings.read_only(data_info_dir)
+        };
+        Ok(Overlay {
+            lower: settings.build(dir).await?,
+        })
+    }
+
+    pub(crate) fn lower(&self) -> &FilesMgr {
+        &self.lower
+    }
+
+    // Read an entry (None for the root key) missing in the upper layer from the lower one, unless deleted
+    pub(crate) async fn read(
+        &self,
+        upper: &FilesMgr,
+        key: Option<&keyexpr>,
+        zpath: &str,
+    ) -> ZResult<Option<(ZBytes, Encoding, Timestamp)>> {
+        if upper.is_whiteout(zpath).await? {
+            return Ok(None);
+        }
+        let zfile = match key {
+            Some(_) => self.lower.to_zfile(zpath),
+            None => self.lower.root_zfile(),
+        };
+        self.lower.read_file(&zfile).await
+    }
+
+    pub(crate) fn status_json(&self, upper: &FilesMgr) -> serde_json::Value {
+        serde_json::json!({
+            "lower_dir": self.lower.base_dir(),
+            "whiteouts": upper.whiteouts_count(),
+        })
+    }
+}
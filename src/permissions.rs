@@ -0,0 +1,132 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+#[cfg(target_os = "linux")]
+use std::{fs::metadata, os::unix::fs::MetadataExt};
+use std::{io, path::Path};
+
+// The error of a write on a read-only file-system
+#[cfg(target_os = "linux")]
+const EROFS: i32 = 30;
+
+// The permissions checked on a directory
+#[cfg(target_os = "linux")]
+const READ: u32 = 0o4;
+#[cfg(target_os = "linux")]
+const WRITE: u32 = 0o2;
+#[cfg(target_os = "linux")]
+const SEARCH: u32 = 0o1;
+
+// Explain an error denying the access to a directory (to be created if missing, and written if `write`): which
+// directory lacks which permission for the user zenohd runs as (typically, a container's volume owned by another
+// user). Return an empty string for the other errors, or if no explanation is found.
+#[cfg(target_os = "linux")]
+pub(crate) fn diagnose(dir: &Path, err: &io::Error, write: bool) -> String {
+    if err.raw_os_error() == Some(EROFS) {
+        return format!(" - the file-system of {dir:?} is mounted read-only");
+    }
+    if err.kind() != io::ErrorKind::PermissionDenied {
+        return String::new();
+    }
+    let user = User::effective();
+    // the ancestors must be searchable, and the deepest existing one readable and writable (to create the missing
+    // directories, or to write in the directory)
+    let mut ancestors: Vec<&Path> = dir.ancestors().filter(|a| a.exists()).collect();
+    ancestors.reverse();
+    for (i, ancestor) in ancestors.iter().enumerate() {
+        let Ok(m) = metadata(ancestor) else {
+            continue;
+        };
+        let last = i + 1 == ancestors.len();
+        let needed = match (last, write || *ancestor != dir) {
+            (false, _) => SEARCH,
+            (true, false) => READ | SEARCH,
+            (true, true) => READ | WRITE | SEARCH,
+        };
+        let missing = needed & !user.permissions(&m);
+        if missing != 0 {
+            return format!(
+                " - {:?} is owned by uid {} gid {} with mode {:o}, lacking the {} permission for zenohd running as {} \
+                (e.g. chown it to {}:{}, or run zenohd as its owner)",
+                ancestor,
+                m.uid(),
+                m.gid(),
+                m.mode() & 0o7777,
+                permission_names(missing),
+                user,
+                user.uid,
+                user.gid
+            );
+        }
+    }
+    format!(
+        " - the permissions of {dir:?} and its ancestors seem granted to zenohd running as {user}: the access may be \
+        denied by an ACL, a security module (SELinux, AppArmor), or the user namespace of a rootless container"
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn diagnose(_dir: &Path, _err: &io::Error, _write: bool) -> String {
+    String::new()
+}
+
+// The effective user and groups of the process
+#[cfg(target_os = "linux")]
+struct User {
+    uid: u32,
+    gid: u32,
+    groups: Vec<u32>,
+}
+
+#[cfg(target_os = "linux")]
+impl User {
+    fn effective() -> Self {
+        // SAFETY: those calls have no precondition, and getgroups fills at most the given length of `groups`
+        let (uid, gid) = unsafe { (libc::geteuid(), libc::getegid()) };
+        let mut groups = vec![0; 256];
+        let count = unsafe { libc::getgroups(groups.len() as libc::c_int, groups.as_mut_ptr()) };
+        groups.truncate(count.max(0) as usize);
+        User { uid, gid, groups }
+    }
+
+    // The permissions (as the "other" bits of a mode) of the user on a file, ignoring the ACLs
+    fn permissions(&self, m: &std::fs::Metadata) -> u32 {
+        let mode = m.mode();
+        if self.uid == 0 {
+            READ | WRITE | SEARCH
+        } else if m.uid() == self.uid {
+            (mode >> 6) & 0o7
+        } else if m.gid() == self.gid || self.groups.contains(&m.gid()) {
+            (mode >> 3) & 0o7
+        } else {
+            mode & 0o7
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl std::fmt::Display for User {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "uid {} gid {}", self.uid, self.gid)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn permission_names(permissions: u32) -> String {
+    [(READ, "read"), (WRITE, "write"), (SEARCH, "execute")]
+        .iter()
+        .filter(|(p, _)| permissions & p != 0)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join("+")
+}
@@ -0,0 +1,90 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{sync::Arc, time::Duration};
+
+use zenoh::Result as ZResult;
+
+use crate::{
+    quota::{DiskQuota, QuotaShare},
+    transforms::Transforms,
+    PayloadTransform,
+};
+
+// The configuration of a policy of a storage, for the keys under a prefix
+#[derive(Clone)]
+pub(crate) struct PolicyConfig {
+    // the prefix of the keys (relative to the storage's strip_prefix), without wildcard
+    pub(crate) prefix: String,
+    // replaces the storage's transforms if set (an empty chain meaning no transform)
+    pub(crate) transforms: Option<Vec<Arc<dyn PayloadTransform>>>,
+    pub(crate) max_total_size: Option<u64>,
+    pub(crate) retention_days: Option<u64>,
+}
+
+// A policy of a storage: overrides the storage's settings for the keys equal to its prefix or under it
+pub(crate) struct Policy {
+    pub(crate) prefix: String,
+    pub(crate) transforms: Option<Transforms>,
+    pub(crate) quota: Option<QuotaShare>,
+    pub(crate) retention: Option<Duration>,
+}
+
+impl Policy {
+    // Create the policy (its quota accounting the files already stored once they are walked)
+    pub(crate) fn new(config: &PolicyConfig) -> ZResult<Self> {
+        let transforms = match &config.transforms {
+            Some(chain) => Some(Transforms::new(chain.clone())?),
+            None => None,
+        };
+        let quota = config.max_total_size.map(|max| {
+            QuotaShare::new(
+                Arc::new(DiskQuota::new(format!("policy {}", config.prefix), max)),
+                0,
+            )
+        });
+        Ok(Policy {
+            prefix: config.prefix.clone(),
+            transforms,
+            quota,
+            retention: config
+                .retention_days
+                .map(|days| Duration::from_secs(days * 24 * 3600)),
+        })
+    }
+
+    // Return true if the key is the policy's prefix, or is under it
+    pub(crate) fn applies_to(&self, zpath: &str) -> bool {
+        zpath
+            .strip_prefix(self.prefix.as_str())
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let mut status = serde_json::Map::new();
+        if let Some(transforms) = &self.transforms {
+            status.insert("payload_transforms".into(), transforms.names().into());
+        }
+        if let Some(quota) = &self.quota {
+            status.insert("max_total_size".into(), quota.max().into());
+            status.insert("total_size".into(), quota.used().into());
+        }
+        if let Some(retention) = self.retention {
+            status.insert(
+                "retention_days".into(),
+                (retention.as_secs() / (24 * 3600)).into(),
+            );
+        }
+        status.into()
+    }
+}
@@ -0,0 +1,172 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::VecDeque,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::SystemTime,
+};
+
+use tracing::trace;
+
+// The maximum size of a prefetched file (the larger ones being read when requested only)
+pub(crate) const MAX_PREFETCHED_FILE_SIZE: u64 = 8 * 1024 * 1024;
+
+// The number of series of keys for which the last read is remembered
+const MAX_SERIES: usize = 64;
+
+// The read-ahead of the files of sequentially read keys: the keys whose last chunk is numbered (e.g.
+// `recording/frame/0001`, `recording/frame/0002`...) form series, and once 2 consecutive keys of a series are read,
+// the files of the next `depth` keys are read in advance and kept in memory, until they are read (or evicted by more
+// recent ones). A prefetched content is only used if the file's size and modification time didn't change meanwhile.
+pub(crate) struct Prefetcher {
+    depth: usize,
+    // the last read number of each series, and the highest number prefetched in it (the most recent at the back)
+    series: Mutex<VecDeque<(Series, u64, u64)>>,
+    // the prefetched contents (the most recent at the back)
+    contents: Mutex<VecDeque<(PathBuf, Prefetched)>>,
+    prefetched: AtomicU64,
+    hits: AtomicU64,
+}
+
+// A series of keys: their parent key, and the parts of their last chunk before and after the number
+#[derive(PartialEq)]
+struct Series {
+    parent: String,
+    prefix: String,
+    suffix: String,
+}
+
+struct Prefetched {
+    content: Vec<u8>,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl Prefetcher {
+    pub(crate) fn new(depth: usize) -> Self {
+        Prefetcher {
+            depth,
+            series: Mutex::new(VecDeque::with_capacity(MAX_SERIES)),
+            contents: Mutex::new(VecDeque::with_capacity(2 * depth)),
+            prefetched: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+        }
+    }
+
+    // Record the read of a key, returning the keys to be prefetched if it follows the previous one of its series
+    pub(crate) fn next_keys(&self, zpath: &str) -> Vec<String> {
+        let (parent, name) = zpath.rsplit_once('/').unwrap_or(("", zpath));
+        let Some((prefix, number, width, suffix)) = split_number(name) else {
+            return vec![];
+        };
+        let series = Series {
+            parent: parent.to_string(),
+            prefix: prefix.to_string(),
+            suffix: suffix.to_string(),
+        };
+        let mut all = self.series.lock().unwrap();
+        let previous = all
+            .iter()
+            .position(|(s, _, _)| *s == series)
+            .and_then(|i| all.remove(i));
+        let mut ahead = number;
+        let mut keys = vec![];
+        // (the prefetching restarting from the key read if it doesn't follow the previous one)
+        if let Some((_, _, prefetched)) =
+            previous.filter(|(_, last, _)| last.checked_add(1) == Some(number))
+        {
+            ahead = prefetched.max(number);
+            let to = number.saturating_add(self.depth as u64);
+            if ahead < to {
+                keys = (ahead + 1..=to)
+                    .map(|n| {
+                        let name = format!("{}{:0width$}{}", prefix, n, suffix, width = width);
+                        match parent.is_empty() {
+                            true => name,
+                            false => format!("{}/{}", parent, name),
+                        }
+                    })
+                    .collect();
+                ahead = to;
+            }
+        }
+        all.push_back((series, number, ahead));
+        while all.len() > MAX_SERIES {
+            all.pop_front();
+        }
+        keys
+    }
+
+    // Keep the content of a file read in advance
+    pub(crate) fn insert(&self, file: PathBuf, content: Vec<u8>, metadata: &Metadata) {
+        trace!("Prefetched file {:?}", file);
+        self.prefetched.fetch_add(1, Ordering::Relaxed);
+        let mut contents = self.contents.lock().unwrap();
+        contents.retain(|(p, _)| *p != file);
+        contents.push_back((
+            file,
+            Prefetched {
+                content,
+                len: metadata.len(),
+                modified: metadata.modified().ok(),
+            },
+        ));
+        while contents.len() > 2 * self.depth {
+            contents.pop_front();
+        }
+    }
+
+    // Take the prefetched content of a file, if any and if the file didn't change since
+    pub(crate) fn take(&self, file: &Path) -> Option<Vec<u8>> {
+        let prefetched = {
+            let mut contents = self.contents.lock().unwrap();
+            let i = contents.iter().position(|(p, _)| p == file)?;
+            contents.remove(i)?.1
+        };
+        let metadata = std::fs::metadata(file).ok()?;
+        if metadata.len() != prefetched.len || metadata.modified().ok() != prefetched.modified {
+            return None;
+        }
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        Some(prefetched.content)
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "depth": self.depth,
+            "cached": self.contents.lock().unwrap().len(),
+            "prefetched": self.prefetched.load(Ordering::Relaxed),
+            "hits": self.hits.load(Ordering::Relaxed),
+        })
+    }
+}
+
+// Split a key chunk around its last number: the part before it, its value, its number of digits (for the zero
+// padding of the next ones) and the part after it
+fn split_number(name: &str) -> Option<(&str, u64, usize, &str)> {
+    let end = name.rfind(|c: char| c.is_ascii_digit())? + 1;
+    let start = end
+        - name[..end]
+            .bytes()
+            .rev()
+            .take_while(u8::is_ascii_digit)
+            .count();
+    let number = name[start..end].parse().ok()?;
+    Some((&name[..start], number, end - start, &name[end..]))
+}
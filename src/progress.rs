@@ -0,0 +1,110 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tracing::{debug, info};
+
+// The interval between the logs of the progress of an initialization
+const LOG_INTERVAL: Duration = Duration::from_secs(10);
+
+// The progress of the initialization of a storage, which may scan all its files (e.g. to account them in a quota, or
+// to verify the data-info database): reported in the admin status and in the logs, for a storage created over a huge
+// directory not to appear hung.
+pub(crate) struct InitProgress {
+    storage: String,
+    started: Instant,
+    phase: Mutex<&'static str>,
+    files_seen: AtomicU64,
+    last_log: Mutex<Instant>,
+    // the duration of the initialization, once completed
+    duration: Mutex<Option<Duration>>,
+}
+
+impl InitProgress {
+    pub(crate) fn new(storage: &str) -> Self {
+        let now = Instant::now();
+        InitProgress {
+            storage: storage.to_string(),
+            started: now,
+            phase: Mutex::new("configuration"),
+            files_seen: AtomicU64::new(0),
+            last_log: Mutex::new(now),
+            duration: Mutex::new(None),
+        }
+    }
+
+    // Start a new phase of the initialization
+    pub(crate) fn phase(&self, phase: &'static str) {
+        debug!("Storage {}: initialization phase {}", self.storage, phase);
+        *self.phase.lock().unwrap() = phase;
+    }
+
+    // Count a file scanned by the current phase, logging the progress periodically (checked every 1024 files)
+    pub(crate) fn file_seen(&self) {
+        let seen = self.files_seen.fetch_add(1, Ordering::Relaxed) + 1;
+        if seen & 0x3ff != 0 {
+            return;
+        }
+        let mut last_log = self.last_log.lock().unwrap();
+        if last_log.elapsed() >= LOG_INTERVAL {
+            *last_log = Instant::now();
+            info!(
+                "Storage {}: initialization in progress ({}): {} files scanned in {}s",
+                self.storage,
+                self.phase.lock().unwrap(),
+                seen,
+                self.started.elapsed().as_secs()
+            );
+        }
+    }
+
+    pub(crate) fn complete(&self) {
+        let duration = self.started.elapsed();
+        *self.duration.lock().unwrap() = Some(duration);
+        *self.phase.lock().unwrap() = "completed";
+        let seen = self.files_seen.load(Ordering::Relaxed);
+        if duration >= LOG_INTERVAL {
+            info!(
+                "Storage {}: initialized in {}s ({} files scanned)",
+                self.storage,
+                duration.as_secs(),
+                seen
+            );
+        } else {
+            debug!(
+                "Storage {}: initialized in {:?} ({} files scanned)",
+                self.storage, duration, seen
+            );
+        }
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let elapsed = self
+            .duration
+            .lock()
+            .unwrap()
+            .unwrap_or_else(|| self.started.elapsed());
+        serde_json::json!({
+            "phase": *self.phase.lock().unwrap(),
+            "files_seen": self.files_seen.load(Ordering::Relaxed),
+            "elapsed_secs": elapsed.as_secs_f64(),
+        })
+    }
+}
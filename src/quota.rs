@@ -0,0 +1,133 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+// A cap on the total size of a set of files (e.g. the files stored by all the storages of a volume)
+pub(crate) struct DiskQuota {
+    // what is capped, for the error messages
+    owner: String,
+    max: u64,
+    used: AtomicU64,
+}
+
+impl DiskQuota {
+    pub(crate) fn new(owner: String, max: u64) -> Self {
+        DiskQuota {
+            owner,
+            max,
+            used: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub(crate) fn max(&self) -> u64 {
+        self.max
+    }
+
+    pub(crate) fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    // Reserve `size` bytes, unless it would exceed the cap
+    fn try_reserve(&self, size: u64) -> bool {
+        self.used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                used.checked_add(size).filter(|total| *total <= self.max)
+            })
+            .is_ok()
+    }
+
+    fn release(&self, size: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                Some(used.saturating_sub(size))
+            });
+    }
+}
+
+// The part of a volume's DiskQuota used by a storage. It's released when dropped (i.e. when the storage is closed).
+pub(crate) struct QuotaShare {
+    quota: Arc<DiskQuota>,
+    used: AtomicU64,
+}
+
+impl QuotaShare {
+    // A share accounting for the `initial` bytes already stored, even if it exceeds the cap
+    pub(crate) fn new(quota: Arc<DiskQuota>, initial: u64) -> Self {
+        quota.used.fetch_add(initial, Ordering::AcqRel);
+        QuotaShare {
+            quota,
+            used: AtomicU64::new(initial),
+        }
+    }
+
+    // Account `size` bytes found stored after the share's creation, even if it exceeds the cap
+    pub(crate) fn account(&self, size: u64) {
+        self.quota.used.fetch_add(size, Ordering::AcqRel);
+        self.used.fetch_add(size, Ordering::AcqRel);
+    }
+
+    // Return true if `size` more bytes would fit in the volume's cap, without reserving them
+    pub(crate) fn would_fit(&self, size: u64) -> bool {
+        self.quota
+            .used()
+            .checked_add(size)
+            .is_some_and(|total| total <= self.quota.max())
+    }
+
+    // Reserve `size` bytes, unless it would exceed the volume's cap
+    pub(crate) fn try_reserve(&self, size: u64) -> bool {
+        if self.quota.try_reserve(size) {
+            self.used.fetch_add(size, Ordering::AcqRel);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn release(&self, size: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+                Some(used.saturating_sub(size))
+            });
+        self.quota.release(size);
+    }
+
+    pub(crate) fn max(&self) -> u64 {
+        self.quota.max()
+    }
+
+    // The bytes used by this share
+    pub(crate) fn used(&self) -> u64 {
+        self.used.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn owner(&self) -> &str {
+        self.quota.owner()
+    }
+}
+
+impl Drop for QuotaShare {
+    fn drop(&mut self) {
+        self.quota.release(self.used.load(Ordering::Acquire));
+    }
+}
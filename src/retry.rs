@@ -0,0 +1,145 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{io, time::Duration};
+
+use tracing::debug;
+
+// The error of an operation on a file whose handle became invalid on the server (e.g. replaced by another client)
+#[cfg(target_os = "linux")]
+const ESTALE: i32 = 116;
+#[cfg(not(target_os = "linux"))]
+const ESTALE: i32 = 70;
+
+// The classes of the errors which may be transient, and so retried
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ErrorClass {
+    // the operation was interrupted, or would have blocked (EINTR, EAGAIN)
+    Interrupted,
+    // the operation timed out (ETIMEDOUT, or a RocksDB timeout)
+    TimedOut,
+    // the file's handle became stale on a network file-system (ESTALE)
+    Stale,
+    // the data-info database is busy (a RocksDB "busy" or "try again" status)
+    Busy,
+}
+
+impl ErrorClass {
+    pub(crate) const ALL: &'static [&'static str] = &["interrupted", "timed_out", "stale", "busy"];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "interrupted" => Some(ErrorClass::Interrupted),
+            "timed_out" => Some(ErrorClass::TimedOut),
+            "stale" => Some(ErrorClass::Stale),
+            "busy" => Some(ErrorClass::Busy),
+            _ => None,
+        }
+    }
+
+    fn of_io(e: &io::Error) -> Option<Self> {
+        match e.kind() {
+            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock => Some(ErrorClass::Interrupted),
+            io::ErrorKind::TimedOut => Some(ErrorClass::TimedOut),
+            _ if e.raw_os_error() == Some(ESTALE) => Some(ErrorClass::Stale),
+            _ => None,
+        }
+    }
+
+    // The class of a RocksDB error, from its status (the prefix of its message)
+    #[cfg(feature = "rocksdb")]
+    pub(crate) fn of_db(message: &str) -> Option<Self> {
+        if message.starts_with("Resource busy")
+            || message.starts_with("Operation failed. Try again")
+        {
+            Some(ErrorClass::Busy)
+        } else if message.starts_with("Operation timed out") {
+            Some(ErrorClass::TimedOut)
+        } else {
+            None
+        }
+    }
+}
+
+// How the file and data-info operations failing with a transient error are retried
+#[derive(Clone, Debug)]
+pub(crate) struct RetryPolicy {
+    // the number of retries after the first attempt
+    retries: usize,
+    // the delay before the first retry, doubled before each next one
+    backoff: Duration,
+    classes: Vec<ErrorClass>,
+}
+
+impl RetryPolicy {
+    pub(crate) const DEFAULT_BACKOFF: Duration = Duration::from_millis(50);
+
+    pub(crate) fn new(retries: usize, backoff: Duration, classes: Vec<ErrorClass>) -> Self {
+        RetryPolicy {
+            retries,
+            backoff,
+            classes,
+        }
+    }
+
+    pub(crate) fn retries(&self) -> usize {
+        self.retries
+    }
+
+    // No retry
+    pub(crate) fn none() -> Self {
+        RetryPolicy::new(0, Duration::ZERO, vec![])
+    }
+
+    // The default policy on network file-systems: 3 retries of the errors of all the classes
+    pub(crate) fn network() -> Self {
+        RetryPolicy::new(
+            3,
+            RetryPolicy::DEFAULT_BACKOFF,
+            vec![
+                ErrorClass::Interrupted,
+                ErrorClass::TimedOut,
+                ErrorClass::Stale,
+                ErrorClass::Busy,
+            ],
+        )
+    }
+
+    // Run an operation, retried while it fails with an error of a retried class
+    pub(crate) fn run<T, E: std::fmt::Display>(
+        &self,
+        mut op: impl FnMut() -> Result<T, E>,
+        class_of: impl Fn(&E) -> Option<ErrorClass>,
+    ) -> Result<T, E> {
+        let mut delay = self.backoff;
+        let mut retries = self.retries;
+        loop {
+            match op() {
+                Err(e)
+                    if retries > 0 && class_of(&e).is_some_and(|c| self.classes.contains(&c)) =>
+                {
+                    debug!("Retry operation in {:?} after error: {}", delay, e);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    retries -= 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    // Run a file operation, retried while it fails with an error of a retried class
+    pub(crate) fn io<T>(&self, op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        self.run(op, ErrorClass::of_io)
+    }
+}
@@ -0,0 +1,190 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::{Duration, SystemTime},
+};
+
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{info, warn};
+use zenoh::{internal::zerror, key_expr::OwnedKeyExpr, Result as ZResult};
+
+use crate::{files_mgt::FilesMgr, spawn_storage_task};
+
+// The maximum number of corrupted and missing entries listed in the report (the others being only counted)
+const MAX_LISTED: usize = 100;
+
+// An integrity scrub of the entries under a key prefix, requested by a control operation: their files are read (at
+// most max_rate per second, for the storage to remain responsive) to check that they can be decoded and match their
+// data-info, then the data-info referring to missing files are reported.
+pub(crate) struct Scrub {
+    prefix: String,
+    max_rate: usize,
+    started: SystemTime,
+    checked: AtomicUsize,
+    // the corrupted entries (their key and why), and the missing ones
+    corrupted: Mutex<Report<(String, String)>>,
+    missing: Mutex<Report<String>>,
+    // set at the end of the scrub: Ok, or the error which interrupted it
+    result: Mutex<Option<Result<(), String>>>,
+}
+
+// The count of the entries found by the scrub, with the first MAX_LISTED ones
+struct Report<T> {
+    count: usize,
+    listed: Vec<T>,
+}
+
+impl<T> Report<T> {
+    fn new() -> Self {
+        Report {
+            count: 0,
+            listed: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, entry: T) {
+        self.count += 1;
+        if self.listed.len() < MAX_LISTED {
+            self.listed.push(entry);
+        }
+    }
+}
+
+impl Scrub {
+    pub(crate) fn new(prefix: String, max_rate: usize) -> Self {
+        Scrub {
+            prefix,
+            max_rate,
+            started: SystemTime::now(),
+            checked: AtomicUsize::new(0),
+            corrupted: Mutex::new(Report::new()),
+            missing: Mutex::new(Report::new()),
+            result: Mutex::new(None),
+        }
+    }
+
+    pub(crate) fn is_running(&self) -> bool {
+        self.result.lock().unwrap().is_none()
+    }
+
+    // Check the entries in background
+    pub(crate) fn spawn(self: &Arc<Self>, files_mgr: Weak<FilesMgr>) {
+        let scrub = self.clone();
+        spawn_storage_task(files_mgr, |files_mgr| async move {
+            let result = scrub.scrub_all(&files_mgr).await;
+            match &result {
+                Ok(()) => info!(
+                    "Scrub of \"{}\" complete: {} files checked, {} corrupted, {} missing",
+                    scrub.prefix,
+                    scrub.checked.load(Ordering::Relaxed),
+                    scrub.corrupted.lock().unwrap().count,
+                    scrub.missing.lock().unwrap().count
+                ),
+                Err(e) => warn!("Scrub of \"{}\" failed: {}", scrub.prefix, e),
+            }
+            *scrub.result.lock().unwrap() = Some(result.map_err(|e| e.to_string()));
+        });
+    }
+
+    async fn scrub_all(&self, files_mgr: &Weak<FilesMgr>) -> ZResult<()> {
+        let (subtree, zpath_expr) = match self.prefix.is_empty() {
+            true => (None, OwnedKeyExpr::new("**")?),
+            false => (
+                Some(OwnedKeyExpr::new(self.prefix.as_str())?),
+                OwnedKeyExpr::new(format!("{}/**", self.prefix))?,
+            ),
+        };
+        let Some(mgr) = FilesMgr::upgrade(files_mgr) else {
+            return Ok(());
+        };
+        // the prefix's own entry (if any) first
+        let own = subtree
+            .as_ref()
+            .map(|key| mgr.to_zfile(key.as_str()))
+            .filter(|zfile| zfile.fspath().is_file());
+        let files = own.into_iter().chain(mgr.matching_files(&zpath_expr));
+        // the FilesMgr only being held while checking a file
+        drop(mgr);
+        let mut ticker = interval(Duration::from_secs(1) / self.max_rate as u32);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        for zfile in files {
+            // stop if the storage was closed meanwhile
+            FilesMgr::idle(files_mgr, ticker.tick())
+                .await
+                .ok_or_else(|| zerror!("the storage was closed"))?;
+            let mgr =
+                FilesMgr::upgrade(files_mgr).ok_or_else(|| zerror!("the storage was closed"))?;
+            if let Some(e) = mgr.check_file(&zfile).await? {
+                warn!("Scrub: file {} is corrupted: {}", zfile, e);
+                self.corrupted
+                    .lock()
+                    .unwrap()
+                    .add((zfile.zpath.to_string(), e));
+            }
+            self.checked.fetch_add(1, Ordering::Relaxed);
+        }
+        let mgr = FilesMgr::upgrade(files_mgr).ok_or_else(|| zerror!("the storage was closed"))?;
+        for zpath in mgr.missing_entries(subtree.as_deref()).await? {
+            warn!("Scrub: the file of {} is missing", zpath);
+            self.missing.lock().unwrap().add(zpath);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let mut status = serde_json::Map::new();
+        status.insert("prefix".into(), self.prefix.clone().into());
+        status.insert("max_rate".into(), self.max_rate.into());
+        status.insert(
+            "started".into(),
+            humantime::format_rfc3339_seconds(self.started)
+                .to_string()
+                .into(),
+        );
+        status.insert(
+            "checked".into(),
+            self.checked.load(Ordering::Relaxed).into(),
+        );
+        let corrupted = self.corrupted.lock().unwrap();
+        status.insert(
+            "corrupted".into(),
+            serde_json::json!({
+                "count": corrupted.count,
+                "entries": corrupted
+                    .listed
+                    .iter()
+                    .map(|(key, error)| serde_json::json!({ "key": key, "error": error }))
+                    .collect::<Vec<_>>(),
+            }),
+        );
+        let missing = self.missing.lock().unwrap();
+        status.insert(
+            "missing".into(),
+            serde_json::json!({ "count": missing.count, "keys": missing.listed }),
+        );
+        match &*self.result.lock().unwrap() {
+            None => status.insert("state".into(), "in_progress".into()),
+            Some(Ok(())) => status.insert("state".into(), "complete".into()),
+            Some(Err(e)) => {
+                status.insert("error".into(), e.clone().into());
+                status.insert("state".into(), "failed".into())
+            }
+        };
+        status.into()
+    }
+}
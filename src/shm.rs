@@ -0,0 +1,76 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use tracing::debug;
+use zenoh::{
+    bytes::ZBytes,
+    internal::zerror,
+    shm::{
+        GarbageCollect, PosixShmProviderBackend, ShmProvider, ShmProviderBuilder, StaticProtocolID,
+        POSIX_PROTOCOL_ID,
+    },
+    Result as ZResult, Wait,
+};
+
+// Serves the large files contents through shared-memory buffers.
+// When the reply is routed to a querier on the same host which has shared-memory enabled,
+// the transport passes the buffer by reference instead of copying the payload.
+pub(crate) struct ShmPayloads {
+    provider: ShmProvider<StaticProtocolID<POSIX_PROTOCOL_ID>, PosixShmProviderBackend>,
+    threshold: usize,
+}
+
+impl ShmPayloads {
+    pub(crate) fn new(pool_size: usize, threshold: usize) -> ZResult<Self> {
+        let backend = PosixShmProviderBackend::builder()
+            .with_size(pool_size)
+            .map_err(|e| zerror!("Invalid shared-memory pool size {}: {:?}", pool_size, e))?
+            .wait()
+            .map_err(|e| zerror!("Failed to create shared-memory pool: {}", e))?;
+        let provider = ShmProviderBuilder::builder()
+            .protocol_id::<POSIX_PROTOCOL_ID>()
+            .backend(backend)
+            .wait();
+        Ok(ShmPayloads {
+            provider,
+            threshold,
+        })
+    }
+
+    // Convert a file content into a payload, copying it in a shared-memory buffer if it's large enough.
+    // If the pool is exhausted, fallback to a regular buffer rather than blocking the query.
+    pub(crate) fn to_payload(&self, content: Vec<u8>) -> ZBytes {
+        if content.len() < self.threshold {
+            return content.into();
+        }
+        match self
+            .provider
+            .alloc(content.len())
+            .with_policy::<GarbageCollect>()
+            .wait()
+        {
+            Ok(mut sbuf) => {
+                sbuf.copy_from_slice(&content);
+                sbuf.into()
+            }
+            Err(e) => {
+                debug!(
+                    "Failed to allocate {} bytes in shared-memory ({:?}), fallback to a regular buffer",
+                    content.len(),
+                    e
+                );
+                content.into()
+            }
+        }
+    }
+}
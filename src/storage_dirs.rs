@@ -0,0 +1,91 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use tracing::trace;
+use zenoh::{internal::bail, Result as ZResult};
+
+// The directories of the storages of the volumes.
+// Storages with nested directories would report each other's entries,
+// and their data-info databases would end up inside each other's trees.
+#[derive(Default, Clone)]
+pub(crate) struct StorageDirs {
+    // the canonical directory of each storage, with the storage's name
+    dirs: Arc<Mutex<Vec<(PathBuf, String)>>>,
+}
+
+impl StorageDirs {
+    // The directories of the storages of all the volumes of the process, whose roots may be nested
+    pub(crate) fn shared() -> Self {
+        static SHARED: OnceLock<StorageDirs> = OnceLock::new();
+        SHARED.get_or_init(StorageDirs::default).clone()
+    }
+
+    // Register the directory of a storage, failing if it's the same as, or nested with, the one of another storage.
+    // The directory is unregistered when the returned guard is dropped.
+    pub(crate) fn register(&self, storage: &str, dir: &Path) -> ZResult<StorageDirGuard> {
+        let dir = match dunce::canonicalize(dir) {
+            Ok(dir) => dir,
+            Err(e) => bail!("Invalid directory {:?} for storage {}: {}", dir, storage, e),
+        };
+        let mut dirs = self.dirs.lock().unwrap();
+        for (other_dir, other) in dirs.iter() {
+            if dir == *other_dir {
+                bail!(
+                    "Cannot create File System Storage {} on {:?}: this directory is already used by storage {}",
+                    storage,
+                    dir,
+                    other
+                )
+            } else if dir.starts_with(other_dir) || other_dir.starts_with(&dir) {
+                bail!(
+                    "Cannot create File System Storage {} on {:?}: this directory is nested with {:?} used by storage {}",
+                    storage,
+                    dir,
+                    other_dir,
+                    other
+                )
+            }
+        }
+        trace!("Register directory {:?} for storage {}", dir, storage);
+        dirs.push((dir.clone(), storage.into()));
+        Ok(StorageDirGuard {
+            dirs: self.clone(),
+            dir,
+        })
+    }
+}
+
+// Keeps the directory of a storage registered, until dropped
+pub(crate) struct StorageDirGuard {
+    dirs: StorageDirs,
+    dir: PathBuf,
+}
+
+impl Drop for StorageDirGuard {
+    fn drop(&mut self) {
+        if self.dir.as_os_str().is_empty() {
+            return;
+        }
+        trace!("Unregister directory {:?}", self.dir);
+        self.dirs
+            .dirs
+            .lock()
+            .unwrap()
+            .retain(|(dir, _)| *dir != self.dir);
+    }
+}
@@ -0,0 +1,208 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Instant,
+};
+
+use tracing::{
+    dispatcher,
+    instrument::{WithDispatch, WithSubscriber},
+    level_filters::LevelFilter,
+    span,
+    subscriber::Interest,
+    Dispatch, Event, Metadata, Subscriber,
+};
+use zenoh::{key_expr::keyexpr, Result as ZResult};
+
+use crate::ROOT_KEY;
+
+// The target of the logs of the operations, with trace_ops
+const OPS_TARGET: &str = "zenoh_backend_fs::ops";
+
+pub(crate) const LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+pub(crate) fn level_from_name(name: &str) -> Option<LevelFilter> {
+    match name {
+        "off" => Some(LevelFilter::OFF),
+        "error" => Some(LevelFilter::ERROR),
+        "warn" => Some(LevelFilter::WARN),
+        "info" => Some(LevelFilter::INFO),
+        "debug" => Some(LevelFilter::DEBUG),
+        "trace" => Some(LevelFilter::TRACE),
+        _ => None,
+    }
+}
+
+// The logging of a storage: the level of its logs, overriding zenohd's one (lower or higher), and the logging of each
+// of its operations. Both can be changed at runtime.
+pub(crate) struct StorageLog {
+    storage: String,
+    // the subscriber of the storage's tasks
+    dispatch: Dispatch,
+    level: Arc<RwLock<Option<LevelFilter>>>,
+    trace_ops: AtomicBool,
+}
+
+impl StorageLog {
+    pub(crate) fn new(storage: &str, level: Option<LevelFilter>, trace_ops: bool) -> Self {
+        let level = Arc::new(RwLock::new(level));
+        let subscriber = StorageSubscriber {
+            global: dispatcher::get_default(Dispatch::clone),
+            level: level.clone(),
+        };
+        StorageLog {
+            storage: storage.to_string(),
+            dispatch: Dispatch::new(subscriber),
+            level,
+            trace_ops: AtomicBool::new(trace_ops),
+        }
+    }
+
+    // Override the level of the storage's logs, or follow zenohd's one again if None
+    pub(crate) fn set_level(&self, level: Option<LevelFilter>) {
+        *self.level.write().unwrap() = level;
+        // for the logs to be checked against the new level
+        tracing::callsite::rebuild_interest_cache();
+    }
+
+    pub(crate) fn set_trace_ops(&self, trace_ops: bool) {
+        self.trace_ops.store(trace_ops, Ordering::Relaxed);
+    }
+
+    // Run a task of the storage (an operation or a background task), its logs being filtered with the storage's level
+    pub(crate) fn scope<F: Future>(&self, task: F) -> WithDispatch<F> {
+        task.with_subscriber(self.dispatch.clone())
+    }
+
+    // Log an operation with its duration and result, if trace_ops is set
+    pub(crate) fn op<T>(
+        &self,
+        op: &str,
+        key: Option<&keyexpr>,
+        start: Instant,
+        result: &ZResult<T>,
+    ) {
+        if !self.trace_ops.load(Ordering::Relaxed) {
+            return;
+        }
+        let key = key.map(|k| k.as_str()).unwrap_or(ROOT_KEY);
+        let elapsed = start.elapsed();
+        dispatcher::with_default(&self.dispatch, || match result {
+            Ok(_) => tracing::info!(
+                target: OPS_TARGET,
+                "Storage {}: {} {} ok in {:?}",
+                self.storage,
+                op,
+                key,
+                elapsed
+            ),
+            Err(e) => tracing::info!(
+                target: OPS_TARGET,
+                "Storage {}: {} {} failed in {:?}: {}",
+                self.storage,
+                op,
+                key,
+                elapsed,
+                e
+            ),
+        })
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let level = self
+            .level
+            .read()
+            .unwrap()
+            .map(|level| level.to_string().to_lowercase());
+        serde_json::json!({
+            "level": level,
+            "trace_ops": self.trace_ops.load(Ordering::Relaxed),
+        })
+    }
+}
+
+// The subscriber of a storage's tasks, forwarding their logs to zenohd's subscriber. While the storage's level is
+// overridden, the storage's logs are filtered with it instead of with zenohd's subscriber.
+struct StorageSubscriber {
+    global: Dispatch,
+    level: Arc<RwLock<Option<LevelFilter>>>,
+}
+
+impl StorageSubscriber {
+    fn is_storage_log(metadata: &Metadata<'_>) -> bool {
+        metadata.target().starts_with(env!("CARGO_CRATE_NAME"))
+    }
+}
+
+impl Subscriber for StorageSubscriber {
+    fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+        let interest = self.global.register_callsite(metadata);
+        if self.level.read().unwrap().is_some() && StorageSubscriber::is_storage_log(metadata) {
+            // depends on the task logging it
+            Interest::sometimes()
+        } else {
+            interest
+        }
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        match *self.level.read().unwrap() {
+            Some(level) if StorageSubscriber::is_storage_log(metadata) => {
+                *metadata.level() <= level
+            }
+            _ => self.global.enabled(metadata),
+        }
+    }
+
+    fn new_span(&self, attrs: &span::Attributes<'_>) -> span::Id {
+        self.global.new_span(attrs)
+    }
+
+    fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+        self.global.record(span, values)
+    }
+
+    fn record_follows_from(&self, span: &span::Id, follows: &span::Id) {
+        self.global.record_follows_from(span, follows)
+    }
+
+    fn event(&self, event: &Event<'_>) {
+        self.global.event(event)
+    }
+
+    fn enter(&self, span: &span::Id) {
+        self.global.enter(span)
+    }
+
+    fn exit(&self, span: &span::Id) {
+        self.global.exit(span)
+    }
+
+    fn clone_span(&self, id: &span::Id) -> span::Id {
+        self.global.clone_span(id)
+    }
+
+    fn try_close(&self, id: span::Id) -> bool {
+        self.global.try_close(id)
+    }
+
+    fn current_span(&self) -> tracing_core::span::Current {
+        self.global.current_span()
+    }
+}
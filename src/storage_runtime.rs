@@ -0,0 +1,66 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::future::Future;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+use zenoh::{internal::zerror, Result as ZResult};
+
+// A runtime dedicated to a storage, for its file operations (which block their thread) and its background tasks not
+// to stall the other storages, e.g. when its directory is on a slow network file-system
+pub(crate) struct StorageRuntime {
+    // always set, until dropped
+    runtime: Option<Runtime>,
+}
+
+impl StorageRuntime {
+    pub(crate) fn new(storage: &str, threads: usize) -> ZResult<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(threads)
+            .thread_name(format!("fs-{storage}"))
+            .enable_all()
+            .build()
+            .map_err(|e| zerror!("Failed to create the runtime of storage {}: {}", storage, e))?;
+        Ok(StorageRuntime {
+            runtime: Some(runtime),
+        })
+    }
+
+    pub(crate) fn handle(&self) -> Handle {
+        self.runtime.as_ref().unwrap().handle().clone()
+    }
+
+    // Run an operation of the storage on its runtime, the calling thread being handed off from the caller's runtime
+    // workers while the operation blocks it
+    fn block_on<F: Future>(&self, task: F) -> F::Output {
+        let handle = self.handle();
+        tokio::task::block_in_place(|| handle.block_on(task))
+    }
+}
+
+impl Drop for StorageRuntime {
+    fn drop(&mut self) {
+        // the storage may be dropped in an async context, where a runtime can't be dropped while waiting for its tasks
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+// Run an operation of a storage, on its dedicated runtime if any
+pub(crate) async fn run_on<F: Future>(runtime: Option<&StorageRuntime>, task: F) -> F::Output {
+    match runtime {
+        Some(runtime) => runtime.block_on(task),
+        None => task.await,
+    }
+}
@@ -0,0 +1,159 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    io::{prelude::*, Cursor},
+    path::Component,
+};
+
+use flate2::read::MultiGzDecoder;
+use tracing::{trace, warn};
+use zenoh::{
+    bytes::Encoding,
+    internal::{bail, zerror},
+    time::Timestamp,
+    Result as ZResult,
+};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+// The maximal capacity allocated upfront for the content of a file read from a tarball, the size declared in its
+// header not being trusted
+const MAX_PREALLOCATED_SIZE: u64 = 1024 * 1024;
+
+// The PAX headers of the entries' metadata in the exported tarballs
+const PAX_ENCODING: &str = "ZENOH.encoding";
+const PAX_TIMESTAMP: &str = "ZENOH.timestamp";
+
+// An entry to be exported in a tarball
+pub(crate) struct TarFile {
+    pub(crate) path: String,
+    pub(crate) content: Vec<u8>,
+    pub(crate) encoding: Encoding,
+    pub(crate) timestamp: Timestamp,
+}
+
+// A tarball of the files, each with its encoding and timestamp as PAX headers
+pub(crate) fn tar_of(files: &[TarFile]) -> ZResult<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for file in files {
+        let encoding = file.encoding.to_string();
+        let timestamp = file.timestamp.to_string();
+        builder
+            .append_pax_extensions([
+                (PAX_ENCODING, encoding.as_bytes()),
+                (PAX_TIMESTAMP, timestamp.as_bytes()),
+            ])
+            .map_err(|e| zerror!("Failed to write {} in tarball: {}", file.path, e))?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(file.content.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(file.timestamp.get_time().as_secs().into());
+        header.set_cksum();
+        builder
+            .append_data(&mut header, &file.path, file.content.as_slice())
+            .map_err(|e| zerror!("Failed to write {} in tarball: {}", file.path, e))?;
+    }
+    builder
+        .into_inner()
+        .map_err(|e| zerror!("Failed to write tarball: {}", e).into())
+}
+
+// Read the regular files of a tarball (possibly gzipped), returning their relative paths (with '/' as separator)
+// and their contents. The other kinds of entries (directories, links...) are skipped.
+// Fails if the tarball exceeds `max_size` bytes once decompressed.
+pub(crate) fn tar_entries(bytes: &[u8], max_size: u64) -> ZResult<Vec<(String, Vec<u8>)>> {
+    let mut result = Vec::new();
+    for_each_tar_entry(Cursor::new(bytes), max_size, |path, content| {
+        result.push((path, content));
+        Ok(())
+    })?;
+    Ok(result)
+}
+
+// Call `f` with the relative path (with '/' as separator) and the content of each regular file of a tarball
+// (possibly gzipped), stopping at the first error or once `max_size` bytes were decompressed.
+pub(crate) fn for_each_tar_entry<R, F>(mut reader: R, max_size: u64, f: F) -> ZResult<()>
+where
+    R: BufRead,
+    F: FnMut(String, Vec<u8>) -> ZResult<()>,
+{
+    if reader
+        .fill_buf()
+        .map_err(|e| zerror!("Failed to read tarball: {}", e))?
+        .starts_with(&GZIP_MAGIC)
+    {
+        read_entries(MultiGzDecoder::new(reader), max_size, f)
+    } else {
+        read_entries(reader, max_size, f)
+    }
+}
+
+fn read_entries<R, F>(reader: R, max_size: u64, mut f: F) -> ZResult<()>
+where
+    R: Read,
+    F: FnMut(String, Vec<u8>) -> ZResult<()>,
+{
+    // (one more byte, to tell a tarball of exactly `max_size` bytes from a larger one)
+    let mut archive = tar::Archive::new(reader.take(max_size.saturating_add(1)));
+    for entry in archive
+        .entries()
+        .map_err(|e| zerror!("Failed to read tarball: {}", e))?
+    {
+        let mut entry = entry.map_err(|e| zerror!("Failed to read tarball: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| zerror!("Failed to read tarball: {}", e))?
+            .into_owned();
+        // (also for the skipped entries, whose content is read through)
+        let size = entry.size();
+        if entry.raw_file_position().saturating_add(size) > max_size {
+            bail!(
+                "Failed to read {:?} in tarball: exceeds the maximum size of {} bytes",
+                path,
+                max_size
+            )
+        }
+        if !entry.header().entry_type().is_file() {
+            if !entry.header().entry_type().is_dir() {
+                warn!("Unpack: skip {:?} which is not a regular file", path);
+            }
+            continue;
+        }
+        let mut chunks = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::Normal(chunk) => chunks.push(chunk.to_string_lossy()),
+                Component::CurDir => (),
+                _ => bail!(
+                    "Invalid path {:?} in tarball: must be relative, without '..'",
+                    path
+                ),
+            }
+        }
+        let mut content = Vec::with_capacity(size.min(MAX_PREALLOCATED_SIZE) as usize);
+        (&mut entry)
+            .take(size)
+            .read_to_end(&mut content)
+            .map_err(|e| zerror!("Failed to read {:?} in tarball: {}", path, e))?;
+        trace!("Unpack: read {:?} ({} bytes)", path, content.len());
+        f(chunks.join("/"), content)?;
+    }
+    if archive.into_inner().limit() == 0 {
+        bail!(
+            "Failed to read tarball: exceeds the maximum size of {} bytes",
+            max_size
+        )
+    }
+    Ok(())
+}
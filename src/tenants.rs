@@ -0,0 +1,65 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{sync::Arc, time::Duration};
+
+use crate::quota::{DiskQuota, QuotaShare};
+
+// The configuration of a tenant of a storage
+#[derive(Clone)]
+pub(crate) struct TenantConfig {
+    // the first chunk of the tenant's keys, which is also the name of its sub-directory
+    pub(crate) name: String,
+    pub(crate) max_total_size: Option<u64>,
+    pub(crate) retention_days: Option<u64>,
+}
+
+// A tenant of a storage: the keys starting with its name, stored in its own sub-directory
+pub(crate) struct Tenant {
+    pub(crate) quota: Option<QuotaShare>,
+    // overrides the storage's retention period for the tenant's entries
+    pub(crate) retention: Option<Duration>,
+}
+
+impl Tenant {
+    // Create the tenant (its quota accounting the files already stored once they are walked)
+    pub(crate) fn new(config: &TenantConfig) -> Self {
+        let quota = config.max_total_size.map(|max| {
+            QuotaShare::new(
+                Arc::new(DiskQuota::new(format!("tenant {}", config.name), max)),
+                0,
+            )
+        });
+        Tenant {
+            quota,
+            retention: config
+                .retention_days
+                .map(|days| Duration::from_secs(days * 24 * 3600)),
+        }
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        let mut status = serde_json::Map::new();
+        if let Some(quota) = &self.quota {
+            status.insert("max_total_size".into(), quota.max().into());
+            status.insert("total_size".into(), quota.used().into());
+        }
+        if let Some(retention) = self.retention {
+            status.insert(
+                "retention_days".into(),
+                (retention.as_secs() / (24 * 3600)).into(),
+            );
+        }
+        status.into()
+    }
+}
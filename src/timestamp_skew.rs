@@ -0,0 +1,129 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::debug;
+use zenoh::{internal::bail, time::Timestamp, Result as ZResult};
+
+// What to do with an update whose timestamp is far in the future or in the past (e.g. from a publisher with a
+// skewed clock), as a future timestamp would win over all the next updates of its key
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SkewPolicy {
+    Accept,
+    ClampToNow,
+    Reject,
+}
+
+impl SkewPolicy {
+    pub(crate) const ALL: &'static [&'static str] = &["accept", "clamp_to_now", "reject"];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "accept" => Some(SkewPolicy::Accept),
+            "clamp_to_now" => Some(SkewPolicy::ClampToNow),
+            "reject" => Some(SkewPolicy::Reject),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            SkewPolicy::Accept => "accept",
+            SkewPolicy::ClampToNow => "clamp_to_now",
+            SkewPolicy::Reject => "reject",
+        }
+    }
+}
+
+// The checker of the timestamps of the updates, counting the skewed ones
+pub(crate) struct TimestampSkew {
+    policy: SkewPolicy,
+    max_future: Duration,
+    max_past: Option<Duration>,
+    future_count: u64,
+    past_count: u64,
+}
+
+impl TimestampSkew {
+    pub(crate) fn new(
+        policy: SkewPolicy,
+        max_future: Duration,
+        max_past: Option<Duration>,
+    ) -> Self {
+        TimestampSkew {
+            policy,
+            max_future,
+            max_past,
+            future_count: 0,
+            past_count: 0,
+        }
+    }
+
+    // Apply the policy to the timestamp of an update (`op` on `key`), returning the timestamp to store
+    pub(crate) fn check(
+        &mut self,
+        timestamp: Timestamp,
+        op: &str,
+        key: &str,
+    ) -> ZResult<Timestamp> {
+        let now = SystemTime::now();
+        let time = timestamp.get_time().to_system_time();
+        let skew = match time.duration_since(now) {
+            Ok(ahead) if ahead > self.max_future => {
+                self.future_count += 1;
+                "future"
+            }
+            Ok(_) => return Ok(timestamp),
+            Err(e) if self.max_past.is_some_and(|max| e.duration() > max) => {
+                self.past_count += 1;
+                "past"
+            }
+            Err(_) => return Ok(timestamp),
+        };
+        match self.policy {
+            SkewPolicy::Accept => {
+                debug!(
+                    "Received {} on {} with a timestamp too far in the {}: {}",
+                    op, key, skew, timestamp
+                );
+                Ok(timestamp)
+            }
+            SkewPolicy::ClampToNow => {
+                debug!(
+                    "Received {} on {} with a timestamp too far in the {}: {} - set to the current time",
+                    op, key, skew, timestamp
+                );
+                Ok(Timestamp::new(
+                    now.duration_since(UNIX_EPOCH).unwrap().into(),
+                    *timestamp.get_id(),
+                ))
+            }
+            SkewPolicy::Reject => bail!(
+                "Received {} on {} with a timestamp too far in the {}: {} - rejected",
+                op,
+                key,
+                skew,
+                timestamp
+            ),
+        }
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "policy": self.policy.name(),
+            "future": self.future_count,
+            "past": self.past_count,
+        })
+    }
+}
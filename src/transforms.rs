@@ -0,0 +1,592 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+    sync::Arc,
+};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Nonce,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use zenoh::{
+    internal::{bail, zerror},
+    Result as ZResult,
+};
+use zstd::zstd_safe::CParameter;
+
+use crate::{FsTransformConfig, FsTransformOptions, PROP_STORAGE_PAYLOAD_TRANSFORMS};
+
+/// A transformation of the payloads stored by a File System Storage: the payloads are encoded before being written
+/// in their file, and decoded when read. The storages can chain several transforms (e.g. a compression then an
+/// encryption), configured with the `payload_transforms` property for the built-in ones, or added with
+/// [`FileSystemStorageBuilder::payload_transform`](crate::FileSystemStorageBuilder::payload_transform).
+///
+/// The names of the transforms applied to a file are recorded in its header, so the files remain readable as long
+/// as the storage is configured with transforms of the same names (whatever their order). The name of a transform
+/// must then not change as long as files encoded by it exist.
+pub trait PayloadTransform: Send + Sync {
+    /// The name of the transform, unique in a storage (at most 255 bytes).
+    fn name(&self) -> &str;
+
+    /// Encode a payload, before it's written.
+    fn encode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>>;
+
+    /// Decode a payload encoded by [`encode`](Self::encode), after it's read.
+    fn decode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>>;
+}
+
+/// The gzip compression of the payloads.
+pub struct GzipTransform {
+    level: u32,
+}
+
+impl GzipTransform {
+    pub const NAME: &'static str = "gzip";
+
+    /// A compression with the given level, from 0 (none) to 9 (best).
+    pub fn new(level: u32) -> Self {
+        GzipTransform {
+            level: level.min(9),
+        }
+    }
+}
+
+impl PayloadTransform for GzipTransform {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn encode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>> {
+        let mut encoder = GzEncoder::new(
+            Vec::with_capacity(payload.len() / 2),
+            Compression::new(self.level),
+        );
+        encoder
+            .write_all(&payload)
+            .and_then(|()| encoder.finish())
+            .map_err(|e| zerror!("Failed to compress payload: {}", e).into())
+    }
+
+    fn decode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>> {
+        let mut content = Vec::with_capacity(payload.len() * 2);
+        GzDecoder::new(payload.as_slice())
+            .read_to_end(&mut content)
+            .map_err(|e| zerror!("Failed to decompress payload: {}", e))?;
+        Ok(content)
+    }
+}
+
+/// The zstd compression of the payloads, in the zstd seekable format: the payload is split in frames compressed
+/// independently, followed by a seek table of their sizes. When this transform is the only one applied to a file, a
+/// range of its value (e.g. read with the `_range` parameter) is decompressed from the frames covering it only.
+pub struct ZstdSeekableTransform {
+    level: i32,
+    frame_size: usize,
+}
+
+impl ZstdSeekableTransform {
+    pub const NAME: &'static str = "zstd-seekable";
+
+    /// The default size of the frames (before compression).
+    pub const DEFAULT_FRAME_SIZE: usize = 64 * 1024;
+
+    /// A compression with the given level, from 1 (fastest) to 22 (best), in frames of the given size (before
+    /// compression, at most 1 GiB).
+    pub fn new(level: i32, frame_size: usize) -> Self {
+        ZstdSeekableTransform {
+            level: level.clamp(1, 22),
+            frame_size: frame_size.clamp(1, MAX_FRAME_SIZE),
+        }
+    }
+}
+
+impl PayloadTransform for ZstdSeekableTransform {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn encode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>> {
+        let failed = |e: io::Error| zerror!("Failed to compress payload: {}", e);
+        let mut compressor = zstd::bulk::Compressor::new(self.level).map_err(failed)?;
+        // each frame with its checksum, checked when it's decompressed
+        compressor
+            .set_parameter(CParameter::ChecksumFlag(true))
+            .map_err(failed)?;
+        let mut content = Vec::with_capacity(payload.len() / 2);
+        let mut seek_table = Vec::new();
+        for chunk in payload.chunks(self.frame_size) {
+            let frame = compressor.compress(chunk).map_err(failed)?;
+            seek_table.push((frame.len() as u32, chunk.len() as u32));
+            content.extend_from_slice(&frame);
+        }
+        // the seek table, in a skippable frame
+        content.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+        content.extend_from_slice(
+            &((seek_table.len() * 8 + SEEK_TABLE_FOOTER_SIZE) as u32).to_le_bytes(),
+        );
+        for (compressed, decompressed) in &seek_table {
+            content.extend_from_slice(&compressed.to_le_bytes());
+            content.extend_from_slice(&decompressed.to_le_bytes());
+        }
+        content.extend_from_slice(&(seek_table.len() as u32).to_le_bytes());
+        content.push(0);
+        content.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+        Ok(content)
+    }
+
+    fn decode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>> {
+        read_seekable_range(&mut Cursor::new(payload.as_slice()), 0, 0..u64::MAX)
+            .map_err(|e| zerror!("Failed to decompress payload: {}", e).into())
+    }
+}
+
+const MAX_FRAME_SIZE: usize = 1 << 30;
+
+// The zstd seekable format: the frames, then a skippable frame (with its magic number and size) containing the
+// compressed and decompressed sizes (u32 little-endian) of each frame, possibly followed by its checksum, and the
+// footer: the number of frames (u32 little-endian), a descriptor (whose highest bit tells if the checksums are
+// present) and the seekable magic number.
+const SKIPPABLE_MAGIC: u32 = 0x184D2A5E;
+const SEEKABLE_MAGIC: u32 = 0x8F92EAB1;
+const SEEK_TABLE_FOOTER_SIZE: usize = 9;
+
+// Decompress the frames of a zstd seekable stream covering a range of its content (the range being truncated to the
+// content's size). The stream starts at the `start` offset of the reader, and ends at the reader's end.
+fn read_seekable_range<R: Read + Seek>(
+    reader: &mut R,
+    start: u64,
+    range: Range<u64>,
+) -> ZResult<Vec<u8>> {
+    let invalid = |e: io::Error| zerror!("Invalid zstd seekable stream: {}", e);
+    let end = reader.seek(SeekFrom::End(0)).map_err(invalid)?;
+    let mut footer = [0u8; SEEK_TABLE_FOOTER_SIZE];
+    if end < start + footer.len() as u64 + 8 {
+        bail!("Invalid zstd seekable stream: too short")
+    }
+    reader
+        .seek(SeekFrom::End(-(footer.len() as i64)))
+        .and_then(|_| reader.read_exact(&mut footer))
+        .map_err(invalid)?;
+    if footer[5..] != SEEKABLE_MAGIC.to_le_bytes() {
+        bail!("Invalid zstd seekable stream: no seek table")
+    }
+    let frames = u32::from_le_bytes(footer[..4].try_into().unwrap()) as u64;
+    let entry_size = if footer[4] & 0x80 != 0 { 12 } else { 8 };
+    let seek_table_size = 8 + frames * entry_size + footer.len() as u64;
+    let Some(frames_end) = end
+        .checked_sub(seek_table_size)
+        .filter(|frames_end| *frames_end >= start)
+    else {
+        bail!("Invalid zstd seekable stream: truncated seek table")
+    };
+    let mut seek_table = vec![0u8; (seek_table_size as usize) - footer.len()];
+    reader
+        .seek(SeekFrom::Start(frames_end))
+        .and_then(|_| reader.read_exact(&mut seek_table))
+        .map_err(invalid)?;
+    if seek_table[..4] != SKIPPABLE_MAGIC.to_le_bytes() {
+        bail!("Invalid zstd seekable stream: no seek table")
+    }
+    let mut content = Vec::new();
+    let (mut offset, mut position) = (start, 0u64);
+    for entry in seek_table[8..].chunks(entry_size as usize) {
+        if position >= range.end {
+            break;
+        }
+        let compressed = u32::from_le_bytes(entry[..4].try_into().unwrap()) as u64;
+        let decompressed = u32::from_le_bytes(entry[4..8].try_into().unwrap()) as u64;
+        // (checked before the frame's buffer is allocated)
+        if decompressed > MAX_FRAME_SIZE as u64 {
+            bail!("Invalid zstd seekable stream: a frame exceeds the maximum frame size")
+        }
+        if offset + compressed > frames_end {
+            bail!("Invalid zstd seekable stream: the frames don't match the seek table")
+        }
+        if position + decompressed > range.start {
+            let mut frame = vec![0u8; compressed as usize];
+            reader
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| reader.read_exact(&mut frame))
+                .map_err(invalid)?;
+            let frame = zstd::bulk::decompress(&frame, decompressed as usize).map_err(invalid)?;
+            if frame.len() as u64 != decompressed {
+                bail!("Invalid zstd seekable stream: the frames don't match the seek table")
+            }
+            let from = range.start.saturating_sub(position) as usize;
+            let to = (range.end - position).min(decompressed) as usize;
+            content.extend_from_slice(&frame[from..to]);
+        }
+        offset += compressed;
+        position += decompressed;
+    }
+    Ok(content)
+}
+
+/// The AES-256-GCM encryption of the payloads: each payload is encrypted with a random nonce, stored before the
+/// ciphertext. The decryption fails if the file was altered.
+pub struct AesGcmTransform {
+    cipher: Aes256Gcm,
+}
+
+impl AesGcmTransform {
+    pub const NAME: &'static str = "aes-256-gcm";
+
+    /// An encryption with a 256-bit key.
+    pub fn new(key: &[u8; 32]) -> Self {
+        AesGcmTransform {
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+
+    /// An encryption with the key read from a file: 32 bytes, or 64 hexadecimal digits (e.g. as generated by
+    /// `openssl rand -hex 32`).
+    pub fn from_key_file(path: &Path) -> ZResult<Self> {
+        let content = std::fs::read(path)
+            .map_err(|e| zerror!("Failed to read encryption key file {:?}: {}", path, e))?;
+        let key = match content.len() {
+            32 => content,
+            _ => {
+                let hex = String::from_utf8_lossy(&content);
+                let hex = hex.trim();
+                if hex.len() != 64 || !hex.is_ascii() {
+                    bail!(
+                        "Invalid encryption key file {:?}: must contain 32 bytes or 64 hexadecimal digits",
+                        path
+                    )
+                }
+                (0..32)
+                    .map(|i| u8::from_str_radix(&hex[2 * i..2 * i + 2], 16))
+                    .collect::<Result<Vec<u8>, _>>()
+                    .map_err(|_| {
+                        zerror!(
+                            "Invalid encryption key file {:?}: must contain 32 bytes or 64 hexadecimal digits",
+                            path
+                        )
+                    })?
+            }
+        };
+        let key: [u8; 32] = key.try_into().unwrap();
+        Ok(Self::new(&key))
+    }
+}
+
+impl PayloadTransform for AesGcmTransform {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn encode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, payload.as_slice())
+            .map_err(|e| zerror!("Failed to encrypt payload: {}", e))?;
+        let mut content = nonce.to_vec();
+        content.extend_from_slice(&ciphertext);
+        Ok(content)
+    }
+
+    fn decode(&self, payload: Vec<u8>) -> ZResult<Vec<u8>> {
+        if payload.len() < NONCE_SIZE {
+            bail!("Failed to decrypt payload: too short")
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_SIZE);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| zerror!("Failed to decrypt payload: wrong key or altered file").into())
+    }
+}
+
+const NONCE_SIZE: usize = 12;
+
+// The header of the files written with transforms: this magic number, the number of transforms applied, then their
+// names (in the order they were applied), each prefixed with its length. The files without this header are read as
+// they are, e.g. the files created without zenoh or before the transforms were configured.
+const MAGIC: &[u8] = b"\x89ZFT";
+
+// The chain of the transforms of a storage, applied in their order on write and in the reverse order on read.
+// The files written with an empty chain are left as they are (without header).
+#[derive(Default)]
+pub(crate) struct Transforms {
+    chain: Vec<Arc<dyn PayloadTransform>>,
+}
+
+impl Transforms {
+    pub(crate) fn new(chain: Vec<Arc<dyn PayloadTransform>>) -> ZResult<Self> {
+        if chain.len() > u8::MAX as usize {
+            bail!("Too many payload transforms: at most 255 can be chained")
+        }
+        for (i, transform) in chain.iter().enumerate() {
+            let name = transform.name();
+            if name.is_empty() || name.len() > u8::MAX as usize {
+                bail!(
+                    r#"Invalid payload transform name "{}": must be 1 to 255 bytes long"#,
+                    name
+                )
+            }
+            if chain[..i].iter().any(|t| t.name() == name) {
+                bail!(r#"Payload transform "{}" is configured twice"#, name)
+            }
+        }
+        Ok(Transforms { chain })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.chain.is_empty()
+    }
+
+    pub(crate) fn names(&self) -> Vec<&str> {
+        self.chain.iter().map(|t| t.name()).collect()
+    }
+
+    pub(crate) fn encode(&self, content: Vec<u8>) -> ZResult<Vec<u8>> {
+        if self.chain.is_empty() {
+            return Ok(content);
+        }
+        let mut content = content;
+        for transform in &self.chain {
+            content = transform.encode(content)?;
+        }
+        let names_len: usize = self.chain.iter().map(|t| 1 + t.name().len()).sum();
+        let mut file = Vec::with_capacity(MAGIC.len() + 1 + names_len + content.len());
+        file.extend_from_slice(MAGIC);
+        file.push(self.chain.len() as u8);
+        for transform in &self.chain {
+            file.push(transform.name().len() as u8);
+            file.extend_from_slice(transform.name().as_bytes());
+        }
+        file.extend_from_slice(&content);
+        Ok(file)
+    }
+
+    // Return the content of a file written with the gzip transform only (i.e. a gzip stream), without its header
+    pub(crate) fn gzip_stream(file: &[u8]) -> Option<&[u8]> {
+        let name = GzipTransform::NAME.as_bytes();
+        file.strip_prefix(MAGIC)?
+            .strip_prefix(&[1, name.len() as u8])?
+            .strip_prefix(name)
+    }
+
+    // Return a range of the content of a file written with the zstd-seekable transform only, decompressing the
+    // frames covering it only, or None if the file was written with other transforms or without. The reader is
+    // positioned at the start of the file's header (e.g. after its envelope).
+    pub(crate) fn seekable_range<R: Read + Seek>(
+        reader: &mut R,
+        range: Range<u64>,
+    ) -> ZResult<Option<Vec<u8>>> {
+        match read_names(reader)? {
+            Some(names) if names == [ZstdSeekableTransform::NAME] => {
+                let start = reader
+                    .stream_position()
+                    .map_err(|e| zerror!("Failed to read file: {}", e))?;
+                read_seekable_range(reader, start, range).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn decode(&self, file: Vec<u8>) -> ZResult<Vec<u8>> {
+        let mut rest = file.as_slice();
+        let Some(names) = read_names(&mut rest)? else {
+            return Ok(file);
+        };
+        let mut content = rest.to_vec();
+        for name in names.iter().rev() {
+            let Some(transform) = self.chain.iter().find(|t| t.name() == name.as_str()) else {
+                bail!(
+                    r#"The file was encoded with the "{}" payload transform, which is not configured"#,
+                    name
+                )
+            };
+            content = transform.decode(content)?;
+        }
+        Ok(content)
+    }
+}
+
+// Read the header of a file written with transforms, returning the names of its transforms (in the order they were
+// applied) and leaving the reader on the transformed content, or None if the file has no header.
+fn read_names<R: Read>(reader: &mut R) -> ZResult<Option<Vec<String>>> {
+    let mut magic = [0u8; 4];
+    match reader.read_exact(&mut magic) {
+        Ok(()) if magic == MAGIC => (),
+        Ok(()) => return Ok(None),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => bail!("Failed to read payload transforms header: {}", e),
+    }
+    let invalid = || zerror!("Invalid payload transforms header");
+    let mut count = [0u8; 1];
+    reader.read_exact(&mut count).map_err(|_| invalid())?;
+    let mut names = Vec::with_capacity(count[0] as usize);
+    for _ in 0..count[0] {
+        let mut len = [0u8; 1];
+        reader.read_exact(&mut len).map_err(|_| invalid())?;
+        let mut name = vec![0u8; len[0] as usize];
+        reader.read_exact(&mut name).map_err(|_| invalid())?;
+        names.push(String::from_utf8(name).map_err(|_| invalid())?);
+    }
+    Ok(Some(names))
+}
+
+// Build the transforms of the `payload_transforms` property: the built-in transforms, each one given by its type or
+// with its type and options.
+pub(crate) fn extract_transforms(
+    configs: &[FsTransformConfig],
+) -> ZResult<Vec<Arc<dyn PayloadTransform>>> {
+    let mut result: Vec<Arc<dyn PayloadTransform>> = Vec::with_capacity(configs.len());
+    for config in configs {
+        let options = match config {
+            FsTransformConfig::Options(options) => options.clone(),
+            FsTransformConfig::Type(t) if t == GzipTransform::NAME => {
+                FsTransformOptions::Gzip { level: None }
+            }
+            FsTransformConfig::Type(t) if t == ZstdSeekableTransform::NAME => {
+                FsTransformOptions::ZstdSeekable {
+                    level: None,
+                    frame_size: None,
+                }
+            }
+            FsTransformConfig::Type(t) if t == AesGcmTransform::NAME => bail!(
+                r#"Invalid value for File System Storage configuration: "{}" in `{}` requires a `key_file` string"#,
+                AesGcmTransform::NAME,
+                PROP_STORAGE_PAYLOAD_TRANSFORMS
+            ),
+            FsTransformConfig::Type(t) => bail!(
+                r#"Invalid value for File System Storage configuration: unknown type of payload transform "{}" in `{}` (supported types: "{}", "{}", "{}")"#,
+                t,
+                PROP_STORAGE_PAYLOAD_TRANSFORMS,
+                GzipTransform::NAME,
+                ZstdSeekableTransform::NAME,
+                AesGcmTransform::NAME
+            ),
+        };
+        match options {
+            FsTransformOptions::Gzip { level } => {
+                let level = match level {
+                    Some(level) if level > 9 => bail!(
+                        r#"Invalid value for File System Storage configuration: the `level` of "{}" in `{}` must be an integer from 0 to 9"#,
+                        GzipTransform::NAME,
+                        PROP_STORAGE_PAYLOAD_TRANSFORMS
+                    ),
+                    Some(level) => level,
+                    None => Compression::default().level(),
+                };
+                result.push(Arc::new(GzipTransform::new(level)));
+            }
+            FsTransformOptions::ZstdSeekable { level, frame_size } => {
+                let level = match level {
+                    Some(level) if !(1..=22).contains(&level) => bail!(
+                        r#"Invalid value for File System Storage configuration: the `level` of "{}" in `{}` must be an integer from 1 to 22"#,
+                        ZstdSeekableTransform::NAME,
+                        PROP_STORAGE_PAYLOAD_TRANSFORMS
+                    ),
+                    Some(level) => level,
+                    None => zstd::DEFAULT_COMPRESSION_LEVEL,
+                };
+                let frame_size = match frame_size {
+                    Some(size) if size.get() > MAX_FRAME_SIZE => bail!(
+                        r#"Invalid value for File System Storage configuration: the `frame_size` of "{}" in `{}` must be an integer from 1 to {}"#,
+                        ZstdSeekableTransform::NAME,
+                        PROP_STORAGE_PAYLOAD_TRANSFORMS,
+                        MAX_FRAME_SIZE
+                    ),
+                    Some(size) => size.get(),
+                    None => ZstdSeekableTransform::DEFAULT_FRAME_SIZE,
+                };
+                result.push(Arc::new(ZstdSeekableTransform::new(level, frame_size)));
+            }
+            FsTransformOptions::AesGcm { key_file } => {
+                result.push(Arc::new(AesGcmTransform::from_key_file(Path::new(
+                    &key_file,
+                ))?));
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payload(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn zstd_seekable_round_trip() {
+        let transform = ZstdSeekableTransform::new(3, 1000);
+        for len in [0, 1, 999, 1000, 1001, 10_000] {
+            let encoded = transform.encode(payload(len)).unwrap();
+            assert_eq!(transform.decode(encoded).unwrap(), payload(len));
+        }
+    }
+
+    #[test]
+    fn zstd_seekable_range() {
+        let transform = ZstdSeekableTransform::new(3, 1000);
+        let value = payload(10_000);
+        // the stream following a header
+        let mut file = b"header".to_vec();
+        file.extend(transform.encode(value.clone()).unwrap());
+        for range in [
+            0..10,
+            990..1010,
+            2500..7500,
+            9990..u64::MAX,
+            20_000..u64::MAX,
+        ] {
+            let from = (range.start as usize).min(value.len());
+            let to = (range.end as usize).min(value.len());
+            let content =
+                read_seekable_range(&mut Cursor::new(file.as_slice()), 6, range.clone()).unwrap();
+            assert_eq!(content, &value[from..to], "range {range:?}");
+        }
+    }
+
+    #[test]
+    fn zstd_seekable_malformed() {
+        let transform = ZstdSeekableTransform::new(3, 1000);
+        let encoded = transform.encode(payload(5000)).unwrap();
+        let len = encoded.len();
+        // (5 frames)
+        let frames_end = len - (8 + 5 * 8 + SEEK_TABLE_FOOTER_SIZE);
+        let altered = |at: Range<usize>, bytes: &[u8]| {
+            let mut altered = encoded.clone();
+            altered[at].copy_from_slice(bytes);
+            transform.decode(altered)
+        };
+        // too short
+        assert!(transform.decode(encoded[..10].to_vec()).is_err());
+        // without seek table
+        assert!(altered(len - 4..len, &[0; 4]).is_err());
+        assert!(altered(frames_end..frames_end + 4, &[0; 4]).is_err());
+        // truncated frames
+        assert!(transform.decode(encoded[1..].to_vec()).is_err());
+        // more frames than the seek table holds
+        assert!(altered(len - 9..len - 5, &u32::MAX.to_le_bytes()).is_err());
+        // a frame larger than the stream
+        assert!(altered(frames_end + 8..frames_end + 12, &u32::MAX.to_le_bytes()).is_err());
+        // a decompressed size exceeding the maximum frame size, rejected before being allocated
+        let e = altered(frames_end + 12..frames_end + 16, &u32::MAX.to_le_bytes()).unwrap_err();
+        assert!(e.to_string().contains("maximum frame size"), "{e}");
+        // a decompressed size not matching the frame
+        assert!(altered(frames_end + 12..frames_end + 16, &999u32.to_le_bytes()).is_err());
+        assert!(altered(frames_end + 12..frames_end + 16, &1001u32.to_le_bytes()).is_err());
+    }
+}
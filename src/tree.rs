@@ -0,0 +1,68 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::collections::{BTreeMap, BTreeSet};
+
+use zenoh::time::Timestamp;
+
+// The summary of the subtree of a direct child of a key: its entries (including the child's own one), their total
+// size, and the most recent timestamp
+#[derive(Default)]
+struct ChildSummary {
+    // the chunks of the child's own direct children
+    children: BTreeSet<String>,
+    entries: usize,
+    size: u64,
+    latest: Option<Timestamp>,
+}
+
+// The per-child summaries of the entries under a key
+#[derive(Default)]
+pub(crate) struct TreeSummary {
+    children: BTreeMap<String, ChildSummary>,
+}
+
+impl TreeSummary {
+    // Add an entry, given its path relative to the summarized key (ignored if empty, i.e. the key's own entry)
+    pub(crate) fn add(&mut self, relpath: &str, size: u64, timestamp: Timestamp) {
+        let mut chunks = relpath.splitn(3, '/');
+        let Some(child) = chunks.next().filter(|c| !c.is_empty()) else {
+            return;
+        };
+        let summary = self.children.entry(child.to_string()).or_default();
+        if let Some(grandchild) = chunks.next() {
+            summary.children.insert(grandchild.to_string());
+        }
+        summary.entries += 1;
+        summary.size += size;
+        summary.latest = summary.latest.max(Some(timestamp));
+    }
+
+    pub(crate) fn latest(&self) -> Option<Timestamp> {
+        self.children.values().filter_map(|s| s.latest).max()
+    }
+
+    // The summaries as a JSON array, in the order of the children's keys (`key_of` giving the full key of a child)
+    pub(crate) fn to_json(&self, key_of: impl Fn(&str) -> String) -> String {
+        serde_json::Value::from_iter(self.children.iter().map(|(child, summary)| {
+            serde_json::json!({
+                "key": key_of(child),
+                "children": summary.children.len(),
+                "entries": summary.entries,
+                "size": summary.size,
+                "latest": summary.latest.map(|t| t.to_string()),
+            })
+        }))
+        .to_string()
+    }
+}
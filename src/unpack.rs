@@ -0,0 +1,201 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    io::{prelude::*, Cursor},
+    path::{Component, Path},
+};
+
+use tracing::{trace, warn};
+use zenoh::{
+    internal::{bail, zerror},
+    Result as ZResult,
+};
+
+use crate::tarball::tar_entries;
+
+// The signatures of a zip file: its first local file header, or the end of central directory of an empty zip file
+const ZIP_MAGIC: [u8; 4] = [b'P', b'K', 0x03, 0x04];
+const EMPTY_ZIP_MAGIC: [u8; 4] = [b'P', b'K', 0x05, 0x06];
+
+// The file type bits of a unix mode, and the type of a regular file
+const S_IFMT: u32 = 0o170000;
+const S_IFREG: u32 = 0o100000;
+
+// Read the regular files of an archive unpacked by a PUT: a zip file, or else a tarball (possibly gzipped).
+// Returns their relative paths (with '/' as separator) and their contents, the other kinds of entries (directories,
+// links...) being skipped. Fails if the files (or the tarball once decompressed) exceed `max_size` bytes.
+pub(crate) fn archive_entries(bytes: &[u8], max_size: u64) -> ZResult<Vec<(String, Vec<u8>)>> {
+    if bytes.starts_with(&ZIP_MAGIC) || bytes.starts_with(&EMPTY_ZIP_MAGIC) {
+        zip_entries(bytes, max_size)
+    } else {
+        tar_entries(bytes, max_size)
+    }
+}
+
+fn zip_entries(bytes: &[u8], max_size: u64) -> ZResult<Vec<(String, Vec<u8>)>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| zerror!("Failed to read zip file: {}", e))?;
+    let mut result = Vec::with_capacity(archive.len());
+    let mut remaining = max_size;
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| zerror!("Failed to read zip file: {}", e))?;
+        let name = file.name().to_string();
+        if file.is_dir() {
+            continue;
+        }
+        match file.unix_mode().map(|mode| mode & S_IFMT) {
+            // (no file type in the zip files created on other systems than unix)
+            None | Some(0) | Some(S_IFREG) => (),
+            Some(_) => {
+                warn!("Unpack: skip {:?} which is not a regular file", name);
+                continue;
+            }
+        }
+        let mut chunks = Vec::new();
+        for component in Path::new(&name).components() {
+            match component {
+                Component::Normal(chunk) => chunks.push(chunk.to_string_lossy()),
+                Component::CurDir => (),
+                _ => bail!(
+                    "Invalid path {:?} in zip file: must be relative, without '..'",
+                    name
+                ),
+            }
+        }
+        if file.size() > remaining {
+            bail!(
+                "Failed to read {:?} in zip file: exceeds the maximum size of {} bytes",
+                name,
+                max_size
+            )
+        }
+        // (the decompressed content being bounded whatever the size declared by the zip file)
+        let mut content = Vec::with_capacity(file.size() as usize);
+        (&mut file)
+            .take(remaining.saturating_add(1))
+            .read_to_end(&mut content)
+            .map_err(|e| zerror!("Failed to read {:?} in zip file: {}", name, e))?;
+        if content.len() as u64 > remaining {
+            bail!(
+                "Failed to read {:?} in zip file: exceeds the maximum size of {} bytes",
+                name,
+                max_size
+            )
+        }
+        remaining -= content.len() as u64;
+        trace!("Unpack: read {:?} ({} bytes)", name, content.len());
+        result.push((chunks.join("/"), content));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use flate2::{write::GzEncoder, Compression};
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    use super::*;
+
+    fn tarball(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn zip_file(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (path, content) in files {
+            writer.start_file(*path, options).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn assert_exceeds(result: ZResult<Vec<(String, Vec<u8>)>>) {
+        let e = result.unwrap_err().to_string();
+        assert!(e.contains("maximum size"), "unexpected error: {e}");
+    }
+
+    #[test]
+    fn unpack_archives() {
+        let files: &[(&str, &[u8])] = &[("a.txt", b"A"), ("b/c.txt", b"BC"), ("./d", b"")];
+        let expected = vec![
+            ("a.txt".to_string(), b"A".to_vec()),
+            ("b/c.txt".to_string(), b"BC".to_vec()),
+            ("d".to_string(), vec![]),
+        ];
+        let tar = tarball(files);
+        assert_eq!(archive_entries(&tar, 1024 * 1024).unwrap(), expected);
+        assert_eq!(archive_entries(&gzip(&tar), 1024 * 1024).unwrap(), expected);
+        assert_eq!(
+            archive_entries(&zip_file(files), 1024 * 1024).unwrap(),
+            expected
+        );
+        assert!(archive_entries(&zip_file(&[]), 1024).unwrap().is_empty());
+    }
+
+    #[test]
+    fn unpack_invalid_paths() {
+        assert!(archive_entries(&zip_file(&[("../a", b"A")]), 1024).is_err());
+        assert!(archive_entries(&zip_file(&[("/a", b"A")]), 1024).is_err());
+    }
+
+    #[test]
+    fn unpack_max_size() {
+        let content = vec![0u8; 100_000];
+        let files: &[(&str, &[u8])] = &[("a", &content), ("b", &content)];
+        let tar = tarball(files);
+        assert!(archive_entries(&tar, tar.len() as u64).is_ok());
+        assert_exceeds(archive_entries(&tar, 150_000));
+        assert_exceeds(archive_entries(&tar, 50_000));
+        // the decompressed size being bounded, whatever the size of the gzipped tarball
+        let gzipped = gzip(&tar);
+        assert!(gzipped.len() < 50_000);
+        assert_exceeds(archive_entries(&gzipped, 50_000));
+        // the total size of the files of a zip file
+        let zip = zip_file(files);
+        assert!(archive_entries(&zip, 200_000).is_ok());
+        assert_exceeds(archive_entries(&zip, 150_000));
+    }
+
+    #[test]
+    fn unpack_forged_size() {
+        // a tar header declaring a huge file, rejected before allocating its content
+        let mut header = tar::Header::new_gnu();
+        header.set_path("a").unwrap();
+        header.set_size(u64::MAX / 2);
+        header.set_mode(0o644);
+        header.set_cksum();
+        let mut tar = header.as_bytes().to_vec();
+        tar.extend_from_slice(&[0u8; 1024]);
+        assert_exceeds(archive_entries(&tar, 1024 * 1024));
+    }
+}
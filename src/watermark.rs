@@ -0,0 +1,117 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+use zenoh::time::Timestamp;
+
+// The maximum number of top-level prefixes whose watermark is tracked (the other ones only advancing the storage's)
+const MAX_PREFIXES: usize = 1024;
+
+// The watermark of a storage: the most recent timestamp of the values it stored, and (if enabled) of the values stored
+// under each top-level prefix, for the monitors to detect the stalled writers without listing the entries.
+pub(crate) struct Watermark {
+    by_prefix: bool,
+    state: Mutex<WatermarkState>,
+}
+
+#[derive(Default)]
+struct WatermarkState {
+    latest: Option<Timestamp>,
+    prefixes: HashMap<String, Timestamp>,
+    // whether some prefixes weren't tracked, MAX_PREFIXES being reached
+    truncated: bool,
+}
+
+impl Watermark {
+    pub(crate) fn new(by_prefix: bool) -> Self {
+        Watermark {
+            by_prefix,
+            state: Mutex::new(WatermarkState::default()),
+        }
+    }
+
+    pub(crate) fn by_prefix(&self) -> bool {
+        self.by_prefix
+    }
+
+    // Advance the watermark with the timestamp of a value stored under a top-level prefix (None for the root entry)
+    pub(crate) fn advance(&self, prefix: Option<&str>, timestamp: &Timestamp) {
+        let mut state = self.state.lock().unwrap();
+        if state.latest.map_or(true, |latest| latest < *timestamp) {
+            state.latest = Some(*timestamp);
+        }
+        let Some(prefix) = prefix.filter(|_| self.by_prefix) else {
+            return;
+        };
+        match state.prefixes.get_mut(prefix) {
+            Some(latest) => {
+                if *latest < *timestamp {
+                    *latest = *timestamp;
+                }
+            }
+            None if state.prefixes.len() < MAX_PREFIXES => {
+                state.prefixes.insert(prefix.to_string(), *timestamp);
+            }
+            None => state.truncated = true,
+        }
+    }
+
+    // The watermark of the storage (if no prefix) or of a top-level prefix, if any value was stored
+    pub(crate) fn latest(&self, prefix: Option<&str>) -> Option<Timestamp> {
+        let state = self.state.lock().unwrap();
+        match prefix {
+            Some(prefix) => state.prefixes.get(prefix).copied(),
+            None => state.latest,
+        }
+    }
+
+    // The watermark of the storage, and of each top-level prefix if enabled (the ones accepted by `is_listed`)
+    pub(crate) fn status_json(&self, is_listed: impl Fn(&str) -> bool) -> serde_json::Value {
+        let state = self.state.lock().unwrap();
+        let mut status = match state.latest {
+            Some(latest) => to_json(&latest),
+            None => serde_json::json!({ "timestamp": null }),
+        };
+        if let (true, Some(obj)) = (self.by_prefix, status.as_object_mut()) {
+            let prefixes: serde_json::Map<_, _> = state
+                .prefixes
+                .iter()
+                .filter(|(prefix, _)| is_listed(prefix))
+                .map(|(prefix, latest)| (prefix.clone(), to_json(latest)))
+                .collect();
+            obj.insert("prefixes".into(), prefixes.into());
+            if state.truncated {
+                obj.insert("truncated".into(), true.into());
+            }
+        }
+        status
+    }
+}
+
+// A timestamp, with its time and its age (in seconds)
+pub(crate) fn to_json(timestamp: &Timestamp) -> serde_json::Value {
+    let time = timestamp.get_time().to_system_time();
+    let age = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or(Duration::ZERO);
+    serde_json::json!({
+        "timestamp": timestamp.to_string(),
+        "time": humantime::format_rfc3339_millis(time).to_string(),
+        "age_secs": age.as_secs(),
+    })
+}
@@ -0,0 +1,262 @@
+//
+// Copyright (c) 2022 ZettaScale Technology
+//
+// This program and the accompanying materials are made available under the
+// terms of the Eclipse Public License 2.0 which is available at
+// http://www.eclipse.org/legal/epl-2.0, or the Apache License, Version 2.0
+// which is available at https://www.apache.org/licenses/LICENSE-2.0.
+//
+// SPDX-License-Identifier: EPL-2.0 OR Apache-2.0
+//
+// Contributors:
+//   ZettaScale Zenoh Team, <zenoh@zettascale.tech>
+//
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use tokio::sync::{oneshot, Semaphore, SemaphorePermit};
+use zenoh::{
+    internal::{bail, zerror},
+    Result as ZResult,
+};
+
+// What to do with a write when the write queue is full
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OverloadPolicy {
+    Block,
+    DropOldest,
+    Error,
+}
+
+impl OverloadPolicy {
+    pub(crate) const ALL: &'static [&'static str] = &["block", "drop_oldest", "error"];
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "block" => Some(OverloadPolicy::Block),
+            "drop_oldest" => Some(OverloadPolicy::DropOldest),
+            "error" => Some(OverloadPolicy::Error),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            OverloadPolicy::Block => "block",
+            OverloadPolicy::DropOldest => "drop_oldest",
+            OverloadPolicy::Error => "error",
+        }
+    }
+}
+
+// The bounded queue of the writes of a storage (waiting for a file operation slot, or in progress), for the bursts of
+// PUTs not to pile up without limit
+pub(crate) struct WriteQueue {
+    depth: usize,
+    policy: OverloadPolicy,
+    slots: Semaphore,
+    // the writes of the queue not started yet (the oldest first), with the sender cancelling them
+    waiting: Mutex<VecDeque<(u64, oneshot::Sender<()>)>>,
+    next_id: AtomicU64,
+    blocked: AtomicU64,
+    dropped: AtomicU64,
+    rejected: AtomicU64,
+}
+
+// A write admitted in the queue, keeping its place until dropped
+pub(crate) struct QueuedWrite<'a> {
+    queue: &'a WriteQueue,
+    _slot: SemaphorePermit<'a>,
+    id: u64,
+    cancelled: oneshot::Receiver<()>,
+}
+
+impl WriteQueue {
+    pub(crate) fn new(depth: usize, policy: OverloadPolicy) -> Self {
+        WriteQueue {
+            depth,
+            policy,
+            slots: Semaphore::new(depth),
+            waiting: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(0),
+            blocked: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+        }
+    }
+
+    // Admit a write in the queue, applying the overload policy if it's full
+    pub(crate) async fn enqueue(&self) -> ZResult<QueuedWrite<'_>> {
+        let slot = match self.slots.try_acquire() {
+            Ok(slot) => slot,
+            Err(_) => {
+                match self.policy {
+                    OverloadPolicy::Error => {
+                        self.rejected.fetch_add(1, Ordering::Relaxed);
+                        bail!(
+                            "Write rejected: the write queue is full ({} writes)",
+                            self.depth
+                        )
+                    }
+                    // make room by dropping the oldest write not started yet (if all are started, wait for one)
+                    OverloadPolicy::DropOldest => match self.waiting.lock().unwrap().pop_front() {
+                        Some((_, cancel)) => {
+                            let _ = cancel.send(());
+                        }
+                        None => {
+                            self.blocked.fetch_add(1, Ordering::Relaxed);
+                        }
+                    },
+                    OverloadPolicy::Block => {
+                        self.blocked.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                self.slots
+                    .acquire()
+                    .await
+                    .map_err(|e| zerror!("Failed to enter the write queue: {}", e))?
+            }
+        };
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (cancel, cancelled) = oneshot::channel();
+        self.waiting.lock().unwrap().push_back((id, cancel));
+        Ok(QueuedWrite {
+            queue: self,
+            _slot: slot,
+            id,
+            cancelled,
+        })
+    }
+
+    pub(crate) fn status_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "depth": self.depth,
+            "overload": self.policy.name(),
+            "queued": self.depth - self.slots.available_permits(),
+            "blocked": self.blocked.load(Ordering::Relaxed),
+            "dropped": self.dropped.load(Ordering::Relaxed),
+            "rejected": self.rejected.load(Ordering::Relaxed),
+        })
+    }
+}
+
+impl QueuedWrite<'_> {
+    // Start the write once `ready` completes (e.g. the acquisition of a file operation slot), unless it's dropped
+    // meanwhile by the overload policy
+    pub(crate) async fn start<T>(&mut self, ready: impl Future<Output = ZResult<T>>) -> ZResult<T> {
+        tokio::select! {
+            result = ready => {
+                self.leave_waiting();
+                result
+            }
+            _ = &mut self.cancelled => {
+                self.queue.dropped.fetch_add(1, Ordering::Relaxed);
+                bail!("Write dropped: the write queue is full ({} writes)", self.queue.depth)
+            }
+        }
+    }
+
+    fn leave_waiting(&self) {
+        self.queue
+            .waiting
+            .lock()
+            .unwrap()
+            .retain(|(id, _)| *id != self.id);
+    }
+}
+
+impl Drop for QueuedWrite<'_> {
+    fn drop(&mut self) {
+        // if not started
+        self.leave_waiting();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use super::*;
+
+    fn status(queue: &WriteQueue, name: &str) -> u64 {
+        queue.status_json()[name].as_u64().unwrap()
+    }
+
+    // The counters of the queue's status: queued, blocked, dropped and rejected writes
+    fn counters(queue: &WriteQueue) -> [u64; 4] {
+        ["queued", "blocked", "dropped", "rejected"].map(|name| status(queue, name))
+    }
+
+    // A write to the queue, started once a file operation slot is acquired
+    async fn write(queue: Arc<WriteQueue>, ops: Arc<Semaphore>) -> ZResult<()> {
+        let mut write = queue.enqueue().await?;
+        write
+            .start(async { ops.acquire().await.map_err(|e| zerror!("{}", e).into()) })
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn overload_block() {
+        let queue = Arc::new(WriteQueue::new(1, OverloadPolicy::Block));
+        let ops = Arc::new(Semaphore::new(1));
+        let mut first = queue.enqueue().await.unwrap();
+        first.start(async { Ok(()) }).await.unwrap();
+        assert_eq!(counters(&queue), [1, 0, 0, 0]);
+        // the queue being full, the next write waits for the first one to leave it
+        let task = tokio::spawn(write(queue.clone(), ops));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!task.is_finished());
+        assert_eq!(counters(&queue), [1, 1, 0, 0]);
+        drop(first);
+        task.await.unwrap().unwrap();
+        assert_eq!(counters(&queue), [0, 1, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn overload_drop_oldest() {
+        let queue = Arc::new(WriteQueue::new(1, OverloadPolicy::DropOldest));
+        let ops = Arc::new(Semaphore::new(0));
+        // a write waiting for a file operation slot
+        let oldest = tokio::spawn(write(queue.clone(), ops.clone()));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(counters(&queue), [1, 0, 0, 0]);
+        // the queue being full, the next write drops it
+        let mut next = queue.enqueue().await.unwrap();
+        let e = oldest.await.unwrap().unwrap_err().to_string();
+        assert!(e.contains("dropped"), "unexpected error: {e}");
+        assert_eq!(counters(&queue), [1, 0, 1, 0]);
+        next.start(async { Ok(()) }).await.unwrap();
+        // the only write being started, the next one waits for it to leave the queue
+        ops.add_permits(1);
+        let task = tokio::spawn(write(queue.clone(), ops));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!task.is_finished());
+        assert_eq!(counters(&queue), [1, 1, 1, 0]);
+        drop(next);
+        task.await.unwrap().unwrap();
+        assert_eq!(counters(&queue), [0, 1, 1, 0]);
+    }
+
+    #[tokio::test]
+    async fn overload_error() {
+        let queue = WriteQueue::new(1, OverloadPolicy::Error);
+        let mut first = queue.enqueue().await.unwrap();
+        first.start(async { Ok(()) }).await.unwrap();
+        // the queue being full, the next write is rejected
+        let e = queue.enqueue().await.err().unwrap().to_string();
+        assert!(e.contains("rejected"), "unexpected error: {e}");
+        assert_eq!(counters(&queue), [1, 0, 0, 1]);
+        drop(first);
+        assert_eq!(counters(&queue), [0, 0, 0, 1]);
+        drop(queue.enqueue().await.unwrap());
+        assert_eq!(counters(&queue), [0, 0, 0, 1]);
+        assert!(queue.waiting.lock().unwrap().is_empty());
+    }
+}